@@ -18,6 +18,8 @@ pub mod names {
     pub const SECURITY_SCAN: &str = "security-scan";
     /// Verify build works.
     pub const BUILD_VERIFY: &str = "build-verify";
+    /// Validate the commit message as a Conventional Commit (commit-msg hook).
+    pub const CONVENTIONAL_COMMIT: &str = "conventional-commit";
 }
 
 /// Returns true if a check name is a built-in check.
@@ -32,6 +34,7 @@ pub fn is_builtin(name: &str) -> bool {
             | names::TEST_INTEGRATION
             | names::SECURITY_SCAN
             | names::BUILD_VERIFY
+            | names::CONVENTIONAL_COMMIT
     )
 }
 
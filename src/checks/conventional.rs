@@ -0,0 +1,215 @@
+//! Native Conventional Commits validation.
+//!
+//! Checks the commit message header (`type(scope)!: subject`) against the
+//! allowed types and subject length in `[commit_msg.conventional]`, without
+//! needing node's commitlint.
+
+use crate::config::ConventionalCommitConfig;
+
+/// Prefixes of messages generated by git itself, which are not validated.
+const GENERATED_PREFIXES: &[&str] = &["Merge ", "Revert \"", "fixup! ", "squash! ", "amend! "];
+
+/// A way a commit message breaks the Conventional Commits format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// The message has no header line.
+    EmptyMessage,
+    /// The header is not `type(scope): subject`.
+    MalformedHeader,
+    /// The type is not in the allowed list.
+    UnknownType {
+        /// The type found in the header.
+        found: String,
+        /// The allowed types.
+        allowed: Vec<String>,
+    },
+    /// The scope parentheses are empty.
+    EmptyScope,
+    /// Nothing follows the `type:` prefix.
+    MissingSubject,
+    /// The subject is longer than allowed.
+    SubjectTooLong {
+        /// Length of the subject in characters.
+        length: usize,
+        /// Maximum allowed length.
+        max: usize,
+    },
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyMessage => write!(f, "Commit message is empty"),
+            Self::MalformedHeader => {
+                write!(f, "Header must look like 'type(scope): subject'")
+            },
+            Self::UnknownType { found, allowed } => {
+                write!(
+                    f,
+                    "Unknown type '{found}' (allowed: {})",
+                    allowed.join(", ")
+                )
+            },
+            Self::EmptyScope => write!(f, "Scope must not be empty"),
+            Self::MissingSubject => write!(f, "Missing subject after the type"),
+            Self::SubjectTooLong { length, max } => {
+                write!(f, "Subject is {length} characters (max {max})")
+            },
+        }
+    }
+}
+
+/// Validates a commit message, returning every violation found.
+///
+/// Comment lines (starting with `#`) are ignored, and messages generated by
+/// git (merges, reverts, fixups) are accepted as-is.
+#[must_use]
+pub fn validate(message: &str, config: &ConventionalCommitConfig) -> Vec<Violation> {
+    let Some(header) = message
+        .lines()
+        .map(str::trim_end)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+    else {
+        return vec![Violation::EmptyMessage];
+    };
+
+    if GENERATED_PREFIXES.iter().any(|p| header.starts_with(p)) {
+        return Vec::new();
+    }
+
+    let Some((prefix, subject)) = header.split_once(':') else {
+        return vec![Violation::MalformedHeader];
+    };
+
+    let prefix = prefix.strip_suffix('!').unwrap_or(prefix);
+    let (kind, scope) = match prefix.split_once('(') {
+        Some((kind, rest)) => match rest.strip_suffix(')') {
+            Some(scope) => (kind, Some(scope)),
+            None => return vec![Violation::MalformedHeader],
+        },
+        None => (prefix, None),
+    };
+
+    if kind.is_empty() || kind.contains(char::is_whitespace) {
+        return vec![Violation::MalformedHeader];
+    }
+
+    let mut violations = Vec::new();
+
+    if !config.types.iter().any(|t| t == kind) {
+        violations.push(Violation::UnknownType {
+            found: kind.to_string(),
+            allowed: config.types.clone(),
+        });
+    }
+
+    if scope.is_some_and(|s| s.trim().is_empty()) {
+        violations.push(Violation::EmptyScope);
+    }
+
+    let subject = subject.trim();
+    let length = subject.chars().count();
+    if subject.is_empty() {
+        violations.push(Violation::MissingSubject);
+    } else if length > config.max_subject_length {
+        violations.push(Violation::SubjectTooLong {
+            length,
+            max: config.max_subject_length,
+        });
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(message: &str) -> Vec<Violation> {
+        validate(message, &ConventionalCommitConfig::default())
+    }
+
+    #[test]
+    fn test_valid_messages() {
+        assert!(check("feat: add retries").is_empty());
+        assert!(check("fix(runner): kill process group on timeout").is_empty());
+        assert!(check("refactor(config)!: rename fail_fast").is_empty());
+        assert!(check("# Please enter a message\n\ndocs: explain extends\n\nBody.").is_empty());
+    }
+
+    #[test]
+    fn test_generated_messages_are_accepted() {
+        assert!(check("Merge branch 'main' into feature").is_empty());
+        assert!(check("Revert \"feat: add retries\"").is_empty());
+        assert!(check("fixup! feat: add retries").is_empty());
+    }
+
+    #[test]
+    fn test_unknown_type() {
+        let violations = check("feature: add retries");
+        assert_eq!(violations.len(), 1);
+        assert!(matches!(
+            &violations[0],
+            Violation::UnknownType { found, .. } if found == "feature"
+        ));
+        assert!(violations[0]
+            .to_string()
+            .starts_with("Unknown type 'feature' (allowed: feat, fix,"));
+    }
+
+    #[test]
+    fn test_custom_types() {
+        let config = ConventionalCommitConfig {
+            types: vec!["wip".to_string()],
+            ..Default::default()
+        };
+        assert!(validate("wip: checkpoint", &config).is_empty());
+        assert_eq!(validate("feat: add retries", &config).len(), 1);
+    }
+
+    #[test]
+    fn test_missing_subject() {
+        assert_eq!(check("fix:"), vec![Violation::MissingSubject]);
+        assert_eq!(check("fix(runner):   "), vec![Violation::MissingSubject]);
+    }
+
+    #[test]
+    fn test_subject_too_long() {
+        let message = format!("feat: {}", "a".repeat(73));
+        assert_eq!(
+            check(&message),
+            vec![Violation::SubjectTooLong {
+                length: 73,
+                max: 72
+            }]
+        );
+        assert_eq!(
+            check(&message)[0].to_string(),
+            "Subject is 73 characters (max 72)"
+        );
+    }
+
+    #[test]
+    fn test_reports_every_violation() {
+        let message = format!("feature(): {}", "a".repeat(80));
+        let violations = check(&message);
+        assert_eq!(violations.len(), 3);
+        assert!(matches!(violations[0], Violation::UnknownType { .. }));
+        assert_eq!(violations[1], Violation::EmptyScope);
+        assert!(matches!(violations[2], Violation::SubjectTooLong { .. }));
+    }
+
+    #[test]
+    fn test_malformed_headers() {
+        assert_eq!(check("add retries"), vec![Violation::MalformedHeader]);
+        assert_eq!(check("feat(runner: x"), vec![Violation::MalformedHeader]);
+        assert_eq!(check("my feat: x"), vec![Violation::MalformedHeader]);
+        assert_eq!(check(": x"), vec![Violation::MalformedHeader]);
+    }
+
+    #[test]
+    fn test_empty_message() {
+        assert_eq!(check(""), vec![Violation::EmptyMessage]);
+        assert_eq!(check("# only comments\n\n"), vec![Violation::EmptyMessage]);
+    }
+}
@@ -3,4 +3,5 @@
 //! This module provides the built-in checks that come with agent-precommit.
 
 pub mod builtin;
+pub mod conventional;
 pub mod precommit;
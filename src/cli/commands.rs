@@ -1,14 +1,28 @@
 //! CLI command implementations.
 
-use crate::config::{Config, CONFIG_FILE_NAME};
-use crate::core::detector::{Detector, Mode};
+use super::RunArgs;
+use crate::checks::conventional;
+use crate::config::{
+    ConditionMatch, Config, ConfigDiff, FailFast, ListDiff, CONFIG_FILE_NAME, DEFAULT_OUTPUT_LINES,
+    JAVA_LINT_PLUGINS,
+};
+use crate::core::cache::{self, Cache};
+use crate::core::detector::{stage_description, Detection, DetectionReason, Detector, Mode};
+use crate::core::doctor;
 use crate::core::error::{Error, Result};
-use crate::core::git::GitRepo;
-use crate::core::runner::Runner;
+use crate::core::executor::NO_OUTPUT_PLACEHOLDER;
+use crate::core::files;
+use crate::core::git::{GitRepo, HooksManager};
+use crate::core::report::{
+    self, expand_report_path, DetectReport, MatrixReport, PresetsReport, RunContext, RunReport,
+    REPORT_OUTPUT_LIMIT,
+};
+use crate::core::runner::{CheckResult, Conditions, MatrixResult, RunResult, Runner};
 use console::style;
-use std::io::{IsTerminal, Write};
-use std::path::PathBuf;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use tokio_util::sync::CancellationToken;
 
 /// Hook script template.
 const HOOK_SCRIPT: &str = r#"#!/bin/sh
@@ -24,28 +38,75 @@ fi
 exec apc run
 "#;
 
+/// Commit-msg hook script template; git passes the message file as `$1`.
+const COMMIT_MSG_HOOK_SCRIPT: &str = r#"#!/bin/sh
+# agent-precommit hook - installed by `apc install --hook commit-msg`
+# https://github.com/agent-precommit/agent-precommit
+
+# Skip if APC_SKIP is set
+if [ "$APC_SKIP" = "1" ]; then
+    exit 0
+fi
+
+# Run agent-precommit's commit-msg checks
+exec apc run --hook commit-msg --message-file "$1"
+"#;
+
 /// Hook marker comment.
 const HOOK_MARKER: &str = "# agent-precommit hook";
 
-/// Initialize configuration.
-pub fn init(preset: Option<&str>, force: bool) -> Result<ExitCode> {
-    let config_path = PathBuf::from(CONFIG_FILE_NAME);
+/// Marks the line `apc install --chain` appends to another tool's hook.
+const CHAIN_MARKER: &str = "# agent-precommit chained";
 
-    // Check if config already exists
-    if config_path.exists() && !force {
-        eprintln!(
-            "{} Configuration already exists: {}",
-            style("!").yellow(),
-            config_path.display()
-        );
-        eprintln!("  Use --force to overwrite.");
-        return Ok(ExitCode::FAILURE);
-    }
+/// Exit code for runs aborted by a signal (128 + SIGINT).
+const EXIT_ABORTED: u8 = 130;
+
+/// Checks listed by `apc run --timings`.
+const TIMINGS_SHOWN: usize = 10;
 
-    // Generate config
-    let config = match preset {
-        Some(p) => Config::for_preset(p),
-        None => {
+/// Builds the config `apc init` starts from: a preset file, a built-in
+/// preset (tailored to the project where it can be), or the defaults.
+fn preset_config(preset: Option<&str>, preset_file: Option<&Path>) -> Result<Config> {
+    let config = match (preset, preset_file) {
+        (_, Some(path)) => {
+            let config = Config::from_preset_file(path)?;
+            eprintln!(
+                "{} Using preset file: {}",
+                style("•").cyan(),
+                path.display()
+            );
+            config
+        },
+        (Some(p), None) if p == crate::presets::names::MAKE => {
+            let mut config = Config::for_preset(p);
+            if let Some(targets) = crate::presets::make_targets(std::path::Path::new(".")) {
+                config.retain_make_targets(&targets);
+            }
+            config
+        },
+        (Some(p), None) if p == crate::presets::names::JAVA => {
+            let mut config = Config::for_preset(p);
+            config.retain_java_plugins(&crate::presets::java_plugins(
+                std::path::Path::new("."),
+                &JAVA_LINT_PLUGINS,
+            ));
+            config
+        },
+        (Some(p), None) if p == crate::presets::names::DENO => {
+            let mut config = Config::for_preset(p);
+            config
+                .use_deno_config_file(crate::presets::deno_config_file(std::path::Path::new(".")));
+            config
+        },
+        (Some(p), None) if p == crate::presets::names::PHP => {
+            let mut config = Config::for_preset(p);
+            config.use_php_analyser_config(crate::presets::php_analyser_config(
+                std::path::Path::new("."),
+            ));
+            config
+        },
+        (Some(p), None) => Config::for_preset(p),
+        (None, None) => {
             // Auto-detect existing pre-commit config
             let mut config = Config::default();
             if PathBuf::from(".pre-commit-config.yaml").exists() {
@@ -59,6 +120,54 @@ pub fn init(preset: Option<&str>, force: bool) -> Result<ExitCode> {
         },
     };
 
+    Ok(config)
+}
+
+/// Initialize configuration.
+///
+/// With `merge`, a preset's checks are added to the existing config instead of
+/// replacing it; `prefer_preset` lets the preset's definitions win conflicts.
+pub fn init(
+    preset: Option<&str>,
+    preset_file: Option<&Path>,
+    force: bool,
+    merge: bool,
+    prefer_preset: bool,
+) -> Result<ExitCode> {
+    let config_path = PathBuf::from(CONFIG_FILE_NAME);
+
+    if merge && preset.is_none() && preset_file.is_none() {
+        eprintln!(
+            "{} --merge needs a preset: pass --preset or --preset-file",
+            style("✗").red()
+        );
+        return Ok(ExitCode::FAILURE);
+    }
+
+    // Check if config already exists
+    if config_path.exists() && !force && !merge {
+        eprintln!(
+            "{} Configuration already exists: {}",
+            style("!").yellow(),
+            config_path.display()
+        );
+        eprintln!("  Use --force to overwrite, or --merge to add a preset's checks.");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let mut config = preset_config(preset, preset_file)?;
+    if merge && config_path.exists() {
+        let mut existing = Config::load_from(&config_path)?;
+        let added = existing.merge_preset(config, prefer_preset);
+        existing.validate()?;
+        eprintln!(
+            "{} Merged {added} check(s) into {}",
+            style("•").cyan(),
+            config_path.display()
+        );
+        config = existing;
+    }
+
     // Write config
     let toml = toml::to_string_pretty(&config).map_err(|e| Error::Internal {
         message: format!("Failed to serialize config: {e}"),
@@ -70,6 +179,15 @@ pub fn init(preset: Option<&str>, force: bool) -> Result<ExitCode> {
 
     if let Some(p) = preset {
         eprintln!("  Using preset: {p}");
+
+        let missing = crate::presets::missing_tools(p);
+        if !missing.is_empty() {
+            eprintln!(
+                "{} Missing tools for this preset: {}",
+                style("!").yellow(),
+                missing.join(", ")
+            );
+        }
     }
 
     eprintln!("\nNext steps:");
@@ -80,10 +198,16 @@ pub fn init(preset: Option<&str>, force: bool) -> Result<ExitCode> {
 }
 
 /// Install git hook.
-pub fn install(force: bool) -> Result<ExitCode> {
+///
+/// With `chain`, adds an `apc run` line to the existing hook instead.
+pub fn install(force: bool, chain: bool, hook: &str) -> Result<ExitCode> {
     let repo = GitRepo::discover()?;
+    if chain {
+        return chain_hook(&repo, hook);
+    }
+
     let hooks_dir = repo.hooks_dir();
-    let hook_path = hooks_dir.join("pre-commit");
+    let hook_path = hooks_dir.join(hook);
 
     // Create hooks directory if needed
     if !hooks_dir.exists() {
@@ -105,12 +229,20 @@ pub fn install(force: bool) -> Result<ExitCode> {
             return Ok(ExitCode::SUCCESS);
         }
 
+        if let Some(manager) = repo.hooks_manager() {
+            eprintln!(
+                "{} {} manages this repository's hooks; `apc install --chain` adds apc to its {hook} hook instead of replacing it",
+                style("!").yellow(),
+                manager.name()
+            );
+        }
+
         if !force {
             return Err(Error::HookExists { path: hook_path });
         }
 
         // Backup existing hook
-        let backup_path = hooks_dir.join("pre-commit.bak");
+        let backup_path = hooks_dir.join(format!("{hook}.bak"));
         std::fs::rename(&hook_path, &backup_path).map_err(|e| Error::io("backup hook", e))?;
         eprintln!(
             "{} Backed up existing hook to {}",
@@ -120,32 +252,132 @@ pub fn install(force: bool) -> Result<ExitCode> {
     }
 
     // Write hook
-    std::fs::write(&hook_path, HOOK_SCRIPT).map_err(|e| Error::io("write hook", e))?;
+    let script = if hook == "commit-msg" {
+        COMMIT_MSG_HOOK_SCRIPT
+    } else {
+        HOOK_SCRIPT
+    };
+    std::fs::write(&hook_path, script).map_err(|e| Error::io("write hook", e))?;
+    make_executable(&hook_path)?;
 
-    // Make executable on Unix
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = std::fs::metadata(&hook_path)
-            .map_err(|e| Error::io("get hook metadata", e))?
-            .permissions();
-        perms.set_mode(0o755);
-        std::fs::set_permissions(&hook_path, perms).map_err(|e| Error::io("set hook perms", e))?;
+    eprintln!(
+        "{} Installed {hook} hook at {}",
+        style("✓").green(),
+        hook_path.display()
+    );
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Returns the hook `apc install --chain` appends to: Husky's own script in
+/// `.husky`, otherwise the hook git runs.
+fn chain_target(repo: &GitRepo, hook: &str) -> PathBuf {
+    match repo.hooks_manager() {
+        Some(HooksManager::Husky) => repo.root().join(".husky").join(hook),
+        _ => repo.hook_path(hook),
+    }
+}
+
+/// Appends an `apc run` line to a hook, creating the hook if needed.
+fn chain_hook(repo: &GitRepo, hook: &str) -> Result<ExitCode> {
+    let path = chain_target(repo, hook);
+    let mut content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => "#!/bin/sh\n".to_string(),
+        Err(e) => return Err(Error::io("read existing hook", e)),
+    };
+
+    if content.contains(CHAIN_MARKER) || content.contains(HOOK_MARKER) {
+        eprintln!(
+            "{} apc already runs from {}",
+            style("✓").green(),
+            path.display()
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let command = if hook == "commit-msg" {
+        "apc run --hook commit-msg --message-file \"$1\""
+    } else {
+        "apc run"
+    };
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
     }
+    content = format!("{content}{command} || exit $? {CHAIN_MARKER}\n");
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| Error::io("create hooks dir", e))?;
+    }
+    std::fs::write(&path, content).map_err(|e| Error::io("write hook", e))?;
+    make_executable(&path)?;
 
     eprintln!(
-        "{} Installed pre-commit hook at {}",
+        "{} Added apc to {hook} hook at {}",
         style("✓").green(),
-        hook_path.display()
+        path.display()
     );
 
     Ok(ExitCode::SUCCESS)
 }
 
-/// Uninstall git hook.
-pub fn uninstall() -> Result<ExitCode> {
+/// Removes the line `apc install --chain` added to a hook, and the hook
+/// itself if apc created it. Returns false if the hook has no such line.
+fn unchain_hook(path: &Path) -> Result<bool> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Ok(false);
+    };
+    if !content.contains(CHAIN_MARKER) {
+        return Ok(false);
+    }
+
+    let kept: String = content
+        .lines()
+        .filter(|line| !line.contains(CHAIN_MARKER))
+        .flat_map(|line| [line, "\n"])
+        .collect();
+    if kept.trim() == "#!/bin/sh" {
+        std::fs::remove_file(path).map_err(|e| Error::io("remove hook", e))?;
+    } else {
+        std::fs::write(path, kept).map_err(|e| Error::io("write hook", e))?;
+    }
+    Ok(true)
+}
+
+/// Makes a hook executable.
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = std::fs::metadata(path)
+        .map_err(|e| Error::io("get hook metadata", e))?
+        .permissions();
+    perms.set_mode(0o755);
+    std::fs::set_permissions(path, perms).map_err(|e| Error::io("set hook perms", e))
+}
+
+/// Makes a hook executable; nothing to do outside Unix.
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)] // matches the Unix signature
+const fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Uninstall git hook, moving the backup `apc install --force` made back into
+/// place if `restore` is set.
+pub fn uninstall(restore: bool, hook: &str) -> Result<ExitCode> {
     let repo = GitRepo::discover()?;
-    let hook_path = repo.hook_path("pre-commit");
+
+    let chained = chain_target(&repo, hook);
+    if unchain_hook(&chained)? {
+        eprintln!(
+            "{} Removed apc from {hook} hook at {}",
+            style("✓").green(),
+            chained.display()
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let hook_path = repo.hook_path(hook);
 
     if !hook_path.exists() {
         eprintln!(
@@ -172,25 +404,68 @@ pub fn uninstall() -> Result<ExitCode> {
     std::fs::remove_file(&hook_path).map_err(|e| Error::io("remove hook", e))?;
 
     eprintln!(
-        "{} Removed pre-commit hook from {}",
+        "{} Removed {hook} hook from {}",
         style("✓").green(),
         hook_path.display()
     );
 
     // Check for backup
-    let backup_path = repo.hooks_dir().join("pre-commit.bak");
-    if backup_path.exists() {
+    let backup_path = repo.hooks_dir().join(format!("{hook}.bak"));
+    if !backup_path.exists() {
+        return Ok(ExitCode::SUCCESS);
+    }
+    if !restore {
         eprintln!(
-            "  Backup exists at {} - restore if needed",
+            "  Backup exists at {} - restore with `apc uninstall --restore`",
             backup_path.display()
         );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Never clobber a hook something else wrote after ours was removed
+    if hook_path.exists() {
+        eprintln!(
+            "{} Not restoring {}: a hook already exists at {}",
+            style("!").yellow(),
+            backup_path.display(),
+            hook_path.display()
+        );
+        return Ok(ExitCode::FAILURE);
     }
 
+    // A rename keeps the backup's permissions, including the executable bit
+    std::fs::rename(&backup_path, &hook_path).map_err(|e| Error::io("restore hook", e))?;
+    eprintln!(
+        "{} Restored previous {hook} hook from {}",
+        style("✓").green(),
+        backup_path.display()
+    );
+
     Ok(ExitCode::SUCCESS)
 }
 
+/// Detects the mode to run in, unless `--mode` overrides it.
+fn run_detection(args: &RunArgs, config: &Config) -> Result<Detection> {
+    if let Some(m) = args.mode.as_deref() {
+        return Ok(Detection {
+            mode: parse_mode(m)?,
+            reason: DetectionReason::CliFlag,
+            agent: None,
+        });
+    }
+
+    let detection = Detector::new(config).detect();
+    eprintln!(
+        "{} Mode: {} ({})",
+        style("•").cyan(),
+        style(detection.mode.name()).bold(),
+        detection.reason
+    );
+    Ok(detection)
+}
+
 /// Run checks.
-pub async fn run(mode_override: Option<&str>, check: Option<&str>, _all: bool) -> Result<ExitCode> {
+pub async fn run(args: &RunArgs, quiet: bool) -> Result<ExitCode> {
     // Check for skip
     if std::env::var("APC_SKIP").ok().as_deref() == Some("1") {
         eprintln!("{} Skipping checks (APC_SKIP=1)", style("•").cyan());
@@ -198,149 +473,1300 @@ pub async fn run(mode_override: Option<&str>, check: Option<&str>, _all: bool) -
     }
 
     // Load config
-    let config = Config::load_or_default()?;
+    let config = load_run_config(quiet)?;
 
-    // Detect or override mode
-    let mode = if let Some(m) = mode_override {
-        m.parse().map_err(|e: String| Error::ConfigInvalid {
-            field: "mode".to_string(),
-            message: e,
-        })?
-    } else {
-        let detector = Detector::new(&config);
-        let detection = detector.detect();
-        eprintln!(
-            "{} Mode: {} ({})",
-            style("•").cyan(),
-            style(detection.mode.name()).bold(),
-            detection.reason
-        );
-        detection.mode
-    };
+    // Unresolved conflicts fail the commit anyway, so say so before any check runs
+    if !args.force && !args.dry_run && !args.plan && args.explain_skip.is_none() && has_conflicts()?
+    {
+        return Ok(ExitCode::FAILURE);
+    }
+
+    if !args.mode_matrix.is_empty() {
+        let modes = args
+            .mode_matrix
+            .iter()
+            .map(|m| parse_mode(m))
+            .collect::<Result<Vec<_>>>()?;
+        let (runner, cancel) = build_runner(config, args)?;
+        return run_matrix(&runner, &modes, args, cancel.as_ref()).await;
+    }
+
+    let detection = run_detection(args, &config)?;
+    let mode = detection.mode;
 
     // Create runner
-    let runner = Runner::new(config);
+    let (mut runner, cancel) = build_runner(config, args)?;
+    if let Some(agent) = detection.agent {
+        runner = runner.with_agent(agent);
+    }
 
-    // Run checks
-    let result = if let Some(name) = check {
-        let check_result = runner.run_single(name, mode).await?;
-        crate::core::runner::RunResult {
-            mode,
-            checks: vec![check_result],
-            duration: std::time::Duration::ZERO,
-        }
-    } else {
-        runner.run(mode).await?
-    };
+    if let Some(name) = args.explain_skip.as_deref() {
+        return explain_skip(&runner, name, mode);
+    }
 
-    // Print summary
-    eprintln!();
-    if result.success() {
-        eprintln!(
-            "{} All checks passed ({} passed, {} skipped) in {:?}",
-            style("✓").green().bold(),
-            result.passed_count(),
-            result.skipped_count(),
-            result.duration
-        );
-        Ok(ExitCode::SUCCESS)
-    } else {
-        eprintln!(
-            "{} {} check(s) failed",
-            style("✗").red().bold(),
-            result.failed_count()
-        );
+    if args.dry_run {
+        return dry_run(&runner, args, mode);
+    }
 
-        // Show failed check details
-        for check in result.failed_checks() {
-            eprintln!();
-            eprintln!("  {} {}", style("Failed:").red(), check.name);
-            if !check.output.combined_output().is_empty() {
-                for line in check.output.combined_output().lines().take(20) {
-                    eprintln!("    {line}");
-                }
+    if args.plan {
+        return print_plan(&runner, args, mode);
+    }
+
+    // Run checks
+    let result =
+        if let Some(message_file) = args.message_file.as_deref().filter(|_| args.hook.is_some()) {
+            if !conventional_ok(runner.config(), message_file)? {
+                return Ok(ExitCode::FAILURE);
+            }
+            runner.run_commit_msg(mode).await?
+        } else if let Some(name) = args.check.as_deref() {
+            let check_result = runner.run_single(name, mode).await?;
+            RunResult {
+                mode,
+                checks: vec![check_result],
+                duration: std::time::Duration::ZERO,
             }
+        } else if !args.tag.is_empty() {
+            runner.run_by_tags(&args.tag, mode).await?
+        } else {
+            runner.run(mode).await?
+        };
+
+    if args.json || args.output_json_pretty || args.json_context {
+        let mut report = run_report(&result, runner.config());
+        if args.json_context {
+            let repo = GitRepo::discover().ok();
+            report = report.with_context(RunContext::new(&detection, repo.as_ref()));
         }
+        println!("{}", report.to_json(args.output_json_pretty)?);
+    }
 
-        Ok(ExitCode::FAILURE)
+    if let Some(format) = args.report.as_deref() {
+        let report = match format {
+            "junit" => run_report(&result, runner.config()).to_junit(),
+            "github" => run_report(&result, runner.config()).to_github(),
+            "sarif" => run_report(&result, runner.config()).to_sarif()?,
+            _ => run_report(&result, runner.config())
+                .with_output_limit(REPORT_OUTPUT_LIMIT)
+                .to_json(false)?,
+        };
+        let path = args.report_file.as_deref().map(|template| {
+            let repo = GitRepo::discover().ok();
+            expand_report_path(template, mode, repo.as_ref(), chrono::Local::now())
+        });
+        write_report(&report, path.as_deref())?;
     }
-}
 
-/// Show detected mode.
-pub fn detect() -> Result<ExitCode> {
-    let config = Config::load_or_default()?;
-    let detector = Detector::new(&config);
-    let detection = detector.detect();
+    if let Some(path) = args.metrics_textfile.as_deref() {
+        write_metrics(&run_report(&result, runner.config()).to_prometheus(), path)?;
+    }
 
-    eprintln!("Detected mode: {}", style(detection.mode.name()).bold());
-    eprintln!("Reason: {}", detection.reason);
+    if args.print_summary_table {
+        eprintln!();
+        eprint!(
+            "{}",
+            table_report(&result, runner.config(), args).to_table()
+        );
+    }
 
-    // Show environment info
-    eprintln!();
-    eprintln!("Environment:");
+    if mode == Mode::Ci && result.success() && runner.config().ci.report_on_success {
+        // Keep stdout clean when it carries a report
+        let stdout_free = !(args.json
+            || args.output_json_pretty
+            || args.json_context
+            || (args.report.is_some() && args.report_file.is_none()));
+        report_github_success(&run_report(&result, runner.config()), stdout_free)?;
+    }
 
-    let env_vars = ["APC_MODE", "AGENT_MODE", "CI", "GITHUB_ACTIONS"];
-    for var in env_vars {
-        if let Ok(value) = std::env::var(var) {
-            eprintln!("  {var}={value}");
-        }
+    if args.timings {
+        print_timings(&result);
     }
 
-    eprintln!();
+    // Print summary
+    let aborted = cancel.as_ref().is_some_and(CancellationToken::is_cancelled);
+    let lines = output_lines(runner.config(), args, mode);
+    Ok(print_summary(&result, aborted, lines))
+}
+
+/// Reports unmerged paths in the index. Returns true if there are any.
+fn has_conflicts() -> Result<bool> {
+    let Ok(repo) = GitRepo::discover() else {
+        return Ok(false);
+    };
+    let unmerged = repo.unmerged_paths()?;
+    if unmerged.is_empty() {
+        return Ok(false);
+    }
+
+    let paths: Vec<String> = unmerged
+        .iter()
+        .map(|p| files::relative_to(p, repo.root()).display().to_string())
+        .collect();
     eprintln!(
-        "TTY: stdin={}, stdout={}",
-        std::io::stdin().is_terminal(),
-        std::io::stdout().is_terminal()
+        "{} Resolve conflicts in: {}",
+        style("✗").red().bold(),
+        paths.join(", ")
     );
+    eprintln!("  Run with --force to run checks anyway.");
+    Ok(true)
+}
 
+/// Prints why a check would or would not run in `mode`, condition by condition.
+fn explain_skip(runner: &Runner, name: &str, mode: Mode) -> Result<ExitCode> {
+    let conditions = runner.explain_conditions(name, mode)?;
+    let config = runner.config();
+
+    let mode_checks = match mode {
+        Mode::Human => &config.human.checks,
+        Mode::Agent | Mode::Ci => &config.agent.checks,
+    };
+    if !mode_checks.iter().any(|c| c == name) {
+        let table = if mode == Mode::Human {
+            "human"
+        } else {
+            "agent"
+        };
+        eprintln!(
+            "{} '{name}' is not in [{table}].checks, so it does not run in {} mode",
+            style("!").yellow(),
+            mode.name()
+        );
+    }
+
+    print_conditions(name, &conditions);
     Ok(ExitCode::SUCCESS)
 }
 
-/// List configured checks.
-pub fn list(mode: Option<&str>) -> Result<ExitCode> {
-    let config = Config::load_or_default()?;
-
-    let mode: Option<Mode> =
-        mode.map(|m| m.parse())
-            .transpose()
-            .map_err(|e: String| Error::ConfigInvalid {
-                field: "mode".to_string(),
-                message: e,
-            })?;
+/// Prints the checks a run would execute or skip, without running them.
+fn dry_run(runner: &Runner, args: &RunArgs, mode: Mode) -> Result<ExitCode> {
+    let plan = if let Some(name) = args.check.as_deref() {
+        vec![runner.plan_single(name, mode)?]
+    } else if !args.tag.is_empty() {
+        runner.plan_by_tags(&args.tag, mode)?
+    } else {
+        runner.plan(mode)?
+    };
 
-    // Print checks by mode
-    if mode.is_none() || mode == Some(Mode::Human) {
-        eprintln!("{}", style("Human mode checks:").bold());
-        for name in &config.human.checks {
-            print_check(&config, name);
+    let skipped = plan.iter().filter(|c| c.skip_reason.is_some()).count();
+    eprintln!(
+        "{} Dry run: {} check(s) in {} mode, {skipped} skipped",
+        style("•").cyan(),
+        plan.len(),
+        mode.name()
+    );
+    let quiet = quiet_skips(runner.config(), args);
+    for check in &plan {
+        match &check.skip_reason {
+            Some(_) if quiet => {},
+            None => eprintln!("  {} {}: {}", style("▸").green(), check.name, check.command),
+            Some(reason) => eprintln!(
+                "  {} {}: {}",
+                style("-").dim(),
+                check.name,
+                style(format!("skipped ({reason})")).dim()
+            ),
         }
-        eprintln!();
     }
 
-    if mode.is_none() || mode == Some(Mode::Agent) || mode == Some(Mode::Ci) {
-        eprintln!("{}", style("Agent mode checks:").bold());
-        for name in &config.agent.checks {
-            print_check(&config, name);
-        }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints the waves a run would execute, one numbered line per wave.
+fn print_plan(runner: &Runner, args: &RunArgs, mode: Mode) -> Result<ExitCode> {
+    let waves = if args.tag.is_empty() {
+        runner.waves(mode)?
+    } else {
+        runner.waves_by_tags(&args.tag, mode)?
+    };
+
+    let count: usize = waves.iter().map(Vec::len).sum();
+    eprintln!(
+        "{} Plan: {count} check(s) in {} wave(s), {} mode",
+        style("•").cyan(),
+        waves.len(),
+        mode.name()
+    );
+    for (i, wave) in waves.iter().enumerate() {
+        eprintln!("  {}. {}", i + 1, wave.join(", "));
     }
 
     Ok(ExitCode::SUCCESS)
 }
 
-/// Prints a check's details.
-fn print_check(config: &Config, name: &str) {
-    let check = config.checks.get(name);
-    let description = check
-        .map(|c| c.description.as_str())
-        .filter(|d| !d.is_empty())
-        .unwrap_or("(no description)");
+/// Prints each `enabled_if` condition with its result, then the verdict.
+fn print_conditions(name: &str, conditions: &Conditions) {
+    if conditions.outcomes.is_empty() {
+        eprintln!("'{name}' has no enabled_if conditions");
+        return;
+    }
 
-    eprintln!("  {} - {}", style(name).cyan(), description);
-}
+    let combine = match conditions.r#match {
+        ConditionMatch::All => "all must hold",
+        ConditionMatch::Any => "any must hold",
+    };
+    eprintln!("enabled_if for '{name}' ({combine}):");
+    for outcome in &conditions.outcomes {
+        let mark = if outcome.met {
+            style("✓").green()
+        } else {
+            style("✗").red()
+        };
+        eprintln!("  {mark} {outcome}");
+    }
 
-/// Validate configuration.
-pub fn validate() -> Result<ExitCode> {
-    match Config::load() {
+    eprintln!();
+    if conditions.enabled() {
+        eprintln!("'{name}' is enabled");
+    } else {
+        eprintln!("'{name}' is skipped: condition not met");
+    }
+}
+
+/// Builds the report for a run, titling checks per `[output].report_use_description`.
+fn run_report(result: &RunResult, config: &Config) -> RunReport {
+    let report = RunReport::from(result);
+    if config.output.report_use_description {
+        report.with_descriptions(&config.checks)
+    } else {
+        report
+    }
+}
+
+/// Builds the report behind `--print-summary-table`, without skipped checks
+/// under `--quiet-skips`.
+fn table_report(result: &RunResult, config: &Config, args: &RunArgs) -> RunReport {
+    let report = run_report(result, config);
+    if quiet_skips(config, args) {
+        report.without_skipped()
+    } else {
+        report
+    }
+}
+
+/// Returns true if skipped checks are left out of per-check listings.
+const fn quiet_skips(config: &Config, args: &RunArgs) -> bool {
+    args.quiet_skips || config.output.quiet_skips
+}
+
+/// Writes `--report` output to `path`, or stdout if none is given.
+fn write_report(report: &str, path: Option<&Path>) -> Result<()> {
+    match path {
+        Some(path) => {
+            if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir).map_err(|e| Error::io("create report dir", e))?;
+            }
+            let contents = format!("{}\n", report.trim_end());
+            std::fs::write(path, contents).map_err(|e| Error::io("write report", e))?;
+        },
+        None => println!("{}", report.trim_end()),
+    }
+    Ok(())
+}
+
+/// Reports a passing CI run to GitHub Actions (`[ci].report_on_success`).
+///
+/// Appends a Markdown table of the checks to `$GITHUB_STEP_SUMMARY` if set,
+/// and prints a `::notice` workflow command if `notice` is true and the run
+/// is on GitHub Actions.
+fn report_github_success(report: &RunReport, notice: bool) -> Result<()> {
+    if let Some(path) = std::env::var_os("GITHUB_STEP_SUMMARY").filter(|p| !p.is_empty()) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| Error::io("open step summary", e))?;
+        file.write_all(report.to_markdown().as_bytes())
+            .map_err(|e| Error::io("write step summary", e))?;
+    }
+
+    if notice && std::env::var("GITHUB_ACTIONS").ok().as_deref() == Some("true") {
+        println!(
+            "::notice title=apc::All checks passed ({} passed, {} skipped) in {:?}",
+            report.passed,
+            report.skipped,
+            std::time::Duration::from_millis(report.duration_ms)
+        );
+    }
+
+    Ok(())
+}
+
+/// Writes `--metrics-textfile` output.
+///
+/// Writes a temporary file and renames it into place, so the textfile
+/// collector never reads a partial file.
+fn write_metrics(metrics: &str, path: &Path) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    std::fs::write(&tmp, metrics).map_err(|e| Error::io("write metrics", e))?;
+    std::fs::rename(&tmp, path).map_err(|e| Error::io("write metrics", e))
+}
+
+/// Parses a mode name from the command line.
+fn parse_mode(name: &str) -> Result<Mode> {
+    name.parse().map_err(|e: String| Error::ConfigInvalid {
+        field: "mode".to_string(),
+        message: e,
+    })
+}
+
+/// Creates a runner configured from the `run` arguments.
+///
+/// Returns the cancellation token when `--abort-on-signal` is set.
+fn build_runner(mut config: Config, args: &RunArgs) -> Result<(Runner, Option<CancellationToken>)> {
+    if args.fail_fast_group {
+        config.agent.fail_fast = FailFast::Group;
+    }
+
+    let mut runner = Runner::new(config)
+        .ignore_conditions(args.all)
+        .stream_output(args.stream);
+
+    if let Some(timeout) = args.timeout {
+        runner = runner.with_timeout(timeout);
+    }
+
+    if let Some(limit) = args.max_parallel {
+        runner = runner.with_max_parallel(limit.get());
+    }
+
+    if let Some(limit) = args.group_parallel {
+        runner = runner.with_group_parallel(limit.get());
+    }
+
+    if !args.only.is_empty() {
+        runner = runner.with_only(args.only.clone());
+    }
+
+    if args.shuffle {
+        let seed = args.seed.unwrap_or_else(random_seed);
+        eprintln!(
+            "{} Shuffling checks with seed {seed} (replay with --shuffle --seed {seed})",
+            style("•").cyan()
+        );
+        runner = runner.with_shuffle(seed);
+    }
+
+    if let Some(path) = &args.message_file {
+        // Checks run from the repo root, so don't leave the path relative
+        let cwd = std::env::current_dir().map_err(|e| Error::io("get current dir", e))?;
+        runner = runner.with_message_file(cwd.join(path));
+    }
+
+    if args.since_ref_auto {
+        if let Some(files) = files_since_default_branch()? {
+            runner = runner.with_files(files);
+        }
+    }
+
+    if args.from_stdin_files {
+        runner = runner.with_files(files_from_stdin()?);
+    }
+
+    if let Some(rev) = args.changed_since.as_deref() {
+        runner = runner
+            .with_files(files_changed_since(rev)?)
+            .only_changed(true);
+    }
+
+    let cancel = if args.abort_on_signal {
+        let token = install_abort_handler()?;
+        runner = runner.with_cancellation(token.clone());
+        Some(token)
+    } else {
+        None
+    };
+
+    Ok((runner, cancel))
+}
+
+/// Returns a seed for `--shuffle` that differs from run to run.
+fn random_seed() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+
+    // RandomState is randomly keyed per process; mix in the time for good measure
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    if let Ok(elapsed) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+        hasher.write_u128(elapsed.as_nanos());
+    }
+    hasher.finish()
+}
+
+/// Runs each mode's checks in turn and reports the combined result.
+async fn run_matrix(
+    runner: &Runner,
+    modes: &[Mode],
+    args: &RunArgs,
+    cancel: Option<&CancellationToken>,
+) -> Result<ExitCode> {
+    let mut matrix = MatrixResult::default();
+
+    for (i, &mode) in modes.iter().enumerate() {
+        eprintln!(
+            "{} Mode: {} (matrix {}/{})",
+            style("•").cyan(),
+            style(mode.name()).bold(),
+            i + 1,
+            modes.len()
+        );
+        matrix.runs.push(runner.run(mode).await?);
+
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            break;
+        }
+    }
+
+    if args.json || args.output_json_pretty {
+        let mut report = MatrixReport::from(&matrix);
+        if runner.config().output.report_use_description {
+            report = report.with_descriptions(&runner.config().checks);
+        }
+        let json = report.to_json(args.output_json_pretty)?;
+        println!("{json}");
+    }
+
+    if args.print_summary_table {
+        for run in &matrix.runs {
+            eprintln!();
+            eprintln!("{}", style(run.mode.name()).bold());
+            eprint!("{}", table_report(run, runner.config(), args).to_table());
+        }
+    }
+
+    let aborted = cancel.is_some_and(CancellationToken::is_cancelled);
+    Ok(print_matrix_summary(&matrix, aborted, |mode| {
+        output_lines(runner.config(), args, mode)
+    }))
+}
+
+/// Returns how many lines of a failed check's output to print in `mode`
+/// (0 for all): `--output-lines`, then `[human]`/`[agent].output_lines`.
+fn output_lines(config: &Config, args: &RunArgs, mode: Mode) -> usize {
+    let configured = match mode {
+        Mode::Human => config.human.output_lines,
+        Mode::Agent | Mode::Ci => config.agent.output_lines,
+    };
+    args.output_lines
+        .or(configured)
+        .unwrap_or(DEFAULT_OUTPUT_LINES)
+}
+
+/// Prints one summary line per mode, then details of failed checks.
+fn print_matrix_summary(
+    matrix: &MatrixResult,
+    aborted: bool,
+    lines: impl Fn(Mode) -> usize,
+) -> ExitCode {
+    eprintln!();
+    for run in &matrix.runs {
+        let icon = if run.success() {
+            style("✓").green().bold()
+        } else {
+            style("✗").red().bold()
+        };
+        eprintln!(
+            "{icon} {}: {} passed, {} failed, {} skipped",
+            run.mode.name(),
+            run.passed_count(),
+            run.failed_count(),
+            run.skipped_count()
+        );
+    }
+
+    for run in &matrix.runs {
+        for check in run.failed_checks() {
+            print_failed_check(check, Some(run.mode), lines(run.mode));
+        }
+    }
+
+    if aborted {
+        ExitCode::from(EXIT_ABORTED)
+    } else if matrix.success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Loads the config for a run, telling first-time users when defaults are in effect.
+fn load_run_config(quiet: bool) -> Result<Config> {
+    match Config::find_config_file() {
+        Ok(path) => Config::load_with_user_config(Some(&path)),
+        Err(Error::ConfigNotFound { .. }) => {
+            if !quiet {
+                eprintln!(
+                    "{} No {CONFIG_FILE_NAME} found, using defaults - run `apc init`",
+                    style("ℹ").cyan()
+                );
+            }
+            Config::load_with_user_config(None)
+        },
+        Err(e) => Err(e),
+    }
+}
+
+/// Prints the slowest checks of a run with their share of its wall-clock time.
+fn print_timings(result: &RunResult) {
+    let slowest = result.slowest(TIMINGS_SHOWN);
+    if slowest.is_empty() {
+        return;
+    }
+
+    let width = slowest.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    let total = result.duration.as_secs_f64();
+    eprintln!();
+    eprintln!("{} Slowest checks:", style("•").cyan());
+    for check in slowest {
+        let duration = check.output.duration;
+        let share = if total > 0.0 {
+            duration.as_secs_f64() / total * 100.0
+        } else {
+            0.0
+        };
+        eprintln!(
+            "  {:<width$}  {:>7}  {}",
+            check.name,
+            report::format_millis(u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)),
+            style(format!("{share:.0}%")).dim()
+        );
+    }
+}
+
+/// Prints the run summary and returns the exit code for the run.
+fn print_summary(result: &RunResult, aborted: bool, lines: usize) -> ExitCode {
+    eprintln!();
+    if aborted {
+        eprintln!(
+            "{} Aborted ({} passed, {} failed before interrupt)",
+            style("✗").red().bold(),
+            result.passed_count(),
+            result.failed_count()
+        );
+        ExitCode::from(EXIT_ABORTED)
+    } else if result.success() {
+        eprintln!(
+            "{} All checks passed ({} passed, {} skipped) in {:?}",
+            style("✓").green().bold(),
+            result.passed_count(),
+            result.skipped_count(),
+            result.duration
+        );
+        for check in result.non_blocking_failures() {
+            eprintln!(
+                "  {} {} failed (non-blocking)",
+                style("⚠").yellow(),
+                check.name
+            );
+        }
+        ExitCode::SUCCESS
+    } else {
+        let non_blocking = result.non_blocking_failures().count();
+        eprintln!(
+            "{} {} check(s) failed",
+            style("✗").red().bold(),
+            result.failed_count() - non_blocking
+        );
+
+        // Show failed check details
+        for check in result.failed_checks() {
+            print_failed_check(check, None, lines);
+        }
+
+        ExitCode::FAILURE
+    }
+}
+
+/// Prints a failed check's output (its first `lines` lines, or all for 0) and
+/// artifacts, labelled with its mode if given.
+fn print_failed_check(check: &CheckResult, mode: Option<Mode>, lines: usize) {
+    let signal = check
+        .output
+        .signal
+        .map(|signal| format!(" (terminated by signal {signal})"))
+        .unwrap_or_default();
+    let name = match mode {
+        Some(mode) => format!("{} ({}){signal}", check.name, mode.name()),
+        None => format!("{}{signal}", check.name),
+    };
+
+    eprintln!();
+    if check.blocking {
+        eprintln!("  {} {name}", style("Failed:").red());
+    } else {
+        eprintln!("  {} {name} failed (non-blocking)", style("⚠").yellow());
+    }
+    if check.output.has_output() {
+        let output = check.output.combined_output();
+        let limit = if lines == 0 { usize::MAX } else { lines };
+        for line in output.lines().take(limit) {
+            eprintln!("    {line}");
+        }
+        let hidden = output.lines().count().saturating_sub(limit);
+        if hidden > 0 {
+            eprintln!(
+                "    {}",
+                style(format!(
+                    "... {hidden} more line(s) (--output-lines 0 shows all)"
+                ))
+                .dim()
+            );
+        }
+    } else {
+        eprintln!("    {}", style(NO_OUTPUT_PLACEHOLDER).dim());
+    }
+    if !check.artifacts.is_empty() {
+        eprintln!("    {}", style("Artifacts:").dim());
+        for artifact in &check.artifacts {
+            eprintln!("      {}", artifact.display());
+        }
+    }
+}
+
+/// Installs SIGINT/SIGTERM handlers that cancel the returned token.
+///
+/// Handlers are registered before returning so no signal is missed once
+/// checks start. A second signal exits immediately with 130 instead of
+/// waiting for the run to wind down.
+fn install_abort_handler() -> Result<CancellationToken> {
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut interrupt =
+            signal(SignalKind::interrupt()).map_err(|e| Error::io("install SIGINT handler", e))?;
+        let mut terminate =
+            signal(SignalKind::terminate()).map_err(|e| Error::io("install SIGTERM handler", e))?;
+
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = interrupt.recv() => {},
+                _ = terminate.recv() => {},
+            }
+            cancel.cancel();
+            tokio::select! {
+                _ = interrupt.recv() => {},
+                _ = terminate.recv() => {},
+            }
+            std::process::exit(i32::from(EXIT_ABORTED));
+        });
+    }
+
+    #[cfg(not(unix))]
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            cancel.cancel();
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(i32::from(EXIT_ABORTED));
+            }
+        }
+    });
+
+    Ok(token)
+}
+
+/// Resolves files changed since the merge-base with the default branch.
+///
+/// Returns `None` (use staged files) when no default branch or merge-base exists.
+fn files_since_default_branch() -> Result<Option<Vec<PathBuf>>> {
+    let repo = GitRepo::discover()?;
+
+    let Some(branch) = repo.default_branch_ref()? else {
+        eprintln!(
+            "{} No default branch found - using staged files",
+            style("•").cyan()
+        );
+        return Ok(None);
+    };
+
+    let Ok(base) = repo.merge_base("HEAD", &branch) else {
+        eprintln!(
+            "{} No merge-base with {branch} - using staged files",
+            style("•").cyan()
+        );
+        return Ok(None);
+    };
+
+    eprintln!(
+        "{} Files changed since {} (merge-base with {branch})",
+        style("•").cyan(),
+        base.get(..7).unwrap_or(&base)
+    );
+
+    repo.changed_files_since(&base).map(Some)
+}
+
+/// Returns the files changed since the merge-base of HEAD and `rev`,
+/// including staged changes, for `--changed-since`.
+fn files_changed_since(rev: &str) -> Result<Vec<PathBuf>> {
+    let repo = GitRepo::discover()?;
+    let base = repo.merge_base("HEAD", rev)?;
+
+    eprintln!(
+        "{} Files changed since {} (merge-base with {rev})",
+        style("•").cyan(),
+        base.get(..7).unwrap_or(&base)
+    );
+
+    repo.changed_files_since(&base)
+}
+
+/// Reads the `--from-stdin-files` list, one path per line.
+fn files_from_stdin() -> Result<Vec<PathBuf>> {
+    let repo = GitRepo::discover()?;
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| Error::io("read file list from stdin", e))?;
+
+    files::parse_file_list(&input, repo.root())
+}
+
+/// Show detected mode.
+pub fn detect(json: bool) -> Result<ExitCode> {
+    let config = Config::load_or_default()?;
+    let detector = Detector::new(&config);
+    let detection = detector.detect();
+
+    if json {
+        let report = DetectReport::new(detection, detector.environment());
+        println!("{}", report.to_json(false)?);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    eprintln!("Detected mode: {}", style(detection.mode.name()).bold());
+    eprintln!("Reason: {}", detection.reason);
+    if let Some(agent) = detection.agent {
+        eprintln!("Agent: {agent}");
+    }
+
+    // Show the order sources are checked in; the first match wins
+    eprintln!();
+    eprintln!("Precedence:");
+    let priority = detector.priority();
+    for (i, stage) in priority.iter().enumerate() {
+        let note = if *stage == "tty" && !config.detection.use_tty_heuristic {
+            " (skipped: detection.use_tty_heuristic = false)"
+        } else {
+            ""
+        };
+        eprintln!("  {}. {}{note}", i + 1, stage_description(stage));
+    }
+    eprintln!("  {}. Default: human", priority.len() + 1);
+
+    // Show environment info
+    eprintln!();
+    eprintln!("Environment:");
+
+    let env_vars = ["APC_MODE", "AGENT_MODE", "CI", "GITHUB_ACTIONS"];
+    for var in env_vars {
+        if let Ok(value) = std::env::var(var) {
+            eprintln!("  {var}={value}");
+        }
+    }
+
+    eprintln!();
+    eprintln!(
+        "TTY: stdin={}, stdout={}",
+        std::io::stdin().is_terminal(),
+        std::io::stdout().is_terminal()
+    );
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// List the built-in presets with what they are for and the checks they add.
+pub fn presets(json: bool) -> Result<ExitCode> {
+    let report = PresetsReport::new();
+    if json {
+        println!("{}", report.to_json(false)?);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let width = report
+        .presets
+        .iter()
+        .map(|p| p.name.len())
+        .max()
+        .unwrap_or_default();
+    for preset in &report.presets {
+        eprintln!(
+            "  {}  {}",
+            style(format!("{:<width$}", preset.name)).cyan(),
+            preset.description
+        );
+        eprintln!(
+            "  {:<width$}  {}",
+            "",
+            style(format!("checks: {}", preset.checks.join(", "))).dim()
+        );
+    }
+    eprintln!();
+    eprintln!("Use one with: apc init --preset <name>");
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Longest command `apc list` shows before truncating it.
+const LIST_COMMAND_WIDTH: usize = 40;
+
+/// A check as `apc list` shows it.
+struct ListedCheck {
+    name: String,
+    /// Other names the check answers to, shown after its name.
+    aliases: String,
+    /// Resolved command, or `None` if no such check is defined.
+    command: Option<String>,
+    /// Its `enabled_if` conditions evaluated against the repo, if they could be.
+    conditions: Option<Conditions>,
+    env: Vec<(String, String)>,
+}
+
+impl ListedCheck {
+    fn new(runner: &Runner, name: &str, mode: Mode) -> Self {
+        let check = runner.config().checks.get(name);
+        let resolved = runner.resolve_check(name, mode).ok();
+        Self {
+            name: name.to_string(),
+            aliases: check
+                .filter(|c| !c.aliases.is_empty())
+                .map(|c| format!(" (also: {})", c.aliases.join(", ")))
+                .unwrap_or_default(),
+            command: match &resolved {
+                Some(resolved) => Some(resolved.command.clone()),
+                None => check.map(|c| c.run.clone()),
+            },
+            conditions: check.and_then(|_| runner.explain_conditions(name, mode).ok()),
+            env: resolved.map(|r| r.env).unwrap_or_default(),
+        }
+    }
+
+    /// Width of the name column this check needs.
+    fn label_width(&self) -> usize {
+        self.name.chars().count() + self.aliases.chars().count()
+    }
+
+    /// The command cut to one line of at most [`LIST_COMMAND_WIDTH`] characters.
+    fn short_command(&self) -> String {
+        let Some(command) = &self.command else {
+            return "(not defined)".to_string();
+        };
+        let first = command.lines().next().unwrap_or_default();
+        if first.len() == command.len() && first.chars().count() <= LIST_COMMAND_WIDTH {
+            return first.to_string();
+        }
+        let cut: String = first.chars().take(LIST_COMMAND_WIDTH - 1).collect();
+        format!("{cut}…")
+    }
+}
+
+/// List configured checks, marking which are enabled in the current repo.
+pub fn list(mode: Option<&str>, verbose: bool) -> Result<ExitCode> {
+    let config = Config::load_or_default()?;
+
+    let mode: Option<Mode> =
+        mode.map(|m| m.parse())
+            .transpose()
+            .map_err(|e: String| Error::ConfigInvalid {
+                field: "mode".to_string(),
+                message: e,
+            })?;
+
+    // Sections of checks by mode, with the mode their conditions are evaluated in
+    let mut sections: Vec<(String, Mode, Vec<String>)> = Vec::new();
+    if mode.is_none() || mode == Some(Mode::Human) {
+        sections.push((
+            "Human mode checks:".to_string(),
+            Mode::Human,
+            config.human.checks.clone(),
+        ));
+    }
+    if mode.is_none() || mode.is_some_and(|m| m.is_thorough()) {
+        sections.push((
+            "Agent mode checks:".to_string(),
+            Mode::Agent,
+            config.agent.checks.clone(),
+        ));
+
+        let mut per_agent: Vec<_> = config.agent.per_agent.iter().collect();
+        per_agent.sort_by_key(|(agent, _)| **agent);
+        for (agent, extra) in per_agent {
+            sections.push((
+                format!("Extra checks for {agent}:"),
+                Mode::Agent,
+                extra.checks.clone(),
+            ));
+        }
+    }
+
+    let runner = Runner::new(config);
+    let sections: Vec<(String, Vec<ListedCheck>)> = sections
+        .into_iter()
+        .map(|(title, mode, names)| {
+            let checks = names
+                .iter()
+                .map(|name| ListedCheck::new(&runner, name, mode))
+                .collect();
+            (title, checks)
+        })
+        .collect();
+
+    let listed = sections.iter().flat_map(|(_, checks)| checks);
+    let widths = (
+        listed
+            .clone()
+            .map(ListedCheck::label_width)
+            .max()
+            .unwrap_or_default(),
+        listed
+            .map(|c| c.short_command().chars().count())
+            .max()
+            .unwrap_or_default(),
+    );
+
+    for (i, (title, checks)) in sections.iter().enumerate() {
+        if i > 0 {
+            eprintln!();
+        }
+        eprintln!("{}", style(title).bold());
+        for check in checks {
+            print_check(runner.config(), check, widths, verbose);
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints a check's details in columns `widths` (name, command) wide.
+fn print_check(config: &Config, listed: &ListedCheck, widths: (usize, usize), verbose: bool) {
+    let check = config.checks.get(&listed.name);
+    let description = check
+        .map(|c| c.description.as_str())
+        .filter(|d| !d.is_empty())
+        .unwrap_or("(no description)");
+    let tags = check
+        .filter(|c| !c.tags.is_empty())
+        .map(|c| format!(" [{}]", c.tags.join(", ")))
+        .unwrap_or_default();
+    let mark = match (&listed.command, &listed.conditions) {
+        (None, _) => style("✗").red(),
+        (Some(_), Some(conditions)) if !conditions.enabled() => style("-").dim(),
+        (Some(_), _) => style("✓").green(),
+    };
+    let (name_width, command_width) = widths;
+
+    let padding = " ".repeat(name_width - listed.label_width());
+
+    eprintln!(
+        "  {mark} {}{}{padding}  {}  {description}{}",
+        style(&listed.name).cyan(),
+        style(&listed.aliases).dim(),
+        style(format!("{:<command_width$}", listed.short_command())).dim(),
+        style(tags).dim()
+    );
+
+    if !verbose {
+        return;
+    }
+    let indent = " ".repeat(name_width + 6);
+    if let Some(command) = &listed.command {
+        eprintln!("{indent}command: {command}");
+    }
+    for (key, value) in &listed.env {
+        eprintln!("{indent}env:     {key}={value}");
+    }
+    if let Some(conditions) = listed
+        .conditions
+        .as_ref()
+        .filter(|c| !c.outcomes.is_empty())
+    {
+        let combine = match conditions.r#match {
+            ConditionMatch::All => "all must hold",
+            ConditionMatch::Any => "any must hold",
+        };
+        eprintln!("{indent}enabled_if ({combine}):");
+        for outcome in &conditions.outcomes {
+            let mark = if outcome.met {
+                style("✓").green()
+            } else {
+                style("✗").red()
+            };
+            eprintln!("{indent}  {mark} {outcome}");
+        }
+    }
+}
+
+/// Explain a check: the command it runs, its env, timeouts and modes, and
+/// how its `enabled_if` conditions evaluate right now.
+pub fn explain(name: &str, mode: Option<&str>) -> Result<ExitCode> {
+    let config = Config::load_or_default()?;
+    let mode = match mode {
+        Some(m) => parse_mode(m)?,
+        None => Detector::new(&config).detect().mode,
+    };
+    let name = &config
+        .check_name(name)
+        .ok_or_else(|| Error::CheckNotFound {
+            name: name.to_string(),
+        })?
+        .to_string();
+    let check = config.checks[name].clone();
+
+    let mut modes = Vec::new();
+    if config.human.checks.iter().any(|c| c == name) {
+        modes.push("human");
+    }
+    if config.agent.checks.iter().any(|c| c == name) {
+        modes.extend(["agent", "ci"]);
+    }
+
+    let runner = Runner::new(config);
+    let resolved = runner.resolve_check(name, mode)?;
+    let human = runner.resolve_check(name, Mode::Human)?;
+    let agent = runner.resolve_check(name, Mode::Agent)?;
+
+    eprintln!("{}", style(name).cyan().bold());
+    if !check.description.is_empty() {
+        eprintln!("  {}", check.description);
+    }
+    if !check.aliases.is_empty() {
+        eprintln!("  aliases: {}", check.aliases.join(", "));
+    }
+    eprintln!("  command: {}", resolved.command);
+    for (key, value) in &resolved.env {
+        eprintln!("  env:     {key}={value}");
+    }
+    if let Some(shell) = &check.shell {
+        eprintln!("  shell:   {shell}");
+    }
+    eprintln!(
+        "  timeout: {} (human), {} (agent, ci)",
+        humantime::format_duration(human.timeout),
+        humantime::format_duration(agent.timeout)
+    );
+    if modes.is_empty() {
+        eprintln!("  modes:   none (not in [human] or [agent] checks)");
+    } else {
+        eprintln!("  modes:   {}", modes.join(", "));
+    }
+
+    eprintln!();
+    eprintln!("In {} mode:", mode.name());
+    print_conditions(name, &runner.explain_conditions(name, mode)?);
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Clears the check result cache.
+pub fn cache_clear() -> Result<ExitCode> {
+    let repo = GitRepo::discover()?;
+    let cleared = Cache::new(repo.git_dir().join(cache::CACHE_FILE_NAME)).clear()?;
+
+    if cleared {
+        eprintln!("{} Cleared check result cache", style("✓").green());
+    } else {
+        eprintln!("{} Check result cache is already empty", style("•").dim());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Diagnose the setup: git repository, hook, configuration and check tools.
+///
+/// Fails if the repo or config is unusable or a check would fail for lack of
+/// a tool; a missing hook or a tool whose check is skipped without it only
+/// warns.
+pub fn doctor() -> Result<ExitCode> {
+    let mut healthy = true;
+
+    let repo = GitRepo::discover();
+    match &repo {
+        Ok(repo) => {
+            eprintln!(
+                "{} Git repository at {}",
+                style("✓").green(),
+                repo.root().display()
+            );
+            let hook_path = repo.hook_path("pre-commit");
+            let installed = std::fs::read_to_string(&hook_path)
+                .is_ok_and(|content| content.contains(HOOK_MARKER))
+                || std::fs::read_to_string(chain_target(repo, "pre-commit"))
+                    .is_ok_and(|content| content.contains(CHAIN_MARKER));
+            if installed {
+                eprintln!("{} Hook installed", style("✓").green());
+            } else {
+                eprintln!(
+                    "{} Hook not installed at {} - run `apc install`",
+                    style("!").yellow(),
+                    hook_path.display()
+                );
+            }
+        },
+        Err(e) => {
+            eprintln!("{} {e}", style("✗").red());
+            healthy = false;
+        },
+    }
+
+    let path = match Config::find_config_file() {
+        Ok(path) => Some(path),
+        Err(Error::ConfigNotFound { .. }) => {
+            eprintln!(
+                "{} No {CONFIG_FILE_NAME} found, using defaults - run `apc init`",
+                style("!").yellow()
+            );
+            None
+        },
+        Err(e) => return Err(e),
+    };
+    let config = match Config::load_with_user_config(path.as_deref()) {
+        Ok(config) => {
+            if path.is_some() {
+                eprintln!("{} Configuration is valid", style("✓").green());
+            }
+            config
+        },
+        Err(e) => {
+            eprintln!("{} Configuration: {e}", style("✗").red());
+            return Ok(ExitCode::FAILURE);
+        },
+    };
+
+    let tools = doctor::tools(&config, repo.as_ref().ok());
+    if !tools.is_empty() {
+        let width = tools.iter().map(|t| t.tool.len()).max().unwrap_or_default();
+        eprintln!("\n{}", style("Tools:").bold());
+        for tool in tools {
+            let (mark, note) = match (tool.found, tool.required) {
+                (true, _) => (style("✓").green(), ""),
+                (false, true) => (style("✗").red(), " (missing)"),
+                (false, false) => (style("!").yellow(), " (missing, checks skipped)"),
+            };
+            healthy &= tool.found || !tool.required;
+            eprintln!(
+                "  {mark} {:<width$}  {}{note}",
+                tool.tool,
+                style(tool.checks.join(", ")).dim()
+            );
+        }
+    }
+
+    Ok(if healthy {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Show whether apc is set up here: config, hook, detected mode and check counts.
+pub fn status() -> Result<ExitCode> {
+    let config = match Config::find_config_file() {
+        Ok(path) => {
+            eprintln!("{} Config: {}", style("✓").green(), path.display());
+            Config::load_with_user_config(Some(&path))?
+        },
+        Err(Error::ConfigNotFound { .. }) => {
+            eprintln!(
+                "{} No {CONFIG_FILE_NAME} found, using defaults - run `apc init`",
+                style("!").yellow()
+            );
+            Config::load_with_user_config(None)?
+        },
+        Err(e) => return Err(e),
+    };
+    if let Some(user) = Config::user_config_file() {
+        eprintln!("{} User config: {}", style("•").cyan(), user.display());
+    }
+
+    match GitRepo::discover() {
+        Ok(repo) => print_hook_status(&repo),
+        Err(e) => eprintln!("{} {e}", style("✗").red()),
+    }
+
+    let detection = Detector::new(&config).detect();
+    eprintln!(
+        "{} Mode: {} ({})",
+        style("•").cyan(),
+        style(detection.mode.name()).bold(),
+        detection.reason
+    );
+    if let Some(agent) = detection.agent {
+        eprintln!("  Agent: {agent}");
+    }
+
+    let per_agent: usize = config
+        .agent
+        .per_agent
+        .values()
+        .map(|extra| extra.checks.len())
+        .sum();
+    let per_agent = if per_agent == 0 {
+        String::new()
+    } else {
+        format!(" (+{per_agent} per-agent)")
+    };
+    eprintln!(
+        "{} Checks: {} human, {} agent{per_agent}, {} commit-msg",
+        style("•").cyan(),
+        config.human.checks.len(),
+        config.agent.checks.len(),
+        config.commit_msg.checks.len()
+    );
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints whether the pre-commit hook is ours, chained, foreign or missing.
+fn print_hook_status(repo: &GitRepo) {
+    let hook_path = repo.hook_path("pre-commit");
+    let chained = chain_target(repo, "pre-commit");
+    let content = std::fs::read_to_string(&hook_path).ok();
+
+    if content.as_deref().is_some_and(|c| c.contains(HOOK_MARKER)) {
+        eprintln!(
+            "{} Hook installed at {}",
+            style("✓").green(),
+            hook_path.display()
+        );
+    } else if std::fs::read_to_string(&chained).is_ok_and(|c| c.contains(CHAIN_MARKER)) {
+        eprintln!(
+            "{} Hook chained into {}",
+            style("✓").green(),
+            chained.display()
+        );
+    } else if content.is_some() {
+        eprintln!(
+            "{} Hook at {} was not installed by agent-precommit",
+            style("!").yellow(),
+            hook_path.display()
+        );
+    } else {
+        eprintln!(
+            "{} Hook not installed at {} - run `apc install`",
+            style("!").yellow(),
+            hook_path.display()
+        );
+    }
+}
+
+/// Validate configuration.
+pub fn validate(schema: bool, strict: bool) -> Result<ExitCode> {
+    // Unknown keys often explain later validation errors, so report them first.
+    // Missing or unparseable files are reported by the load below.
+    if schema && !validate_schema().unwrap_or(true) {
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let loaded = if strict {
+        Config::load_strict()
+    } else {
+        Config::load()
+    };
+
+    match loaded {
         Ok(config) => match config.validate() {
             Ok(()) => {
                 eprintln!("{} Configuration is valid", style("✓").green());
@@ -367,6 +1793,132 @@ pub fn validate() -> Result<ExitCode> {
     }
 }
 
+/// Checks the config file for keys not in the schema. Returns false if any exist.
+fn validate_schema() -> Result<bool> {
+    let path = Config::find_config_file()?;
+    let content = std::fs::read_to_string(&path).map_err(|e| Error::io("read config", e))?;
+    let unknown = Config::unknown_keys_in(&path, &content)?;
+
+    if unknown.is_empty() {
+        return Ok(true);
+    }
+
+    eprintln!(
+        "{} Configuration does not match schema ({} unknown key(s)):",
+        style("✗").red(),
+        unknown.len()
+    );
+    for key in &unknown {
+        eprintln!("  {} {key}", style("unknown key:").yellow());
+    }
+    Ok(false)
+}
+
+/// Validate a commit message file (the commit-msg hook's argument).
+pub fn commit_msg(file: &Path) -> Result<ExitCode> {
+    if std::env::var("APC_SKIP").ok().as_deref() == Some("1") {
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let config = Config::load_or_default()?;
+    if conventional_ok(&config, file)? {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Validates a commit message file against `[commit_msg.conventional]`,
+/// printing any violations. Returns true if the message passes or no
+/// convention is configured.
+fn conventional_ok(config: &Config, file: &Path) -> Result<bool> {
+    let Some(conventional) = &config.commit_msg.conventional else {
+        return Ok(true);
+    };
+
+    let message = std::fs::read_to_string(file).map_err(|e| Error::io("read commit message", e))?;
+    let violations = conventional::validate(&message, conventional);
+    if violations.is_empty() {
+        return Ok(true);
+    }
+
+    eprintln!(
+        "{} Commit message is not a Conventional Commit:",
+        style("✗").red()
+    );
+    for violation in &violations {
+        eprintln!("  {violation}");
+    }
+    Ok(false)
+}
+
+/// Show how the configuration differs from a preset.
+pub fn diff_config(preset: &str) -> Result<ExitCode> {
+    let config = Config::load()?;
+    let diff = ConfigDiff::between(&Config::for_preset(preset), &config);
+
+    if diff.is_empty() {
+        eprintln!(
+            "{} No differences from preset '{preset}'",
+            style("✓").green()
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    eprintln!("Differences from preset '{preset}':");
+    print_list_diff("human.checks", &diff.human_checks);
+    print_list_diff("agent.checks", &diff.agent_checks);
+
+    if !(diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty()) {
+        eprintln!();
+        eprintln!("{}", style("[checks]").bold());
+        for name in &diff.added {
+            eprintln!("  {} {name}", style("+").green());
+        }
+        for name in &diff.removed {
+            eprintln!("  {} {name}", style("-").red());
+        }
+        for change in &diff.changed {
+            eprintln!("  {} {}", style("~").yellow(), change.name);
+            for field in &change.fields {
+                eprintln!(
+                    "      {}: {} -> {}",
+                    field.field,
+                    field.base.as_deref().unwrap_or("(unset)"),
+                    field.current.as_deref().unwrap_or("(unset)")
+                );
+            }
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints the names added to and removed from a mode's check list.
+fn print_list_diff(label: &str, diff: &ListDiff) {
+    if diff.is_empty() {
+        return;
+    }
+    eprintln!();
+    eprintln!("{}", style(label).bold());
+    for name in &diff.added {
+        eprintln!("  {} {name}", style("+").green());
+    }
+    for name in &diff.removed {
+        eprintln!("  {} {name}", style("-").red());
+    }
+}
+
+/// Print the configuration JSON Schema.
+pub fn schema() -> Result<ExitCode> {
+    let schema =
+        serde_json::to_string_pretty(&Config::json_schema()).map_err(|e| Error::Internal {
+            message: format!("Failed to serialize schema: {e}"),
+        })?;
+    println!("{schema}");
+    Ok(ExitCode::SUCCESS)
+}
+
 /// Show configuration.
 pub fn config(raw: bool) -> Result<ExitCode> {
     match Config::find_config_file() {
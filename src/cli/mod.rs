@@ -8,12 +8,16 @@
 //! - `detect`: Show detected mode
 //! - `list`: List configured checks
 //! - `validate`: Validate configuration
+//! - `schema`: Print the configuration JSON Schema
 
 mod commands;
 
 use crate::core::error::Result;
 use clap::{Parser, Subcommand};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 use std::process::ExitCode;
+use std::time::Duration;
 use tracing_subscriber::EnvFilter;
 
 /// Smart pre-commit hooks for humans and AI coding agents.
@@ -39,6 +43,7 @@ Environment variables:
   APC_MODE=human|agent|ci   Force a specific mode
   AGENT_MODE=1              Trigger agent mode
   APC_SKIP=1                Skip all checks
+  APC_SKIP_CHECKS=a,b       Skip only the named checks
 "#,
     propagate_version = true
 )]
@@ -74,48 +79,70 @@ pub enum ColorChoice {
 
 /// Available subcommands.
 #[derive(Debug, Subcommand)]
+#[allow(clippy::large_enum_variant)] // parsed once per process
 pub enum Commands {
     /// Initialize agent-precommit configuration.
     #[command(visible_alias = "i")]
     Init {
         /// Use a preset configuration.
-        #[arg(short, long, value_parser = ["python", "node", "rust", "go"])]
+        #[arg(short, long, value_parser = ["python", "node", "rust", "go", "make", "lua", "ruby", "java", "deno", "php"])]
         preset: Option<String>,
 
+        /// Use a shared preset file: a full config, or a map of checks to run in agent mode.
+        #[arg(long, value_name = "PATH", conflicts_with = "preset")]
+        preset_file: Option<PathBuf>,
+
         /// Overwrite existing configuration.
         #[arg(short, long)]
         force: bool,
+
+        /// Add the preset's checks to the existing configuration instead of replacing it.
+        #[arg(long, conflicts_with = "force")]
+        merge: bool,
+
+        /// With --merge, replace existing checks that share a name with the preset's.
+        #[arg(long, requires = "merge")]
+        prefer_preset: bool,
     },
 
-    /// Install the git pre-commit hook.
+    /// Install a git hook (pre-commit by default).
     Install {
         /// Overwrite existing hook.
         #[arg(short, long)]
         force: bool,
-    },
-
-    /// Remove the git pre-commit hook.
-    Uninstall,
 
-    /// Run checks manually.
-    #[command(visible_alias = "r")]
-    Run {
-        /// Force a specific mode.
-        #[arg(short, long, value_parser = ["human", "agent", "ci"])]
-        mode: Option<String>,
+        /// Add an `apc run` line to the existing hook (e.g. Husky's or
+        /// Lefthook's) instead of replacing it.
+        #[arg(long, conflicts_with = "force")]
+        chain: bool,
 
-        /// Run only a specific check.
-        #[arg(short, long)]
-        check: Option<String>,
+        /// Hook to install.
+        #[arg(long, default_value = "pre-commit", value_parser = ["pre-commit", "commit-msg"])]
+        hook: String,
+    },
 
-        /// Run all checks regardless of conditions.
+    /// Remove a git hook installed by `apc install`.
+    Uninstall {
+        /// Put back the hook `apc install --force` backed up.
         #[arg(long)]
-        all: bool,
+        restore: bool,
+
+        /// Hook to remove.
+        #[arg(long, default_value = "pre-commit", value_parser = ["pre-commit", "commit-msg"])]
+        hook: String,
     },
 
+    /// Run checks manually.
+    #[command(visible_alias = "r")]
+    Run(RunArgs),
+
     /// Show the detected mode and reasoning.
     #[command(visible_alias = "d")]
-    Detect,
+    Detect {
+        /// Print the detection as JSON on stdout.
+        #[arg(long)]
+        json: bool,
+    },
 
     /// List all configured checks.
     #[command(visible_alias = "l")]
@@ -127,7 +154,61 @@ pub enum Commands {
 
     /// Validate the configuration file.
     #[command(visible_alias = "v")]
-    Validate,
+    Validate {
+        /// Also check the file against the JSON Schema, reporting unknown keys.
+        #[arg(long)]
+        schema: bool,
+
+        /// Fail if the file contains keys that lenient loading would ignore.
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// Print the JSON Schema for the configuration file.
+    Schema,
+
+    /// Compare the configuration's checks against a preset.
+    DiffConfig {
+        /// Preset to compare against.
+        #[arg(short, long, value_parser = ["python", "node", "rust", "go", "make", "lua", "ruby", "java", "deno", "php"])]
+        preset: String,
+    },
+
+    /// Validate a commit message against `[commit_msg.conventional]`.
+    CommitMsg {
+        /// Message file passed by git to the commit-msg hook.
+        file: PathBuf,
+    },
+
+    /// Check that the repo, hook, config and check tools are ready.
+    Doctor,
+
+    /// Show the active config, hook install state, detected mode and check counts.
+    Status,
+
+    /// List the built-in presets and the checks each configures.
+    Presets {
+        /// Print the presets as JSON on stdout.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Manage the check result cache.
+    Cache {
+        /// Cache action.
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+
+    /// Show how a check resolves: its command, conditions, timeouts and modes.
+    Explain {
+        /// Name of the check.
+        check: String,
+
+        /// Evaluate conditions for this mode instead of the detected one.
+        #[arg(short, long, value_parser = ["human", "agent", "ci"])]
+        mode: Option<String>,
+    },
 
     /// Show configuration file location and contents.
     Config {
@@ -144,6 +225,183 @@ pub enum Commands {
     },
 }
 
+/// Actions of the `cache` subcommand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Subcommand)]
+pub enum CacheCommand {
+    /// Delete every cached check result.
+    Clear,
+}
+
+/// Arguments for the `run` subcommand.
+#[derive(Debug, Default, clap::Args)]
+#[allow(clippy::struct_excessive_bools)] // independent CLI flags
+pub struct RunArgs {
+    /// Force a specific mode.
+    #[arg(short, long, value_parser = ["human", "agent", "ci"])]
+    pub mode: Option<String>,
+
+    /// Run only a specific check.
+    #[arg(short, long)]
+    pub check: Option<String>,
+
+    /// Run several modes in sequence and report them together (e.g. human,agent).
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = ["human", "agent", "ci"],
+        conflicts_with_all = ["mode", "check"]
+    )]
+    pub mode_matrix: Vec<String>,
+
+    /// Run only these checks from the mode's list (comma-separated or repeated).
+    ///
+    /// Unlike `--check`, the checks keep the mode's timeout and grouping.
+    #[arg(long, value_delimiter = ',', conflicts_with = "check")]
+    pub only: Vec<String>,
+
+    /// Run every check with one of these tags, whatever its mode (comma-separated or repeated).
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["check", "only", "mode_matrix"])]
+    pub tag: Vec<String>,
+
+    /// Explain which of this check's `enabled_if` conditions fail, without running anything.
+    #[arg(
+        long,
+        value_name = "CHECK",
+        conflicts_with_all = ["check", "only", "tag", "mode_matrix"]
+    )]
+    pub explain_skip: Option<String>,
+
+    /// Show which checks would run or be skipped, and their commands, without running them.
+    #[arg(long, conflicts_with_all = ["explain_skip", "mode_matrix"])]
+    pub dry_run: bool,
+
+    /// Print the waves checks would run in, in order, without running them.
+    #[arg(
+        long,
+        visible_alias = "list-only",
+        conflicts_with_all = ["check", "explain_skip", "mode_matrix", "dry_run"]
+    )]
+    pub plan: bool,
+
+    /// Run checks even if the index has unresolved merge conflicts.
+    #[arg(long)]
+    pub force: bool,
+
+    /// Run the checks of a git hook other than pre-commit (`commit-msg` runs `[commit_msg].checks`).
+    #[arg(
+        long,
+        value_parser = ["commit-msg"],
+        requires = "message_file",
+        conflicts_with_all = ["check", "only", "tag", "mode_matrix", "explain_skip", "dry_run", "plan"]
+    )]
+    pub hook: Option<String>,
+
+    /// Commit message file, passed to every check as `APC_COMMIT_MSG_FILE`.
+    #[arg(long, value_name = "PATH")]
+    pub message_file: Option<PathBuf>,
+
+    /// Run all checks regardless of conditions.
+    #[arg(long)]
+    pub all: bool,
+
+    /// Override the mode and per-check timeouts for this run (e.g. 30m).
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// Run at most this many checks at once, overriding `[human]`/`[agent].max_parallel`.
+    #[arg(long)]
+    pub max_parallel: Option<NonZeroUsize>,
+
+    /// Run at most this many checks of a parallel group at once, unless the group sets `max_parallel`.
+    #[arg(long)]
+    pub group_parallel: Option<NonZeroUsize>,
+
+    /// If a check fails, cancel the rest of its parallel group but keep running later groups.
+    #[arg(long)]
+    pub fail_fast_group: bool,
+
+    /// Randomize the order of checks within each parallel stage, to expose hidden ordering dependencies.
+    #[arg(long)]
+    pub shuffle: bool,
+
+    /// Seed for `--shuffle`, to replay a run's order (default: random, and printed).
+    #[arg(long, requires = "shuffle")]
+    pub seed: Option<u64>,
+
+    /// Scope `{files}` to changes since the merge-base with the default branch.
+    ///
+    /// Falls back to staged files when no default branch can be resolved.
+    #[arg(long)]
+    pub since_ref_auto: bool,
+
+    /// Scope `{files}` to the newline-separated paths on stdin (relative to the repo root).
+    #[arg(long, conflicts_with = "since_ref_auto")]
+    pub from_stdin_files: bool,
+
+    /// Scope `{files}` to changes since the merge-base with this ref, and skip checks
+    /// whose `paths` match none of them, in any mode.
+    #[arg(long, value_name = "REF", conflicts_with_all = ["since_ref_auto", "from_stdin_files"])]
+    pub changed_since: Option<String>,
+
+    /// Echo check output live (prefixed with the check name) while still capturing it.
+    #[arg(long)]
+    pub stream: bool,
+
+    /// Print the run result as JSON on stdout.
+    #[arg(long)]
+    pub json: bool,
+
+    /// Print the run result as pretty-printed JSON on stdout (implies --json).
+    #[arg(long)]
+    pub output_json_pretty: bool,
+
+    /// Include mode detection and repo details in the JSON report (implies --json).
+    #[arg(long, conflicts_with = "mode_matrix")]
+    pub json_context: bool,
+
+    /// Emit a report of the whole run in this format, on stdout or to `--report-file`.
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_parser = ["json", "junit", "github", "sarif"],
+        conflicts_with = "mode_matrix"
+    )]
+    pub report: Option<String>,
+
+    /// Write the `--report` output to this file instead of stdout.
+    ///
+    /// `{date}`, `{time}`, `{mode}`, `{branch}` and `{sha}` in the path are
+    /// expanded, e.g. `reports/{date}-{branch}.xml`.
+    #[arg(long, value_name = "PATH", requires = "report")]
+    pub report_file: Option<PathBuf>,
+
+    /// Write Prometheus metrics for the run to this file, for node_exporter's textfile collector.
+    #[arg(long, value_name = "PATH", conflicts_with = "mode_matrix")]
+    pub metrics_textfile: Option<PathBuf>,
+
+    /// On SIGINT/SIGTERM, kill running checks and report partial results (exit 130).
+    #[arg(long)]
+    pub abort_on_signal: bool,
+
+    /// Print a table of every check with its status and duration, even on success.
+    #[arg(long)]
+    pub print_summary_table: bool,
+
+    /// Lines of each failed check's output to print in the summary (0 = all).
+    ///
+    /// Overrides `[human]`/`[agent].output_lines` (default: 20).
+    #[arg(long, value_name = "N")]
+    pub output_lines: Option<usize>,
+
+    /// Leave skipped checks out of per-check listings; the summary still counts them.
+    #[arg(long)]
+    pub quiet_skips: bool,
+
+    /// List the slowest checks with their share of the run time (on with --verbose).
+    #[arg(long)]
+    pub timings: bool,
+}
+
 /// Runs the CLI.
 pub async fn run() -> Result<ExitCode> {
     let cli = Cli::parse();
@@ -156,21 +414,44 @@ pub async fn run() -> Result<ExitCode> {
 
     // If no subcommand, run the default action (same as `apc run`)
     match cli.command {
-        Some(Commands::Init { preset, force }) => commands::init(preset.as_deref(), force),
-        Some(Commands::Install { force }) => commands::install(force),
-        Some(Commands::Uninstall) => commands::uninstall(),
-        Some(Commands::Run { mode, check, all }) => {
-            commands::run(mode.as_deref(), check.as_deref(), all).await
+        Some(Commands::Init {
+            preset,
+            preset_file,
+            force,
+            merge,
+            prefer_preset,
+        }) => commands::init(
+            preset.as_deref(),
+            preset_file.as_deref(),
+            force,
+            merge,
+            prefer_preset,
+        ),
+        Some(Commands::Install { force, chain, hook }) => commands::install(force, chain, &hook),
+        Some(Commands::Uninstall { restore, hook }) => commands::uninstall(restore, &hook),
+        Some(Commands::Run(mut args)) => {
+            args.timings |= cli.verbose;
+            commands::run(&args, cli.quiet).await
         },
-        Some(Commands::Detect) => commands::detect(),
-        Some(Commands::List { mode }) => commands::list(mode.as_deref()),
-        Some(Commands::Validate) => commands::validate(),
+        Some(Commands::Detect { json }) => commands::detect(json),
+        Some(Commands::List { mode }) => commands::list(mode.as_deref(), cli.verbose),
+        Some(Commands::Validate { schema, strict }) => commands::validate(schema, strict),
+        Some(Commands::Schema) => commands::schema(),
+        Some(Commands::DiffConfig { preset }) => commands::diff_config(&preset),
+        Some(Commands::CommitMsg { file }) => commands::commit_msg(&file),
+        Some(Commands::Doctor) => commands::doctor(),
+        Some(Commands::Status) => commands::status(),
+        Some(Commands::Presets { json }) => commands::presets(json),
+        Some(Commands::Cache {
+            action: CacheCommand::Clear,
+        }) => commands::cache_clear(),
+        Some(Commands::Explain { check, mode }) => commands::explain(&check, mode.as_deref()),
         Some(Commands::Config { raw }) => commands::config(raw),
         Some(Commands::Completions { shell }) => {
             commands::completions(shell);
             Ok(ExitCode::SUCCESS)
         },
-        None => commands::run(None, None, false).await,
+        None => commands::run(&RunArgs::default(), cli.quiet).await,
     }
 }
 
@@ -238,7 +519,10 @@ mod tests {
             cli.command,
             Some(Commands::Init {
                 preset: None,
-                force: false
+                preset_file: None,
+                force: false,
+                merge: false,
+                prefer_preset: false,
             })
         ));
     }
@@ -250,7 +534,10 @@ mod tests {
             cli.command,
             Some(Commands::Init {
                 preset: Some(_),
-                force: false
+                preset_file: None,
+                force: false,
+                merge: false,
+                prefer_preset: false,
             })
         ));
     }
@@ -262,7 +549,10 @@ mod tests {
             cli.command,
             Some(Commands::Init {
                 preset: None,
-                force: true
+                preset_file: None,
+                force: true,
+                merge: false,
+                prefer_preset: false,
             })
         ));
     }
@@ -275,9 +565,53 @@ mod tests {
             cli.command,
             Some(Commands::Init {
                 preset: Some(_),
-                force: true
+                preset_file: None,
+                force: true,
+                merge: false,
+                prefer_preset: false,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_init_with_preset_file() {
+        let cli =
+            Cli::try_parse_from(["apc", "init", "--preset-file", "company.toml"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Init {
+                preset: None,
+                preset_file: Some(ref path),
+                ..
+            }) if path == &PathBuf::from("company.toml")
+        ));
+        assert!(Cli::try_parse_from([
+            "apc",
+            "init",
+            "--preset",
+            "rust",
+            "--preset-file",
+            "company.toml"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_init_merge() {
+        let cli =
+            Cli::try_parse_from(["apc", "init", "--preset", "rust", "--merge"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Init {
+                merge: true,
+                prefer_preset: false,
+                ..
             })
         ));
+        assert!(
+            Cli::try_parse_from(["apc", "init", "--preset", "rust", "--prefer-preset"]).is_err()
+        );
+        assert!(Cli::try_parse_from(["apc", "init", "--merge", "--force"]).is_err());
     }
 
     #[test]
@@ -297,7 +631,7 @@ mod tests {
         let cli = Cli::try_parse_from(["apc", "install"]).expect("parse");
         assert!(matches!(
             cli.command,
-            Some(Commands::Install { force: false })
+            Some(Commands::Install { force: false, ref hook, .. }) if hook == "pre-commit"
         ));
     }
 
@@ -306,14 +640,151 @@ mod tests {
         let cli = Cli::try_parse_from(["apc", "install", "--force"]).expect("parse");
         assert!(matches!(
             cli.command,
-            Some(Commands::Install { force: true })
+            Some(Commands::Install { force: true, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_install_with_chain() {
+        let cli = Cli::try_parse_from(["apc", "install", "--chain"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Install { chain: true, .. })
         ));
+
+        assert!(Cli::try_parse_from(["apc", "install", "--chain", "--force"]).is_err());
     }
 
     #[test]
     fn test_parse_uninstall() {
         let cli = Cli::try_parse_from(["apc", "uninstall"]).expect("parse");
-        assert!(matches!(cli.command, Some(Commands::Uninstall)));
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Uninstall { restore: false, ref hook }) if hook == "pre-commit"
+        ));
+    }
+
+    #[test]
+    fn test_parse_uninstall_with_restore() {
+        let cli = Cli::try_parse_from(["apc", "uninstall", "--restore"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Uninstall { restore: true, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_run_shuffle_seed() {
+        let cli = Cli::try_parse_from(["apc", "run", "--shuffle", "--seed", "42"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs {
+                shuffle: true,
+                seed: Some(42),
+                ..
+            }))
+        ));
+
+        assert!(Cli::try_parse_from(["apc", "run", "--seed", "42"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_commit_msg_hook() {
+        let cli = Cli::try_parse_from(["apc", "install", "--hook", "commit-msg"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Install { force: false, ref hook, .. }) if hook == "commit-msg"
+        ));
+
+        let cli = Cli::try_parse_from([
+            "apc",
+            "run",
+            "--hook",
+            "commit-msg",
+            "--message-file",
+            ".git/COMMIT_EDITMSG",
+        ])
+        .expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs { hook: Some(ref hook), message_file: Some(ref file), .. }))
+                if hook == "commit-msg" && file.ends_with("COMMIT_EDITMSG")
+        ));
+
+        assert!(Cli::try_parse_from(["apc", "run", "--hook", "commit-msg"]).is_err());
+        assert!(Cli::try_parse_from(["apc", "install", "--hook", "post-commit"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_explain() {
+        let cli =
+            Cli::try_parse_from(["apc", "explain", "lint", "--mode", "agent"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Explain { ref check, ref mode })
+                if check == "lint" && mode.as_deref() == Some("agent")
+        ));
+        assert!(Cli::try_parse_from(["apc", "explain"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_run_plan() {
+        let cli = Cli::try_parse_from(["apc", "run", "--plan"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs { plan: true, .. }))
+        ));
+        let cli = Cli::try_parse_from(["apc", "run", "--list-only"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs { plan: true, .. }))
+        ));
+        assert!(Cli::try_parse_from(["apc", "run", "--plan", "--dry-run"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_run_dry_run() {
+        let cli = Cli::try_parse_from(["apc", "run", "--dry-run", "--tag", "fast"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs { dry_run: true, ref tag, .. })) if tag == &["fast"]
+        ));
+        assert!(
+            Cli::try_parse_from(["apc", "run", "--dry-run", "--explain-skip", "lint"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_doctor() {
+        let cli = Cli::try_parse_from(["apc", "doctor"]).expect("parse");
+        assert!(matches!(cli.command, Some(Commands::Doctor)));
+    }
+
+    #[test]
+    fn test_parse_presets() {
+        let cli = Cli::try_parse_from(["apc", "presets", "--json"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Presets { json: true })
+        ));
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let cli = Cli::try_parse_from(["apc", "status"]).expect("parse");
+        assert!(matches!(cli.command, Some(Commands::Status)));
+    }
+
+    #[test]
+    fn test_parse_cache_clear() {
+        let cli = Cli::try_parse_from(["apc", "cache", "clear"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Cache {
+                action: CacheCommand::Clear
+            })
+        ));
+        assert!(Cli::try_parse_from(["apc", "cache"]).is_err());
     }
 
     #[test]
@@ -321,20 +792,178 @@ mod tests {
         let cli = Cli::try_parse_from(["apc", "run"]).expect("parse");
         assert!(matches!(
             cli.command,
-            Some(Commands::Run {
+            Some(Commands::Run(RunArgs {
                 mode: None,
                 check: None,
-                all: false
-            })
+                ref only,
+                ref mode_matrix,
+                ref tag,
+                explain_skip: None,
+                dry_run: false,
+                plan: false,
+                force: false,
+                hook: None,
+                message_file: None,
+                all: false,
+                timeout: None,
+                max_parallel: None,
+                group_parallel: None,
+                fail_fast_group: false,
+                shuffle: false,
+                seed: None,
+                since_ref_auto: false,
+                from_stdin_files: false,
+                changed_since: None,
+                stream: false,
+                json: false,
+                output_json_pretty: false,
+                json_context: false,
+                report: None,
+                report_file: None,
+                metrics_textfile: None,
+                abort_on_signal: false,
+                print_summary_table: false,
+                output_lines: None,
+                quiet_skips: false,
+                timings: false,
+            })) if only.is_empty() && mode_matrix.is_empty() && tag.is_empty()
+        ));
+    }
+
+    #[test]
+    fn test_parse_run_only() {
+        let cli = Cli::try_parse_from(["apc", "run", "--only", "lint,test", "--only", "build"])
+            .expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs { ref only, .. })) if only == &["lint", "test", "build"]
+        ));
+    }
+
+    #[test]
+    fn test_parse_run_max_parallel() {
+        let cli = Cli::try_parse_from(["apc", "run", "--max-parallel", "3"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs {
+                max_parallel: Some(n),
+                ..
+            })) if n.get() == 3
+        ));
+        assert!(Cli::try_parse_from(["apc", "run", "--max-parallel", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_run_report() {
+        let cli = Cli::try_parse_from([
+            "apc",
+            "run",
+            "--report",
+            "json",
+            "--report-file",
+            "report.json",
+        ])
+        .expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs {
+                ref report,
+                ref report_file,
+                ..
+            })) if report.as_deref() == Some("json")
+                && report_file.as_deref() == Some(std::path::Path::new("report.json"))
+        ));
+
+        let cli = Cli::try_parse_from(["apc", "run", "--report", "junit"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs { ref report, .. })) if report.as_deref() == Some("junit")
+        ));
+
+        assert!(Cli::try_parse_from(["apc", "run", "--report", "xml"]).is_err());
+        assert!(Cli::try_parse_from(["apc", "run", "--report-file", "report.json"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_run_explain_skip() {
+        let cli = Cli::try_parse_from(["apc", "run", "--explain-skip", "lint"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs { ref explain_skip, .. })) if explain_skip.as_deref() == Some("lint")
+        ));
+
+        let result = Cli::try_parse_from(["apc", "run", "--explain-skip", "lint", "--check", "x"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_run_metrics_textfile() {
+        let cli = Cli::try_parse_from(["apc", "run", "--metrics-textfile", "/var/lib/apc.prom"])
+            .expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs {
+                ref metrics_textfile,
+                ..
+            })) if metrics_textfile.as_deref() == Some(std::path::Path::new("/var/lib/apc.prom"))
         ));
     }
 
+    #[test]
+    fn test_parse_run_from_stdin_files() {
+        let cli = Cli::try_parse_from(["apc", "run", "--from-stdin-files"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs {
+                from_stdin_files: true,
+                ..
+            }))
+        ));
+
+        let result = Cli::try_parse_from(["apc", "run", "--from-stdin-files", "--since-ref-auto"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_run_fail_fast_group() {
+        let cli = Cli::try_parse_from(["apc", "run", "--fail-fast-group"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs {
+                fail_fast_group: true,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_parse_run_mode_matrix() {
+        let cli =
+            Cli::try_parse_from(["apc", "run", "--mode-matrix", "human,agent"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs { ref mode_matrix, .. })) if mode_matrix == &["human", "agent"]
+        ));
+
+        assert!(Cli::try_parse_from(["apc", "run", "--mode-matrix", "robot"]).is_err());
+        assert!(
+            Cli::try_parse_from(["apc", "run", "--mode-matrix", "human", "--mode", "agent"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_run_only_conflicts_with_check() {
+        let result = Cli::try_parse_from(["apc", "run", "--only", "lint", "--check", "test"]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_parse_run_with_mode() {
         let cli = Cli::try_parse_from(["apc", "run", "--mode", "human"]).expect("parse");
         assert!(matches!(
             cli.command,
-            Some(Commands::Run { mode: Some(_), .. })
+            Some(Commands::Run(RunArgs { mode: Some(_), .. }))
         ));
     }
 
@@ -343,7 +972,7 @@ mod tests {
         let cli = Cli::try_parse_from(["apc", "run", "--mode", "agent"]).expect("parse");
         assert!(matches!(
             cli.command,
-            Some(Commands::Run { mode: Some(_), .. })
+            Some(Commands::Run(RunArgs { mode: Some(_), .. }))
         ));
     }
 
@@ -352,7 +981,7 @@ mod tests {
         let cli = Cli::try_parse_from(["apc", "run", "--mode", "ci"]).expect("parse");
         assert!(matches!(
             cli.command,
-            Some(Commands::Run { mode: Some(_), .. })
+            Some(Commands::Run(RunArgs { mode: Some(_), .. }))
         ));
     }
 
@@ -367,32 +996,122 @@ mod tests {
         let cli = Cli::try_parse_from(["apc", "run", "--check", "lint"]).expect("parse");
         assert!(matches!(
             cli.command,
-            Some(Commands::Run { check: Some(_), .. })
+            Some(Commands::Run(RunArgs { check: Some(_), .. }))
         ));
     }
 
     #[test]
     fn test_parse_run_with_all() {
         let cli = Cli::try_parse_from(["apc", "run", "--all"]).expect("parse");
-        assert!(matches!(cli.command, Some(Commands::Run { all: true, .. })));
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs { all: true, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_parse_run_with_since_ref_auto() {
+        let cli = Cli::try_parse_from(["apc", "run", "--since-ref-auto"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs {
+                since_ref_auto: true,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_parse_run_with_changed_since() {
+        let cli =
+            Cli::try_parse_from(["apc", "run", "--changed-since", "origin/main"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs {
+                changed_since: Some(ref rev),
+                ..
+            })) if rev == "origin/main"
+        ));
+
+        assert!(
+            Cli::try_parse_from(["apc", "run", "--changed-since", "main", "--since-ref-auto"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_run_output_lines() {
+        let cli = Cli::try_parse_from(["apc", "run", "--output-lines", "0"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs {
+                output_lines: Some(0),
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_parse_run_with_json() {
+        let cli = Cli::try_parse_from(["apc", "run", "--json"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs { json: true, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_parse_run_with_output_json_pretty() {
+        let cli = Cli::try_parse_from(["apc", "run", "--output-json-pretty"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs {
+                output_json_pretty: true,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_parse_run_with_abort_on_signal() {
+        let cli = Cli::try_parse_from(["apc", "run", "--abort-on-signal"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs {
+                abort_on_signal: true,
+                ..
+            }))
+        ));
     }
 
     #[test]
     fn test_parse_run_alias() {
         let cli = Cli::try_parse_from(["apc", "r"]).expect("parse run alias");
-        assert!(matches!(cli.command, Some(Commands::Run { .. })));
+        assert!(matches!(cli.command, Some(Commands::Run(_))));
     }
 
     #[test]
     fn test_parse_detect() {
         let cli = Cli::try_parse_from(["apc", "detect"]).expect("parse");
-        assert!(matches!(cli.command, Some(Commands::Detect)));
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Detect { json: false })
+        ));
+    }
+
+    #[test]
+    fn test_parse_detect_json() {
+        let cli = Cli::try_parse_from(["apc", "detect", "--json"]).expect("parse");
+        assert!(matches!(cli.command, Some(Commands::Detect { json: true })));
     }
 
     #[test]
     fn test_parse_detect_alias() {
         let cli = Cli::try_parse_from(["apc", "d"]).expect("parse detect alias");
-        assert!(matches!(cli.command, Some(Commands::Detect)));
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Detect { json: false })
+        ));
     }
 
     #[test]
@@ -410,6 +1129,13 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_parse_list_verbose() {
+        let cli = Cli::try_parse_from(["apc", "list", "--verbose"]).expect("parse");
+        assert!(cli.verbose);
+        assert!(matches!(cli.command, Some(Commands::List { .. })));
+    }
+
     #[test]
     fn test_parse_list_alias() {
         let cli = Cli::try_parse_from(["apc", "l"]).expect("parse list alias");
@@ -419,13 +1145,104 @@ mod tests {
     #[test]
     fn test_parse_validate() {
         let cli = Cli::try_parse_from(["apc", "validate"]).expect("parse");
-        assert!(matches!(cli.command, Some(Commands::Validate)));
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Validate {
+                schema: false,
+                strict: false
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_run_tag() {
+        let cli = Cli::try_parse_from(["apc", "run", "--tag", "fast,lint", "--tag", "docs"])
+            .expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs { ref tag, .. })) if tag == &["fast", "lint", "docs"]
+        ));
+
+        assert!(Cli::try_parse_from(["apc", "run", "--tag", "fast", "--check", "lint"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_run_group_parallel() {
+        let cli = Cli::try_parse_from(["apc", "run", "--group-parallel", "2"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs { group_parallel: Some(n), .. })) if n.get() == 2
+        ));
+
+        assert!(Cli::try_parse_from(["apc", "run", "--group-parallel", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_run_timeout() {
+        let cli = Cli::try_parse_from(["apc", "run", "--timeout", "30m"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Run(RunArgs { timeout: Some(t), .. })) if t == Duration::from_secs(1800)
+        ));
+
+        let err =
+            Cli::try_parse_from(["apc", "run", "--timeout", "soon"]).expect_err("bad duration");
+        assert!(err
+            .to_string()
+            .contains("invalid value 'soon' for '--timeout <TIMEOUT>'"));
+    }
+
+    #[test]
+    fn test_parse_diff_config() {
+        let cli = Cli::try_parse_from(["apc", "diff-config", "--preset", "rust"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::DiffConfig { ref preset }) if preset == "rust"
+        ));
+
+        assert!(Cli::try_parse_from(["apc", "diff-config"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_commit_msg() {
+        let cli = Cli::try_parse_from(["apc", "commit-msg", ".git/COMMIT_EDITMSG"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::CommitMsg { ref file }) if file.ends_with("COMMIT_EDITMSG")
+        ));
     }
 
     #[test]
     fn test_parse_validate_alias() {
         let cli = Cli::try_parse_from(["apc", "v"]).expect("parse validate alias");
-        assert!(matches!(cli.command, Some(Commands::Validate)));
+        assert!(matches!(cli.command, Some(Commands::Validate { .. })));
+    }
+
+    #[test]
+    fn test_parse_validate_schema() {
+        let cli = Cli::try_parse_from(["apc", "validate", "--schema"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Validate {
+                schema: true,
+                strict: false
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_validate_strict() {
+        let cli = Cli::try_parse_from(["apc", "validate", "--strict"]).expect("parse");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Validate { strict: true, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_schema() {
+        let cli = Cli::try_parse_from(["apc", "schema"]).expect("parse");
+        assert!(matches!(cli.command, Some(Commands::Schema)));
     }
 
     #[test]
@@ -539,7 +1356,9 @@ mod tests {
 
     #[test]
     fn test_all_valid_presets_accepted() {
-        for preset in ["python", "node", "rust", "go"] {
+        for preset in [
+            "python", "node", "rust", "go", "make", "lua", "ruby", "java", "deno", "php",
+        ] {
             let result = Cli::try_parse_from(["apc", "init", "--preset", preset]);
             assert!(result.is_ok(), "Preset '{}' should be accepted", preset);
         }
@@ -0,0 +1,207 @@
+//! Structural comparison between two configurations.
+//!
+//! Used by `apc diff-config` to show how a project's config has drifted from
+//! the preset it started from. Only the mode check lists and `[checks]` are
+//! compared; check definitions are compared field by field through their
+//! serialized TOML form.
+
+use super::{CheckConfig, Config};
+use std::collections::BTreeSet;
+
+/// Differences between a base configuration and the current one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigDiff {
+    /// Changes to `human.checks`.
+    pub human_checks: ListDiff,
+    /// Changes to `agent.checks`.
+    pub agent_checks: ListDiff,
+    /// Checks defined only in the current config.
+    pub added: Vec<String>,
+    /// Checks defined only in the base config.
+    pub removed: Vec<String>,
+    /// Checks defined in both but with different settings.
+    pub changed: Vec<CheckChange>,
+}
+
+impl ConfigDiff {
+    /// Compares `current` against `base`.
+    #[must_use]
+    pub fn between(base: &Config, current: &Config) -> Self {
+        let base_names: BTreeSet<&String> = base.checks.keys().collect();
+        let current_names: BTreeSet<&String> = current.checks.keys().collect();
+
+        let changed = base_names
+            .intersection(&current_names)
+            .filter_map(|name| {
+                let fields = field_changes(&base.checks[*name], &current.checks[*name]);
+                (!fields.is_empty()).then(|| CheckChange {
+                    name: (*name).clone(),
+                    fields,
+                })
+            })
+            .collect();
+
+        Self {
+            human_checks: ListDiff::between(&base.human.checks, &current.human.checks),
+            agent_checks: ListDiff::between(&base.agent.checks, &current.agent.checks),
+            added: current_names
+                .difference(&base_names)
+                .map(|n| (*n).clone())
+                .collect(),
+            removed: base_names
+                .difference(&current_names)
+                .map(|n| (*n).clone())
+                .collect(),
+            changed,
+        }
+    }
+
+    /// Returns true if the configurations match.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.human_checks.is_empty()
+            && self.agent_checks.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+/// Entries added to or removed from a list of check names.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListDiff {
+    /// Names only in the current list.
+    pub added: Vec<String>,
+    /// Names only in the base list.
+    pub removed: Vec<String>,
+}
+
+impl ListDiff {
+    fn between(base: &[String], current: &[String]) -> Self {
+        Self {
+            added: current
+                .iter()
+                .filter(|n| !base.contains(n))
+                .cloned()
+                .collect(),
+            removed: base
+                .iter()
+                .filter(|n| !current.contains(n))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Returns true if the lists hold the same names.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// A check whose definition differs between the configurations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckChange {
+    /// Name of the check.
+    pub name: String,
+    /// Fields that differ, in name order.
+    pub fields: Vec<FieldChange>,
+}
+
+/// One differing field of a check, with values rendered as TOML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    /// Field name (e.g. `run`).
+    pub field: String,
+    /// Value in the base config, if set.
+    pub base: Option<String>,
+    /// Value in the current config, if set.
+    pub current: Option<String>,
+}
+
+/// Compares two check definitions field by field.
+fn field_changes(base: &CheckConfig, current: &CheckConfig) -> Vec<FieldChange> {
+    let base = to_table(base);
+    let current = to_table(current);
+    let fields: BTreeSet<&String> = base.keys().chain(current.keys()).collect();
+
+    fields
+        .into_iter()
+        .filter(|field| base.get(*field) != current.get(*field))
+        .map(|field| FieldChange {
+            field: field.clone(),
+            base: base.get(field).map(ToString::to_string),
+            current: current.get(field).map(ToString::to_string),
+        })
+        .collect()
+}
+
+/// Serializes a check to a TOML table, omitting unset fields.
+fn to_table(check: &CheckConfig) -> toml::Table {
+    toml::Table::try_from(check).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_configs_have_no_diff() {
+        let preset = Config::for_preset("rust");
+        assert!(ConfigDiff::between(&preset, &preset.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_added_and_changed_checks() {
+        let preset = Config::for_preset("rust");
+        let mut current = preset.clone();
+        current.checks.insert(
+            "audit".to_string(),
+            CheckConfig::from_command("cargo audit".to_string()),
+        );
+        current.agent.checks.push("audit".to_string());
+        let unit = current.checks.get_mut("test-unit").expect("preset check");
+        let preset_run = unit.run.clone();
+        unit.run = "cargo nextest run".to_string();
+        unit.timeout = Some("20m".to_string());
+
+        let diff = ConfigDiff::between(&preset, &current);
+        assert_eq!(diff.added, ["audit"]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.agent_checks.added, ["audit"]);
+        assert!(diff.human_checks.is_empty());
+
+        assert_eq!(diff.changed.len(), 1);
+        let change = &diff.changed[0];
+        assert_eq!(change.name, "test-unit");
+        assert_eq!(
+            change.fields,
+            [
+                FieldChange {
+                    field: "run".to_string(),
+                    base: Some(format!("\"{preset_run}\"")),
+                    current: Some("\"cargo nextest run\"".to_string()),
+                },
+                FieldChange {
+                    field: "timeout".to_string(),
+                    base: None,
+                    current: Some("\"20m\"".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_removed_checks() {
+        let preset = Config::for_preset("rust");
+        let mut current = preset.clone();
+        current.checks.remove("test-unit");
+        current.agent.checks.retain(|c| c != "test-unit");
+
+        let diff = ConfigDiff::between(&preset, &current);
+        assert_eq!(diff.removed, ["test-unit"]);
+        assert_eq!(diff.agent_checks.removed, ["test-unit"]);
+        assert!(diff.added.is_empty());
+        assert!(diff.changed.is_empty());
+    }
+}
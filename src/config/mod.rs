@@ -3,18 +3,49 @@
 //! This module provides configuration loading and validation,
 //! supporting both `agent-precommit.toml` files and sensible defaults.
 
+mod diff;
+mod schema;
+
+pub use diff::{CheckChange, ConfigDiff, FieldChange, ListDiff};
+
+use crate::core::detector::{AgentKind, DETECTION_STAGES};
 use crate::core::error::{Error, Result};
+use crate::core::executor::Executor;
+use crate::core::graph;
+use crate::core::interpolate;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
 
 /// Default configuration file name.
 pub const CONFIG_FILE_NAME: &str = "agent-precommit.toml";
 
+/// Configuration file names searched for, in order of preference.
+pub const CONFIG_FILE_NAMES: &[&str] = &[
+    CONFIG_FILE_NAME,
+    "agent-precommit.yaml",
+    "agent-precommit.yml",
+];
+
+/// Lines of a failed check's output shown in the run summary by default.
+pub const DEFAULT_OUTPUT_LINES: usize = 20;
+
+/// Shortest share of a mode timeout per sequential check that looks intended.
+pub const MIN_TIMEOUT_PER_CHECK: Duration = Duration::from_secs(1);
+
 /// Main configuration structure.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct Config {
+    /// Config format version (see [`Config::CURRENT_VERSION`]).
+    pub version: u32,
+    /// Base configuration to load first, relative to this file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
     /// Detection settings.
     pub detection: DetectionConfig,
     /// Integration with other tools.
@@ -23,6 +54,14 @@ pub struct Config {
     pub human: ModeConfig,
     /// Agent mode settings.
     pub agent: AgentModeConfig,
+    /// Output settings.
+    pub output: OutputConfig,
+    /// Values shared by all checks.
+    pub globals: GlobalsConfig,
+    /// Commit message checks run by `apc commit-msg`.
+    pub commit_msg: CommitMsgConfig,
+    /// CI mode settings.
+    pub ci: CiConfig,
     /// Check definitions.
     #[serde(default)]
     pub checks: HashMap<String, CheckConfig>,
@@ -31,45 +70,238 @@ pub struct Config {
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: Self::CURRENT_VERSION,
+            extends: None,
             detection: DetectionConfig::default(),
             integration: IntegrationConfig::default(),
             human: ModeConfig::default_human(),
             agent: AgentModeConfig::default(),
+            output: OutputConfig::default(),
+            globals: GlobalsConfig::default(),
+            commit_msg: CommitMsgConfig::default(),
+            ci: CiConfig::default(),
             checks: default_checks(),
         }
     }
 }
 
 impl Config {
-    /// Loads configuration from the default location.
+    /// Newest config format version this build understands.
+    pub const CURRENT_VERSION: u32 = 1;
+
+    /// Loads configuration from the default location, merged over the
+    /// user-level config if there is one.
     pub fn load() -> Result<Self> {
         let path = Self::find_config_file()?;
-        Self::load_from(&path)
+        Self::load_with_user_config(Some(&path))
     }
 
     /// Loads configuration or returns defaults if not found.
+    ///
+    /// The user-level config still applies when the repo has none.
     pub fn load_or_default() -> Result<Self> {
         match Self::find_config_file() {
-            Ok(path) => Self::load_from(&path),
-            Err(Error::ConfigNotFound { .. }) => Ok(Self::default()),
+            Ok(path) => Self::load_with_user_config(Some(&path)),
+            Err(Error::ConfigNotFound { .. }) => Self::load_with_user_config(None),
             Err(e) => Err(e),
         }
     }
 
+    /// Loads the repo config at `path` merged over the user-level config from
+    /// [`Config::user_config_file`], so repo settings win.
+    ///
+    /// With neither file present this is the default configuration.
+    pub fn load_with_user_config(path: Option<&Path>) -> Result<Self> {
+        let user = Self::user_config_file();
+        match (path, user) {
+            (Some(path), user) => Self::load_over(path, user.as_deref()),
+            (None, Some(user)) => Self::load_from(&user),
+            (None, None) => Ok(Self::default()),
+        }
+    }
+
+    /// Returns the user-level config file, if one exists.
+    ///
+    /// The candidates from [`Config::user_config_paths`] are tried in order.
+    #[must_use]
+    pub fn user_config_file() -> Option<PathBuf> {
+        Self::user_config_paths()
+            .into_iter()
+            .find(|path| path.is_file())
+    }
+
+    /// Returns where a user-level config is looked for, in order:
+    /// `$XDG_CONFIG_HOME/agent-precommit/config.toml`, then
+    /// `~/.config/agent-precommit/config.toml`.
+    #[must_use]
+    pub fn user_config_paths() -> Vec<PathBuf> {
+        user_config_paths_in(
+            std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from),
+            dirs::home_dir(),
+        )
+    }
+
     /// Loads configuration from a specific path.
     pub fn load_from(path: &Path) -> Result<Self> {
+        Self::load_over(path, None)
+    }
+
+    /// Loads configuration from `path`, merged over the config at `user` if
+    /// given.
+    fn load_over(path: &Path, user: Option<&Path>) -> Result<Self> {
         let content = std::fs::read_to_string(path).map_err(|e| Error::io("read config", e))?;
 
-        let config: Self = toml::from_str(&content)
-            .map_err(|e| Error::config_parse_with_source("Failed to parse TOML", e))?;
+        let mut config: Self = if is_yaml(path) {
+            serde_yaml::from_str(&content)
+                .map_err(|e| Error::config_parse_with_source("Failed to parse YAML", e))?
+        } else {
+            toml::from_str(&content)
+                .map_err(|e| Error::config_parse_with_source("Failed to parse TOML", e))?
+        };
+
+        let extends = config.extends.take();
+        if extends.is_some() || user.is_some() {
+            let mut merged = match user {
+                Some(user) => Self::load_extended(user, &mut Vec::new())?,
+                None => toml::Table::new(),
+            };
+            merge_tables(&mut merged, Self::load_extended(path, &mut Vec::new())?);
+            config = toml::Value::Table(merged)
+                .try_into()
+                .map_err(|e| Error::config_parse_with_source("Failed to parse TOML", e))?;
+            config.extends = extends;
+        }
+
+        if config.version > Self::CURRENT_VERSION {
+            tracing::warn!(
+                version = config.version,
+                supported = Self::CURRENT_VERSION,
+                "Config was written for a newer version of apc; unknown settings are ignored"
+            );
+        }
 
         config.validate()?;
 
+        // Short timeouts may be deliberate, so just warn (strict loading rejects zero)
+        for warning in config.timeout_warnings() {
+            tracing::warn!("{warning}");
+        }
+
+        Ok(config)
+    }
+
+    /// Reads `path` as a table with the files it `extends` merged underneath.
+    ///
+    /// `chain` holds the files already being loaded, to detect cycles.
+    fn load_extended(path: &Path, chain: &mut Vec<PathBuf>) -> Result<toml::Table> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| Error::io("canonicalize config path", e))?;
+        let cycle = chain.contains(&canonical);
+        chain.push(canonical);
+        if cycle {
+            return Err(Error::ConfigExtendsCycle {
+                chain: chain.clone(),
+            });
+        }
+
+        let content = std::fs::read_to_string(path).map_err(|e| Error::io("read config", e))?;
+        let mut table = parse_table(path, &content)?;
+
+        let Some(extends) = table.remove("extends") else {
+            return Ok(table);
+        };
+        let Some(extends) = extends.as_str() else {
+            return Err(Error::ConfigInvalid {
+                field: "extends".to_string(),
+                message: "Expected a file path".to_string(),
+            });
+        };
+
+        let base_path = path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join(extends);
+        if !base_path.is_file() {
+            return Err(Error::ConfigExtendsNotFound {
+                path: base_path,
+                from: path.to_path_buf(),
+            });
+        }
+
+        let mut base = Self::load_extended(&base_path, chain)?;
+        merge_tables(&mut base, table);
+        Ok(base)
+    }
+
+    /// Loads configuration from the default location, rejecting unknown keys.
+    pub fn load_strict() -> Result<Self> {
+        let path = Self::find_config_file()?;
+        Self::load_strict_from(&path)
+    }
+
+    /// Loads configuration from a specific path, rejecting unknown keys.
+    ///
+    /// Lenient loading ignores keys that are not part of the schema, so a typo
+    /// like `timout` silently falls back to the default. Strict loading reports
+    /// every such key along with the line it was found on.
+    pub fn load_strict_from(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| Error::io("read config", e))?;
+
+        let unknown = Self::unknown_keys_in(path, &content)?;
+        if !unknown.is_empty() {
+            // Line lookup understands TOML only
+            let locations: Vec<String> = unknown
+                .iter()
+                .map(
+                    |key| match schema::locate(&content, key).filter(|_| !is_yaml(path)) {
+                        Some(line) => format!("{key} (line {line})"),
+                        None => key.clone(),
+                    },
+                )
+                .collect();
+            return Err(Error::ConfigInvalid {
+                field: locations.join(", "),
+                message: "Unknown key".to_string(),
+            });
+        }
+
+        let config = Self::load_from(path)?;
+        if let Some(field) = config.zero_timeouts().into_iter().next() {
+            return Err(Error::ConfigInvalid {
+                field,
+                message: "Timeout must be greater than zero".to_string(),
+            });
+        }
         Ok(config)
     }
 
+    /// Returns the JSON Schema for the configuration file.
+    #[must_use]
+    pub fn json_schema() -> serde_json::Value {
+        schema::json_schema()
+    }
+
+    /// Returns the dotted paths of keys in `content` that are not part of the schema.
+    ///
+    /// Lenient loading ignores such keys, so this is how typos like `[agnet]`
+    /// are surfaced.
+    pub fn unknown_keys(content: &str) -> Result<Vec<String>> {
+        Self::unknown_keys_in(Path::new(CONFIG_FILE_NAME), content)
+    }
+
+    /// Like [`Config::unknown_keys`], parsing `content` as YAML if `path` has a
+    /// `.yaml`/`.yml` extension.
+    pub fn unknown_keys_in(path: &Path, content: &str) -> Result<Vec<String>> {
+        let table = parse_table(path, content)?;
+        Ok(schema::unknown_keys(&toml::Value::Table(table)))
+    }
+
     /// Finds the configuration file by searching up the directory tree.
     ///
+    /// Each directory is searched for the names in [`CONFIG_FILE_NAMES`], and
+    /// the first one found wins.
+    ///
     /// # Security
     ///
     /// This function canonicalizes paths to prevent symlink attacks where
@@ -84,8 +316,19 @@ impl Config {
 
         let mut current = cwd.as_path();
         loop {
-            let config_path = current.join(CONFIG_FILE_NAME);
-            if config_path.exists() {
+            let found: Vec<PathBuf> = CONFIG_FILE_NAMES
+                .iter()
+                .map(|name| current.join(name))
+                .filter(|path| path.exists())
+                .collect();
+            if let Some(config_path) = found.first() {
+                for ignored in &found[1..] {
+                    tracing::warn!(
+                        using = %config_path.display(),
+                        ignored = %ignored.display(),
+                        "Ignoring config file shadowed by another in the same directory"
+                    );
+                }
                 // Canonicalize the config path to ensure it resolves to a real location
                 let canonical_path = config_path
                     .canonicalize()
@@ -106,6 +349,113 @@ impl Config {
 
     /// Validates the configuration.
     pub fn validate(&self) -> Result<()> {
+        if self.version == 0 {
+            return Err(Error::ConfigInvalid {
+                field: "version".to_string(),
+                message: format!(
+                    "Unsupported version 0 (this apc supports 1 to {})",
+                    Self::CURRENT_VERSION
+                ),
+            });
+        }
+
+        self.validate_detection()?;
+        self.validate_modes()?;
+        self.validate_aliases()?;
+        self.validate_check_lists()?;
+        self.validate_output()?;
+
+        // Validate that checks in parallel groups are also in agent.checks
+        for (group_idx, group) in self.agent.parallel_groups.iter().enumerate() {
+            if group.max_parallel() == Some(0) {
+                return Err(Error::ConfigInvalid {
+                    field: format!("agent.parallel_groups[{}].max_parallel", group_idx),
+                    message: "Must be at least 1".to_string(),
+                });
+            }
+            for check_name in group.checks() {
+                if !self.agent.checks.contains(check_name) {
+                    return Err(Error::ConfigInvalid {
+                        field: format!("agent.parallel_groups[{}]", group_idx),
+                        message: format!(
+                            "Check '{}' is in a parallel group but not in agent.checks",
+                            check_name
+                        ),
+                    });
+                }
+            }
+        }
+
+        // Validate that check commands are non-empty
+        for (name, check) in &self.checks {
+            if check.run.trim().is_empty() {
+                return Err(Error::ConfigInvalid {
+                    field: format!("checks.{}.run", name),
+                    message: "Check command cannot be empty".to_string(),
+                });
+            }
+
+            for dependency in &check.needs {
+                if !self.checks.contains_key(dependency) {
+                    return Err(Error::ConfigInvalid {
+                        field: format!("checks.{}.needs", name),
+                        message: format!(
+                            "Check '{}' is needed but not defined in [checks]",
+                            dependency
+                        ),
+                    });
+                }
+            }
+
+            if !check.env_inherit.is_empty() && !check.clean_env {
+                return Err(Error::ConfigInvalid {
+                    field: format!("checks.{name}.env_inherit"),
+                    message: "Only applies with clean_env = true".to_string(),
+                });
+            }
+
+            // Container checks always run under the image's `sh`
+            if check.shell.is_some() && check.container.is_some() {
+                return Err(Error::ConfigInvalid {
+                    field: format!("checks.{name}.shell"),
+                    message: "Cannot be combined with container".to_string(),
+                });
+            }
+
+            self.validate_references(name, check)?;
+
+            Self::validate_durations(name, check)?;
+            Self::validate_globs(name, check)?;
+
+            // A missing shell may only be missing on this machine, so just warn
+            if let Some(shell) = &check.shell {
+                if !Executor::command_exists(shell) {
+                    tracing::warn!(check = %name, shell = %shell, "Check shell not found in PATH");
+                }
+            }
+
+            if let Some(condition) = &check.enabled_if {
+                Self::validate_condition(name, condition)?;
+            }
+        }
+
+        if let Some(shell) = &self.globals.shell {
+            if !Executor::command_exists(shell) {
+                tracing::warn!(shell = %shell, "globals.shell not found in PATH");
+            }
+        }
+
+        // Validate that check dependencies are acyclic
+        let mut names: Vec<String> = self.checks.keys().cloned().collect();
+        names.sort();
+        graph::waves(&names, &self.checks)?;
+
+        Ok(())
+    }
+
+    /// Validates the `[human]` and `[agent]` timeouts and parallelism, and the
+    /// timeout grace period.
+    fn validate_modes(&self) -> Result<()> {
         // Validate timeouts are parseable
         if humantime::parse_duration(&self.human.timeout).is_err() {
             return Err(Error::ConfigInvalid {
@@ -121,53 +471,283 @@ impl Config {
             });
         }
 
-        // Validate that checks referenced in human mode exist in [checks]
-        for check_name in &self.human.checks {
-            if !self.checks.contains_key(check_name) {
+        if let Some(total) = &self.agent.total_timeout {
+            if humantime::parse_duration(total).is_err() {
                 return Err(Error::ConfigInvalid {
-                    field: "human.checks".to_string(),
-                    message: format!(
-                        "Check '{}' is referenced but not defined in [checks]",
-                        check_name
-                    ),
+                    field: "agent.total_timeout".to_string(),
+                    message: format!("Invalid duration: {total}"),
+                });
+            }
+        }
+
+        if let Some(grace) = &self.globals.timeout_grace {
+            if humantime::parse_duration(grace).is_err() {
+                return Err(Error::ConfigInvalid {
+                    field: "globals.timeout_grace".to_string(),
+                    message: format!("Invalid duration: {}", grace),
+                });
+            }
+        }
+
+        for (field, max) in [
+            ("human.max_parallel", self.human.max_parallel),
+            ("agent.max_parallel", self.agent.max_parallel),
+        ] {
+            if max == Some(0) {
+                return Err(Error::ConfigInvalid {
+                    field: field.to_string(),
+                    message: "Must be at least 1".to_string(),
                 });
             }
         }
 
-        // Validate that checks referenced in agent mode exist in [checks]
-        for check_name in &self.agent.checks {
-            if !self.checks.contains_key(check_name) {
+        Ok(())
+    }
+
+    /// Returns warnings for timeouts that are zero or too short for the
+    /// checks they cover.
+    ///
+    /// A mode timeout is suspicious if it leaves less than
+    /// [`MIN_TIMEOUT_PER_CHECK`] for each check that runs one after another
+    /// under it. Unparseable timeouts are left to [`Config::validate`].
+    #[must_use]
+    pub fn timeout_warnings(&self) -> Vec<String> {
+        let zero = self.zero_timeouts();
+        let mut warnings: Vec<String> = zero
+            .iter()
+            .map(|field| format!("{field} is 0s, so checks using it always time out"))
+            .collect();
+
+        for (field, timeout, checks, max_parallel) in [
+            (
+                "human.timeout",
+                &self.human.timeout,
+                &self.human.checks,
+                self.human.max_parallel,
+            ),
+            (
+                "agent.timeout",
+                &self.agent.timeout,
+                &self.agent.checks,
+                self.agent.max_parallel,
+            ),
+        ] {
+            let Ok(timeout) = humantime::parse_duration(timeout) else {
+                continue;
+            };
+            // Only checks without their own timeout fall back to the mode's
+            let covered = checks
+                .iter()
+                .filter_map(|name| self.checks.get(name))
+                .filter(|check| check.timeout.is_none())
+                .count();
+            let sequential = covered.div_ceil(max_parallel.unwrap_or(1).max(1));
+            let minimum = MIN_TIMEOUT_PER_CHECK * u32::try_from(sequential).unwrap_or(u32::MAX);
+
+            if !zero.iter().any(|z| z == field) && timeout < minimum {
+                warnings.push(format!(
+                    "{field} of {} leaves under {} per check for {sequential} sequential checks",
+                    humantime::format_duration(timeout),
+                    humantime::format_duration(MIN_TIMEOUT_PER_CHECK)
+                ));
+            }
+        }
+
+        warnings
+    }
+
+    /// Returns the fields of mode and check timeouts set to zero.
+    fn zero_timeouts(&self) -> Vec<String> {
+        let is_zero = |value: &str| humantime::parse_duration(value).is_ok_and(|d| d.is_zero());
+
+        let mut fields: Vec<String> = [
+            ("human.timeout", Some(&self.human.timeout)),
+            ("agent.timeout", Some(&self.agent.timeout)),
+            ("agent.total_timeout", self.agent.total_timeout.as_ref()),
+        ]
+        .into_iter()
+        .filter(|(_, timeout)| timeout.is_some_and(|t| is_zero(t)))
+        .map(|(field, _)| field.to_string())
+        .collect();
+
+        let mut checks: Vec<String> = self
+            .checks
+            .iter()
+            .filter(|(_, check)| check.timeout.as_deref().is_some_and(is_zero))
+            .map(|(name, _)| format!("checks.{name}.timeout"))
+            .collect();
+        checks.sort();
+        fields.extend(checks);
+        fields
+    }
+
+    /// Validates that `detection.priority` names each real stage at most once.
+    fn validate_detection(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for stage in &self.detection.priority {
+            if !DETECTION_STAGES.contains(&stage.as_str()) {
                 return Err(Error::ConfigInvalid {
-                    field: "agent.checks".to_string(),
+                    field: "detection.priority".to_string(),
                     message: format!(
-                        "Check '{}' is referenced but not defined in [checks]",
-                        check_name
+                        "Unknown detection stage '{}' (expected one of: {})",
+                        stage,
+                        DETECTION_STAGES.join(", ")
                     ),
                 });
             }
+            if !seen.insert(stage) {
+                return Err(Error::ConfigInvalid {
+                    field: "detection.priority".to_string(),
+                    message: format!("Stage '{}' is listed more than once", stage),
+                });
+            }
         }
+        Ok(())
+    }
 
-        // Validate that checks in parallel groups are also in agent.checks
-        for (group_idx, group) in self.agent.parallel_groups.iter().enumerate() {
-            for check_name in group {
-                if !self.agent.checks.contains(check_name) {
+    /// Validates that a check's `retry_delay` and `timeout` are parseable.
+    fn validate_durations(name: &str, check: &CheckConfig) -> Result<()> {
+        for (field, value) in [
+            ("retry_delay", &check.retry_delay),
+            ("timeout", &check.timeout),
+        ] {
+            if let Some(value) = value {
+                if humantime::parse_duration(value).is_err() {
                     return Err(Error::ConfigInvalid {
-                        field: format!("agent.parallel_groups[{}]", group_idx),
-                        message: format!(
-                            "Check '{}' is in a parallel group but not in agent.checks",
-                            check_name
-                        ),
+                        field: format!("checks.{}.{}", name, field),
+                        message: format!("Invalid duration: {}", value),
                     });
                 }
             }
         }
+        Ok(())
+    }
 
-        // Validate that check commands are non-empty
-        for (name, check) in &self.checks {
-            if check.run.trim().is_empty() {
+    /// Validates that `[output].artifact_dir` names a directory inside the
+    /// repository, since apc clears it before collecting artifacts.
+    fn validate_output(&self) -> Result<()> {
+        let Some(dir) = &self.output.artifact_dir else {
+            return Ok(());
+        };
+
+        let components: Vec<_> = Path::new(dir).components().collect();
+        let inside = components
+            .iter()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        let named = components.iter().any(|c| matches!(c, Component::Normal(_)));
+        if !inside || !named {
+            return Err(Error::ConfigInvalid {
+                field: "output.artifact_dir".to_string(),
+                message: format!(
+                    "'{dir}' must be a relative path to a directory inside the repository"
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Validates that the checks each mode lists are defined in `[checks]`.
+    fn validate_check_lists(&self) -> Result<()> {
+        let mut per_agent: Vec<_> = self.agent.per_agent.iter().collect();
+        per_agent.sort_by_key(|(agent, _)| **agent);
+        let lists = [
+            ("human.checks".to_string(), &self.human.checks),
+            ("agent.checks".to_string(), &self.agent.checks),
+        ]
+        .into_iter()
+        .chain(per_agent.into_iter().map(|(agent, extra)| {
+            (
+                format!("agent.per_agent.{}.checks", agent.key()),
+                &extra.checks,
+            )
+        }))
+        .chain([("commit_msg.checks".to_string(), &self.commit_msg.checks)]);
+
+        for (field, names) in lists {
+            if let Some(name) = names.iter().find(|n| !self.checks.contains_key(*n)) {
                 return Err(Error::ConfigInvalid {
-                    field: format!("checks.{}.run", name),
-                    message: "Check command cannot be empty".to_string(),
+                    field,
+                    message: format!("Check '{name}' is referenced but not defined in [checks]"),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates that check aliases don't shadow check names or each other.
+    fn validate_aliases(&self) -> Result<()> {
+        let mut names: Vec<&String> = self.checks.keys().collect();
+        names.sort();
+
+        let mut seen: HashMap<&str, &str> = HashMap::new();
+        for name in names {
+            for alias in &self.checks[name].aliases {
+                let message = if self.checks.contains_key(alias) {
+                    format!("'{alias}' is already the name of a check")
+                } else if let Some(other) = seen.insert(alias, name) {
+                    format!("'{alias}' is already an alias of '{other}'")
+                } else {
+                    continue;
+                };
+                return Err(Error::ConfigInvalid {
+                    field: format!("checks.{name}.aliases"),
+                    message,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the name of the check called `name`, or of the check that has
+    /// `name` as an alias.
+    #[must_use]
+    pub fn check_name<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        if self.checks.contains_key(name) {
+            return Some(name);
+        }
+        self.checks
+            .iter()
+            .find(|(_, check)| check.aliases.iter().any(|a| a == name))
+            .map(|(check_name, _)| check_name.as_str())
+    }
+
+    /// Validates that a check's `enabled_if` condition is satisfiable as written.
+    fn validate_condition(name: &str, condition: &EnabledCondition) -> Result<()> {
+        if condition.r#match.is_some() && condition.is_empty() {
+            return Err(Error::ConfigInvalid {
+                field: format!("checks.{}.enabled_if.match", name),
+                message: "No conditions to combine".to_string(),
+            });
+        }
+
+        if condition.file_exists.is_some() && condition.file_exists == condition.file_absent {
+            return Err(Error::ConfigInvalid {
+                field: format!("checks.{}.enabled_if.file_absent", name),
+                message: format!(
+                    "'{}' is also listed in file_exists",
+                    condition.file_absent.as_deref().unwrap_or_default()
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Validates that a check's `files_changed` condition and artifact patterns are valid globs.
+    fn validate_globs(name: &str, check: &CheckConfig) -> Result<()> {
+        let condition = check
+            .enabled_if
+            .as_ref()
+            .and_then(|c| c.files_changed.as_ref())
+            .map(|pattern| ("enabled_if.files_changed", pattern));
+        let artifacts = check.artifacts.iter().map(|pattern| ("artifacts", pattern));
+
+        for (field, pattern) in condition.into_iter().chain(artifacts) {
+            if let Err(e) = glob::Pattern::new(pattern) {
+                return Err(Error::ConfigInvalid {
+                    field: format!("checks.{}.{}", name, field),
+                    message: format!("Invalid glob '{}': {}", pattern, e),
                 });
             }
         }
@@ -175,6 +755,41 @@ impl Config {
         Ok(())
     }
 
+    /// Validates that every `${VAR}` in a check's `run` and `env` can be resolved.
+    ///
+    /// `run` may also use the check's own env; env values may not refer to each other.
+    fn validate_references(&self, name: &str, check: &CheckConfig) -> Result<()> {
+        let unknown = |value: &str, own_env: bool| {
+            interpolate::references(value)
+                .into_iter()
+                .find(|var| {
+                    !(own_env && check.env.contains_key(*var))
+                        && self.globals.resolve(var).is_none()
+                })
+                .map(str::to_string)
+        };
+
+        let mut fields = vec![(format!("checks.{name}.run"), unknown(&check.run, true))];
+        let mut env: Vec<_> = check.env.iter().collect();
+        env.sort();
+        for (key, value) in env {
+            fields.push((format!("checks.{name}.env.{key}"), unknown(value, false)));
+        }
+
+        match fields
+            .into_iter()
+            .find_map(|(field, var)| var.map(|v| (field, v)))
+        {
+            Some((field, var)) => Err(Error::ConfigInvalid {
+                field,
+                message: format!(
+                    "Unknown variable ${{{var}}} - define it in [globals.env] or the environment"
+                ),
+            }),
+            None => Ok(()),
+        }
+    }
+
     /// Generates default configuration as a string.
     ///
     /// # Errors
@@ -192,79 +807,286 @@ impl Config {
     pub fn for_preset(preset: &str) -> Self {
         let mut config = Self::default();
 
-        match preset {
-            "python" => {
-                config.agent.checks = vec![
-                    "pre-commit-all".to_string(),
-                    "no-merge-conflicts".to_string(),
-                    "test-unit".to_string(),
-                    "test-integration".to_string(),
-                    "security-scan".to_string(),
-                    "build-verify".to_string(),
-                ];
-                config.checks.extend(python_checks());
-            },
-            "node" | "nodejs" | "typescript" => {
-                config.agent.checks = vec![
-                    "pre-commit-all".to_string(),
-                    "no-merge-conflicts".to_string(),
-                    "lint".to_string(),
-                    "typecheck".to_string(),
-                    "test-unit".to_string(),
-                    "build-verify".to_string(),
-                ];
-                config.checks.extend(node_checks());
-            },
-            "rust" => {
-                config.agent.checks = vec![
-                    "no-merge-conflicts".to_string(),
-                    "fmt-check".to_string(),
-                    "clippy".to_string(),
-                    "test-unit".to_string(),
-                    "build-verify".to_string(),
-                ];
-                config.checks.extend(rust_checks());
-            },
-            "go" => {
-                config.agent.checks = vec![
-                    "no-merge-conflicts".to_string(),
-                    "fmt-check".to_string(),
-                    "lint".to_string(),
-                    "test-unit".to_string(),
-                    "build-verify".to_string(),
-                ];
-                config.checks.extend(go_checks());
-            },
-            _ => {},
-        }
+        let (agent_checks, checks): (&[&str], _) = match preset {
+            "python" => (
+                &[
+                    "pre-commit-all",
+                    "no-merge-conflicts",
+                    "test-unit",
+                    "test-integration",
+                    "security-scan",
+                    "build-verify",
+                ],
+                python_checks(),
+            ),
+            "node" | "nodejs" | "typescript" => (
+                &[
+                    "pre-commit-all",
+                    "no-merge-conflicts",
+                    "lint",
+                    "typecheck",
+                    "test-unit",
+                    "build-verify",
+                ],
+                node_checks(),
+            ),
+            "rust" => (
+                &[
+                    "no-merge-conflicts",
+                    "fmt-check",
+                    "clippy",
+                    "test-unit",
+                    "build-verify",
+                ],
+                rust_checks(),
+            ),
+            "go" => (
+                &[
+                    "no-merge-conflicts",
+                    "fmt-check",
+                    "lint",
+                    "test-unit",
+                    "build-verify",
+                ],
+                go_checks(),
+            ),
+            "make" => (
+                &["no-merge-conflicts", "lint", "test-unit", "build-verify"],
+                make_checks(),
+            ),
+            "lua" => (
+                &["no-merge-conflicts", "fmt-check", "lint", "test-unit"],
+                lua_checks(),
+            ),
+            "java" => (
+                &[
+                    "no-merge-conflicts",
+                    "spotless",
+                    "checkstyle",
+                    "test-unit",
+                    "maven-verify",
+                    "gradle-check",
+                ],
+                java_checks(),
+            ),
+            "deno" => (
+                &[
+                    "no-merge-conflicts",
+                    "fmt-check",
+                    "lint",
+                    "typecheck",
+                    "test-unit",
+                ],
+                deno_checks(),
+            ),
+            "ruby" => (
+                &[
+                    "no-merge-conflicts",
+                    "lint",
+                    "test-unit",
+                    "security-scan",
+                    "build-verify",
+                ],
+                ruby_checks(),
+            ),
+            "php" => (
+                &[
+                    "no-merge-conflicts",
+                    "fmt-check",
+                    "typecheck",
+                    "test-unit",
+                    "build-verify",
+                ],
+                php_checks(),
+            ),
+            _ => return config,
+        };
+        config.agent.checks = agent_checks.iter().map(ToString::to_string).collect();
+        config.checks.extend(checks);
 
         config
     }
-}
-
-/// Detection configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[serde(default)]
-pub struct DetectionConfig {
-    /// Force a specific mode (overrides auto-detection).
-    pub mode: Option<String>,
-    /// Additional environment variables that indicate an agent.
-    pub agent_env_vars: Vec<String>,
-}
 
-/// Integration configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
-pub struct IntegrationConfig {
-    /// Enable pre-commit framework integration.
-    pub pre_commit: bool,
-    /// Path to pre-commit config file.
-    pub pre_commit_path: String,
-}
+    /// Loads a shared preset from `path` to use in place of a built-in one.
+    ///
+    /// The file is either a full configuration (whose `extends` chain is
+    /// flattened into it) or just a map of check tables, which are added to
+    /// the default configuration and run in agent mode.
+    pub fn from_preset_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| Error::io("read preset", e))?;
+        let table = parse_table(path, &content)?;
+
+        let is_checks_map = !table.is_empty()
+            && table
+                .values()
+                .all(|value| value.as_table().is_some_and(|t| t.contains_key("run")));
+        if !is_checks_map {
+            let mut config = Self::load_from(path)?;
+            // The base is already merged in, and its path would not resolve from the new file
+            config.extends = None;
+            return Ok(config);
+        }
 
-impl Default for IntegrationConfig {
-    fn default() -> Self {
-        Self {
+        let mut config = Self::default();
+        let mut names: Vec<String> = table.keys().cloned().collect();
+        names.sort();
+        let checks: HashMap<String, CheckConfig> = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e| Error::config_parse_with_source("Failed to parse preset checks", e))?;
+        config.checks.extend(checks);
+        for name in names {
+            if !config.agent.checks.contains(&name) {
+                config.agent.checks.push(name);
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Adds a preset's checks to this config, returning how many were added.
+    ///
+    /// Checks this config already defines keep their definition unless
+    /// `prefer_preset` is set. Check names missing from the human and agent
+    /// lists are appended; every other setting (timeouts, ...) is left alone.
+    pub fn merge_preset(&mut self, preset: Self, prefer_preset: bool) -> usize {
+        let mut added = 0;
+        for (name, check) in preset.checks {
+            match self.checks.entry(name) {
+                Entry::Vacant(entry) => {
+                    entry.insert(check);
+                    added += 1;
+                },
+                Entry::Occupied(mut entry) if prefer_preset => {
+                    entry.insert(check);
+                },
+                Entry::Occupied(_) => {},
+            }
+        }
+
+        for (list, preset_list) in [
+            (&mut self.human.checks, preset.human.checks),
+            (&mut self.agent.checks, preset.agent.checks),
+        ] {
+            for name in preset_list {
+                if !list.contains(&name) {
+                    list.push(name);
+                }
+            }
+        }
+        added
+    }
+
+    /// Drops Makefile-backed checks whose target is not in `targets`.
+    ///
+    /// Used by the `make` preset after introspecting the project's Makefile.
+    pub fn retain_make_targets(&mut self, targets: &HashSet<String>) {
+        for (check, target) in MAKE_TARGET_CHECKS {
+            if !targets.contains(target) {
+                self.remove_check(check);
+            }
+        }
+    }
+
+    /// Drops Java lint checks whose build plugin is not in `plugins`.
+    ///
+    /// Used by the `java` preset after reading the project's build files.
+    pub fn retain_java_plugins(&mut self, plugins: &HashSet<String>) {
+        for plugin in JAVA_LINT_PLUGINS {
+            if !plugins.contains(plugin) {
+                self.remove_check(plugin);
+            }
+        }
+    }
+
+    /// Gates the `deno` preset's checks on `file` (e.g. `deno.jsonc`) instead
+    /// of `deno.json`.
+    pub fn use_deno_config_file(&mut self, file: &str) {
+        for (name, ..) in DENO_CHECKS {
+            if let Some(condition) = self
+                .checks
+                .get_mut(name)
+                .and_then(|check| check.enabled_if.as_mut())
+            {
+                condition.file_exists = Some(file.to_string());
+            }
+        }
+    }
+
+    /// Gates the `php` preset's static analysis on `file` (e.g. `psalm.xml`)
+    /// instead of `phpstan.neon`.
+    pub fn use_php_analyser_config(&mut self, file: &str) {
+        if let Some(condition) = self
+            .checks
+            .get_mut("typecheck")
+            .and_then(|check| check.enabled_if.as_mut())
+        {
+            condition.file_exists = Some(file.to_string());
+        }
+    }
+
+    /// Removes a check and every reference to it from the mode lists.
+    fn remove_check(&mut self, check: &str) {
+        self.checks.remove(check);
+        self.human.checks.retain(|c| c != check);
+        self.agent.checks.retain(|c| c != check);
+        for group in &mut self.agent.parallel_groups {
+            group.checks_mut().retain(|c| c != check);
+        }
+    }
+}
+
+/// Detection configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct DetectionConfig {
+    /// Force a specific mode (overrides auto-detection).
+    pub mode: Option<String>,
+    /// Additional environment variables that indicate an agent.
+    pub agent_env_vars: Vec<String>,
+    /// Git author or committer names and emails that indicate an agent
+    /// (e.g., "bot@company.com"), matched case-insensitively.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub agent_authors: Vec<String>,
+    /// Treat a run without a TTY on stdin and stdout as an agent.
+    ///
+    /// Turn off for cron jobs and other non-interactive runs that are not agents.
+    pub use_tty_heuristic: bool,
+    /// Order to try detection stages in; the first match wins.
+    ///
+    /// Stages: `apc_mode`, `agent_mode`, `known_agent_env`, `custom_agent_env`,
+    /// `ci`, `agent_author`, `tty`. Omitted stages are not tried. Empty means
+    /// the default order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub priority: Vec<String>,
+}
+
+impl Default for DetectionConfig {
+    fn default() -> Self {
+        Self {
+            mode: None,
+            agent_env_vars: Vec::new(),
+            agent_authors: Vec::new(),
+            use_tty_heuristic: true,
+            priority: Vec::new(),
+        }
+    }
+}
+
+/// Integration configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct IntegrationConfig {
+    /// Enable pre-commit framework integration.
+    pub pre_commit: bool,
+    /// Path to pre-commit config file.
+    pub pre_commit_path: String,
+}
+
+impl Default for IntegrationConfig {
+    fn default() -> Self {
+        Self {
             pre_commit: false,
             pre_commit_path: ".pre-commit-config.yaml".to_string(),
         }
@@ -272,8 +1094,9 @@ impl Default for IntegrationConfig {
 }
 
 /// Mode-specific configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct ModeConfig {
     /// Checks to run in this mode.
     pub checks: Vec<String>,
@@ -281,6 +1104,22 @@ pub struct ModeConfig {
     pub timeout: String,
     /// Whether to stop on first failure.
     pub fail_fast: bool,
+    /// Stash unstaged changes while checks run, so they see only the index.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stash_unstaged: bool,
+    /// Maximum number of checks running at once (default: 1, i.e. sequential).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_parallel: Option<usize>,
+    /// Skip checks whose `paths` match none of the staged files (human mode only).
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub only_changed: bool,
+    /// Shell for checks that set none (e.g., "bash", "pwsh"); defaults to `sh`,
+    /// or on Windows to PowerShell when installed and `cmd` otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_shell: Option<String>,
+    /// Lines of a failed check's output shown in the run summary (default: 20; 0 shows all).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_lines: Option<usize>,
 }
 
 impl ModeConfig {
@@ -289,6 +1128,11 @@ impl ModeConfig {
             checks: vec!["pre-commit".to_string()],
             timeout: "30s".to_string(),
             fail_fast: true,
+            stash_unstaged: false,
+            max_parallel: None,
+            only_changed: false,
+            default_shell: None,
+            output_lines: None,
         }
     }
 }
@@ -300,17 +1144,53 @@ impl Default for ModeConfig {
 }
 
 /// Agent mode configuration with parallel execution support.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(default)]
+#[schemars(deny_unknown_fields, transform = schema::alias_max_concurrency)]
 pub struct AgentModeConfig {
     /// Checks to run in agent mode.
     pub checks: Vec<String>,
     /// Timeout for all checks.
     pub timeout: String,
-    /// Whether to stop on first failure.
-    pub fail_fast: bool,
+    /// Wall-clock budget for the whole run (e.g. "30m"). When it runs out,
+    /// running checks are killed and the rest are not started; all of them fail.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_timeout: Option<String>,
+    /// Whether to stop on first failure: `off`, `group`, or `run` (`true`/`false` also work).
+    pub fail_fast: FailFast,
     /// Groups of checks that can run in parallel.
-    pub parallel_groups: Vec<Vec<String>>,
+    pub parallel_groups: Vec<ParallelGroup>,
+    /// How checks are batched when `parallel_groups` is empty: `all` runs
+    /// them together, `none` one at a time, and `by_tag` one tag at a time.
+    pub auto_parallel: AutoParallel,
+    /// Maximum number of checks running at once (default: number of CPUs).
+    /// Also accepted as `max_concurrency`.
+    #[serde(alias = "max_concurrency", skip_serializing_if = "Option::is_none")]
+    pub max_parallel: Option<usize>,
+    /// Shell for checks that set none (e.g., "bash", "pwsh"); defaults to `sh`,
+    /// or on Windows to PowerShell when installed and `cmd` otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_shell: Option<String>,
+    /// Lines of a failed check's output shown in the run summary (default: 20; 0 shows all).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_lines: Option<usize>,
+    /// Whether to skip checks whose inputs are unchanged since they last
+    /// passed, keyed on their `paths` (or all files when unset).
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cache: bool,
+    /// Extra checks run after `checks` when a specific agent is detected,
+    /// e.g. `[agent.per_agent.devin] checks = ["security-scan"]`.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub per_agent: HashMap<AgentKind, PerAgentConfig>,
+}
+
+/// Extra agent mode checks for one agent.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct PerAgentConfig {
+    /// Checks appended to `agent.checks` when this agent is detected.
+    pub checks: Vec<String>,
 }
 
 impl Default for AgentModeConfig {
@@ -322,26 +1202,218 @@ impl Default for AgentModeConfig {
                 "test-unit".to_string(),
             ],
             timeout: "15m".to_string(),
-            fail_fast: false,
+            total_timeout: None,
+            fail_fast: FailFast::Off,
             parallel_groups: Vec::new(),
+            auto_parallel: AutoParallel::All,
+            max_parallel: None,
+            default_shell: None,
+            output_lines: None,
+            cache: false,
+            per_agent: HashMap::new(),
+        }
+    }
+}
+
+/// A group of agent checks that run concurrently.
+///
+/// Written as a plain list of check names, or as a table with its own
+/// concurrency cap: `{ checks = ["unit", "e2e"], max_parallel = 1 }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum ParallelGroup {
+    /// Checks limited only by the global concurrency cap.
+    Checks(Vec<String>),
+    /// Checks of which at most `max_parallel` run at once.
+    Limited {
+        /// Checks in the group.
+        checks: Vec<String>,
+        /// Maximum number of the group's checks running at once.
+        max_parallel: usize,
+    },
+}
+
+impl ParallelGroup {
+    /// Returns the checks in the group.
+    #[must_use]
+    pub fn checks(&self) -> &[String] {
+        match self {
+            Self::Checks(checks) | Self::Limited { checks, .. } => checks,
+        }
+    }
+
+    /// Returns the checks in the group for editing.
+    pub fn checks_mut(&mut self) -> &mut Vec<String> {
+        match self {
+            Self::Checks(checks) | Self::Limited { checks, .. } => checks,
+        }
+    }
+
+    /// Returns the group's own concurrency cap, if it has one.
+    #[must_use]
+    pub const fn max_parallel(&self) -> Option<usize> {
+        match self {
+            Self::Checks(_) => None,
+            Self::Limited { max_parallel, .. } => Some(*max_parallel),
+        }
+    }
+}
+
+impl From<Vec<String>> for ParallelGroup {
+    fn from(checks: Vec<String>) -> Self {
+        Self::Checks(checks)
+    }
+}
+
+/// How agent checks are batched when no `parallel_groups` are configured.
+///
+/// Checks always wait for the checks they `need`; the strategy decides what
+/// runs together within those dependency waves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoParallel {
+    /// Run each wave's checks together.
+    #[default]
+    All,
+    /// Run checks one at a time.
+    None,
+    /// Run checks sharing their first tag together, one tag after another.
+    /// Untagged checks run together after the tagged ones.
+    ByTag,
+}
+
+/// How far a failing check stops an agent run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "FailFastSetting", into = "FailFastSetting")]
+pub enum FailFast {
+    /// Run every check.
+    #[default]
+    Off,
+    /// Cancel the rest of the failing check's parallel group, then continue.
+    Group,
+    /// Cancel the rest of the failing check's parallel group, then stop.
+    Run,
+}
+
+/// `fail_fast` as written in TOML: a bool (`true` means `run`) or a scope name.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum FailFastSetting {
+    Enabled(bool),
+    Scope(String),
+}
+
+impl TryFrom<FailFastSetting> for FailFast {
+    type Error = String;
+
+    fn try_from(setting: FailFastSetting) -> std::result::Result<Self, Self::Error> {
+        match setting {
+            FailFastSetting::Enabled(false) => Ok(Self::Off),
+            FailFastSetting::Enabled(true) => Ok(Self::Run),
+            FailFastSetting::Scope(scope) => match scope.as_str() {
+                "off" => Ok(Self::Off),
+                "group" => Ok(Self::Group),
+                "run" => Ok(Self::Run),
+                _ => Err(format!(
+                    "invalid fail_fast '{scope}', expected off, group, run, or a bool"
+                )),
+            },
+        }
+    }
+}
+
+impl From<FailFast> for FailFastSetting {
+    fn from(fail_fast: FailFast) -> Self {
+        match fail_fast {
+            FailFast::Off => Self::Enabled(false),
+            FailFast::Group => Self::Scope("group".to_string()),
+            FailFast::Run => Self::Enabled(true),
         }
     }
 }
 
 /// Configuration for a single check.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(default)]
+#[schemars(deny_unknown_fields)]
+#[allow(clippy::struct_excessive_bools)] // independent config flags
 pub struct CheckConfig {
     /// Command to run.
     pub run: String,
     /// Human-readable description.
     pub description: String,
+    /// Labels for selecting checks across modes with `apc run --tag`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Other names the check answers to in `apc run --check`, `--only` and
+    /// `apc explain`, e.g. its name in a tool migrated from.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
     /// Condition for enabling the check.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub enabled_if: Option<EnabledCondition>,
     /// Environment variables to set.
     #[serde(default)]
     pub env: HashMap<String, String>,
+    /// Run without the parent environment: only `PATH`, `HOME`, the
+    /// `env_inherit` variables and `env` are set.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub clean_env: bool,
+    /// Parent environment variables a `clean_env` check still sees.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub env_inherit: Vec<String>,
+    /// Timeout for this check (e.g., "10m"), overriding the mode timeout.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+    /// Checks that must pass before this one runs.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub needs: Vec<String>,
+    /// Report failures of this check without failing the run.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub allow_failure: bool,
+    /// Pass when the command exits non-zero and fail when it exits 0, for
+    /// checks asserting that something is rejected. Timeouts still fail.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub expect_failure: bool,
+    /// Times to re-run the check after a non-zero exit (timeouts are not retried).
+    #[serde(skip_serializing_if = "is_zero")]
+    pub retries: u32,
+    /// Delay between retry attempts (e.g., "2s").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_delay: Option<String>,
+    /// Glob patterns (relative to the repo root) of files to collect after the check fails.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<String>,
+    /// Collect artifacts even when the check passes.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub artifacts_always: bool,
+    /// Glob patterns (relative to the repo root) of files this check covers.
+    ///
+    /// With `[human].only_changed`, the check is skipped unless a staged file
+    /// matches one of them. Checks without `paths` always run.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// Glob patterns (relative to the repo root) of the check's inputs.
+    ///
+    /// When set, a pass is cached and the check is skipped until one of the
+    /// matched files (or the check itself) changes.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub depends_files: Vec<String>,
+    /// Expose the staged files to the command as `APC_STAGED_FILES`
+    /// (newline-separated), `APC_STAGED_FILES_COUNT` and `APC_STAGED_FILES_<n>`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pass_staged_files: bool,
+    /// Write the staged changes (`git diff --cached`) to a temporary file whose
+    /// path is exposed to the command as `APC_STAGED_DIFF_FILE`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pass_staged_diff: bool,
+    /// Shell that runs `run` (e.g., "bash", "pwsh"); defaults to the mode's `default_shell`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    /// Docker image to run the check in (e.g., "rust:1.82"), with the repo mounted at `/w`.
+    /// The command runs under the image's `sh`, so `shell` may not be set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
 }
 
 impl CheckConfig {
@@ -352,14 +1424,118 @@ impl CheckConfig {
             description: cmd.clone(),
             run: cmd,
             enabled_if: None,
-            env: HashMap::new(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Output configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct OutputConfig {
+    /// Directory to collect check artifacts into, relative to the repo root.
+    ///
+    /// Defaults to `.git/apc-artifacts`. Each check gets its own subdirectory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artifact_dir: Option<String>,
+    /// Title checks in reports by their description instead of their name.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub report_use_description: bool,
+    /// Leave skipped checks out of per-check listings (as `--quiet-skips`).
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub quiet_skips: bool,
+}
+
+/// Values shared by all checks.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct GlobalsConfig {
+    /// Variables available as `${VAR}` in check commands and env values.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// Bytes of stdout and of stderr kept per check (default 10 MiB); the
+    /// rest is discarded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_bytes: Option<usize>,
+    /// Time a timed-out check gets to clean up after SIGTERM before it is
+    /// killed (e.g., "5s"). Unset kills it right away.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_grace: Option<String>,
+    /// Shell for checks in modes without a `default_shell` (e.g., "pwsh");
+    /// unset uses the platform's.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+}
+
+impl GlobalsConfig {
+    /// Resolves a `${VAR}` reference from the globals, then the process environment.
+    #[must_use]
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        self.env
+            .get(name)
+            .cloned()
+            .or_else(|| std::env::var(name).ok())
+    }
+}
+
+/// CI mode configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct CiConfig {
+    /// When every check passes in CI mode on GitHub Actions, write a table of
+    /// the checks to `$GITHUB_STEP_SUMMARY` and emit a `::notice`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub report_on_success: bool,
+}
+
+/// Commit message configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct CommitMsgConfig {
+    /// Require Conventional Commits headers (`type(scope): subject`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conventional: Option<ConventionalCommitConfig>,
+
+    /// Checks run by the commit-msg hook (`apc run --hook commit-msg`).
+    /// Each sees the message file's path in `APC_COMMIT_MSG_FILE`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<String>,
+}
+
+/// Conventional Commits settings.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct ConventionalCommitConfig {
+    /// Allowed commit types.
+    pub types: Vec<String>,
+    /// Maximum length of the subject, in characters.
+    pub max_subject_length: usize,
+}
+
+impl Default for ConventionalCommitConfig {
+    fn default() -> Self {
+        Self {
+            types: [
+                "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore",
+                "revert",
+            ]
+            .iter()
+            .map(|t| (*t).to_string())
+            .collect(),
+            max_subject_length: 72,
         }
     }
 }
 
 /// Condition for enabling a check.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct EnabledCondition {
     /// Check if a file exists.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -370,6 +1546,97 @@ pub struct EnabledCondition {
     /// Check if a command exists in PATH.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub command_exists: Option<String>,
+    /// Check that a file does not exist.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_absent: Option<String>,
+    /// Check that a command is not in PATH.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_absent: Option<String>,
+    /// Check if any changed file matches a glob (e.g., "**/*.py").
+    ///
+    /// Human mode matches staged files; agent and CI modes match the whole
+    /// working tree.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files_changed: Option<String>,
+    /// How to combine the conditions above (default: all must hold).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r#match: Option<ConditionMatch>,
+}
+
+impl EnabledCondition {
+    /// Returns true if no conditions are set.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.file_exists.is_none()
+            && self.dir_exists.is_none()
+            && self.command_exists.is_none()
+            && self.file_absent.is_none()
+            && self.command_absent.is_none()
+            && self.files_changed.is_none()
+    }
+}
+
+/// How the conditions in an [`EnabledCondition`] are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ConditionMatch {
+    /// Every condition must hold.
+    #[default]
+    All,
+    /// At least one condition must hold.
+    Any,
+}
+
+/// Serde helper for skipping zero counts.
+#[allow(clippy::trivially_copy_pass_by_ref)] // serde passes fields by reference
+const fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+/// Returns true if `path` names a YAML config (`.yaml` or `.yml`).
+fn is_yaml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"))
+}
+
+/// Parses config `content` read from `path` into a table, as YAML or TOML by
+/// its extension.
+fn parse_table(path: &Path, content: &str) -> Result<toml::Table> {
+    if is_yaml(path) {
+        serde_yaml::from_str(content)
+            .map_err(|e| Error::config_parse_with_source("Failed to parse YAML", e))
+    } else {
+        toml::from_str(content)
+            .map_err(|e| Error::config_parse_with_source("Failed to parse TOML", e))
+    }
+}
+
+/// Returns the user-level config candidates for the given `$XDG_CONFIG_HOME`
+/// and home directory, most specific first and without duplicates.
+fn user_config_paths_in(xdg_config_home: Option<PathBuf>, home: Option<PathBuf>) -> Vec<PathBuf> {
+    // The XDG spec says relative values are invalid and should be ignored
+    let xdg = xdg_config_home.filter(|dir| dir.is_absolute());
+    let mut paths: Vec<PathBuf> = xdg
+        .into_iter()
+        .chain(home.map(|home| home.join(".config")))
+        .map(|dir| dir.join("agent-precommit").join("config.toml"))
+        .collect();
+    paths.dedup();
+    paths
+}
+
+/// Merges `overlay` into `base`: nested tables merge by key, anything else is replaced.
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => {
+                merge_tables(base, overlay);
+            },
+            (_, value) => {
+                base.insert(key, value);
+            },
+        }
+    }
 }
 
 /// Default checks for all configurations.
@@ -385,7 +1652,7 @@ fn default_checks() -> HashMap<String, CheckConfig> {
                 file_exists: Some(".pre-commit-config.yaml".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
@@ -398,7 +1665,7 @@ fn default_checks() -> HashMap<String, CheckConfig> {
                 file_exists: Some(".pre-commit-config.yaml".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
@@ -408,7 +1675,7 @@ fn default_checks() -> HashMap<String, CheckConfig> {
             run: "echo 'No test command configured. Use apc init --preset <lang> or define checks.test-unit.run in your config.'".to_string(),
             description: "Run unit tests (configure with a preset or custom command)".to_string(),
             enabled_if: None,
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
@@ -431,7 +1698,7 @@ echo "✓ No conflicts with $MAIN_BRANCH"
             .to_string(),
             description: "Ensure no merge conflicts with main/master".to_string(),
             enabled_if: None,
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
@@ -443,237 +1710,522 @@ fn python_checks() -> HashMap<String, CheckConfig> {
     let mut checks = HashMap::new();
 
     checks.insert(
-        "test-unit".to_string(),
+        "test-unit".to_string(),
+        CheckConfig {
+            run: "pytest -x -q".to_string(),
+            description: "Run unit tests".to_string(),
+            enabled_if: Some(EnabledCondition {
+                file_exists: Some("pyproject.toml".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+
+    checks.insert(
+        "test-integration".to_string(),
+        CheckConfig {
+            run: "pytest tests/integration/ -v".to_string(),
+            description: "Run integration tests".to_string(),
+            enabled_if: Some(EnabledCondition {
+                dir_exists: Some("tests/integration".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+
+    checks.insert(
+        "security-scan".to_string(),
+        CheckConfig {
+            run: "gitleaks detect --source . --no-git".to_string(),
+            description: "Scan for secrets".to_string(),
+            enabled_if: Some(EnabledCondition {
+                command_exists: Some("gitleaks".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+
+    checks.insert(
+        "build-verify".to_string(),
+        CheckConfig {
+            run: "python -m build --no-isolation".to_string(),
+            description: "Verify package builds".to_string(),
+            enabled_if: Some(EnabledCondition {
+                file_exists: Some("pyproject.toml".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+
+    checks
+}
+
+/// Node.js/TypeScript checks.
+fn node_checks() -> HashMap<String, CheckConfig> {
+    let mut checks = HashMap::new();
+
+    checks.insert(
+        "lint".to_string(),
+        CheckConfig {
+            run: "npm run lint".to_string(),
+            description: "Run ESLint".to_string(),
+            enabled_if: Some(EnabledCondition {
+                file_exists: Some("package.json".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+
+    checks.insert(
+        "typecheck".to_string(),
+        CheckConfig {
+            run: "npm run typecheck || npx tsc --noEmit".to_string(),
+            description: "Run TypeScript type checking".to_string(),
+            enabled_if: Some(EnabledCondition {
+                file_exists: Some("tsconfig.json".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+
+    checks.insert(
+        "test-unit".to_string(),
+        CheckConfig {
+            run: "npm test".to_string(),
+            description: "Run unit tests".to_string(),
+            enabled_if: Some(EnabledCondition {
+                file_exists: Some("package.json".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+
+    checks.insert(
+        "build-verify".to_string(),
+        CheckConfig {
+            run: "npm run build".to_string(),
+            description: "Verify build works".to_string(),
+            enabled_if: Some(EnabledCondition {
+                file_exists: Some("package.json".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+
+    checks
+}
+
+/// Rust checks.
+fn rust_checks() -> HashMap<String, CheckConfig> {
+    let mut checks = HashMap::new();
+
+    checks.insert(
+        "fmt-check".to_string(),
+        CheckConfig {
+            run: "cargo fmt --all -- --check".to_string(),
+            description: "Check code formatting".to_string(),
+            enabled_if: Some(EnabledCondition {
+                file_exists: Some("Cargo.toml".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+
+    checks.insert(
+        "clippy".to_string(),
+        CheckConfig {
+            run: "cargo clippy --all-targets --all-features -- -D warnings".to_string(),
+            description: "Run Clippy lints".to_string(),
+            enabled_if: Some(EnabledCondition {
+                file_exists: Some("Cargo.toml".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+
+    checks.insert(
+        "test-unit".to_string(),
+        CheckConfig {
+            run: "cargo test".to_string(),
+            description: "Run unit tests".to_string(),
+            enabled_if: Some(EnabledCondition {
+                file_exists: Some("Cargo.toml".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+
+    checks.insert(
+        "build-verify".to_string(),
+        CheckConfig {
+            run: "cargo build --release".to_string(),
+            description: "Verify release build".to_string(),
+            enabled_if: Some(EnabledCondition {
+                file_exists: Some("Cargo.toml".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+
+    checks
+}
+
+/// Go checks.
+fn go_checks() -> HashMap<String, CheckConfig> {
+    let mut checks = HashMap::new();
+
+    checks.insert(
+        "fmt-check".to_string(),
         CheckConfig {
-            run: "pytest -x -q".to_string(),
-            description: "Run unit tests".to_string(),
+            run: "test -z \"$(gofmt -l .)\"".to_string(),
+            description: "Check code formatting".to_string(),
             enabled_if: Some(EnabledCondition {
-                file_exists: Some("pyproject.toml".to_string()),
+                file_exists: Some("go.mod".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks.insert(
-        "test-integration".to_string(),
+        "lint".to_string(),
         CheckConfig {
-            run: "pytest tests/integration/ -v".to_string(),
-            description: "Run integration tests".to_string(),
+            run: "golangci-lint run".to_string(),
+            description: "Run golangci-lint".to_string(),
             enabled_if: Some(EnabledCondition {
-                dir_exists: Some("tests/integration".to_string()),
+                command_exists: Some("golangci-lint".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks.insert(
-        "security-scan".to_string(),
+        "test-unit".to_string(),
         CheckConfig {
-            run: "gitleaks detect --source . --no-git".to_string(),
-            description: "Scan for secrets".to_string(),
+            run: "go test ./...".to_string(),
+            description: "Run unit tests".to_string(),
             enabled_if: Some(EnabledCondition {
-                command_exists: Some("gitleaks".to_string()),
+                file_exists: Some("go.mod".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks.insert(
         "build-verify".to_string(),
         CheckConfig {
-            run: "python -m build --no-isolation".to_string(),
-            description: "Verify package builds".to_string(),
+            run: "go build ./...".to_string(),
+            description: "Verify build works".to_string(),
             enabled_if: Some(EnabledCondition {
-                file_exists: Some("pyproject.toml".to_string()),
+                file_exists: Some("go.mod".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks
 }
 
-/// Node.js/TypeScript checks.
-fn node_checks() -> HashMap<String, CheckConfig> {
+/// Ruby/Rails checks.
+fn ruby_checks() -> HashMap<String, CheckConfig> {
     let mut checks = HashMap::new();
 
     checks.insert(
         "lint".to_string(),
         CheckConfig {
-            run: "npm run lint".to_string(),
-            description: "Run ESLint".to_string(),
+            run: "if command -v rubocop >/dev/null 2>&1; then rubocop; else standardrb; fi"
+                .to_string(),
+            description: "Run RuboCop (or standardrb)".to_string(),
             enabled_if: Some(EnabledCondition {
-                file_exists: Some("package.json".to_string()),
+                file_exists: Some("Gemfile".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks.insert(
-        "typecheck".to_string(),
+        "test-unit".to_string(),
         CheckConfig {
-            run: "npm run typecheck || npx tsc --noEmit".to_string(),
-            description: "Run TypeScript type checking".to_string(),
+            run: "if [ -f Gemfile ]; then bundle exec rspec; else rspec; fi".to_string(),
+            description: "Run RSpec tests".to_string(),
             enabled_if: Some(EnabledCondition {
-                file_exists: Some("tsconfig.json".to_string()),
+                dir_exists: Some("spec".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks.insert(
-        "test-unit".to_string(),
+        "security-scan".to_string(),
         CheckConfig {
-            run: "npm test".to_string(),
-            description: "Run unit tests".to_string(),
+            run: "bundle exec brakeman --quiet --no-pager".to_string(),
+            description: "Scan Rails code for vulnerabilities".to_string(),
             enabled_if: Some(EnabledCondition {
-                file_exists: Some("package.json".to_string()),
+                command_exists: Some("brakeman".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks.insert(
         "build-verify".to_string(),
         CheckConfig {
-            run: "npm run build".to_string(),
-            description: "Verify build works".to_string(),
+            run: "bundle install --quiet".to_string(),
+            description: "Verify gems install".to_string(),
             enabled_if: Some(EnabledCondition {
-                file_exists: Some("package.json".to_string()),
+                file_exists: Some("Gemfile".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks
 }
 
-/// Rust checks.
-fn rust_checks() -> HashMap<String, CheckConfig> {
+/// PHP/Composer checks.
+fn php_checks() -> HashMap<String, CheckConfig> {
     let mut checks = HashMap::new();
 
     checks.insert(
         "fmt-check".to_string(),
         CheckConfig {
-            run: "cargo fmt --all -- --check".to_string(),
+            run: "php-cs-fixer fix --dry-run --diff".to_string(),
             description: "Check code formatting".to_string(),
             enabled_if: Some(EnabledCondition {
-                file_exists: Some("Cargo.toml".to_string()),
+                command_exists: Some("php-cs-fixer".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks.insert(
-        "clippy".to_string(),
+        "typecheck".to_string(),
         CheckConfig {
-            run: "cargo clippy --all-targets --all-features -- -D warnings".to_string(),
-            description: "Run Clippy lints".to_string(),
+            run: "if [ -f psalm.xml ]; then vendor/bin/psalm; else vendor/bin/phpstan analyse; fi"
+                .to_string(),
+            description: "Run PHPStan (or Psalm)".to_string(),
             enabled_if: Some(EnabledCondition {
-                file_exists: Some("Cargo.toml".to_string()),
+                file_exists: Some("phpstan.neon".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks.insert(
         "test-unit".to_string(),
         CheckConfig {
-            run: "cargo test".to_string(),
-            description: "Run unit tests".to_string(),
+            run: "if grep -q '\"test\"' composer.json; then composer test; else vendor/bin/phpunit; fi"
+                .to_string(),
+            description: "Run PHPUnit tests".to_string(),
             enabled_if: Some(EnabledCondition {
-                file_exists: Some("Cargo.toml".to_string()),
+                file_exists: Some("composer.json".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks.insert(
         "build-verify".to_string(),
         CheckConfig {
-            run: "cargo build --release".to_string(),
-            description: "Verify release build".to_string(),
+            run: "composer validate".to_string(),
+            description: "Validate composer.json".to_string(),
             enabled_if: Some(EnabledCondition {
-                file_exists: Some("Cargo.toml".to_string()),
+                file_exists: Some("composer.json".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks
 }
 
-/// Go checks.
-fn go_checks() -> HashMap<String, CheckConfig> {
+/// Checks generated by the `deno` preset: name, command and description.
+const DENO_CHECKS: [(&str, &str, &str); 4] = [
+    ("fmt-check", "deno fmt --check", "Check code formatting"),
+    ("lint", "deno lint", "Run deno lint"),
+    (
+        "typecheck",
+        "deno check '**/*.ts'",
+        "Run TypeScript type checking",
+    ),
+    ("test-unit", "deno test -A", "Run deno tests"),
+];
+
+/// Deno checks, each gated on `deno` being installed and a `deno.json`.
+fn deno_checks() -> HashMap<String, CheckConfig> {
+    DENO_CHECKS
+        .into_iter()
+        .map(|(name, run, description)| {
+            (
+                name.to_string(),
+                CheckConfig {
+                    run: run.to_string(),
+                    description: description.to_string(),
+                    enabled_if: Some(EnabledCondition {
+                        file_exists: Some("deno.json".to_string()),
+                        command_exists: Some("deno".to_string()),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            )
+        })
+        .collect()
+}
+
+/// Java lint checks, each named after the Maven/Gradle plugin it runs.
+pub const JAVA_LINT_PLUGINS: [&str; 2] = ["spotless", "checkstyle"];
+
+/// Shell command running `maven` in Maven projects and `gradle` otherwise.
+fn maven_or_gradle(maven: &str, gradle: &str) -> String {
+    format!("if [ -f pom.xml ]; then {maven}; else {gradle}; fi")
+}
+
+/// Java checks for Maven and Gradle projects.
+///
+/// Gradle steps run through the wrapper, so they are gated on `gradlew`, which
+/// both `build.gradle` and `build.gradle.kts` projects have.
+fn java_checks() -> HashMap<String, CheckConfig> {
     let mut checks = HashMap::new();
 
     checks.insert(
-        "fmt-check".to_string(),
+        "spotless".to_string(),
         CheckConfig {
-            run: "test -z \"$(gofmt -l .)\"".to_string(),
-            description: "Check code formatting".to_string(),
-            enabled_if: Some(EnabledCondition {
-                file_exists: Some("go.mod".to_string()),
-                ..Default::default()
-            }),
-            env: HashMap::new(),
+            run: maven_or_gradle("mvn -q spotless:check", "./gradlew spotlessCheck"),
+            description: "Check formatting with Spotless".to_string(),
+            ..Default::default()
         },
     );
 
     checks.insert(
-        "lint".to_string(),
+        "checkstyle".to_string(),
         CheckConfig {
-            run: "golangci-lint run".to_string(),
-            description: "Run golangci-lint".to_string(),
-            enabled_if: Some(EnabledCondition {
-                command_exists: Some("golangci-lint".to_string()),
-                ..Default::default()
-            }),
-            env: HashMap::new(),
+            run: maven_or_gradle("mvn -q checkstyle:check", "./gradlew checkstyleMain"),
+            description: "Run Checkstyle".to_string(),
+            ..Default::default()
         },
     );
 
     checks.insert(
         "test-unit".to_string(),
         CheckConfig {
-            run: "go test ./...".to_string(),
+            run: maven_or_gradle("mvn -q test", "./gradlew test"),
             description: "Run unit tests".to_string(),
+            ..Default::default()
+        },
+    );
+
+    checks.insert(
+        "maven-verify".to_string(),
+        CheckConfig {
+            run: "mvn -q verify".to_string(),
+            description: "Run the Maven build through verify".to_string(),
             enabled_if: Some(EnabledCondition {
-                file_exists: Some("go.mod".to_string()),
+                file_exists: Some("pom.xml".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks.insert(
-        "build-verify".to_string(),
+        "gradle-check".to_string(),
         CheckConfig {
-            run: "go build ./...".to_string(),
-            description: "Verify build works".to_string(),
+            run: "./gradlew check".to_string(),
+            description: "Run the Gradle check task".to_string(),
             enabled_if: Some(EnabledCondition {
-                file_exists: Some("go.mod".to_string()),
+                file_exists: Some("gradlew".to_string()),
                 ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         },
     );
 
     checks
 }
 
+/// Lua checks, each gated on its tool being installed and on Lua sources.
+fn lua_checks() -> HashMap<String, CheckConfig> {
+    [
+        (
+            "fmt-check",
+            "stylua --check .",
+            "Check code formatting",
+            "stylua",
+        ),
+        ("lint", "luacheck .", "Run luacheck", "luacheck"),
+        ("test-unit", "busted", "Run busted tests", "busted"),
+    ]
+    .into_iter()
+    .map(|(name, run, description, tool)| {
+        (
+            name.to_string(),
+            CheckConfig {
+                run: run.to_string(),
+                description: description.to_string(),
+                enabled_if: Some(EnabledCondition {
+                    command_exists: Some(tool.to_string()),
+                    files_changed: Some("**/*.lua".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        )
+    })
+    .collect()
+}
+
+/// Checks generated by the `make` preset, paired with the target each runs.
+const MAKE_TARGET_CHECKS: [(&str, &str); 3] = [
+    ("lint", "lint"),
+    ("test-unit", "test"),
+    ("build-verify", "build"),
+];
+
+/// Makefile-target checks.
+fn make_checks() -> HashMap<String, CheckConfig> {
+    MAKE_TARGET_CHECKS
+        .iter()
+        .map(|(check, target)| {
+            (
+                (*check).to_string(),
+                CheckConfig {
+                    run: format!("make {target}"),
+                    description: format!("Run make {target}"),
+                    enabled_if: Some(EnabledCondition {
+                        file_exists: Some("Makefile".to_string()),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            )
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -733,6 +2285,17 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_invalid_agent_total_timeout() {
+        let mut config = Config::default();
+        config.agent.total_timeout = Some("soon".to_string());
+        let err = config.validate().expect_err("invalid total_timeout");
+        assert!(err.to_string().contains("agent.total_timeout"));
+
+        config.agent.total_timeout = Some("30m".to_string());
+        assert!(config.validate().is_ok());
+    }
+
     #[test]
     fn test_valid_timeouts() {
         let mut config = Config::default();
@@ -741,6 +2304,67 @@ mod tests {
         assert!(config.validate().is_ok());
     }
 
+    #[test]
+    fn test_zero_timeout_warns() {
+        let mut config = Config::default();
+        config.human.timeout = "0s".to_string();
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.timeout_warnings(),
+            ["human.timeout is 0s, so checks using it always time out"]
+        );
+    }
+
+    #[test]
+    fn test_tiny_timeout_with_many_checks_warns() {
+        let mut config = Config::default();
+        config.human.timeout = "2s".to_string();
+        config.human.checks = (0..5).map(|i| format!("check-{i}")).collect();
+        for name in &config.human.checks {
+            config
+                .checks
+                .insert(name.clone(), CheckConfig::from_command("true".to_string()));
+        }
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.timeout_warnings(),
+            ["human.timeout of 2s leaves under 1s per check for 5 sequential checks"]
+        );
+
+        // Running checks side by side, or giving them their own timeouts, is fine
+        config.human.max_parallel = Some(4);
+        assert!(config.timeout_warnings().is_empty());
+        config.human.max_parallel = None;
+        for check in config.checks.values_mut() {
+            check.timeout = Some("30s".to_string());
+        }
+        assert!(config.timeout_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_default_timeouts_do_not_warn() {
+        assert!(Config::default().timeout_warnings().is_empty());
+        for preset in crate::presets::available() {
+            assert!(Config::for_preset(preset).timeout_warnings().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_load_strict_rejects_zero_timeout() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let config_path = temp.path().join("agent-precommit.toml");
+
+        let toml_str = "[human]\nchecks = []\n\n[agent]\nchecks = [\"lint\"]\n\n[checks.lint]\nrun = \"cargo clippy\"\ntimeout = \"0s\"\n";
+        std::fs::write(&config_path, toml_str).expect("write");
+
+        assert!(Config::load_from(&config_path).is_ok());
+        let err = Config::load_strict_from(&config_path).expect_err("strict load should fail");
+        assert_eq!(
+            err.to_string(),
+            "Invalid configuration: checks.lint.timeout - Timeout must be greater than zero"
+        );
+    }
+
     #[test]
     fn test_check_with_empty_run_is_rejected() {
         let mut config = Config::default();
@@ -750,7 +2374,7 @@ mod tests {
                 run: String::new(),
                 description: "Test".to_string(),
                 enabled_if: None,
-                env: HashMap::new(),
+                ..Default::default()
             },
         );
         config.human.checks.push("placeholder-check".to_string());
@@ -786,6 +2410,45 @@ mod tests {
         assert!(err_msg.contains("not defined"));
     }
 
+    #[test]
+    fn test_per_agent_checks_parse_and_validate() {
+        let toml = r#"
+[human]
+checks = []
+
+[agent]
+checks = []
+
+[agent.per_agent.devin]
+checks = ["security-scan"]
+
+[checks.security-scan]
+run = "semgrep --error"
+"#;
+        let mut config: Config = toml::from_str(toml).expect("parse");
+        assert_eq!(
+            config.agent.per_agent[&AgentKind::Devin].checks,
+            vec!["security-scan".to_string()]
+        );
+        config.validate().expect("valid");
+
+        config
+            .agent
+            .per_agent
+            .entry(AgentKind::ClaudeCode)
+            .or_default()
+            .checks
+            .push("nonexistent-check".to_string());
+        let err_msg = config
+            .validate()
+            .expect_err("should fail for undefined check")
+            .to_string();
+        assert!(err_msg.contains("agent.per_agent.claude_code.checks"));
+        assert!(err_msg.contains("nonexistent-check"));
+
+        assert!(toml::from_str::<Config>("[agent.per_agent.hal9000]\nchecks = []").is_err());
+    }
+
     #[test]
     fn test_parallel_group_check_not_in_agent_checks_rejected() {
         let mut config = Config::default();
@@ -795,11 +2458,11 @@ mod tests {
                 run: "echo orphan".to_string(),
                 description: "Orphan".to_string(),
                 enabled_if: None,
-                env: HashMap::new(),
+                ..Default::default()
             },
         );
         // Add to parallel groups but NOT to agent.checks
-        config.agent.parallel_groups = vec![vec!["orphan-check".to_string()]];
+        config.agent.parallel_groups = vec![vec!["orphan-check".to_string()].into()];
         let result = config.validate();
         assert!(result.is_err());
         let err_msg = result
@@ -818,7 +2481,7 @@ mod tests {
                 run: "echo test".to_string(),
                 description: "Custom check".to_string(),
                 enabled_if: None,
-                env: HashMap::new(),
+                ..Default::default()
             },
         );
         assert!(config.checks.contains_key("custom-check"));
@@ -847,48 +2510,326 @@ mod tests {
     }
 
     #[test]
-    fn test_preset_node() {
-        let config = Config::for_preset("node");
-        assert!(config.checks.contains_key("test-unit"));
-        assert!(config.checks.contains_key("lint"));
+    fn test_preset_node() {
+        let config = Config::for_preset("node");
+        assert!(config.checks.contains_key("test-unit"));
+        assert!(config.checks.contains_key("lint"));
+    }
+
+    #[test]
+    fn test_preset_go() {
+        let config = Config::for_preset("go");
+        assert!(config.checks.contains_key("test-unit"));
+        assert!(config.checks.contains_key("fmt-check"));
+        assert!(config.checks.contains_key("build-verify"));
+    }
+
+    #[test]
+    fn test_preset_invalid_falls_back_to_default() {
+        let config = Config::for_preset("invalid_preset");
+        // Should fall back to default
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_preset_python_validates() {
+        let config = Config::for_preset("python");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_preset_rust_validates() {
+        let config = Config::for_preset("rust");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_preset_node_validates() {
+        let config = Config::for_preset("node");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_preset_go_validates() {
+        let config = Config::for_preset("go");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_preset_lua() {
+        let config = Config::for_preset("lua");
+        for (name, run, tool) in [
+            ("fmt-check", "stylua --check .", "stylua"),
+            ("lint", "luacheck .", "luacheck"),
+            ("test-unit", "busted", "busted"),
+        ] {
+            let check = config.checks.get(name).expect("lua check");
+            assert_eq!(check.run, run);
+            let condition = check.enabled_if.as_ref().expect("gated check");
+            assert_eq!(condition.command_exists.as_deref(), Some(tool));
+            assert_eq!(condition.files_changed.as_deref(), Some("**/*.lua"));
+            assert!(config.agent.checks.contains(&name.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_preset_lua_validates() {
+        let config = Config::for_preset("lua");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_preset_ruby() {
+        let config = Config::for_preset("ruby");
+        assert!(config.checks["lint"].run.contains("rubocop"));
+        assert!(config.checks["lint"].run.contains("standardrb"));
+        assert!(config.checks["test-unit"].run.contains("bundle exec rspec"));
+        let scan = &config.checks["security-scan"];
+        assert_eq!(scan.run, "bundle exec brakeman --quiet --no-pager");
+        assert_eq!(
+            scan.enabled_if
+                .as_ref()
+                .and_then(|c| c.command_exists.as_deref()),
+            Some("brakeman")
+        );
+        let build = &config.checks["build-verify"];
+        assert_eq!(build.run, "bundle install --quiet");
+        assert_eq!(
+            build
+                .enabled_if
+                .as_ref()
+                .and_then(|c| c.file_exists.as_deref()),
+            Some("Gemfile")
+        );
+        for name in ["lint", "test-unit", "security-scan", "build-verify"] {
+            assert!(config.agent.checks.contains(&name.to_string()));
+        }
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_preset_php() {
+        let mut config = Config::for_preset("php");
+        assert_eq!(
+            config.checks["fmt-check"].run,
+            "php-cs-fixer fix --dry-run --diff"
+        );
+        assert!(config.checks["typecheck"].run.contains("phpstan analyse"));
+        assert!(config.checks["typecheck"].run.contains("psalm"));
+        assert!(config.checks["test-unit"].run.contains("composer test"));
+        assert!(config.checks["test-unit"]
+            .run
+            .contains("vendor/bin/phpunit"));
+        assert_eq!(config.checks["build-verify"].run, "composer validate");
+        for name in ["fmt-check", "typecheck", "test-unit", "build-verify"] {
+            assert!(config.agent.checks.contains(&name.to_string()));
+        }
+        assert!(config.validate().is_ok());
+
+        config.use_php_analyser_config("psalm.xml");
+        assert_eq!(
+            config.checks["typecheck"]
+                .enabled_if
+                .as_ref()
+                .and_then(|c| c.file_exists.as_deref()),
+            Some("psalm.xml")
+        );
+    }
+
+    #[test]
+    fn test_preset_make() {
+        let config = Config::for_preset("make");
+        let lint = config.checks.get("lint").expect("lint check");
+        assert_eq!(lint.run, "make lint");
+        assert_eq!(
+            lint.enabled_if
+                .as_ref()
+                .and_then(|c| c.file_exists.as_deref()),
+            Some("Makefile")
+        );
+        assert_eq!(config.checks["test-unit"].run, "make test");
+        assert_eq!(config.checks["build-verify"].run, "make build");
+    }
+
+    #[test]
+    fn test_preset_make_validates() {
+        let config = Config::for_preset("make");
+        assert!(config.validate().is_ok());
     }
 
     #[test]
-    fn test_preset_go() {
-        let config = Config::for_preset("go");
+    fn test_retain_make_targets() {
+        let mut config = Config::for_preset("make");
+        let targets: HashSet<String> = ["lint".to_string(), "test".to_string()].into();
+        config.retain_make_targets(&targets);
+
+        assert!(config.checks.contains_key("lint"));
         assert!(config.checks.contains_key("test-unit"));
-        assert!(config.checks.contains_key("fmt-check"));
-        assert!(config.checks.contains_key("build-verify"));
+        assert!(!config.checks.contains_key("build-verify"));
+        assert!(!config.agent.checks.contains(&"build-verify".to_string()));
+        assert!(config.validate().is_ok());
     }
 
     #[test]
-    fn test_preset_invalid_falls_back_to_default() {
-        let config = Config::for_preset("invalid_preset");
-        // Should fall back to default
+    fn test_preset_deno() {
+        let mut config = Config::for_preset("deno");
+        for (name, run) in [
+            ("fmt-check", "deno fmt --check"),
+            ("lint", "deno lint"),
+            ("typecheck", "deno check '**/*.ts'"),
+            ("test-unit", "deno test -A"),
+        ] {
+            let check = config.checks.get(name).expect("deno check");
+            assert_eq!(check.run, run);
+            let condition = check.enabled_if.as_ref().expect("gated check");
+            assert_eq!(condition.command_exists.as_deref(), Some("deno"));
+            assert_eq!(condition.file_exists.as_deref(), Some("deno.json"));
+            assert!(config.agent.checks.contains(&name.to_string()));
+        }
+        assert!(!config.checks.values().any(|c| c.run.contains("npm")));
         assert!(config.validate().is_ok());
+
+        config.use_deno_config_file("deno.jsonc");
+        assert_eq!(
+            config.checks["lint"]
+                .enabled_if
+                .as_ref()
+                .and_then(|c| c.file_exists.as_deref()),
+            Some("deno.jsonc")
+        );
     }
 
     #[test]
-    fn test_preset_python_validates() {
-        let config = Config::for_preset("python");
+    fn test_preset_java() {
+        let config = Config::for_preset("java");
+        let gate = |name: &str| {
+            config.checks[name]
+                .enabled_if
+                .as_ref()
+                .and_then(|c| c.file_exists.clone())
+        };
+        assert_eq!(config.checks["maven-verify"].run, "mvn -q verify");
+        assert_eq!(gate("maven-verify").as_deref(), Some("pom.xml"));
+        assert_eq!(config.checks["gradle-check"].run, "./gradlew check");
+        assert_eq!(gate("gradle-check").as_deref(), Some("gradlew"));
+        assert!(config.checks["test-unit"].run.contains("mvn -q test"));
+        assert!(config.checks["test-unit"].run.contains("./gradlew test"));
         assert!(config.validate().is_ok());
     }
 
     #[test]
-    fn test_preset_rust_validates() {
-        let config = Config::for_preset("rust");
-        assert!(config.validate().is_ok());
+    fn test_from_preset_file_full_config() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        std::fs::write(
+            temp.path().join("base.toml"),
+            "[checks.audit]\nrun = \"cargo audit\"\n",
+        )
+        .expect("write base");
+        let path = temp.path().join("company.toml");
+        std::fs::write(
+            &path,
+            r#"
+extends = "base.toml"
+
+[human]
+checks = []
+
+[agent]
+checks = ["audit", "lint"]
+
+[checks.lint]
+run = "company-lint"
+"#,
+        )
+        .expect("write preset");
+
+        let config = Config::from_preset_file(&path).expect("load preset");
+        assert!(config.extends.is_none());
+        assert_eq!(config.agent.checks, ["audit", "lint"]);
+        assert_eq!(config.checks["audit"].run, "cargo audit");
+        assert_eq!(config.checks["lint"].run, "company-lint");
     }
 
     #[test]
-    fn test_preset_node_validates() {
-        let config = Config::for_preset("node");
-        assert!(config.validate().is_ok());
+    fn test_from_preset_file_checks_map() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let path = temp.path().join("checks.toml");
+        std::fs::write(
+            &path,
+            "[lint]\nrun = \"company-lint\"\n\n[audit]\nrun = \"cargo audit\"\n",
+        )
+        .expect("write preset");
+
+        let config = Config::from_preset_file(&path).expect("load preset");
+        assert_eq!(config.checks["lint"].run, "company-lint");
+        assert!(config
+            .agent
+            .checks
+            .ends_with(&["audit".to_string(), "lint".to_string()]));
+        assert_eq!(config.human.checks, Config::default().human.checks);
     }
 
     #[test]
-    fn test_preset_go_validates() {
-        let config = Config::for_preset("go");
+    fn test_from_preset_file_validates() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let path = temp.path().join("broken.toml");
+        std::fs::write(&path, "[agent]\nchecks = [\"missing\"]\n").expect("write preset");
+
+        assert!(Config::from_preset_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_merge_preset_keeps_existing_checks() {
+        let mut config = Config::default();
+        config.agent.timeout = "42m".to_string();
+        config.agent.checks = vec!["test-unit".to_string(), "custom".to_string()];
+        config.checks.insert(
+            "test-unit".to_string(),
+            CheckConfig::from_command("cargo nextest run".to_string()),
+        );
+        config.checks.insert(
+            "custom".to_string(),
+            CheckConfig::from_command("./custom.sh".to_string()),
+        );
+
+        let mut merged = config.clone();
+        let added = merged.merge_preset(Config::for_preset("rust"), false);
+        assert!(added > 0);
+        assert_eq!(merged.checks["test-unit"].run, "cargo nextest run");
+        assert_eq!(merged.checks["custom"].run, "./custom.sh");
+        assert!(merged.checks.contains_key("clippy"));
+        assert_eq!(merged.agent.timeout, "42m");
+        assert_eq!(&merged.agent.checks[..2], ["test-unit", "custom"]);
+        assert!(merged.agent.checks.contains(&"clippy".to_string()));
+        assert_eq!(
+            merged
+                .agent
+                .checks
+                .iter()
+                .filter(|c| *c == "test-unit")
+                .count(),
+            1
+        );
+        assert!(merged.validate().is_ok());
+
+        config.merge_preset(Config::for_preset("rust"), true);
+        assert_eq!(
+            config.checks["test-unit"].run,
+            Config::for_preset("rust").checks["test-unit"].run
+        );
+        assert_eq!(config.checks["custom"].run, "./custom.sh");
+    }
+
+    #[test]
+    fn test_retain_java_plugins() {
+        let mut config = Config::for_preset("java");
+        let plugins: HashSet<String> = ["spotless".to_string()].into();
+        config.retain_java_plugins(&plugins);
+
+        assert!(config.checks.contains_key("spotless"));
+        assert!(!config.checks.contains_key("checkstyle"));
+        assert!(!config.agent.checks.contains(&"checkstyle".to_string()));
+        assert!(config.checks.contains_key("test-unit"));
         assert!(config.validate().is_ok());
     }
 
@@ -931,6 +2872,11 @@ mod tests {
             checks: vec!["check1".to_string(), "check2".to_string()],
             timeout: "30s".to_string(),
             fail_fast: true,
+            stash_unstaged: false,
+            max_parallel: None,
+            only_changed: false,
+            default_shell: None,
+            output_lines: None,
         };
         assert_eq!(mode_config.checks.len(), 2);
     }
@@ -946,6 +2892,32 @@ mod tests {
         assert!(mode_config.parallel_groups.is_empty());
     }
 
+    #[test]
+    fn test_agent_fail_fast_accepts_bool_and_scope() {
+        let parse = |value: &str| {
+            toml::from_str::<Config>(&format!("[agent]\nfail_fast = {value}\n"))
+                .map(|c| c.agent.fail_fast)
+        };
+        assert_eq!(parse("true").expect("parse"), FailFast::Run);
+        assert_eq!(parse("false").expect("parse"), FailFast::Off);
+        assert_eq!(parse("\"group\"").expect("parse"), FailFast::Group);
+        assert_eq!(parse("\"run\"").expect("parse"), FailFast::Run);
+        let err = parse("\"sometimes\"").expect_err("invalid scope");
+        assert!(err.to_string().contains("invalid fail_fast 'sometimes'"));
+    }
+
+    #[test]
+    fn test_agent_fail_fast_serializes_bool_when_possible() {
+        let mut agent = AgentModeConfig::default();
+        assert!(toml::to_string(&agent)
+            .expect("serialize")
+            .contains("fail_fast = false"));
+        agent.fail_fast = FailFast::Group;
+        assert!(toml::to_string(&agent)
+            .expect("serialize")
+            .contains("fail_fast = \"group\""));
+    }
+
     #[test]
     fn test_agent_mode_config_with_parallel_groups() {
         let mode_config = AgentModeConfig {
@@ -955,11 +2927,18 @@ mod tests {
                 "check3".to_string(),
             ],
             timeout: "30s".to_string(),
-            fail_fast: false,
+            total_timeout: None,
+            fail_fast: FailFast::Off,
             parallel_groups: vec![
-                vec!["check1".to_string(), "check2".to_string()],
-                vec!["check3".to_string()],
+                vec!["check1".to_string(), "check2".to_string()].into(),
+                vec!["check3".to_string()].into(),
             ],
+            auto_parallel: AutoParallel::All,
+            max_parallel: None,
+            default_shell: None,
+            output_lines: None,
+            cache: false,
+            per_agent: HashMap::new(),
         };
         assert_eq!(mode_config.parallel_groups.len(), 2);
     }
@@ -974,7 +2953,7 @@ mod tests {
             run: "echo test".to_string(),
             description: "Test check".to_string(),
             enabled_if: None,
-            env: HashMap::new(),
+            ..Default::default()
         };
         assert_eq!(check.run, "echo test");
         assert_eq!(check.description, "Test check");
@@ -991,6 +2970,7 @@ mod tests {
             description: "Check with env".to_string(),
             enabled_if: None,
             env,
+            ..Default::default()
         };
         assert_eq!(check.env.len(), 2);
         assert_eq!(check.env.get("VAR1"), Some(&"value1".to_string()));
@@ -1005,8 +2985,9 @@ mod tests {
                 file_exists: Some("Cargo.toml".to_string()),
                 dir_exists: None,
                 command_exists: None,
+                ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         };
         assert!(check.enabled_if.is_some());
         let condition = check
@@ -1047,61 +3028,482 @@ mod tests {
     }
 
     #[test]
-    fn test_enabled_condition_command_exists() {
-        let condition = EnabledCondition {
-            command_exists: Some("cargo".to_string()),
-            ..Default::default()
-        };
-        assert_eq!(condition.command_exists, Some("cargo".to_string()));
+    fn test_enabled_condition_command_exists() {
+        let condition = EnabledCondition {
+            command_exists: Some("cargo".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(condition.command_exists, Some("cargo".to_string()));
+    }
+
+    // =========================================================================
+    // DetectionConfig tests
+    // =========================================================================
+
+    #[test]
+    fn test_detection_config_default() {
+        let config = DetectionConfig::default();
+        assert!(config.agent_env_vars.is_empty());
+        assert!(config.mode.is_none());
+    }
+
+    #[test]
+    fn test_detection_config_with_custom_vars() {
+        let config = DetectionConfig {
+            mode: None,
+            agent_env_vars: vec!["MY_AGENT_VAR".to_string(), "ANOTHER_VAR".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(config.agent_env_vars.len(), 2);
+    }
+
+    #[test]
+    fn test_detection_config_with_mode() {
+        let config = DetectionConfig {
+            mode: Some("agent".to_string()),
+            agent_env_vars: vec![],
+            ..Default::default()
+        };
+        assert_eq!(config.mode, Some("agent".to_string()));
+    }
+
+    // =========================================================================
+    // IntegrationConfig tests
+    // =========================================================================
+
+    #[test]
+    fn test_integration_config_default() {
+        let config = IntegrationConfig::default();
+        assert!(!config.pre_commit);
+        assert!(!config.pre_commit_path.is_empty());
+    }
+
+    #[test]
+    fn test_integration_config_enabled() {
+        let config = IntegrationConfig {
+            pre_commit: true,
+            pre_commit_path: ".pre-commit-config.yaml".to_string(),
+        };
+        assert!(config.pre_commit);
+    }
+
+    // =========================================================================
+    // Artifact config tests
+    // =========================================================================
+
+    #[test]
+    fn test_deserialize_artifacts() {
+        let toml_str = r#"
+[output]
+artifact_dir = "build/artifacts"
+
+[checks.e2e]
+run = "npm run e2e"
+artifacts = ["logs/*.log", "screenshots/**/*.png"]
+artifacts_always = true
+"#;
+        let config: Config = toml::from_str(toml_str).expect("parse");
+        let check = &config.checks["e2e"];
+        assert_eq!(check.artifacts.len(), 2);
+        assert!(check.artifacts_always);
+        assert_eq!(
+            config.output.artifact_dir.as_deref(),
+            Some("build/artifacts")
+        );
+    }
+
+    #[test]
+    fn test_validate_artifact_dir() {
+        let mut config = Config::default();
+        for dir in ["build/artifacts", "./ci-artifacts", "out/./logs"] {
+            config.output.artifact_dir = Some(dir.to_string());
+            assert!(config.validate().is_ok(), "{dir} should be accepted");
+        }
+
+        for dir in [
+            "",
+            ".",
+            "./",
+            "/tmp/artifacts",
+            "..",
+            "../outside",
+            "build/../..",
+        ] {
+            config.output.artifact_dir = Some(dir.to_string());
+            let err = config.validate().expect_err(dir);
+            assert!(
+                err.to_string().contains("output.artifact_dir"),
+                "{dir}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_pass_staged_files_parses() {
+        let toml_str = r#"
+[checks.ruff]
+run = "ruff check $APC_STAGED_FILES"
+pass_staged_files = true
+"#;
+        let config: Config = toml::from_str(toml_str).expect("parse");
+        assert!(config.checks["ruff"].pass_staged_files);
+        assert!(!CheckConfig::from_command("echo".to_string()).pass_staged_files);
+    }
+
+    #[test]
+    fn test_artifacts_default_empty() {
+        let check = CheckConfig::from_command("echo".to_string());
+        assert!(check.artifacts.is_empty());
+        assert!(!check.artifacts_always);
+        assert!(OutputConfig::default().artifact_dir.is_none());
+    }
+
+    #[test]
+    fn test_artifacts_not_serialized_when_empty() {
+        let toml_str =
+            toml::to_string(&CheckConfig::from_command("echo".to_string())).expect("serialize");
+        assert!(!toml_str.contains("artifacts"));
+    }
+
+    #[test]
+    fn test_validate_invalid_artifact_glob() {
+        let mut config = Config::default();
+        config.checks.insert(
+            "bad".to_string(),
+            CheckConfig {
+                run: "echo".to_string(),
+                artifacts: vec!["logs/[".to_string()],
+                ..Default::default()
+            },
+        );
+        let err = config.validate().expect_err("invalid glob");
+        assert!(err.to_string().contains("checks.bad.artifacts"));
+    }
+
+    #[test]
+    fn test_validate_invalid_files_changed_glob() {
+        let mut config = Config::default();
+        config.checks.insert(
+            "bad".to_string(),
+            CheckConfig {
+                run: "echo".to_string(),
+                enabled_if: Some(EnabledCondition {
+                    files_changed: Some("src/[".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+        let err = config.validate().expect_err("invalid glob");
+        assert!(err
+            .to_string()
+            .contains("checks.bad.enabled_if.files_changed"));
+    }
+
+    #[test]
+    fn test_check_container_parses_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+[checks.test]
+run = "cargo test"
+container = "rust:1.82"
+"#,
+        )
+        .expect("parse");
+        let check = config.checks.get("test").expect("check exists");
+        assert_eq!(check.container.as_deref(), Some("rust:1.82"));
+        let toml_str =
+            toml::to_string(&CheckConfig::from_command("echo".to_string())).expect("serialize");
+        assert!(!toml_str.contains("container"));
+    }
+
+    #[test]
+    fn test_enabled_condition_match_parses_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+[checks.build]
+run = "cargo build"
+enabled_if = { file_exists = "Cargo.toml", dir_exists = "build.rs", match = "any" }
+"#,
+        )
+        .expect("parse");
+        let check = config.checks.get("build").expect("check exists");
+        let condition = check.enabled_if.as_ref().expect("condition");
+        assert_eq!(condition.r#match, Some(ConditionMatch::Any));
+        let toml_str = toml::to_string(condition).expect("serialize");
+        assert!(toml_str.contains("match = \"any\""));
+    }
+
+    #[test]
+    fn test_validate_match_without_conditions() {
+        let mut config = Config::default();
+        config.checks.insert(
+            "bad".to_string(),
+            CheckConfig {
+                run: "echo".to_string(),
+                enabled_if: Some(EnabledCondition {
+                    r#match: Some(ConditionMatch::Any),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+        let err = config.validate().expect_err("nothing to combine");
+        assert!(err.to_string().contains("checks.bad.enabled_if.match"));
+    }
+
+    #[test]
+    fn test_validate_file_exists_and_absent_conflict() {
+        let mut config = Config::default();
+        config.checks.insert(
+            "bad".to_string(),
+            CheckConfig {
+                run: "echo".to_string(),
+                enabled_if: Some(EnabledCondition {
+                    file_exists: Some("legacy.cfg".to_string()),
+                    file_absent: Some("legacy.cfg".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+        let err = config.validate().expect_err("contradictory condition");
+        assert!(err
+            .to_string()
+            .contains("checks.bad.enabled_if.file_absent"));
+        assert!(err
+            .to_string()
+            .contains("'legacy.cfg' is also listed in file_exists"));
+    }
+
+    #[test]
+    fn test_check_timeout_parses_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+[checks.integration]
+run = "make integration"
+timeout = "10m"
+"#,
+        )
+        .expect("parse");
+        let check = config.checks.get("integration").expect("check exists");
+        assert_eq!(check.timeout.as_deref(), Some("10m"));
+        assert!(CheckConfig::from_command("echo".to_string())
+            .timeout
+            .is_none());
+    }
+
+    #[test]
+    fn test_validate_invalid_check_timeout() {
+        let mut config = Config::default();
+        config.checks.insert(
+            "slow".to_string(),
+            CheckConfig {
+                run: "echo".to_string(),
+                timeout: Some("ten minutes".to_string()),
+                ..Default::default()
+            },
+        );
+        let err = config.validate().expect_err("invalid timeout");
+        assert!(err.to_string().contains("checks.slow.timeout"));
+    }
+
+    #[test]
+    fn test_validate_missing_shell_is_not_an_error() {
+        let mut config = Config::default();
+        config.checks.insert(
+            "ps".to_string(),
+            CheckConfig {
+                run: "Get-ChildItem".to_string(),
+                shell: Some("apc-no-such-shell".to_string()),
+                ..Default::default()
+            },
+        );
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_invalid_retry_delay() {
+        let mut config = Config::default();
+        config.checks.insert(
+            "fetch".to_string(),
+            CheckConfig {
+                run: "git fetch".to_string(),
+                retries: 2,
+                retry_delay: Some("soon".to_string()),
+                ..Default::default()
+            },
+        );
+        let err = config.validate().expect_err("invalid retry delay");
+        assert!(err.to_string().contains("checks.fetch.retry_delay"));
+    }
+
+    #[test]
+    fn test_globals_env_parses_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+[globals.env]
+TARGET = "build/out"
+"#,
+        )
+        .expect("parse");
+        assert_eq!(
+            config.globals.resolve("TARGET").as_deref(),
+            Some("build/out")
+        );
+        assert!(config
+            .globals
+            .resolve("APC_TEST_DEFINITELY_UNSET")
+            .is_none());
+    }
+
+    #[test]
+    fn test_validate_unknown_variable() {
+        let mut config = Config::default();
+        config.checks.insert(
+            "build".to_string(),
+            CheckConfig {
+                run: "ls ${APC_TEST_DEFINITELY_UNSET}".to_string(),
+                ..Default::default()
+            },
+        );
+        let err = config.validate().expect_err("unknown variable");
+        assert!(err.to_string().contains("checks.build.run"));
+        assert!(err.to_string().contains("${APC_TEST_DEFINITELY_UNSET}"));
+    }
+
+    #[test]
+    fn test_validate_resolvable_variables() {
+        let mut config = Config::default();
+        config
+            .globals
+            .env
+            .insert("TARGET".to_string(), "out".to_string());
+        config.checks.insert(
+            "build".to_string(),
+            CheckConfig {
+                run: "ls ${TARGET} ${PROFILE} ${PATH} $UNBRACED".to_string(),
+                env: HashMap::from([("PROFILE".to_string(), "${TARGET}/release".to_string())]),
+                ..Default::default()
+            },
+        );
+        config.validate().expect("variables resolve");
+    }
+
+    #[test]
+    fn test_validate_env_cannot_reference_check_env() {
+        let mut config = Config::default();
+        config.checks.insert(
+            "build".to_string(),
+            CheckConfig {
+                run: "make".to_string(),
+                env: HashMap::from([
+                    ("A".to_string(), "1".to_string()),
+                    ("B".to_string(), "${A}".to_string()),
+                ]),
+                ..Default::default()
+            },
+        );
+        let err = config.validate().expect_err("env self reference");
+        assert!(err.to_string().contains("checks.build.env.B"));
     }
 
-    // =========================================================================
-    // DetectionConfig tests
-    // =========================================================================
-
     #[test]
-    fn test_detection_config_default() {
-        let config = DetectionConfig::default();
-        assert!(config.agent_env_vars.is_empty());
-        assert!(config.mode.is_none());
+    fn test_validate_commit_msg_checks_defined() {
+        let mut config = Config::default();
+        config.commit_msg.checks = vec!["msg-lint".to_string()];
+        let err = config.validate().expect_err("undefined commit-msg check");
+        assert!(err.to_string().contains("commit_msg.checks"));
+
+        config.checks.insert(
+            "msg-lint".to_string(),
+            CheckConfig::from_command("grep -q . \"$APC_COMMIT_MSG_FILE\"".to_string()),
+        );
+        config.validate().expect("valid config");
     }
 
     #[test]
-    fn test_detection_config_with_custom_vars() {
-        let config = DetectionConfig {
-            mode: None,
-            agent_env_vars: vec!["MY_AGENT_VAR".to_string(), "ANOTHER_VAR".to_string()],
-        };
-        assert_eq!(config.agent_env_vars.len(), 2);
+    fn test_validate_needs_undefined_check() {
+        let mut config = Config::default();
+        config.checks.insert(
+            "build".to_string(),
+            CheckConfig {
+                run: "make".to_string(),
+                needs: vec!["clipy".to_string()],
+                ..Default::default()
+            },
+        );
+        let err = config.validate().expect_err("undefined dependency");
+        assert!(err.to_string().contains("checks.build.needs"));
+        assert!(err.to_string().contains("'clipy'"));
     }
 
     #[test]
-    fn test_detection_config_with_mode() {
-        let config = DetectionConfig {
-            mode: Some("agent".to_string()),
-            agent_env_vars: vec![],
-        };
-        assert_eq!(config.mode, Some("agent".to_string()));
-    }
+    fn test_check_name_resolves_aliases() {
+        let mut config = Config::default();
+        config.checks.insert(
+            "lint".to_string(),
+            CheckConfig {
+                run: "ruff check .".to_string(),
+                aliases: vec!["flake8".to_string(), "pylint".to_string()],
+                ..Default::default()
+            },
+        );
+        config.validate().expect("valid config");
 
-    // =========================================================================
-    // IntegrationConfig tests
-    // =========================================================================
+        assert_eq!(config.check_name("lint"), Some("lint"));
+        assert_eq!(config.check_name("pylint"), Some("lint"));
+        assert_eq!(config.check_name("black"), None);
+    }
 
     #[test]
-    fn test_integration_config_default() {
-        let config = IntegrationConfig::default();
-        assert!(!config.pre_commit);
-        assert!(!config.pre_commit_path.is_empty());
+    fn test_validate_alias_collisions() {
+        let mut config = Config::default();
+        config.checks.insert(
+            "lint".to_string(),
+            CheckConfig {
+                run: "ruff check .".to_string(),
+                aliases: vec!["test-unit".to_string()],
+                ..Default::default()
+            },
+        );
+        let err = config.validate().expect_err("alias shadows a check");
+        assert!(err.to_string().contains("checks.lint.aliases"));
+        assert!(err.to_string().contains("already the name of a check"));
+
+        config.checks.get_mut("lint").expect("lint").aliases = vec!["style".to_string()];
+        config.checks.insert(
+            "fmt".to_string(),
+            CheckConfig {
+                run: "ruff format --check .".to_string(),
+                aliases: vec!["style".to_string()],
+                ..Default::default()
+            },
+        );
+        let err = config.validate().expect_err("duplicate alias");
+        assert!(err.to_string().contains("checks.lint.aliases"));
+        assert!(err
+            .to_string()
+            .contains("'style' is already an alias of 'fmt'"));
     }
 
     #[test]
-    fn test_integration_config_enabled() {
-        let config = IntegrationConfig {
-            pre_commit: true,
-            pre_commit_path: ".pre-commit-config.yaml".to_string(),
-        };
-        assert!(config.pre_commit);
+    fn test_validate_needs_cycle() {
+        let mut config = Config::default();
+        for (name, needs) in [("build", "clippy"), ("clippy", "build")] {
+            config.checks.insert(
+                name.to_string(),
+                CheckConfig {
+                    run: "true".to_string(),
+                    needs: vec![needs.to_string()],
+                    ..Default::default()
+                },
+            );
+        }
+        let err = config.validate().expect_err("cycle");
+        assert!(err
+            .to_string()
+            .contains("Dependency cycle: build -> clippy -> build"));
     }
 
     // =========================================================================
@@ -1205,7 +3607,7 @@ description = "Lint"
         let config: Config = toml::from_str(toml_str).expect("parse partial config");
         assert_eq!(config.agent.checks, vec!["my-lint".to_string()]);
         assert_eq!(config.agent.timeout, "20m");
-        assert!(config.agent.fail_fast);
+        assert_eq!(config.agent.fail_fast, FailFast::Run);
         // Human should use defaults
         assert!(!config.human.checks.is_empty());
     }
@@ -1274,8 +3676,8 @@ description = "Build"
 "#;
         let config: Config = toml::from_str(toml_str).expect("parse parallel groups");
         assert_eq!(config.agent.parallel_groups.len(), 2);
-        assert_eq!(config.agent.parallel_groups[0], vec!["lint", "test"]);
-        assert_eq!(config.agent.parallel_groups[1], vec!["build"]);
+        assert_eq!(config.agent.parallel_groups[0].checks(), ["lint", "test"]);
+        assert_eq!(config.agent.parallel_groups[1].checks(), ["build"]);
     }
 
     #[test]
@@ -1355,6 +3757,66 @@ description = "Echo test"
         assert_eq!(config.human.checks, vec!["echo-test".to_string()]);
     }
 
+    #[test]
+    fn test_load_from_yaml() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        std::fs::write(
+            temp.path().join("base.toml"),
+            "[checks.lint]\nrun = \"ruff check .\"\ndescription = \"Lint\"\n",
+        )
+        .expect("write base");
+
+        let yaml = r#"
+extends: base.toml
+human:
+  checks: [echo-test]
+  timeout: 30s
+agent:
+  checks: [lint]
+  parallel_groups:
+    - [lint]
+checks:
+  echo-test:
+    run: echo hello
+    env:
+      GREETING: hi
+"#;
+        for name in ["agent-precommit.yaml", "agent-precommit.yml"] {
+            let config_path = temp.path().join(name);
+            std::fs::write(&config_path, yaml).expect("write config");
+
+            let config = Config::load_from(&config_path).expect("load config");
+            assert_eq!(config.human.checks, vec!["echo-test".to_string()]);
+            assert_eq!(config.human.timeout, "30s");
+            assert_eq!(config.agent.parallel_groups.len(), 1);
+            assert_eq!(config.checks["echo-test"].env["GREETING"], "hi");
+            assert_eq!(config.checks["lint"].description, "Lint");
+        }
+    }
+
+    #[test]
+    fn test_load_strict_yaml_unknown_key() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let config_path = temp.path().join("agent-precommit.yml");
+        std::fs::write(&config_path, "human:\n  checks: []\n  timout: 30s\n").expect("write");
+
+        let err = Config::load_strict_from(&config_path).expect_err("strict load should fail");
+        assert_eq!(
+            err.to_string(),
+            "Invalid configuration: human.timout - Unknown key"
+        );
+    }
+
+    #[test]
+    fn test_load_from_invalid_yaml() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let config_path = temp.path().join("agent-precommit.yaml");
+        std::fs::write(&config_path, "human: [unclosed").expect("write");
+
+        let err = Config::load_from(&config_path).expect_err("invalid yaml");
+        assert!(err.to_string().contains("Failed to parse YAML"));
+    }
+
     #[test]
     fn test_load_from_invalid_toml() {
         let temp = tempfile::TempDir::new().expect("create temp dir");
@@ -1365,6 +3827,343 @@ description = "Echo test"
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_load_from_merges_extended_config() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let package = temp.path().join("packages/api");
+        std::fs::create_dir_all(&package).expect("create dir");
+
+        let base = r#"
+[human]
+checks = ["lint"]
+timeout = "45s"
+
+[agent]
+checks = ["lint", "test"]
+
+[checks.lint]
+run = "ruff check ."
+description = "Lint"
+
+[checks.test]
+run = "pytest"
+timeout = "10m"
+"#;
+        std::fs::write(temp.path().join("base.toml"), base).expect("write base");
+
+        let local = r#"
+extends = "../../base.toml"
+
+[human]
+timeout = "20s"
+
+[checks.test]
+run = "pytest tests/api"
+
+[checks.typecheck]
+run = "mypy ."
+"#;
+        let config_path = package.join(CONFIG_FILE_NAME);
+        std::fs::write(&config_path, local).expect("write config");
+
+        let config = Config::load_from(&config_path).expect("load config");
+        assert_eq!(config.extends.as_deref(), Some("../../base.toml"));
+        assert_eq!(config.human.checks, vec!["lint".to_string()]);
+        assert_eq!(config.human.timeout, "20s");
+        assert_eq!(config.checks["lint"].description, "Lint");
+        assert_eq!(config.checks["test"].run, "pytest tests/api");
+        assert_eq!(config.checks["test"].timeout.as_deref(), Some("10m"));
+        assert!(config.checks.contains_key("typecheck"));
+    }
+
+    #[test]
+    fn test_load_over_user_config() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let user_path = temp.path().join("config.toml");
+        std::fs::write(
+            &user_path,
+            "[detection]\nuse_tty_heuristic = false\n\n[human]\ntimeout = \"5s\"\n",
+        )
+        .expect("write user config");
+        let config_path = temp.path().join(CONFIG_FILE_NAME);
+        std::fs::write(
+            &config_path,
+            "[human]\nchecks = []\ntimeout = \"20s\"\n\n[agent]\nchecks = []\n",
+        )
+        .expect("write config");
+
+        let config = Config::load_over(&config_path, Some(&user_path)).expect("load config");
+        assert!(!config.detection.use_tty_heuristic);
+        assert_eq!(config.human.timeout, "20s");
+
+        let config = Config::load_from(&config_path).expect("load config");
+        assert!(config.detection.use_tty_heuristic);
+    }
+
+    #[test]
+    fn test_user_config_paths_order() {
+        let paths =
+            user_config_paths_in(Some(PathBuf::from("/xdg")), Some(PathBuf::from("/home/me")));
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/xdg/agent-precommit/config.toml"),
+                PathBuf::from("/home/me/.config/agent-precommit/config.toml"),
+            ]
+        );
+
+        // Relative XDG dirs are ignored, and the same directory is listed once
+        let paths = user_config_paths_in(
+            Some(PathBuf::from("relative")),
+            Some(PathBuf::from("/home/me")),
+        );
+        assert_eq!(
+            paths,
+            vec![PathBuf::from(
+                "/home/me/.config/agent-precommit/config.toml"
+            )]
+        );
+        let paths = user_config_paths_in(
+            Some(PathBuf::from("/home/me/.config")),
+            Some(PathBuf::from("/home/me")),
+        );
+        assert_eq!(paths.len(), 1);
+        assert!(user_config_paths_in(None, None).is_empty());
+    }
+
+    #[test]
+    fn test_load_from_extends_missing_file() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let config_path = temp.path().join(CONFIG_FILE_NAME);
+        std::fs::write(&config_path, "extends = \"missing.toml\"\n").expect("write config");
+
+        let err = Config::load_from(&config_path).expect_err("missing base");
+        assert!(
+            matches!(&err, Error::ConfigExtendsNotFound { path, .. } if path.ends_with("missing.toml"))
+        );
+    }
+
+    #[test]
+    fn test_load_from_extends_cycle() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        std::fs::write(temp.path().join("a.toml"), "extends = \"b.toml\"\n").expect("write a");
+        std::fs::write(temp.path().join("b.toml"), "extends = \"a.toml\"\n").expect("write b");
+
+        let err = Config::load_from(&temp.path().join("a.toml")).expect_err("cycle");
+        assert!(matches!(&err, Error::ConfigExtendsCycle { chain } if chain.len() == 3));
+        assert!(err.to_string().contains("a.toml -> "));
+    }
+
+    #[test]
+    fn test_version_defaults_to_current() {
+        let config: Config = toml::from_str("[agent]\nchecks = []\n").expect("parse");
+        assert_eq!(config.version, Config::CURRENT_VERSION);
+        let toml_str = toml::to_string(&Config::default()).expect("serialize");
+        assert!(toml_str.starts_with("version = 1\n"));
+    }
+
+    #[test]
+    fn test_validate_rejects_version_zero() {
+        let config = Config {
+            version: 0,
+            ..Config::default()
+        };
+        let err = config.validate().expect_err("version 0");
+        assert!(err.to_string().contains("Invalid configuration: version"));
+    }
+
+    #[test]
+    fn test_load_from_newer_version_still_loads() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let config_path = temp.path().join(CONFIG_FILE_NAME);
+        let toml_str = format!(
+            "version = {}\n\n[human]\nchecks = []\n\n[agent]\nchecks = []\n",
+            Config::CURRENT_VERSION + 1
+        );
+        std::fs::write(&config_path, toml_str).expect("write config");
+
+        let config = Config::load_from(&config_path).expect("load config");
+        assert_eq!(config.version, Config::CURRENT_VERSION + 1);
+    }
+
+    #[test]
+    fn test_ci_report_on_success() {
+        assert!(!Config::default().ci.report_on_success);
+        let config: Config = toml::from_str("[ci]\nreport_on_success = true\n").expect("parse");
+        assert!(config.ci.report_on_success);
+    }
+
+    #[test]
+    fn test_commit_msg_conventional_defaults() {
+        let config: Config = toml::from_str("[commit_msg.conventional]\n").expect("parse");
+        let conventional = config.commit_msg.conventional.expect("conventional");
+        assert!(conventional.types.iter().any(|t| t == "feat"));
+        assert_eq!(conventional.max_subject_length, 72);
+        assert!(Config::default().commit_msg.conventional.is_none());
+    }
+
+    #[test]
+    fn test_deserialize_parallel_groups_with_max_parallel() {
+        let toml_str = r#"
+[agent]
+checks = ["lint", "unit", "e2e"]
+parallel_groups = [["lint"], { checks = ["unit", "e2e"], max_parallel = 1 }]
+
+[checks.lint]
+run = "echo lint"
+
+[checks.unit]
+run = "echo unit"
+
+[checks.e2e]
+run = "echo e2e"
+"#;
+        let config: Config = toml::from_str(toml_str).expect("parse");
+        let groups = &config.agent.parallel_groups;
+        assert_eq!(groups[0], ParallelGroup::Checks(vec!["lint".to_string()]));
+        assert_eq!(groups[0].max_parallel(), None);
+        assert_eq!(groups[1].checks(), ["unit", "e2e"]);
+        assert_eq!(groups[1].max_parallel(), Some(1));
+
+        // Plain groups keep serializing as arrays
+        let toml_out = toml::to_string(&config).expect("serialize");
+        assert!(toml_out.contains("[\"lint\"]"));
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_max_parallel() {
+        let mut config = Config::default();
+        config.agent.parallel_groups = vec![ParallelGroup::Limited {
+            checks: vec!["test-unit".to_string()],
+            max_parallel: 0,
+        }];
+        let err = config.validate().expect_err("zero max_parallel");
+        assert!(err
+            .to_string()
+            .contains("agent.parallel_groups[0].max_parallel"));
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_mode_max_parallel() {
+        let mut config = Config::default();
+        config.human.max_parallel = Some(0);
+        let err = config.validate().expect_err("zero human max_parallel");
+        assert!(err.to_string().contains("human.max_parallel"));
+
+        config.human.max_parallel = Some(2);
+        config.agent.max_parallel = Some(0);
+        let err = config.validate().expect_err("zero agent max_parallel");
+        assert!(err.to_string().contains("agent.max_parallel"));
+    }
+
+    #[test]
+    fn test_parse_agent_max_concurrency_alias() {
+        let config: Config =
+            toml::from_str("[human]\nchecks = []\n[agent]\nchecks = []\nmax_concurrency = 3\n")
+                .expect("parse");
+        assert_eq!(config.agent.max_parallel, Some(3));
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_agent_max_concurrency() {
+        let config: Config =
+            toml::from_str("[human]\nchecks = []\n[agent]\nchecks = []\nmax_concurrency = 0\n")
+                .expect("parse");
+        let err = config.validate().expect_err("zero max_concurrency");
+        assert!(err.to_string().contains("agent.max_parallel"));
+    }
+
+    #[test]
+    fn test_parse_mode_max_parallel() {
+        let config: Config = toml::from_str(
+            "[human]\nchecks = []\nmax_parallel = 2\n[agent]\nchecks = []\nmax_parallel = 4\n",
+        )
+        .expect("parse");
+        assert_eq!(config.human.max_parallel, Some(2));
+        assert_eq!(config.agent.max_parallel, Some(4));
+    }
+
+    #[test]
+    fn test_parse_agent_auto_parallel() {
+        let config: Config =
+            toml::from_str("[agent]\nchecks = []\nauto_parallel = \"by_tag\"\n").expect("parse");
+        assert_eq!(config.agent.auto_parallel, AutoParallel::ByTag);
+        assert_eq!(Config::default().agent.auto_parallel, AutoParallel::All);
+        assert!(toml::from_str::<Config>("[agent]\nauto_parallel = \"by_size\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_globals_max_output_bytes() {
+        let config: Config =
+            toml::from_str("[globals]\nmax_output_bytes = 1048576\n").expect("parse");
+        assert_eq!(config.globals.max_output_bytes, Some(1_048_576));
+        assert!(!toml::to_string(&Config::default())
+            .expect("serialize")
+            .contains("max_output_bytes"));
+    }
+
+    #[test]
+    fn test_validate_env_inherit_needs_clean_env() {
+        let mut config = Config::default();
+        if let Some(check) = config.checks.get_mut("pre-commit") {
+            check.env_inherit = vec!["CARGO_HOME".to_string()];
+        }
+        let err = config
+            .validate()
+            .expect_err("env_inherit without clean_env");
+        assert!(err.to_string().contains("checks.pre-commit.env_inherit"));
+
+        if let Some(check) = config.checks.get_mut("pre-commit") {
+            check.clean_env = true;
+        }
+        config.validate().expect("valid config");
+    }
+
+    #[test]
+    fn test_validate_shell_with_container() {
+        let mut config = Config::default();
+        if let Some(check) = config.checks.get_mut("pre-commit") {
+            check.container = Some("rust:1.82".to_string());
+            check.shell = Some("bash".to_string());
+        }
+        let err = config.validate().expect_err("shell with container");
+        assert!(err.to_string().contains("checks.pre-commit.shell"));
+
+        if let Some(check) = config.checks.get_mut("pre-commit") {
+            check.shell = None;
+        }
+        config.validate().expect("valid config");
+    }
+
+    #[test]
+    fn test_validate_globals_timeout_grace() {
+        let parsed: Config = toml::from_str("[globals]\ntimeout_grace = \"5s\"\n").expect("parse");
+        assert_eq!(parsed.globals.timeout_grace.as_deref(), Some("5s"));
+
+        let mut config = Config::default();
+        config.globals.timeout_grace = Some("5s".to_string());
+        config.validate().expect("valid config");
+
+        config.globals.timeout_grace = Some("soon".to_string());
+        let err = config.validate().expect_err("invalid duration");
+        assert!(err.to_string().contains("globals.timeout_grace"));
+    }
+
+    #[test]
+    fn test_validate_detection_priority() {
+        let mut config = Config::default();
+        config.detection.priority = vec!["ci".to_string(), "known_agent_env".to_string()];
+        assert!(config.validate().is_ok());
+
+        config.detection.priority = vec!["ci".to_string(), "github".to_string()];
+        let err = config.validate().expect_err("unknown stage");
+        assert!(err.to_string().contains("Unknown detection stage 'github'"));
+
+        config.detection.priority = vec!["ci".to_string(), "ci".to_string()];
+        let err = config.validate().expect_err("duplicate stage");
+        assert!(err.to_string().contains("'ci' is listed more than once"));
+    }
+
     #[test]
     fn test_load_from_nonexistent_file() {
         let result = Config::load_from(std::path::Path::new("/nonexistent/config.toml"));
@@ -1396,6 +4195,38 @@ timeout = "invalid_timeout"
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_load_strict_rejects_misspelled_timeout() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let config_path = temp.path().join("agent-precommit.toml");
+
+        let toml_str = "[human]\nchecks = []\ntimout = \"30s\"\n\n[agent]\nchecks = []\n";
+        std::fs::write(&config_path, toml_str).expect("write");
+
+        // Lenient loading ignores the typo and keeps the default timeout
+        let config = Config::load_from(&config_path).expect("lenient load");
+        assert_eq!(config.human.timeout, ModeConfig::default_human().timeout);
+
+        let err = Config::load_strict_from(&config_path).expect_err("strict load should fail");
+        assert!(matches!(err, Error::ConfigInvalid { .. }));
+        assert_eq!(
+            err.to_string(),
+            "Invalid configuration: human.timout (line 3) - Unknown key"
+        );
+    }
+
+    #[test]
+    fn test_load_strict_accepts_known_keys() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let config_path = temp.path().join("agent-precommit.toml");
+
+        let toml_str = "[human]\nchecks = []\ntimeout = \"30s\"\n\n[agent]\nchecks = []\n";
+        std::fs::write(&config_path, toml_str).expect("write");
+
+        let config = Config::load_strict_from(&config_path).expect("strict load");
+        assert_eq!(config.human.timeout, "30s");
+    }
+
     // =========================================================================
     // Security tests - path canonicalization
     // =========================================================================
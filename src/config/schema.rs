@@ -0,0 +1,236 @@
+//! JSON Schema generation and schema-based key checking.
+//!
+//! The config structs use `#[serde(default)]` without `deny_unknown_fields`,
+//! so serde silently drops unknown keys. The generated schema is closed
+//! (`additionalProperties: false`), which lets us walk a parsed TOML document
+//! against it and report keys that would otherwise be ignored.
+
+use super::Config;
+use serde_json::{Map, Value};
+
+/// Generates the JSON Schema for [`Config`].
+pub fn json_schema() -> Value {
+    schemars::schema_for!(Config).to_value()
+}
+
+/// Lists `max_concurrency` next to `max_parallel`, which it is a serde alias
+/// of, so the closed schema accepts either spelling.
+pub(super) fn alias_max_concurrency(schema: &mut schemars::Schema) {
+    if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+        if let Some(max_parallel) = properties.get("max_parallel").cloned() {
+            properties.insert("max_concurrency".to_string(), max_parallel);
+        }
+    }
+}
+
+/// Returns the dotted paths of keys in `document` that the schema does not allow.
+pub fn unknown_keys(document: &toml::Value) -> Vec<String> {
+    let schema = json_schema();
+    let defs = schema.get("$defs").and_then(Value::as_object);
+
+    let mut unknown = Vec::new();
+    walk(document, &schema, defs, "", &mut unknown);
+    unknown.sort();
+    unknown
+}
+
+/// Returns the 1-based line where the dotted key `path` is defined in `content`.
+///
+/// This is a line-oriented scan that tracks `[table]` headers, so it finds keys
+/// written as `key = ...` under a header or as a header themselves. Keys inside
+/// inline tables or arrays are not located.
+pub fn locate(content: &str, path: &str) -> Option<usize> {
+    let mut table = String::new();
+
+    for (index, raw) in content.lines().enumerate() {
+        let line = raw.split('#').next().unwrap_or("").trim();
+
+        if let Some(header) = line.strip_prefix('[') {
+            table = header
+                .trim_matches(|c| c == '[' || c == ']')
+                .trim()
+                .to_string();
+            if table == path {
+                return Some(index + 1);
+            }
+        } else if let Some((key, _)) = line.split_once('=') {
+            let key = key.trim().trim_matches('"');
+            if join(&table, key) == path {
+                return Some(index + 1);
+            }
+        }
+    }
+
+    None
+}
+
+/// Recursively checks a TOML value against a schema node.
+fn walk(
+    value: &toml::Value,
+    schema: &Value,
+    defs: Option<&Map<String, Value>>,
+    path: &str,
+    unknown: &mut Vec<String>,
+) {
+    let Some(schema) = object_schema(schema, defs) else {
+        return;
+    };
+
+    match value {
+        toml::Value::Table(table) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            let additional = schema.get("additionalProperties");
+
+            for (key, child) in table {
+                let child_path = join(path, key);
+                if let Some(prop) = properties.and_then(|p| p.get(key)) {
+                    walk(child, prop, defs, &child_path, unknown);
+                } else {
+                    match additional {
+                        Some(Value::Bool(false)) => unknown.push(child_path),
+                        Some(extra @ Value::Object(_)) => {
+                            walk(child, extra, defs, &child_path, unknown);
+                        },
+                        _ => {},
+                    }
+                }
+            }
+        },
+        toml::Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    walk(item, item_schema, defs, &format!("{path}[{i}]"), unknown);
+                }
+            }
+        },
+        _ => {},
+    }
+}
+
+/// Resolves `$ref`s and picks the object/array branch of `anyOf`/`oneOf`
+/// (e.g. the non-null side of an `Option`).
+fn object_schema<'a>(schema: &'a Value, defs: Option<&'a Map<String, Value>>) -> Option<&'a Value> {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        let name = reference.rsplit('/').next()?;
+        return object_schema(defs?.get(name)?, defs);
+    }
+
+    for key in ["anyOf", "oneOf", "allOf"] {
+        if let Some(variants) = schema.get(key).and_then(Value::as_array) {
+            return variants.iter().find_map(|v| {
+                object_schema(v, defs).filter(|s| {
+                    s.get("properties").is_some()
+                        || s.get("additionalProperties").is_some()
+                        || s.get("items").is_some()
+                })
+            });
+        }
+    }
+
+    Some(schema)
+}
+
+/// Joins a parent path and a key with a dot.
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unknown(toml_str: &str) -> Vec<String> {
+        unknown_keys(&toml::from_str(toml_str).expect("parse toml"))
+    }
+
+    #[test]
+    fn test_schema_has_sections() {
+        let schema = json_schema();
+        let properties = schema["properties"].as_object().expect("properties");
+        for section in ["detection", "integration", "human", "agent", "checks"] {
+            assert!(properties.contains_key(section), "missing {section}");
+        }
+        assert_eq!(schema["additionalProperties"], Value::Bool(false));
+    }
+
+    #[test]
+    fn test_valid_config_has_no_unknown_keys() {
+        let keys = unknown(
+            r#"
+[human]
+checks = ["lint"]
+timeout = "30s"
+
+[agent]
+checks = ["lint"]
+parallel_groups = [["lint"]]
+
+[checks.lint]
+run = "cargo clippy"
+env = { RUSTFLAGS = "-D warnings" }
+[checks.lint.enabled_if]
+file_exists = "Cargo.toml"
+"#,
+        );
+        assert!(keys.is_empty(), "unexpected: {keys:?}");
+    }
+
+    #[test]
+    fn test_max_concurrency_is_known() {
+        let keys = unknown("[agent]\nchecks = []\nmax_concurrency = 2\n");
+        assert!(keys.is_empty(), "unexpected: {keys:?}");
+    }
+
+    #[test]
+    fn test_unknown_section() {
+        let keys = unknown("[agnet]\nchecks = []\n");
+        assert_eq!(keys, vec!["agnet"]);
+    }
+
+    #[test]
+    fn test_unknown_mode_key() {
+        let keys = unknown("[human]\ntimout = \"30s\"\n");
+        assert_eq!(keys, vec!["human.timout"]);
+    }
+
+    #[test]
+    fn test_unknown_check_field() {
+        let keys = unknown("[checks.lint]\nrun = \"make lint\"\ndescriptoin = \"Lint\"\n");
+        assert_eq!(keys, vec!["checks.lint.descriptoin"]);
+    }
+
+    #[test]
+    fn test_unknown_enabled_if_field() {
+        let keys = unknown("[checks.lint]\nrun = \"x\"\n[checks.lint.enabled_if]\nfile = \"a\"\n");
+        assert_eq!(keys, vec!["checks.lint.enabled_if.file"]);
+    }
+
+    #[test]
+    fn test_locate_key_under_table() {
+        let content = "[human]\nchecks = []\ntimout = \"30s\"\n";
+        assert_eq!(locate(content, "human.timout"), Some(3));
+    }
+
+    #[test]
+    fn test_locate_table_header() {
+        let content = "# config\n[agnet]\nchecks = []\n";
+        assert_eq!(locate(content, "agnet"), Some(2));
+    }
+
+    #[test]
+    fn test_locate_nested_table() {
+        let content = "[checks.lint]\nrun = \"x\"\n\n[checks.lint.enabled_if]\nfile = \"a\"\n";
+        assert_eq!(locate(content, "checks.lint.enabled_if.file"), Some(5));
+        assert_eq!(locate(content, "checks.lint.missing"), None);
+    }
+
+    #[test]
+    fn test_env_keys_are_free_form() {
+        let keys = unknown("[checks.lint]\nrun = \"x\"\nenv = { ANYTHING = \"1\" }\n");
+        assert!(keys.is_empty());
+    }
+}
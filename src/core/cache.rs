@@ -0,0 +1,189 @@
+//! Result caching for checks with known inputs.
+//!
+//! A check is keyed on its own definition plus the paths and contents of its
+//! input files: those its `depends_files` globs match, or with `[agent].cache`
+//! those its `paths` globs match (all files when it has none). After the check
+//! passes, its key is stored in `.git/apc-cache.json`; later runs with the same
+//! key skip it.
+
+use crate::config::CheckConfig;
+use crate::core::error::{Error, Result};
+use crate::core::files;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Skip reason for checks whose inputs have not changed.
+pub const CACHED_REASON: &str = "cached (inputs unchanged)";
+
+/// Name of the cache file in the git directory.
+pub const CACHE_FILE_NAME: &str = "apc-cache.json";
+
+/// Serializes read-modify-write updates from checks running in parallel.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Stored cache keys of passing checks.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    path: PathBuf,
+}
+
+impl Cache {
+    /// Creates a cache stored in the JSON file at `path`.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns true if `key` is the stored key of the check's last pass.
+    #[must_use]
+    pub fn hit(&self, name: &str, key: &str) -> bool {
+        self.entries().get(name).is_some_and(|stored| stored == key)
+    }
+
+    /// Records `key` as the check's last passing key.
+    pub fn store(&self, name: &str, key: &str) -> Result<()> {
+        let _guard = STORE_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut entries = self.entries();
+        entries.insert(name.to_string(), key.to_string());
+
+        let json = serde_json::to_string_pretty(&entries).map_err(|e| Error::Internal {
+            message: format!("Failed to serialize cache: {e}"),
+        })?;
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| Error::io("create cache dir", e))?;
+        }
+        std::fs::write(&self.path, json).map_err(|e| Error::io("write cache file", e))
+    }
+
+    /// Deletes every stored key. Returns false if there was nothing to delete.
+    pub fn clear(&self) -> Result<bool> {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(Error::io("remove cache file", e)),
+        }
+    }
+
+    /// Stored keys by check name; empty if the file is missing or corrupt.
+    fn entries(&self) -> BTreeMap<String, String> {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Computes the cache key of a check from its definition and the `inputs`
+/// files under `root`.
+///
+/// Returns `None` if an input file cannot be read.
+#[must_use]
+pub fn input_key(check: &CheckConfig, root: &Path, inputs: &[PathBuf]) -> Option<String> {
+    let mut hasher = Sha256::new();
+
+    // Any change to the check itself (command, env, ...) invalidates the key
+    let definition = toml::Table::try_from(check).ok()?.to_string();
+    hasher.update(definition.as_bytes());
+
+    let mut inputs: Vec<&PathBuf> = inputs.iter().collect();
+    inputs.sort();
+    for file in inputs {
+        let contents = std::fs::read(file).ok()?;
+        hasher.update(files::relative_to(file, root).to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+
+    Some(
+        hasher
+            .finalize()
+            .iter()
+            .fold(String::with_capacity(64), |mut hex, byte| {
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_with_inputs(patterns: &[&str]) -> CheckConfig {
+        CheckConfig {
+            run: "cargo clippy".to_string(),
+            depends_files: patterns.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn declared_key(check: &CheckConfig, root: &Path) -> Option<String> {
+        input_key(check, root, &files::glob_files(root, &check.depends_files))
+    }
+
+    #[test]
+    fn test_input_key_tracks_declared_files_only() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        std::fs::create_dir(temp.path().join("src")).expect("create src");
+        std::fs::write(temp.path().join("src/lib.rs"), "fn a() {}").expect("write file");
+        std::fs::write(temp.path().join("README.md"), "hello").expect("write file");
+        let check = check_with_inputs(&["src/**/*.rs", "Cargo.*"]);
+
+        let key = declared_key(&check, temp.path()).expect("key");
+        assert_eq!(key.len(), 64);
+
+        std::fs::write(temp.path().join("README.md"), "changed").expect("write file");
+        assert_eq!(declared_key(&check, temp.path()).as_ref(), Some(&key));
+
+        std::fs::write(temp.path().join("src/lib.rs"), "fn b() {}").expect("write file");
+        assert_ne!(declared_key(&check, temp.path()).as_ref(), Some(&key));
+    }
+
+    #[test]
+    fn test_input_key_changes_with_new_input_or_command() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let check = check_with_inputs(&["Cargo.*"]);
+        let key = declared_key(&check, temp.path()).expect("key");
+
+        std::fs::write(temp.path().join("Cargo.toml"), "").expect("write file");
+        assert_ne!(declared_key(&check, temp.path()).as_ref(), Some(&key));
+
+        let mut other = check.clone();
+        other.run = "cargo clippy -- -D warnings".to_string();
+        assert_ne!(
+            declared_key(&other, temp.path()),
+            declared_key(&check, temp.path())
+        );
+    }
+
+    #[test]
+    fn test_cache_hit_and_store() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let cache = Cache::new(temp.path().join(CACHE_FILE_NAME));
+
+        assert!(!cache.hit("clippy", "abc"));
+        cache.store("clippy", "abc").expect("store");
+        cache.store("test", "def").expect("store");
+        assert!(cache.hit("clippy", "abc"));
+        assert!(!cache.hit("clippy", "def"));
+        assert!(cache.hit("test", "def"));
+        assert!(!cache.hit("test", "abc"));
+    }
+
+    #[test]
+    fn test_cache_clear() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let cache = Cache::new(temp.path().join(CACHE_FILE_NAME));
+
+        assert!(!cache.clear().expect("clear"));
+        cache.store("clippy", "abc").expect("store");
+        assert!(cache.clear().expect("clear"));
+        assert!(!cache.hit("clippy", "abc"));
+    }
+}
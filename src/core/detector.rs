@@ -4,11 +4,15 @@
 //! is being made by a human developer or an AI coding agent.
 
 use crate::config::Config;
+use crate::core::git::GitRepo;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::env;
 use std::io::IsTerminal;
 
 /// The detected commit mode.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Mode {
     /// Human developer - fast checks, staged files only.
     #[default]
@@ -57,8 +61,14 @@ impl std::str::FromStr for Mode {
 }
 
 /// Reason for mode detection - useful for debugging.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Serializes as `{"kind": "known_agent_env_var", "value": "CLAUDE_CODE"}`,
+/// with `value` present only for reasons that carry one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
 pub enum DetectionReason {
+    /// Mode set via the `--mode` command-line flag.
+    CliFlag,
     /// Mode set via APC_MODE environment variable.
     ExplicitApcMode(String),
     /// Mode set via AGENT_MODE environment variable.
@@ -69,6 +79,8 @@ pub enum DetectionReason {
     CustomAgentEnvVar(String),
     /// CI environment detected.
     CiEnvironment(String),
+    /// Git author or committer listed in `detection.agent_authors`.
+    AgentAuthor(String),
     /// No TTY detected (non-interactive).
     NoTty,
     /// Default fallback to human mode.
@@ -78,11 +90,13 @@ pub enum DetectionReason {
 impl std::fmt::Display for DetectionReason {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Self::CliFlag => write!(f, "--mode flag"),
             Self::ExplicitApcMode(value) => write!(f, "APC_MODE={value}"),
             Self::ExplicitAgentMode => write!(f, "AGENT_MODE=1"),
             Self::KnownAgentEnvVar(var) => write!(f, "Known agent env var: {var}"),
             Self::CustomAgentEnvVar(var) => write!(f, "Custom agent env var: {var}"),
             Self::CiEnvironment(var) => write!(f, "CI environment: {var}"),
+            Self::AgentAuthor(author) => write!(f, "Agent git author: {author}"),
             Self::NoTty => write!(f, "No TTY detected (non-interactive)"),
             Self::Default => write!(f, "Default (no agent indicators)"),
         }
@@ -96,6 +110,144 @@ pub struct Detection {
     pub mode: Mode,
     /// Reason for the detection.
     pub reason: DetectionReason,
+    /// The agent, when a known agent env var identified it.
+    pub agent: Option<AgentKind>,
+}
+
+/// A coding agent recognized by its environment variables.
+///
+/// Written in snake case (e.g. `claude_code`) in `[agent.per_agent]`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentKind {
+    /// Claude Code.
+    ClaudeCode,
+    /// Cursor.
+    Cursor,
+    /// Aider.
+    Aider,
+    /// OpenAI Codex.
+    Codex,
+    /// Devin.
+    Devin,
+    /// Cline.
+    Cline,
+    /// Continue.dev.
+    Continue,
+    /// GitHub Copilot Workspace.
+    Copilot,
+    /// Amazon CodeWhisperer / Q.
+    AmazonQ,
+    /// Sourcegraph Cody.
+    Cody,
+    /// Tabnine.
+    Tabnine,
+    /// Replit Agent.
+    Replit,
+}
+
+impl AgentKind {
+    /// Returns the agent a known agent env var belongs to, if it names one.
+    #[must_use]
+    pub fn from_env_var(var: &str) -> Option<Self> {
+        AGENT_ENV_VAR_KINDS
+            .iter()
+            .find(|(name, _)| *name == var)
+            .map(|(_, kind)| *kind)
+    }
+
+    /// Returns the agent's name in config keys, e.g. `claude_code`.
+    #[must_use]
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::ClaudeCode => "claude_code",
+            Self::Cursor => "cursor",
+            Self::Aider => "aider",
+            Self::Codex => "codex",
+            Self::Devin => "devin",
+            Self::Cline => "cline",
+            Self::Continue => "continue",
+            Self::Copilot => "copilot",
+            Self::AmazonQ => "amazon_q",
+            Self::Cody => "cody",
+            Self::Tabnine => "tabnine",
+            Self::Replit => "replit",
+        }
+    }
+
+    /// Returns the agent's display name.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::ClaudeCode => "Claude Code",
+            Self::Cursor => "Cursor",
+            Self::Aider => "Aider",
+            Self::Codex => "Codex",
+            Self::Devin => "Devin",
+            Self::Cline => "Cline",
+            Self::Continue => "Continue",
+            Self::Copilot => "Copilot",
+            Self::AmazonQ => "Amazon Q",
+            Self::Cody => "Cody",
+            Self::Tabnine => "Tabnine",
+            Self::Replit => "Replit",
+        }
+    }
+}
+
+impl std::fmt::Display for AgentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The environment detection looked at, for `apc detect --json`.
+///
+/// Agent and CI variables are listed by name only, since some hold tokens.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnvironmentSnapshot {
+    /// Value of `APC_MODE`, if set.
+    pub apc_mode: Option<String>,
+    /// Value of `AGENT_MODE`, if set.
+    pub agent_mode: Option<String>,
+    /// Known and configured agent variables that are set.
+    pub agent_env_vars: Vec<String>,
+    /// CI variables that are set.
+    pub ci_env_vars: Vec<String>,
+    /// Whether stdin is a terminal.
+    pub stdin_tty: bool,
+    /// Whether stdout is a terminal.
+    pub stdout_tty: bool,
+}
+
+/// Detection stages, in their default priority order.
+///
+/// These are the names accepted in `detection.priority`.
+pub const DETECTION_STAGES: &[&str] = &[
+    "apc_mode",
+    "agent_mode",
+    "known_agent_env",
+    "custom_agent_env",
+    "ci",
+    "agent_author",
+    "tty",
+];
+
+/// Describes what a detection stage looks at, for `apc detect`.
+#[must_use]
+pub fn stage_description(stage: &str) -> &'static str {
+    match stage {
+        "apc_mode" => "APC_MODE",
+        "agent_mode" => "AGENT_MODE=1",
+        "known_agent_env" => "Known agent env vars",
+        "custom_agent_env" => "detection.agent_env_vars",
+        "ci" => "CI env vars",
+        "agent_author" => "detection.agent_authors",
+        "tty" => "No TTY on stdin and stdout",
+        _ => "Unknown stage",
+    }
 }
 
 /// Detector for determining commit mode.
@@ -145,6 +297,33 @@ const KNOWN_AGENT_ENV_VARS: &[&str] = &[
     "CODING_AGENT",
 ];
 
+/// The agent each product-specific entry of `KNOWN_AGENT_ENV_VARS` belongs
+/// to. The generic variables name no agent.
+const AGENT_ENV_VAR_KINDS: &[(&str, AgentKind)] = &[
+    ("CLAUDE_CODE", AgentKind::ClaudeCode),
+    ("ANTHROPIC_PROJECT_ID", AgentKind::ClaudeCode),
+    ("CURSOR_SESSION", AgentKind::Cursor),
+    ("CURSOR_TRACE_ID", AgentKind::Cursor),
+    ("AIDER_MODEL", AgentKind::Aider),
+    ("AIDER_CHAT_HISTORY_FILE", AgentKind::Aider),
+    ("CODEX_SESSION", AgentKind::Codex),
+    ("OPENAI_API_KEY_FOR_AGENT", AgentKind::Codex),
+    ("DEVIN_SESSION", AgentKind::Devin),
+    ("DEVIN_API_KEY", AgentKind::Devin),
+    ("CLINE_SESSION", AgentKind::Cline),
+    ("CLINE_API_KEY", AgentKind::Cline),
+    ("CONTINUE_SESSION", AgentKind::Continue),
+    ("CONTINUE_GLOBAL_DIR", AgentKind::Continue),
+    ("GITHUB_COPILOT_WORKSPACE", AgentKind::Copilot),
+    ("AWS_CODEWHISPERER_SESSION", AgentKind::AmazonQ),
+    ("AMAZON_Q_SESSION", AgentKind::AmazonQ),
+    ("CODY_SESSION", AgentKind::Cody),
+    ("SRC_ACCESS_TOKEN", AgentKind::Cody),
+    ("TABNINE_SESSION", AgentKind::Tabnine),
+    ("REPLIT_AGENT", AgentKind::Replit),
+    ("REPL_ID", AgentKind::Replit),
+];
+
 /// Known environment variables that indicate a CI environment.
 const KNOWN_CI_ENV_VARS: &[&str] = &[
     "CI",
@@ -178,42 +357,82 @@ impl<'a> Detector<'a> {
     }
 
     /// Detects the commit mode based on environment.
+    ///
+    /// Stages are tried in `detection.priority` order (default:
+    /// [`DETECTION_STAGES`]) and the first that matches wins.
     #[must_use]
     pub fn detect(&self) -> Detection {
-        // Priority 1: Explicit APC_MODE override
-        if let Some(detection) = self.check_apc_mode() {
-            return detection;
-        }
-
-        // Priority 2: AGENT_MODE=1 flag
-        if let Some(detection) = self.check_agent_mode_flag() {
-            return detection;
+        for stage in self.priority() {
+            if let Some(detection) = self.check_stage(stage) {
+                return detection;
+            }
         }
 
-        // Priority 3: Known agent environment variables
-        if let Some(detection) = self.check_known_agent_env_vars() {
-            return detection;
+        // Default: Human mode
+        Detection {
+            mode: Mode::Human,
+            reason: DetectionReason::Default,
+            agent: None,
         }
+    }
 
-        // Priority 4: Custom agent environment variables from config
-        if let Some(detection) = self.check_custom_agent_env_vars() {
-            return detection;
+    /// Returns the stages `detect` tries, in order.
+    #[must_use]
+    pub fn priority(&self) -> Vec<&str> {
+        if self.config.detection.priority.is_empty() {
+            DETECTION_STAGES.to_vec()
+        } else {
+            self.config
+                .detection
+                .priority
+                .iter()
+                .map(String::as_str)
+                .collect()
         }
+    }
 
-        // Priority 5: CI environment detection
-        if let Some(detection) = self.check_ci_environment() {
-            return detection;
+    /// Captures the environment variables and terminals that detection reads.
+    #[must_use]
+    pub fn environment(&self) -> EnvironmentSnapshot {
+        let is_set = |var: &&str| env::var_os(var).is_some();
+        EnvironmentSnapshot {
+            apc_mode: env::var("APC_MODE").ok(),
+            agent_mode: env::var("AGENT_MODE").ok(),
+            agent_env_vars: KNOWN_AGENT_ENV_VARS
+                .iter()
+                .copied()
+                .chain(
+                    self.config
+                        .detection
+                        .agent_env_vars
+                        .iter()
+                        .map(String::as_str),
+                )
+                .filter(is_set)
+                .map(str::to_string)
+                .collect(),
+            ci_env_vars: KNOWN_CI_ENV_VARS
+                .iter()
+                .copied()
+                .filter(is_set)
+                .map(str::to_string)
+                .collect(),
+            stdin_tty: std::io::stdin().is_terminal(),
+            stdout_tty: std::io::stdout().is_terminal(),
         }
+    }
 
-        // Priority 6: TTY detection (fallback heuristic)
-        if let Some(detection) = self.check_tty() {
-            return detection;
-        }
-
-        // Default: Human mode
-        Detection {
-            mode: Mode::Human,
-            reason: DetectionReason::Default,
+    /// Runs a single detection stage by name.
+    fn check_stage(&self, stage: &str) -> Option<Detection> {
+        match stage {
+            "apc_mode" => self.check_apc_mode(),
+            "agent_mode" => self.check_agent_mode_flag(),
+            "known_agent_env" => self.check_known_agent_env_vars(),
+            "custom_agent_env" => self.check_custom_agent_env_vars(),
+            "ci" => self.check_ci_environment(),
+            "agent_author" => self.check_agent_author(),
+            "tty" => self.check_tty(),
+            _ => None,
         }
     }
 
@@ -224,6 +443,7 @@ impl<'a> Detector<'a> {
             Detection {
                 mode,
                 reason: DetectionReason::ExplicitApcMode(value),
+                agent: None,
             }
         })
     }
@@ -235,6 +455,7 @@ impl<'a> Detector<'a> {
                 Some(Detection {
                     mode: Mode::Agent,
                     reason: DetectionReason::ExplicitAgentMode,
+                    agent: None,
                 })
             } else {
                 None
@@ -249,6 +470,7 @@ impl<'a> Detector<'a> {
                 return Some(Detection {
                     mode: Mode::Agent,
                     reason: DetectionReason::KnownAgentEnvVar((*var).to_string()),
+                    agent: AgentKind::from_env_var(var),
                 });
             }
         }
@@ -262,6 +484,7 @@ impl<'a> Detector<'a> {
                 return Some(Detection {
                     mode: Mode::Agent,
                     reason: DetectionReason::CustomAgentEnvVar(var.clone()),
+                    agent: None,
                 });
             }
         }
@@ -275,14 +498,48 @@ impl<'a> Detector<'a> {
                 return Some(Detection {
                     mode: Mode::Ci,
                     reason: DetectionReason::CiEnvironment((*var).to_string()),
+                    agent: None,
                 });
             }
         }
         None
     }
 
+    /// Checks whether git would commit as one of `detection.agent_authors`.
+    fn check_agent_author(&self) -> Option<Detection> {
+        let authors = &self.config.detection.agent_authors;
+        if authors.is_empty() {
+            return None;
+        }
+
+        let repo = GitRepo::discover().ok()?;
+        Self::match_agent_author(authors, &repo.commit_identities())
+    }
+
+    /// Returns the first name or email in `identities` listed in `authors`.
+    fn match_agent_author(
+        authors: &[String],
+        identities: &[(String, String)],
+    ) -> Option<Detection> {
+        identities
+            .iter()
+            .flat_map(|(name, email)| [email, name])
+            .find(|value| authors.iter().any(|a| a.eq_ignore_ascii_case(value)))
+            .map(|value| Detection {
+                mode: Mode::Agent,
+                reason: DetectionReason::AgentAuthor(value.clone()),
+                agent: None,
+            })
+    }
+
     /// Checks for TTY presence (non-interactive = likely agent).
+    ///
+    /// Skipped when `detection.use_tty_heuristic` is off.
     fn check_tty(&self) -> Option<Detection> {
+        if !self.config.detection.use_tty_heuristic {
+            return None;
+        }
+
         let stdin_is_tty = std::io::stdin().is_terminal();
         let stdout_is_tty = std::io::stdout().is_terminal();
 
@@ -292,6 +549,7 @@ impl<'a> Detector<'a> {
             return Some(Detection {
                 mode: Mode::Agent,
                 reason: DetectionReason::NoTty,
+                agent: None,
             });
         }
 
@@ -387,6 +645,12 @@ mod tests {
     // DetectionReason tests
     // =========================================================================
 
+    #[test]
+    fn test_detection_reason_display_cli_flag() {
+        let reason = DetectionReason::CliFlag;
+        assert_eq!(reason.to_string(), "--mode flag");
+    }
+
     #[test]
     fn test_detection_reason_display_explicit_apc_mode() {
         let reason = DetectionReason::ExplicitApcMode("agent".to_string());
@@ -463,6 +727,7 @@ mod tests {
         let detection = Detection {
             mode: Mode::Agent,
             reason: DetectionReason::ExplicitAgentMode,
+            agent: None,
         };
         assert_eq!(detection.mode, Mode::Agent);
         assert_eq!(detection.reason, DetectionReason::ExplicitAgentMode);
@@ -473,6 +738,7 @@ mod tests {
         let detection = Detection {
             mode: Mode::Ci,
             reason: DetectionReason::CiEnvironment("CI".to_string()),
+            agent: None,
         };
         let cloned = detection.clone();
         assert_eq!(detection.mode, cloned.mode);
@@ -749,6 +1015,7 @@ mod tests {
             detection.reason,
             DetectionReason::KnownAgentEnvVar("CURSOR_SESSION".to_string())
         );
+        assert_eq!(detection.agent, Some(AgentKind::Cursor));
     }
 
     #[test]
@@ -849,6 +1116,111 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_agent_env_var_kinds_are_known_vars() {
+        for (var, _) in AGENT_ENV_VAR_KINDS {
+            assert!(KNOWN_AGENT_ENV_VARS.contains(var), "{var} is not known");
+        }
+        assert_eq!(
+            AgentKind::from_env_var("CLAUDE_CODE"),
+            Some(AgentKind::ClaudeCode)
+        );
+        assert_eq!(AgentKind::from_env_var("AI_AGENT"), None);
+        assert_eq!(AgentKind::AmazonQ.to_string(), "Amazon Q");
+    }
+
+    #[test]
+    fn test_check_tty_disabled_by_config() {
+        let mut config = Config::default();
+        config.detection.use_tty_heuristic = false;
+        let detector = Detector::new(&config);
+        assert!(detector.check_tty().is_none());
+    }
+
+    #[test]
+    fn test_priority_defaults_to_all_stages() {
+        let config = Config::default();
+        let detector = Detector::new(&config);
+        assert_eq!(detector.priority(), DETECTION_STAGES);
+    }
+
+    #[test]
+    fn test_detect_follows_configured_priority() {
+        // PATH is always set, so only the configured stages decide the outcome
+        let mut config = Config::default();
+        config.detection.agent_env_vars = vec!["PATH".to_string()];
+        config.detection.priority = vec!["custom_agent_env".to_string()];
+        let detector = Detector::new(&config);
+
+        let detection = detector.detect();
+        assert_eq!(detection.mode, Mode::Agent);
+        assert_eq!(
+            detection.reason,
+            DetectionReason::CustomAgentEnvVar("PATH".to_string())
+        );
+    }
+
+    #[test]
+    fn test_match_agent_author() {
+        let authors = vec!["Bot@Company.com".to_string(), "release-bot".to_string()];
+        let human = ("Ada".to_string(), "ada@company.com".to_string());
+        let bot = ("Coding Bot".to_string(), "bot@company.com".to_string());
+
+        assert!(Detector::match_agent_author(&authors, std::slice::from_ref(&human)).is_none());
+        let detection =
+            Detector::match_agent_author(&authors, &[human, bot]).expect("agent author");
+        assert_eq!(detection.mode, Mode::Agent);
+        assert_eq!(
+            detection.reason,
+            DetectionReason::AgentAuthor("bot@company.com".to_string())
+        );
+        assert_eq!(
+            detection.reason.to_string(),
+            "Agent git author: bot@company.com"
+        );
+
+        let named = ("release-bot".to_string(), "ci@company.com".to_string());
+        assert!(Detector::match_agent_author(&authors, &[named]).is_some());
+    }
+
+    #[test]
+    fn test_check_agent_author_needs_configured_authors() {
+        let config = Config::default();
+        assert!(Detector::new(&config).check_agent_author().is_none());
+    }
+
+    #[test]
+    fn test_stage_descriptions_cover_all_stages() {
+        for stage in DETECTION_STAGES {
+            assert_ne!(stage_description(stage), "Unknown stage");
+        }
+    }
+
+    #[test]
+    fn test_detection_reason_serializes_tagged() {
+        let json =
+            serde_json::to_value(DetectionReason::KnownAgentEnvVar("CLAUDE_CODE".to_string()))
+                .expect("serialize");
+        assert_eq!(
+            json,
+            serde_json::json!({"kind": "known_agent_env_var", "value": "CLAUDE_CODE"})
+        );
+        let json = serde_json::to_value(DetectionReason::NoTty).expect("serialize");
+        assert_eq!(json, serde_json::json!({"kind": "no_tty"}));
+        assert_eq!(
+            serde_json::to_value(Mode::Agent).expect("serialize"),
+            serde_json::json!("agent")
+        );
+    }
+
+    #[test]
+    fn test_environment_lists_configured_agent_vars() {
+        let mut config = Config::default();
+        config.detection.agent_env_vars = vec!["PATH".to_string()];
+        let snapshot = Detector::new(&config).environment();
+        assert!(snapshot.agent_env_vars.contains(&"PATH".to_string()));
+    }
+
     #[test]
     fn test_known_agent_env_vars_no_duplicates() {
         let mut seen = std::collections::HashSet::new();
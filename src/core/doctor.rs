@@ -0,0 +1,219 @@
+//! Tool availability checks for `apc doctor`.
+//!
+//! Each check a mode runs needs some program on `PATH`: the one named by its
+//! `command_exists` condition, and the first word of its `run` command. A
+//! missing tool is only a hard failure if nothing skips the check without it.
+
+use crate::config::Config;
+use crate::core::executor::Executor;
+use crate::core::git::GitRepo;
+use crate::core::runner::evaluate_conditions;
+use std::collections::BTreeMap;
+
+/// Shell builtins and keywords that never resolve to a program on `PATH`.
+const SHELL_BUILTINS: &[&str] = &[
+    "!", ".", ":", "[", "cd", "echo", "exit", "export", "false", "for", "if", "printf", "set",
+    "source", "test", "true", "while",
+];
+
+/// A program used by one or more checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolStatus {
+    /// Program name (e.g. `cargo`).
+    pub tool: String,
+    /// Checks that use the program, in name order.
+    pub checks: Vec<String>,
+    /// Whether the program was found on `PATH`.
+    pub found: bool,
+    /// Whether a check fails without the program, rather than being skipped
+    /// by its `command_exists` condition or another unmet condition.
+    pub required: bool,
+}
+
+/// Returns the programs used by every check in the `[human]` and `[agent]`
+/// lists (including `[agent.per_agent]`), sorted by name.
+///
+/// Checks run in a container only need `docker`. Conditions are evaluated
+/// against `repo`, except `files_changed`, which varies from commit to commit.
+#[must_use]
+pub fn tools(config: &Config, repo: Option<&GitRepo>) -> Vec<ToolStatus> {
+    let mut tools: BTreeMap<String, ToolStatus> = BTreeMap::new();
+    let mut names: Vec<&String> = config
+        .human
+        .checks
+        .iter()
+        .chain(&config.agent.checks)
+        .chain(
+            config
+                .agent
+                .per_agent
+                .values()
+                .flat_map(|extra| &extra.checks),
+        )
+        .collect();
+    names.sort();
+    names.dedup();
+
+    for name in names {
+        let Some(check) = config.checks.get(name) else {
+            continue;
+        };
+        let gate = check
+            .enabled_if
+            .as_ref()
+            .and_then(|c| c.command_exists.as_deref());
+        let runs = gate.is_none() && evaluate_conditions(check, repo, None).enabled();
+        let command = if check.container.is_some() {
+            Some("docker")
+        } else {
+            program(&check.run)
+        };
+
+        for tool in gate.into_iter().chain(command) {
+            let status = tools.entry(tool.to_string()).or_insert_with(|| ToolStatus {
+                tool: tool.to_string(),
+                checks: Vec::new(),
+                found: Executor::command_exists(tool),
+                required: false,
+            });
+            if !status.checks.contains(name) {
+                status.checks.push(name.clone());
+            }
+            status.required |= runs;
+        }
+    }
+
+    tools.into_values().collect()
+}
+
+/// Returns the program a shell command runs, or `None` if it cannot be told
+/// statically (a variable, a path, or a shell builtin).
+///
+/// Leading `VAR=value` assignments are skipped.
+#[must_use]
+pub fn program(run: &str) -> Option<&str> {
+    run.split_whitespace()
+        .find(|word| !is_assignment(word))
+        .filter(|word| {
+            !word.contains(['$', '/', '\\', '{', '(', '\'', '"']) && !SHELL_BUILTINS.contains(word)
+        })
+}
+
+/// Returns true for a `NAME=value` environment assignment.
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CheckConfig, EnabledCondition};
+
+    #[test]
+    fn test_program() {
+        assert_eq!(program("cargo test --all"), Some("cargo"));
+        assert_eq!(program("RUST_LOG=debug CI=1 cargo test"), Some("cargo"));
+        assert_eq!(program("  ruff check ."), Some("ruff"));
+        assert_eq!(program("./scripts/lint.sh"), None);
+        assert_eq!(program("${LINTER} ."), None);
+        assert_eq!(program("test -f Cargo.toml"), None);
+        assert_eq!(program("FOO=bar"), None);
+        assert_eq!(program(""), None);
+    }
+
+    #[test]
+    fn test_tools_marks_gated_tools_optional() {
+        let mut config = Config::default();
+        config.human.checks = vec!["fmt".to_string(), "lint".to_string()];
+        config.agent.checks = vec!["lint".to_string(), "missing".to_string()];
+        config.checks.insert(
+            "fmt".to_string(),
+            CheckConfig::from_command("apc-doctor-test-missing-tool --check".to_string()),
+        );
+        config.checks.insert(
+            "lint".to_string(),
+            CheckConfig {
+                run: "apc-doctor-test-linter .".to_string(),
+                enabled_if: Some(EnabledCondition {
+                    command_exists: Some("apc-doctor-test-linter".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+
+        let tools = tools(&config, None);
+        assert_eq!(
+            tools,
+            [
+                ToolStatus {
+                    tool: "apc-doctor-test-linter".to_string(),
+                    checks: vec!["lint".to_string()],
+                    found: false,
+                    required: false,
+                },
+                ToolStatus {
+                    tool: "apc-doctor-test-missing-tool".to_string(),
+                    checks: vec!["fmt".to_string()],
+                    found: false,
+                    required: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tools_skipped_checks_are_optional() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(temp.path())
+            .output()
+            .expect("init repo");
+        let repo = GitRepo::discover_from(temp.path()).expect("discover repo");
+
+        let mut config = Config::default();
+        config.human.checks = vec!["hooks".to_string()];
+        config.agent.checks = Vec::new();
+        config.checks.insert(
+            "hooks".to_string(),
+            CheckConfig {
+                run: "apc-doctor-test-hooks run".to_string(),
+                enabled_if: Some(EnabledCondition {
+                    file_exists: Some(".hooks.yaml".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+
+        assert!(!tools(&config, Some(&repo))[0].required);
+
+        std::fs::write(temp.path().join(".hooks.yaml"), "").expect("write file");
+        assert!(tools(&config, Some(&repo))[0].required);
+    }
+
+    #[test]
+    fn test_tools_container_needs_docker() {
+        let mut config = Config::default();
+        config.human.checks = vec!["test".to_string()];
+        config.agent.checks = Vec::new();
+        config.checks.insert(
+            "test".to_string(),
+            CheckConfig {
+                run: "cargo test".to_string(),
+                container: Some("rust:1.82".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let tools = tools(&config, None);
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].tool, "docker");
+        assert!(tools[0].required);
+    }
+}
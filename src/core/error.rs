@@ -39,6 +39,22 @@ pub enum Error {
         message: String,
     },
 
+    /// A configuration's `extends` chain leads back to itself.
+    #[error("Configuration extends cycle: {}", display_chain(.chain))]
+    ConfigExtendsCycle {
+        /// Files in the chain, ending with the repeated one.
+        chain: Vec<PathBuf>,
+    },
+
+    /// A configuration's `extends` target does not exist.
+    #[error("Extended configuration not found: {path} (extended by {from})")]
+    ConfigExtendsNotFound {
+        /// Path of the missing file.
+        path: PathBuf,
+        /// Configuration that extends it.
+        from: PathBuf,
+    },
+
     // =========================================================================
     // Git errors
     // =========================================================================
@@ -206,6 +222,8 @@ impl Error {
             self,
             Self::ConfigNotFound { .. }
                 | Self::ConfigInvalid { .. }
+                | Self::ConfigExtendsCycle { .. }
+                | Self::ConfigExtendsNotFound { .. }
                 | Self::NotGitRepo
                 | Self::HookExists { .. }
                 | Self::PreCommitNotFound
@@ -219,15 +237,26 @@ impl Error {
         match self {
             Self::CheckFailed { exit_code, .. } => exit_code.unwrap_or(1),
             Self::CheckTimeout { .. } => 124, // Standard timeout exit code
-            Self::ConfigNotFound { .. } | Self::ConfigParse { .. } | Self::ConfigInvalid { .. } => {
-                78
-            }, // EX_CONFIG
+            Self::ConfigNotFound { .. }
+            | Self::ConfigParse { .. }
+            | Self::ConfigInvalid { .. }
+            | Self::ConfigExtendsCycle { .. }
+            | Self::ConfigExtendsNotFound { .. } => 78, // EX_CONFIG
             Self::NotGitRepo | Self::GitOperation { .. } | Self::GitHooksDir => 65, // EX_DATAERR
             _ => 1,
         }
     }
 }
 
+/// Formats a chain of config files as `a -> b -> a`.
+fn display_chain(chain: &[PathBuf]) -> String {
+    chain
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -268,6 +297,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_display_config_extends_cycle() {
+        let err = Error::ConfigExtendsCycle {
+            chain: vec![
+                PathBuf::from("/a.toml"),
+                PathBuf::from("/b.toml"),
+                PathBuf::from("/a.toml"),
+            ],
+        };
+        assert_eq!(
+            err.to_string(),
+            "Configuration extends cycle: /a.toml -> /b.toml -> /a.toml"
+        );
+    }
+
+    #[test]
+    fn test_display_config_extends_not_found() {
+        let err = Error::ConfigExtendsNotFound {
+            path: PathBuf::from("/base.toml"),
+            from: PathBuf::from("/pkg/agent-precommit.toml"),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Extended configuration not found: /base.toml (extended by /pkg/agent-precommit.toml)"
+        );
+    }
+
     #[test]
     fn test_display_not_git_repo() {
         let err = Error::NotGitRepo;
@@ -541,6 +597,21 @@ mod tests {
         .is_user_error());
     }
 
+    #[test]
+    fn test_config_extends_errors_are_user_config_errors() {
+        let errors = [
+            Error::ConfigExtendsCycle { chain: Vec::new() },
+            Error::ConfigExtendsNotFound {
+                path: PathBuf::from("x"),
+                from: PathBuf::from("y"),
+            },
+        ];
+        for err in errors {
+            assert!(err.is_user_error());
+            assert_eq!(err.exit_code(), 78);
+        }
+    }
+
     #[test]
     fn test_is_user_error_config_invalid() {
         assert!(Error::ConfigInvalid {
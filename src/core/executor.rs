@@ -5,11 +5,34 @@
 
 use crate::core::error::{Error, Result};
 use std::path::Path;
-use std::process::Stdio;
+use std::process::{ExitStatus, Stdio};
+use std::sync::OnceLock;
 use std::time::Duration;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::Command;
-use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
+
+#[cfg(unix)]
+use nix::sys::signal::Signal;
+#[cfg(unix)]
+use nix::unistd::Pid;
+
+/// Default cap on each captured output stream (10 MiB).
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 10 * 1024 * 1024;
+
+/// Appended to an output stream cut at the capture limit.
+pub const OUTPUT_TRUNCATED_MARKER: &str = "... [output truncated]";
+
+/// Shown in place of a command's output when it printed nothing.
+pub const NO_OUTPUT_PLACEHOLDER: &str = "(no output)";
+
+/// Parent environment variables kept in a clean environment. Windows
+/// programs also need `SystemRoot` to start.
+pub const CLEAN_ENV_VARS: &[&str] = if cfg!(windows) {
+    &["PATH", "HOME", "SystemRoot"]
+} else {
+    &["PATH", "HOME"]
+};
 
 /// Output from a command execution.
 #[derive(Debug, Clone)]
@@ -22,6 +45,11 @@ pub struct CommandOutput {
     pub stderr: String,
     /// Whether the command was killed due to timeout.
     pub timed_out: bool,
+    /// Whether the command was killed because its cancellation token fired.
+    pub cancelled: bool,
+    /// Signal that terminated the command (e.g., 11 for a segfault), on Unix.
+    /// `exit_code` is then 1.
+    pub signal: Option<i32>,
     /// Duration the command took to run.
     pub duration: Duration,
 }
@@ -33,6 +61,12 @@ impl CommandOutput {
         self.exit_code == 0 && !self.timed_out
     }
 
+    /// Returns true if the command printed anything other than whitespace.
+    #[must_use]
+    pub fn has_output(&self) -> bool {
+        !self.stdout.trim().is_empty() || !self.stderr.trim().is_empty()
+    }
+
     /// Returns combined stdout and stderr output.
     #[must_use]
     pub fn combined_output(&self) -> String {
@@ -46,6 +80,18 @@ impl CommandOutput {
     }
 }
 
+/// What a command reads on stdin.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StdinMode {
+    /// Nothing: reads see end of file right away.
+    #[default]
+    Null,
+    /// apc's own stdin.
+    Inherit,
+    /// These bytes, then end of file.
+    Bytes(Vec<u8>),
+}
+
 /// Options for command execution.
 #[derive(Debug, Clone)]
 pub struct ExecuteOptions {
@@ -55,10 +101,24 @@ pub struct ExecuteOptions {
     pub timeout: Option<Duration>,
     /// Environment variables to set.
     pub env: Vec<(String, String)>,
+    /// When set, the command gets none of the parent environment except
+    /// these variables and [`CLEAN_ENV_VARS`].
+    pub inherit_env: Option<Vec<String>>,
+    /// What the command reads on stdin.
+    pub stdin: StdinMode,
     /// Whether to capture output (vs streaming to console).
     pub capture_output: bool,
-    /// Shell to use (default: sh on Unix, cmd on Windows).
+    /// When capturing, also echo each output line to stderr after this prefix.
+    pub stream_prefix: Option<String>,
+    /// Shell to use (default: [`Executor::default_shell`]).
     pub shell: Option<String>,
+    /// Token that aborts the command (killing its process tree) when cancelled.
+    pub cancel: Option<CancellationToken>,
+    /// Maximum bytes kept of each captured stream; the rest is discarded.
+    pub max_output_bytes: usize,
+    /// On timeout, time between asking the command to exit (SIGTERM) and
+    /// killing it. `None` kills it right away.
+    pub grace_period: Option<Duration>,
 }
 
 impl Default for ExecuteOptions {
@@ -67,8 +127,14 @@ impl Default for ExecuteOptions {
             cwd: None,
             timeout: Some(Duration::from_secs(300)), // 5 minutes default
             env: Vec::new(),
+            inherit_env: None,
+            stdin: StdinMode::Null,
             capture_output: true,
+            stream_prefix: None,
             shell: None,
+            cancel: None,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            grace_period: None,
         }
     }
 }
@@ -95,12 +161,72 @@ impl ExecuteOptions {
         self
     }
 
+    /// Runs the command without the parent environment, except `PATH`,
+    /// `HOME` and the `inherit` variables.
+    #[must_use]
+    pub fn clean_env(mut self, inherit: Vec<String>) -> Self {
+        self.inherit_env = Some(inherit);
+        self
+    }
+
+    /// Sets what the command reads on stdin.
+    #[must_use]
+    pub fn stdin(mut self, stdin: StdinMode) -> Self {
+        self.stdin = stdin;
+        self
+    }
+
     /// Sets whether to capture output.
     #[must_use]
     pub const fn capture_output(mut self, capture: bool) -> Self {
         self.capture_output = capture;
         self
     }
+
+    /// Sets the shell that runs the command.
+    #[must_use]
+    pub fn shell(mut self, shell: impl Into<String>) -> Self {
+        self.shell = Some(shell.into());
+        self
+    }
+
+    /// Captures output and also echoes each line to stderr as it arrives,
+    /// prefixed with `prefix`.
+    #[must_use]
+    pub fn capture_and_stream(mut self, prefix: impl Into<String>) -> Self {
+        self.capture_output = true;
+        self.stream_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets how many bytes of each captured stream to keep.
+    #[must_use]
+    pub const fn max_output_bytes(mut self, limit: usize) -> Self {
+        self.max_output_bytes = limit;
+        self
+    }
+
+    /// Sets how long a timed-out command may clean up after SIGTERM before
+    /// it is killed.
+    #[must_use]
+    pub const fn grace_period(mut self, grace: Duration) -> Self {
+        self.grace_period = Some(grace);
+        self
+    }
+
+    /// Sets the cancellation token.
+    #[must_use]
+    pub fn cancel(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+}
+
+/// How a command execution ended.
+enum Outcome {
+    Finished(Result<(ExitStatus, String, String)>),
+    TimedOut,
+    Cancelled,
 }
 
 /// Executor for running shell commands.
@@ -119,28 +245,28 @@ impl Executor {
         let start = std::time::Instant::now();
 
         // Determine shell
-        let (shell, shell_arg) = if cfg!(windows) {
-            (options.shell.as_deref().unwrap_or("cmd"), "/C")
-        } else {
-            (options.shell.as_deref().unwrap_or("sh"), "-c")
+        let shell = match options.shell.as_deref() {
+            Some(shell) => shell,
+            None => Self::default_shell(),
         };
 
         // Build command
         let mut cmd = Command::new(shell);
-        cmd.arg(shell_arg).arg(command);
+        cmd.args(shell_args(shell)).arg(command);
 
         // Set working directory
         if let Some(ref cwd) = options.cwd {
             cmd.current_dir(cwd);
         }
 
-        // Set environment variables
-        for (key, value) in &options.env {
-            cmd.env(key, value);
-        }
+        set_env(&mut cmd, &options);
 
-        // Configure output handling
-        cmd.stdin(Stdio::null());
+        // Configure input and output handling
+        cmd.stdin(match options.stdin {
+            StdinMode::Null => Stdio::null(),
+            StdinMode::Inherit => Stdio::inherit(),
+            StdinMode::Bytes(_) => Stdio::piped(),
+        });
 
         if options.capture_output {
             cmd.stdout(Stdio::piped());
@@ -150,79 +276,113 @@ impl Executor {
             cmd.stderr(Stdio::inherit());
         }
 
+        // Cancellable commands, and those given time to clean up, run in
+        // their own process group so the whole tree (not just the shell) can
+        // be signalled.
+        let grouped = options.cancel.is_some() || options.grace_period.is_some();
+        #[cfg(unix)]
+        if grouped {
+            cmd.process_group(0);
+        }
+
         // Spawn the process
         let mut child = cmd.spawn().map_err(|e| Error::io("spawn command", e))?;
 
-        // Handle timeout
-        let result = if let Some(timeout_duration) = options.timeout {
-            match timeout(timeout_duration, async {
-                self.wait_for_output(&mut child, options.capture_output)
-                    .await
-            })
-            .await
-            {
-                Ok(result) => result,
-                Err(_) => {
-                    // Kill the process on timeout - ignore result since we're returning anyway
-                    drop(child.kill().await);
-                    return Ok(CommandOutput {
-                        exit_code: 124,
-                        stdout: String::new(),
-                        stderr: "Command timed out".to_string(),
-                        timed_out: true,
-                        duration: start.elapsed(),
-                    });
-                },
+        let outcome = {
+            let wait = self.wait_for_output(&mut child, &options);
+            let deadline = async {
+                match options.timeout {
+                    Some(d) => tokio::time::sleep(d).await,
+                    None => std::future::pending().await,
+                }
+            };
+            let cancelled = async {
+                match &options.cancel {
+                    Some(token) => token.cancelled().await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                result = wait => Outcome::Finished(result),
+                () = deadline => Outcome::TimedOut,
+                () = cancelled => Outcome::Cancelled,
             }
-        } else {
-            self.wait_for_output(&mut child, options.capture_output)
-                .await
         };
 
-        let (exit_code, stdout, stderr) = result?;
+        let cancelled = matches!(outcome, Outcome::Cancelled);
+        let (exit_code, signal, stdout, stderr, timed_out) = match outcome {
+            Outcome::Finished(result) => {
+                let (status, stdout, stderr) = result?;
+                let exit_code = status.code().unwrap_or(1);
+                (exit_code, exit_signal(status), stdout, stderr, false)
+            },
+            Outcome::TimedOut => {
+                // Stop the process on timeout - ignore result since we're returning anyway
+                match options.grace_period {
+                    Some(grace) => terminate_tree(&mut child, grouped, grace).await,
+                    None => kill_tree(&mut child, grouped).await,
+                }
+                (
+                    124,
+                    None,
+                    String::new(),
+                    "Command timed out".to_string(),
+                    true,
+                )
+            },
+            Outcome::Cancelled => {
+                kill_tree(&mut child, grouped).await;
+                (
+                    130,
+                    None,
+                    String::new(),
+                    "Command aborted".to_string(),
+                    false,
+                )
+            },
+        };
 
         Ok(CommandOutput {
             exit_code,
             stdout,
             stderr,
-            timed_out: false,
+            timed_out,
+            cancelled,
+            signal,
             duration: start.elapsed(),
         })
     }
 
     /// Waits for the command to complete and captures output.
+    ///
+    /// With a stream prefix, captured lines are also echoed to stderr.
     async fn wait_for_output(
         &self,
         child: &mut tokio::process::Child,
-        capture: bool,
-    ) -> Result<(i32, String, String)> {
-        if capture {
-            let stdout = child.stdout.take();
-            let stderr = child.stderr.take();
-
-            let stdout_handle = tokio::spawn(async move {
-                let mut output = String::new();
-                if let Some(stdout) = stdout {
-                    let mut reader = BufReader::new(stdout).lines();
-                    while let Ok(Some(line)) = reader.next_line().await {
-                        output.push_str(&line);
-                        output.push('\n');
-                    }
-                }
-                output
+        options: &ExecuteOptions,
+    ) -> Result<(ExitStatus, String, String)> {
+        // Feed stdin alongside reading output, so neither pipe fills up and
+        // blocks the other. A command that exits without reading it all
+        // breaks the pipe, which is not an error.
+        if let (StdinMode::Bytes(bytes), Some(mut stdin)) = (&options.stdin, child.stdin.take()) {
+            let bytes = bytes.clone();
+            tokio::spawn(async move {
+                stdin.write_all(&bytes).await.ok();
             });
+        }
 
-            let stderr_handle = tokio::spawn(async move {
-                let mut output = String::new();
-                if let Some(stderr) = stderr {
-                    let mut reader = BufReader::new(stderr).lines();
-                    while let Ok(Some(line)) = reader.next_line().await {
-                        output.push_str(&line);
-                        output.push('\n');
-                    }
-                }
-                output
-            });
+        if options.capture_output {
+            let stdout_handle = tokio::spawn(read_lines(
+                child.stdout.take(),
+                options.stream_prefix.clone(),
+                options.max_output_bytes,
+            ));
+            let stderr_handle = tokio::spawn(read_lines(
+                child.stderr.take(),
+                options.stream_prefix.clone(),
+                options.max_output_bytes,
+            ));
 
             let status = child
                 .wait()
@@ -236,13 +396,13 @@ impl Executor {
                 message: format!("stderr task failed: {e}"),
             })?;
 
-            Ok((status.code().unwrap_or(1), stdout, stderr))
+            Ok((status, stdout, stderr))
         } else {
             let status = child
                 .wait()
                 .await
                 .map_err(|e| Error::io("wait for command", e))?;
-            Ok((status.code().unwrap_or(1), String::new(), String::new()))
+            Ok((status, String::new(), String::new()))
         }
     }
 
@@ -251,6 +411,186 @@ impl Executor {
     pub fn command_exists(command: &str) -> bool {
         which::which(command).is_ok()
     }
+
+    /// Returns the shell checks run in when they name none: `sh` on Unix, and
+    /// on Windows PowerShell (`pwsh`, then `powershell`) if installed, else
+    /// `cmd`, which cannot run multi-line scripts.
+    #[must_use]
+    pub fn default_shell() -> &'static str {
+        static SHELL: OnceLock<&str> = OnceLock::new();
+        SHELL.get_or_init(|| {
+            if cfg!(windows) {
+                ["pwsh", "powershell"]
+                    .into_iter()
+                    .find(|shell| Self::command_exists(shell))
+                    .unwrap_or("cmd")
+            } else {
+                "sh"
+            }
+        })
+    }
+}
+
+/// Sets the command's environment variables, clearing the inherited ones
+/// first for a clean environment.
+fn set_env(cmd: &mut Command, options: &ExecuteOptions) {
+    if let Some(inherit) = &options.inherit_env {
+        cmd.env_clear();
+        let keep = CLEAN_ENV_VARS
+            .iter()
+            .copied()
+            .chain(inherit.iter().map(String::as_str));
+        for key in keep {
+            if let Some(value) = std::env::var_os(key) {
+                cmd.env(key, value);
+            }
+        }
+    }
+    for (key, value) in &options.env {
+        cmd.env(key, value);
+    }
+}
+
+/// Returns the signal that terminated a process, if one did.
+#[cfg(unix)]
+fn exit_signal(status: ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+/// Returns the signal that terminated a process, if one did.
+#[cfg(not(unix))]
+const fn exit_signal(_status: ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Returns the arguments that make `shell` run the command string after them.
+fn shell_args(shell: &str) -> &'static [&'static str] {
+    let name = Path::new(shell)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_ascii_lowercase());
+    match name.as_deref() {
+        Some("cmd") => &["/C"],
+        Some("pwsh" | "powershell") => &["-NoProfile", "-Command"],
+        _ => &["-c"],
+    }
+}
+
+/// Reads a pipe to the end, returning its lines and echoing each one to
+/// stderr after `prefix` if set.
+///
+/// Only the first `max_bytes` are kept, followed by
+/// [`OUTPUT_TRUNCATED_MARKER`]; the rest is still read (and echoed) so the
+/// command never blocks on a full pipe.
+async fn read_lines<R>(pipe: Option<R>, prefix: Option<String>, max_bytes: usize) -> String
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let mut output = String::new();
+    let mut truncated = false;
+    if let Some(pipe) = pipe {
+        let mut reader = BufReader::new(pipe).lines();
+        while let Ok(Some(line)) = reader.next_line().await {
+            if let Some(prefix) = &prefix {
+                eprintln!("{prefix}{line}");
+            }
+            if truncated {
+                continue;
+            }
+            if output.len() + line.len() + 1 > max_bytes {
+                let mut end = max_bytes.saturating_sub(output.len()).min(line.len());
+                while !line.is_char_boundary(end) {
+                    end -= 1;
+                }
+                output.push_str(&line[..end]);
+                truncated = true;
+                continue;
+            }
+            output.push_str(&line);
+            output.push('\n');
+        }
+    }
+
+    if truncated {
+        if !output.is_empty() && !output.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push_str(OUTPUT_TRUNCATED_MARKER);
+        output.push('\n');
+    }
+    output
+}
+
+/// Kills a child process and, if it leads its own process group, the group.
+async fn kill_tree(child: &mut tokio::process::Child, grouped: bool) {
+    #[cfg(unix)]
+    if grouped {
+        if let Some(pid) = child.id() {
+            send_signal(pid, true, Some(Signal::SIGKILL));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = grouped;
+
+    drop(child.kill().await);
+}
+
+/// How often a terminated process group is checked for survivors.
+#[cfg(unix)]
+const GROUP_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Asks a child process (and its group) to exit with SIGTERM, then kills
+/// whatever is still running after `grace`.
+///
+/// For a group, the wait lasts until every process in it has exited, not
+/// just the shell, which usually exits first and leaves its children
+/// cleaning up.
+///
+/// Off Unix there is no SIGTERM, so the process is killed right away.
+async fn terminate_tree(child: &mut tokio::process::Child, grouped: bool, grace: Duration) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        send_signal(pid, grouped, Some(Signal::SIGTERM));
+        if grouped {
+            let deadline = tokio::time::Instant::now() + grace;
+            loop {
+                // Reap the shell so it doesn't linger in the group as a zombie
+                drop(child.try_wait());
+                if !send_signal(pid, true, None) {
+                    return;
+                }
+                let now = tokio::time::Instant::now();
+                if now >= deadline {
+                    break;
+                }
+                tokio::time::sleep(GROUP_POLL_INTERVAL.min(deadline - now)).await;
+            }
+        } else if tokio::time::timeout(grace, child.wait()).await.is_ok() {
+            return;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = grace;
+
+    kill_tree(child, grouped).await;
+}
+
+/// Sends `signal` to a process, or to the process group it leads if
+/// `grouped`. With no signal, only checks that the target exists.
+///
+/// Returns false if the target no longer exists.
+#[cfg(unix)]
+fn send_signal(pid: u32, grouped: bool, signal: Option<Signal>) -> bool {
+    let Ok(pid) = i32::try_from(pid) else {
+        return false;
+    };
+    let pid = Pid::from_raw(pid);
+    let sent = if grouped {
+        nix::sys::signal::killpg(pid, signal)
+    } else {
+        nix::sys::signal::kill(pid, signal)
+    };
+    sent.is_ok()
 }
 
 #[cfg(test)]
@@ -268,6 +608,8 @@ mod tests {
             stdout: "test".to_string(),
             stderr: String::new(),
             timed_out: false,
+            cancelled: false,
+            signal: None,
             duration: Duration::from_secs(1),
         };
         assert!(output.success());
@@ -280,6 +622,8 @@ mod tests {
             stdout: String::new(),
             stderr: "error".to_string(),
             timed_out: false,
+            cancelled: false,
+            signal: None,
             duration: Duration::from_secs(1),
         };
         assert!(!output.success());
@@ -292,6 +636,8 @@ mod tests {
             stdout: String::new(),
             stderr: String::new(),
             timed_out: true,
+            cancelled: false,
+            signal: None,
             duration: Duration::from_secs(1),
         };
         assert!(!output.success());
@@ -304,6 +650,8 @@ mod tests {
             stdout: "stdout content".to_string(),
             stderr: String::new(),
             timed_out: false,
+            cancelled: false,
+            signal: None,
             duration: Duration::from_secs(1),
         };
         assert_eq!(output.combined_output(), "stdout content");
@@ -316,6 +664,8 @@ mod tests {
             stdout: String::new(),
             stderr: "stderr content".to_string(),
             timed_out: false,
+            cancelled: false,
+            signal: None,
             duration: Duration::from_secs(1),
         };
         assert_eq!(output.combined_output(), "stderr content");
@@ -328,6 +678,8 @@ mod tests {
             stdout: "stdout".to_string(),
             stderr: "stderr".to_string(),
             timed_out: false,
+            cancelled: false,
+            signal: None,
             duration: Duration::from_secs(1),
         };
         let combined = output.combined_output();
@@ -342,9 +694,12 @@ mod tests {
             stdout: String::new(),
             stderr: String::new(),
             timed_out: false,
+            cancelled: false,
+            signal: None,
             duration: Duration::from_secs(1),
         };
         assert!(output.combined_output().is_empty());
+        assert!(!output.has_output());
     }
 
     // =========================================================================
@@ -359,6 +714,7 @@ mod tests {
         assert_eq!(options.timeout, Some(Duration::from_secs(300)));
         assert!(options.env.is_empty());
         assert!(options.capture_output);
+        assert!(options.stream_prefix.is_none());
         assert!(options.shell.is_none());
     }
 
@@ -394,6 +750,14 @@ mod tests {
         assert!(!options.capture_output);
     }
 
+    #[test]
+    fn test_execute_options_cancel() {
+        let opts = ExecuteOptions::default();
+        assert!(opts.cancel.is_none());
+        let opts = opts.cancel(CancellationToken::new());
+        assert!(opts.cancel.is_some());
+    }
+
     #[test]
     fn test_execute_options_chaining() {
         let options = ExecuteOptions::default()
@@ -479,6 +843,76 @@ mod tests {
         assert!(output.stderr.contains("error"));
     }
 
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_execute_with_custom_shell() {
+        if !Executor::command_exists("bash") {
+            return;
+        }
+        let output = Executor::new()
+            .execute(
+                "echo ${BASH_VERSION:+bash}",
+                ExecuteOptions::default().shell("bash"),
+            )
+            .await
+            .expect("should succeed");
+        assert_eq!(output.stdout, "bash\n");
+    }
+
+    #[tokio::test]
+    async fn test_execute_capture_and_stream_keeps_output() {
+        let executor = Executor::new();
+        let options = ExecuteOptions::default()
+            .capture_output(false)
+            .capture_and_stream("[check] ");
+        assert!(options.capture_output);
+        assert_eq!(options.stream_prefix.as_deref(), Some("[check] "));
+
+        let output = executor
+            .execute("echo out && echo err >&2", options)
+            .await
+            .expect("should succeed");
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_execute_truncates_output_at_limit() {
+        // Far more output than a pipe buffer holds, so the command would block
+        // if the reader stopped draining at the limit
+        let output = Executor::new()
+            .execute(
+                "yes 0123456789 | head -n 20000; echo done >&2",
+                ExecuteOptions::default().max_output_bytes(25),
+            )
+            .await
+            .expect("should succeed");
+
+        assert!(output.success());
+        assert_eq!(
+            output.stdout,
+            format!("0123456789\n0123456789\n012\n{OUTPUT_TRUNCATED_MARKER}\n")
+        );
+        assert_eq!(output.stderr, "done\n");
+    }
+
+    #[tokio::test]
+    async fn test_execute_keeps_output_within_limit() {
+        let output = Executor::new()
+            .execute("echo short", ExecuteOptions::default().max_output_bytes(6))
+            .await
+            .expect("should succeed");
+        assert_eq!(output.stdout, "short\n");
+    }
+
+    #[tokio::test]
+    async fn test_read_lines_truncates_at_char_boundary() {
+        let input: &[u8] = "héllo\n".as_bytes();
+        let output = read_lines(Some(input), None, 2).await;
+        assert_eq!(output, format!("h\n{OUTPUT_TRUNCATED_MARKER}\n"));
+    }
+
     #[tokio::test]
     #[cfg(unix)]
     async fn test_execute_with_environment_variable() {
@@ -496,6 +930,88 @@ mod tests {
         assert!(output.stdout.contains("test_value"));
     }
 
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_execute_reports_terminating_signal() {
+        let executor = Executor::new();
+
+        let output = executor
+            .execute("kill -KILL $$", ExecuteOptions::default())
+            .await
+            .expect("execute");
+        assert!(!output.success());
+        assert_eq!(output.signal, Some(9));
+        assert_eq!(output.exit_code, 1);
+
+        let output = executor
+            .execute("exit 1", ExecuteOptions::default())
+            .await
+            .expect("execute");
+        assert_eq!(output.signal, None);
+        assert_eq!(output.exit_code, 1);
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_execute_stdin() {
+        let executor = Executor::new();
+
+        let output = executor
+            .execute("cat", ExecuteOptions::default())
+            .await
+            .expect("execute");
+        assert!(output.success());
+        assert!(output.stdout.is_empty());
+
+        let input = "fix: handle empty input\nbody\n".repeat(10_000);
+        let output = executor
+            .execute(
+                "wc -l",
+                ExecuteOptions::default().stdin(StdinMode::Bytes(input.into_bytes())),
+            )
+            .await
+            .expect("execute");
+        assert_eq!(output.stdout.trim(), "20000");
+
+        // Exiting without reading stdin is fine
+        let output = executor
+            .execute(
+                "true",
+                ExecuteOptions::default().stdin(StdinMode::Bytes(vec![b'x'; 1 << 20])),
+            )
+            .await
+            .expect("execute");
+        assert!(output.success());
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_execute_clean_env() {
+        // Cargo sets CARGO_PKG_NAME for the test process
+        let executor = Executor::new();
+        let run = |options: ExecuteOptions| {
+            executor.execute(
+                "echo \"[$CARGO_PKG_NAME] [$TEST_VAR]\"; command -v sh",
+                options.env("TEST_VAR", "set"),
+            )
+        };
+
+        let output = run(ExecuteOptions::default()).await.expect("execute");
+        assert!(output.stdout.contains("[agent-precommit] [set]"));
+
+        let output = run(ExecuteOptions::default().clean_env(Vec::new()))
+            .await
+            .expect("execute");
+        assert!(output.success(), "PATH is kept");
+        assert!(output.stdout.contains("[] [set]"));
+
+        let inherit = vec!["CARGO_PKG_NAME".to_string()];
+        let output = run(ExecuteOptions::default().clean_env(inherit))
+            .await
+            .expect("execute");
+        assert!(output.stdout.contains("[agent-precommit] [set]"));
+    }
+
     #[tokio::test]
     #[cfg(windows)]
     async fn test_execute_with_environment_variable() {
@@ -562,6 +1078,133 @@ mod tests {
         assert_eq!(output.exit_code, 124);
     }
 
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_execute_timeout_grace_lets_command_clean_up() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let marker = temp.path().join("cleaned");
+        let command = format!(
+            "trap 'echo done > {}; exit 1' TERM; sleep 10 & wait",
+            marker.display()
+        );
+
+        let start = std::time::Instant::now();
+        let output = Executor::new()
+            .execute(
+                &command,
+                ExecuteOptions::default()
+                    .timeout(Duration::from_millis(200))
+                    .grace_period(Duration::from_secs(5)),
+            )
+            .await
+            .expect("should complete");
+
+        assert!(output.timed_out);
+        assert!(marker.exists());
+        // The command exited on SIGTERM, well before the grace period ran out
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_execute_timeout_grace_covers_grandchildren() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let marker = temp.path().join("flushed");
+        // The shell exits on SIGTERM at once; its grandchild needs a moment
+        let command = format!(
+            "sh -c 'trap \"sleep 0.3; echo done > {}; exit 0\" TERM; sleep 10 & wait' & wait",
+            marker.display()
+        );
+
+        let output = Executor::new()
+            .execute(
+                &command,
+                ExecuteOptions::default()
+                    .timeout(Duration::from_millis(300))
+                    .grace_period(Duration::from_secs(5)),
+            )
+            .await
+            .expect("should complete");
+
+        assert!(output.timed_out);
+        assert!(marker.exists(), "grandchild should get the grace period");
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_execute_timeout_grace_kills_after_grace() {
+        let start = std::time::Instant::now();
+        let output = Executor::new()
+            .execute(
+                "trap '' TERM; sleep 10",
+                ExecuteOptions::default()
+                    .timeout(Duration::from_millis(100))
+                    .grace_period(Duration::from_millis(300)),
+            )
+            .await
+            .expect("should complete");
+
+        assert!(output.timed_out);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_execute_cancelled() {
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            canceller.cancel();
+        });
+
+        let start = std::time::Instant::now();
+        let output = Executor::new()
+            .execute("sleep 10", ExecuteOptions::default().cancel(token))
+            .await
+            .expect("should complete");
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(!output.success());
+        assert!(!output.timed_out);
+        assert!(output.cancelled);
+        assert_eq!(output.exit_code, 130);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_cancelled_kills_process_tree() {
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            canceller.cancel();
+        });
+
+        // The shell forks `sleep`, so killing only the shell would orphan it
+        Executor::new()
+            .execute(
+                "sleep 27.1828; echo done",
+                ExecuteOptions::default().cancel(token),
+            )
+            .await
+            .expect("should complete");
+
+        // SIGKILL delivery is asynchronous; give the kernel a moment to reap
+        let mut orphaned = true;
+        for _ in 0..100 {
+            let survivors = std::process::Command::new("pgrep")
+                .args(["-f", "sleep 27.1828"])
+                .output()
+                .expect("run pgrep");
+            if survivors.stdout.is_empty() {
+                orphaned = false;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(!orphaned, "orphaned sleep remains");
+    }
+
     #[tokio::test]
     async fn test_execute_duration_is_recorded() {
         let executor = Executor::new();
@@ -592,6 +1235,45 @@ mod tests {
         assert!(output.stdout.contains("line3"));
     }
 
+    #[test]
+    fn test_shell_args() {
+        assert_eq!(shell_args("sh"), ["-c"]);
+        assert_eq!(shell_args("/bin/bash"), ["-c"]);
+        assert_eq!(shell_args("CMD.EXE"), ["/C"]);
+        assert_eq!(shell_args("pwsh"), ["-NoProfile", "-Command"]);
+        assert_eq!(
+            shell_args("C:/Program Files/PowerShell/7/pwsh.exe"),
+            ["-NoProfile", "-Command"]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_default_shell_unix() {
+        assert_eq!(Executor::default_shell(), "sh");
+    }
+
+    #[tokio::test]
+    #[cfg(windows)]
+    async fn test_execute_multi_line_powershell() {
+        let shell = Executor::default_shell();
+        if shell == "cmd" {
+            return;
+        }
+
+        let executor = Executor::new();
+        let output = executor
+            .execute(
+                "$name = 'apc'\nif ($name -eq 'apc') {\n    Write-Output \"hello $name\"\n}\nexit 3",
+                ExecuteOptions::default().shell(shell),
+            )
+            .await
+            .expect("should complete");
+
+        assert_eq!(output.exit_code, 3);
+        assert!(output.stdout.contains("hello apc"));
+    }
+
     #[test]
     fn test_command_exists() {
         // 'sh' should exist on Unix, 'cmd' on Windows
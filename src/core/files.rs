@@ -0,0 +1,409 @@
+//! File selection for file-scoped checks.
+//!
+//! A check can reference the `{files}` placeholder in its `run` command to receive
+//! the set of files the run is scoped to. By default this is the list of staged
+//! files, but callers may supply an explicit list (e.g. files changed since the
+//! merge-base with the default branch, or a list read from stdin). It also
+//! provides glob matching over the working tree, used for collecting check
+//! artifacts.
+//!
+//! Paths matched by a `.apcignore` file at the repo root (gitignore syntax) are
+//! left out of every file selection.
+
+use crate::core::error::{Error, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+
+/// Placeholder replaced with the selected files in check commands.
+pub const FILES_PLACEHOLDER: &str = "{files}";
+
+/// File at the repo root listing paths that file-scoped checks never see.
+pub const IGNORE_FILE_NAME: &str = ".apcignore";
+
+/// The patterns of a repo's `.apcignore` file.
+#[derive(Debug, Clone)]
+pub struct IgnoreFile {
+    root: PathBuf,
+    matcher: Gitignore,
+}
+
+impl IgnoreFile {
+    /// Loads `.apcignore` from `root`, or returns `None` if there is none.
+    ///
+    /// Invalid patterns are skipped with a warning.
+    #[must_use]
+    pub fn load(root: &Path) -> Option<Self> {
+        let path = root.join(IGNORE_FILE_NAME);
+        if !path.is_file() {
+            return None;
+        }
+
+        let mut builder = GitignoreBuilder::new(root);
+        if let Some(e) = builder.add(&path) {
+            tracing::warn!(error = %e, "Invalid pattern in {IGNORE_FILE_NAME}");
+        }
+        match builder.build() {
+            Ok(matcher) => Some(Self {
+                root: root.to_path_buf(),
+                matcher,
+            }),
+            Err(e) => {
+                tracing::warn!(error = %e, "Ignoring unreadable {IGNORE_FILE_NAME}");
+                None
+            },
+        }
+    }
+
+    /// Returns true if `path` (absolute, or relative to the root) is ignored,
+    /// directly or through one of its parent directories.
+    #[must_use]
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let relative = relative_to(path, &self.root);
+        relative.is_relative()
+            && self
+                .matcher
+                .matched_path_or_any_parents(&relative, false)
+                .is_ignore()
+    }
+
+    /// Removes the ignored paths from `files`.
+    pub fn retain(&self, files: &mut Vec<PathBuf>) {
+        files.retain(|file| !self.is_ignored(file));
+    }
+}
+
+/// Returns true if a command references the `{files}` placeholder.
+#[must_use]
+pub fn uses_files(command: &str) -> bool {
+    command.contains(FILES_PLACEHOLDER)
+}
+
+/// Replaces `{files}` in a command with the given files.
+///
+/// Paths are made relative to `root` (checks run from the repository root) and
+/// shell-quoted, so filenames containing spaces or quotes are passed intact.
+#[must_use]
+pub fn expand_files(command: &str, files: &[PathBuf], root: &Path) -> String {
+    let joined = files
+        .iter()
+        .map(|f| shell_quote(&relative_to(f, root).to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    command.replace(FILES_PLACEHOLDER, &joined)
+}
+
+/// Parses a newline-separated list of paths into absolute paths under `root`.
+///
+/// Relative paths are taken from `root` (as git prints them) and blank lines
+/// are ignored. A path that resolves outside `root` is an error.
+pub fn parse_file_list(input: &str, root: &Path) -> Result<Vec<PathBuf>> {
+    input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            within_root(Path::new(line), root).ok_or_else(|| Error::ConfigInvalid {
+                field: "from-stdin-files".to_string(),
+                message: format!("'{line}' is outside the repository"),
+            })
+        })
+        .collect()
+}
+
+/// Resolves `path` against `root` without touching the filesystem, returning
+/// `None` if it escapes `root`.
+fn within_root(path: &Path, root: &Path) -> Option<PathBuf> {
+    let relative = if path.is_absolute() {
+        path.strip_prefix(root).ok()?
+    } else {
+        path
+    };
+
+    let mut resolved = root.to_path_buf();
+    let mut depth = 0usize;
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => {
+                resolved.push(part);
+                depth += 1;
+            },
+            Component::ParentDir => {
+                depth = depth.checked_sub(1)?;
+                resolved.pop();
+            },
+            Component::CurDir => {},
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(resolved)
+}
+
+/// Returns `path` relative to `root`, or `path` unchanged if it lies outside `root`.
+#[must_use]
+pub fn relative_to(path: &Path, root: &Path) -> PathBuf {
+    path.strip_prefix(root).unwrap_or(path).to_path_buf()
+}
+
+/// Returns the files under `root` matching any of the glob patterns.
+///
+/// Patterns are relative to `root` and support `**`. Directories are skipped
+/// and the result is sorted and free of duplicates. Invalid patterns match
+/// nothing.
+#[must_use]
+pub fn glob_files(root: &Path, patterns: &[String]) -> Vec<PathBuf> {
+    let mut matches = BTreeSet::new();
+
+    for pattern in patterns {
+        let full = root.join(pattern);
+        let Ok(paths) = glob::glob(&full.to_string_lossy()) else {
+            tracing::warn!(pattern = %pattern, "Invalid glob pattern");
+            continue;
+        };
+        matches.extend(
+            paths
+                .filter_map(std::result::Result::ok)
+                .filter(|p| p.is_file()),
+        );
+    }
+
+    matches.into_iter().collect()
+}
+
+/// Marks a directory as created by [`collect_artifacts`], so it may be
+/// cleared on the next run.
+pub const ARTIFACT_MARKER: &str = ".apc-artifacts";
+
+/// Copies the files matching `patterns` under `root` into `dest`.
+///
+/// Relative paths are preserved inside `dest`, which is cleared first so it
+/// only ever holds artifacts from the latest run. A `dest` without the
+/// [`ARTIFACT_MARKER`] was not created by apc and is left alone, failing the
+/// collection instead. Returns the copied paths.
+pub fn collect_artifacts(root: &Path, patterns: &[String], dest: &Path) -> Result<Vec<PathBuf>> {
+    if dest.exists() {
+        if !dest.join(ARTIFACT_MARKER).is_file() {
+            return Err(Error::io(
+                "clear artifact dir",
+                std::io::Error::other(format!(
+                    "{} was not created by apc; not clearing it",
+                    dest.display()
+                )),
+            ));
+        }
+        std::fs::remove_dir_all(dest).map_err(|e| Error::io("clear artifact dir", e))?;
+    }
+    std::fs::create_dir_all(dest).map_err(|e| Error::io("create artifact dir", e))?;
+    std::fs::write(dest.join(ARTIFACT_MARKER), "")
+        .map_err(|e| Error::io("create artifact dir", e))?;
+
+    let mut collected = Vec::new();
+    for file in glob_files(root, patterns) {
+        let relative = match file.strip_prefix(root) {
+            Ok(rel) => rel.to_path_buf(),
+            Err(_) => PathBuf::from(file.file_name().unwrap_or(file.as_os_str())),
+        };
+        let target = dest.join(relative);
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| Error::io("create artifact dir", e))?;
+        }
+        std::fs::copy(&file, &target).map_err(|e| Error::io("copy artifact", e))?;
+        collected.push(target);
+    }
+
+    Ok(collected)
+}
+
+/// Quotes a string for safe use as a single POSIX shell word.
+pub fn shell_quote(s: &str) -> String {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '.' | '_' | '-' | '+'))
+    {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+#[allow(clippy::literal_string_with_formatting_args)] // `{files}` is our placeholder
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_file() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let root = temp.path();
+        assert!(IgnoreFile::load(root).is_none());
+
+        std::fs::write(
+            root.join(IGNORE_FILE_NAME),
+            "# generated code\nvendor/\n*.pb.go\n!keep.pb.go\n",
+        )
+        .expect("write ignore file");
+        let ignore = IgnoreFile::load(root).expect("load ignore file");
+
+        let mut files = vec![
+            root.join("vendor/lib/a.go"),
+            root.join("api/service.pb.go"),
+            root.join("api/keep.pb.go"),
+            root.join("main.go"),
+            PathBuf::from("vendor.go"),
+        ];
+        ignore.retain(&mut files);
+        assert_eq!(
+            files,
+            [
+                root.join("api/keep.pb.go"),
+                root.join("main.go"),
+                PathBuf::from("vendor.go")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_uses_files() {
+        assert!(uses_files("ruff check {files}"));
+        assert!(!uses_files("ruff check ."));
+    }
+
+    #[test]
+    fn test_expand_files_relative_to_root() {
+        let root = Path::new("/repo");
+        let files = vec![PathBuf::from("/repo/src/a.py"), PathBuf::from("/repo/b.py")];
+        assert_eq!(
+            expand_files("ruff check {files}", &files, root),
+            "ruff check src/a.py b.py"
+        );
+    }
+
+    #[test]
+    fn test_expand_files_empty() {
+        assert_eq!(
+            expand_files("lint {files}", &[], Path::new("/repo")),
+            "lint "
+        );
+    }
+
+    #[test]
+    fn test_expand_files_quotes_special_characters() {
+        let root = Path::new("/repo");
+        let files = vec![
+            PathBuf::from("/repo/my file.txt"),
+            PathBuf::from("/repo/it's.txt"),
+        ];
+        assert_eq!(
+            expand_files("cat {files}", &files, root),
+            r"cat 'my file.txt' 'it'\''s.txt'"
+        );
+    }
+
+    #[test]
+    fn test_expand_files_without_placeholder_is_unchanged() {
+        let files = vec![PathBuf::from("/repo/a.rs")];
+        assert_eq!(
+            expand_files("cargo test", &files, Path::new("/repo")),
+            "cargo test"
+        );
+    }
+
+    #[test]
+    fn test_glob_files() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("logs/nested")).expect("create dirs");
+        std::fs::write(root.join("logs/a.log"), "a").expect("write");
+        std::fs::write(root.join("logs/nested/b.log"), "b").expect("write");
+        std::fs::write(root.join("logs/c.txt"), "c").expect("write");
+
+        let files = glob_files(
+            root,
+            &["logs/**/*.log".to_string(), "logs/a.log".to_string()],
+        );
+        assert_eq!(
+            files,
+            vec![root.join("logs/a.log"), root.join("logs/nested/b.log")]
+        );
+    }
+
+    #[test]
+    fn test_glob_files_no_matches() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        assert!(glob_files(temp.path(), &["*.log".to_string()]).is_empty());
+        assert!(glob_files(temp.path(), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_collect_artifacts_preserves_layout() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("out/logs")).expect("create dirs");
+        std::fs::write(root.join("out/logs/run.log"), "log").expect("write");
+
+        let dest = root.join("artifacts/check");
+        std::fs::create_dir_all(&dest).expect("create dest");
+        std::fs::write(dest.join(ARTIFACT_MARKER), "").expect("write marker");
+        std::fs::write(dest.join("stale.log"), "old").expect("write stale");
+
+        let collected =
+            collect_artifacts(root, &["out/**/*.log".to_string()], &dest).expect("collect");
+
+        assert_eq!(collected, vec![dest.join("out/logs/run.log")]);
+        assert!(dest.join("out/logs/run.log").exists());
+        assert!(!dest.join("stale.log").exists());
+        assert!(dest.join(ARTIFACT_MARKER).is_file());
+    }
+
+    #[test]
+    fn test_collect_artifacts_keeps_foreign_dir() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let root = temp.path();
+        std::fs::write(root.join("run.log"), "log").expect("write");
+
+        let dest = root.join("docs");
+        std::fs::create_dir_all(&dest).expect("create dest");
+        std::fs::write(dest.join("guide.md"), "keep me").expect("write doc");
+
+        let err = collect_artifacts(root, &["*.log".to_string()], &dest)
+            .expect_err("should not clear a directory apc didn't create");
+        assert!(err.to_string().contains("clear artifact dir"), "{err}");
+        assert!(dest.join("guide.md").exists());
+    }
+
+    #[test]
+    fn test_relative_to_outside_root() {
+        assert_eq!(
+            relative_to(Path::new("/other/a.rs"), Path::new("/repo")),
+            PathBuf::from("/other/a.rs")
+        );
+    }
+
+    #[test]
+    fn test_parse_file_list() {
+        let root = Path::new("/repo");
+        let files =
+            parse_file_list("src/a.rs\n\n  ./docs/../b.md  \n/repo/c/d.py\n", root).expect("parse");
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("/repo/src/a.rs"),
+                PathBuf::from("/repo/b.md"),
+                PathBuf::from("/repo/c/d.py"),
+            ]
+        );
+        assert!(parse_file_list("", root).expect("parse").is_empty());
+    }
+
+    #[test]
+    fn test_parse_file_list_rejects_paths_outside_root() {
+        let root = Path::new("/repo");
+        for outside in ["../secret", "src/../../etc/passwd", "/etc/passwd"] {
+            let err = parse_file_list(outside, root).expect_err("outside root");
+            assert!(err
+                .to_string()
+                .contains(&format!("'{outside}' is outside the repository")));
+        }
+    }
+}
@@ -7,6 +7,34 @@ use crate::core::error::{Error, Result};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Config files that mark a repository as using Lefthook.
+const LEFTHOOK_CONFIGS: &[&str] = &[
+    "lefthook.yml",
+    "lefthook.yaml",
+    ".lefthook.yml",
+    ".lefthook.yaml",
+];
+
+/// A third-party tool that manages a repository's git hooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HooksManager {
+    /// Husky, whose hooks are scripts in `.husky`.
+    Husky,
+    /// Lefthook, which generates hooks from `lefthook.yml`.
+    Lefthook,
+}
+
+impl HooksManager {
+    /// Returns the tool's name.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Husky => "Husky",
+            Self::Lefthook => "Lefthook",
+        }
+    }
+}
+
 /// Represents a Git repository.
 #[derive(Debug, Clone)]
 pub struct GitRepo {
@@ -14,19 +42,89 @@ pub struct GitRepo {
     root: PathBuf,
     /// Path to the .git directory (or file for worktrees).
     git_dir: PathBuf,
+    /// Path to the directory shared by all worktrees, which holds the hooks.
+    /// Same as `git_dir` outside linked worktrees.
+    common_dir: PathBuf,
 }
 
 impl GitRepo {
     /// Discovers the Git repository from the current directory.
+    ///
+    /// `GIT_DIR` and `GIT_WORK_TREE` take precedence when set, since hooks and
+    /// CI jobs may run from outside the work tree.
     pub fn discover() -> Result<Self> {
-        Self::discover_from(&std::env::current_dir().map_err(|e| Error::io("get current dir", e))?)
+        let cwd = std::env::current_dir().map_err(|e| Error::io("get current dir", e))?;
+        let env_path = |key| {
+            std::env::var_os(key)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        };
+        Self::discover_with(
+            &cwd,
+            env_path("GIT_DIR").as_deref(),
+            env_path("GIT_WORK_TREE").as_deref(),
+        )
     }
 
     /// Discovers the Git repository from a specific path.
     pub fn discover_from(path: &Path) -> Result<Self> {
-        let output = Command::new("git")
-            .args(["rev-parse", "--show-toplevel", "--git-dir"])
-            .current_dir(path)
+        Self::rev_parse(path, None)
+    }
+
+    /// Discovers the repository from `cwd`, preferring an explicit git dir and
+    /// work tree (relative paths are resolved against `cwd`).
+    ///
+    /// With only one of them given, `rev-parse` fills in the other.
+    fn discover_with(cwd: &Path, git_dir: Option<&Path>, work_tree: Option<&Path>) -> Result<Self> {
+        let git_dir = git_dir.map(|p| cwd.join(p));
+        let work_tree = work_tree.map(|p| cwd.join(p));
+
+        if let Some(dir) = git_dir.as_ref().filter(|d| !d.exists()) {
+            return Err(Error::git(
+                "discover repository",
+                format!("GIT_DIR {} does not exist", dir.display()),
+            ));
+        }
+        if let Some(dir) = work_tree.as_ref().filter(|d| !d.is_dir()) {
+            return Err(Error::git(
+                "discover repository",
+                format!("GIT_WORK_TREE {} is not a directory", dir.display()),
+            ));
+        }
+
+        match (git_dir, work_tree) {
+            (Some(git_dir), Some(root)) => Ok(Self {
+                root,
+                common_dir: common_dir_of(&git_dir),
+                git_dir,
+            }),
+            (git_dir, work_tree) => {
+                let repo =
+                    Self::rev_parse(work_tree.as_deref().unwrap_or(cwd), git_dir.as_deref())?;
+                Ok(Self {
+                    root: work_tree.unwrap_or(repo.root),
+                    git_dir: git_dir.unwrap_or(repo.git_dir),
+                    common_dir: repo.common_dir,
+                })
+            },
+        }
+    }
+
+    /// Asks git for the repository containing `path`, optionally with an
+    /// explicit `GIT_DIR`.
+    fn rev_parse(path: &Path, git_dir: Option<&Path>) -> Result<Self> {
+        let mut cmd = Command::new("git");
+        cmd.args([
+            "rev-parse",
+            "--show-toplevel",
+            "--git-dir",
+            "--git-common-dir",
+        ])
+        .current_dir(path);
+        if let Some(git_dir) = git_dir {
+            cmd.env("GIT_DIR", git_dir);
+        }
+        let output = cmd
             .output()
             .map_err(|e| Error::io("run git rev-parse", e))?;
 
@@ -51,7 +149,16 @@ impl GitRepo {
             })
             .ok_or(Error::NotGitRepo)?;
 
-        Ok(Self { root, git_dir })
+        // Relative to the directory git ran in
+        let common_dir = lines
+            .next()
+            .map_or_else(|| git_dir.clone(), |s| path.join(s));
+
+        Ok(Self {
+            root,
+            git_dir,
+            common_dir,
+        })
     }
 
     /// Returns the root directory of the repository.
@@ -66,29 +173,50 @@ impl GitRepo {
         &self.git_dir
     }
 
+    /// Returns the directory shared by all worktrees: the main `.git`
+    /// directory, even from a linked worktree.
+    #[must_use]
+    pub fn common_dir(&self) -> &Path {
+        &self.common_dir
+    }
+
     /// Returns the hooks directory path.
     #[must_use]
     pub fn hooks_dir(&self) -> PathBuf {
-        // Check for custom hooks path first
-        if let Ok(output) = Command::new("git")
+        // Check for custom hooks path first, then default to .git/hooks,
+        // which linked worktrees share
+        self.hooks_path_config()
+            .unwrap_or_else(|| self.common_dir.join("hooks"))
+    }
+
+    /// Returns `core.hooksPath`, resolved against the repository root.
+    fn hooks_path_config(&self) -> Option<PathBuf> {
+        let output = Command::new("git")
             .args(["config", "--get", "core.hooksPath"])
             .current_dir(&self.root)
             .output()
-        {
-            if output.status.success() {
-                let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                if !path.is_empty() {
-                    let hooks_path = PathBuf::from(&path);
-                    if hooks_path.is_absolute() {
-                        return hooks_path;
-                    }
-                    return self.root.join(hooks_path);
-                }
-            }
-        }
+            .ok()
+            .filter(|output| output.status.success())?;
+        let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (!path.is_empty()).then(|| self.root.join(path))
+    }
 
-        // Default to .git/hooks
-        self.git_dir.join("hooks")
+    /// Returns the tool that manages the repository's hooks, if any.
+    ///
+    /// Husky is recognized by `core.hooksPath` pointing into `.husky` or by a
+    /// `.husky` directory, Lefthook by its config file.
+    #[must_use]
+    pub fn hooks_manager(&self) -> Option<HooksManager> {
+        let husky_path = self
+            .hooks_path_config()
+            .is_some_and(|path| path.components().any(|c| c.as_os_str() == ".husky"));
+        if husky_path || self.root.join(".husky").is_dir() {
+            return Some(HooksManager::Husky);
+        }
+        LEFTHOOK_CONFIGS
+            .iter()
+            .any(|name| self.root.join(name).is_file())
+            .then_some(HooksManager::Lefthook)
     }
 
     /// Returns the path to a specific hook.
@@ -118,6 +246,60 @@ impl GitRepo {
         Ok(files)
     }
 
+    /// Returns the staged changes as a unified diff (`git diff --cached`).
+    pub fn staged_diff(&self) -> Result<String> {
+        let output = Command::new("git")
+            .args(["diff", "--cached", "--no-color", "--no-ext-diff"])
+            .current_dir(&self.root)
+            .output()
+            .map_err(|e| Error::io("get staged diff", e))?;
+
+        if !output.status.success() {
+            return Err(Error::git("diff --cached", "Failed to get staged diff"));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Returns the paths with unresolved merge conflicts in the index.
+    pub fn unmerged_paths(&self) -> Result<Vec<PathBuf>> {
+        let output = Command::new("git")
+            .args(["diff", "--name-only", "--diff-filter=U"])
+            .current_dir(&self.root)
+            .output()
+            .map_err(|e| Error::io("get unmerged paths", e))?;
+
+        if !output.status.success() {
+            return Err(Error::git(
+                "diff --diff-filter=U",
+                "Failed to get unmerged paths",
+            ));
+        }
+
+        let paths = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|s| !s.is_empty())
+            .map(|s| self.root.join(s))
+            .collect();
+
+        Ok(paths)
+    }
+
+    /// Returns all files in the working tree that are tracked or not ignored.
+    pub fn working_tree_files(&self) -> Result<Vec<PathBuf>> {
+        let files = self
+            .run_git(
+                "ls-files",
+                &["ls-files", "--cached", "--others", "--exclude-standard"],
+            )?
+            .lines()
+            .filter(|s| !s.is_empty())
+            .map(|s| self.root.join(s))
+            .collect();
+
+        Ok(files)
+    }
+
     /// Returns the current branch name.
     pub fn current_branch(&self) -> Result<String> {
         let output = Command::new("git")
@@ -133,6 +315,30 @@ impl GitRepo {
         Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     }
 
+    /// Returns the author and committer git would record for a new commit,
+    /// as `(name, email)` pairs, skipping identities git cannot determine.
+    ///
+    /// Unlike `user.name`/`user.email`, this honors `GIT_AUTHOR_*` and
+    /// `GIT_COMMITTER_*` overrides.
+    #[must_use]
+    pub fn commit_identities(&self) -> Vec<(String, String)> {
+        ["GIT_AUTHOR_IDENT", "GIT_COMMITTER_IDENT"]
+            .iter()
+            .filter_map(|var| self.run_git("var", &["var", var]).ok())
+            .filter_map(|ident| {
+                // "Name <email> 1700000000 +0000"
+                let (name, rest) = ident.split_once('<')?;
+                let (email, _) = rest.split_once('>')?;
+                Some((name.trim().to_string(), email.trim().to_string()))
+            })
+            .collect()
+    }
+
+    /// Returns the abbreviated hash of the `HEAD` commit.
+    pub fn head_sha(&self) -> Result<String> {
+        self.run_git("rev-parse", &["rev-parse", "--short", "HEAD"])
+    }
+
     /// Returns the main branch name (main or master).
     pub fn main_branch(&self) -> Result<String> {
         // Try 'main' first
@@ -161,6 +367,103 @@ impl GitRepo {
         Ok("main".to_string())
     }
 
+    /// Resolves a ref for the default branch.
+    ///
+    /// Prefers the remote's `HEAD` (`origin/HEAD`), then `origin/main`,
+    /// `origin/master`, and finally local `main` or `master`. Returns `None`
+    /// if none of these exist.
+    pub fn default_branch_ref(&self) -> Result<Option<String>> {
+        let output = Command::new("git")
+            .args([
+                "symbolic-ref",
+                "--quiet",
+                "--short",
+                "refs/remotes/origin/HEAD",
+            ])
+            .current_dir(&self.root)
+            .output()
+            .map_err(|e| Error::io("resolve origin/HEAD", e))?;
+
+        if output.status.success() {
+            let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if !name.is_empty() {
+                return Ok(Some(name));
+            }
+        }
+
+        for candidate in ["origin/main", "origin/master", "main", "master"] {
+            if self.rev_exists(candidate)? {
+                return Ok(Some(candidate.to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the merge-base commit of two revisions.
+    pub fn merge_base(&self, a: &str, b: &str) -> Result<String> {
+        let output = Command::new("git")
+            .args(["merge-base", a, b])
+            .current_dir(&self.root)
+            .output()
+            .map_err(|e| Error::io("run git merge-base", e))?;
+
+        if !output.status.success() {
+            // Exit status 1 with no message means the revisions share no history
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            let message = if stderr.is_empty() {
+                format!("No common ancestor between {a} and {b}")
+            } else {
+                stderr
+            };
+            return Err(Error::git("merge-base", message));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Returns the files changed between a revision and the index.
+    ///
+    /// This covers both commits made since `rev` and currently staged changes.
+    pub fn changed_files_since(&self, rev: &str) -> Result<Vec<PathBuf>> {
+        let output = Command::new("git")
+            .args(["diff", "--cached", "--name-only", "--diff-filter=ACMR", rev])
+            .current_dir(&self.root)
+            .output()
+            .map_err(|e| Error::io("get changed files", e))?;
+
+        if !output.status.success() {
+            return Err(Error::git(
+                "diff --cached",
+                format!("Failed to get files changed since {rev}"),
+            ));
+        }
+
+        let files = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|s| !s.is_empty())
+            .map(|s| self.root.join(s))
+            .collect();
+
+        Ok(files)
+    }
+
+    /// Checks if a revision resolves to a commit.
+    fn rev_exists(&self, rev: &str) -> Result<bool> {
+        let output = Command::new("git")
+            .args([
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("{rev}^{{commit}}"),
+            ])
+            .current_dir(&self.root)
+            .output()
+            .map_err(|e| Error::io("verify revision", e))?;
+
+        Ok(output.status.success())
+    }
+
     /// Fetches updates from the remote for a specific branch.
     pub fn fetch_branch(&self, branch: &str) -> Result<()> {
         let output = Command::new("git")
@@ -192,6 +495,72 @@ impl GitRepo {
         Ok(!output.stdout.is_empty())
     }
 
+    /// Checks if tracked files have changes that are not staged.
+    pub fn has_unstaged_changes(&self) -> Result<bool> {
+        let output = Command::new("git")
+            .args(["diff", "--quiet"])
+            .current_dir(&self.root)
+            .output()
+            .map_err(|e| Error::io("check unstaged changes", e))?;
+
+        match output.status.code() {
+            Some(0) => Ok(false),
+            Some(1) => Ok(true),
+            _ => Err(Error::git("diff", "Failed to check unstaged changes")),
+        }
+    }
+
+    /// Stashes unstaged changes so the working tree matches the index.
+    ///
+    /// Returns `None` when there is nothing to stash. The returned guard puts the
+    /// changes back when restored or dropped, discarding any edits checks made
+    /// to tracked files in the meantime. Untracked files are left in place.
+    pub fn stash_unstaged(&self) -> Result<Option<StashGuard>> {
+        if !self.has_unstaged_changes()? {
+            return Ok(None);
+        }
+
+        if !self.rev_exists("HEAD")? {
+            tracing::warn!("Cannot stash unstaged changes before the first commit");
+            return Ok(None);
+        }
+
+        self.run_git(
+            "stash push",
+            &[
+                "stash",
+                "push",
+                "--keep-index",
+                "--quiet",
+                "-m",
+                "apc: unstaged changes",
+            ],
+        )?;
+        let stash = self.run_git("rev-parse", &["rev-parse", "refs/stash"])?;
+
+        Ok(Some(StashGuard {
+            repo: self.clone(),
+            stash,
+            restored: false,
+        }))
+    }
+
+    /// Runs a git command in the repository root, returning trimmed stdout.
+    fn run_git(&self, operation: &str, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&self.root)
+            .output()
+            .map_err(|e| Error::io(format!("run git {operation}"), e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::git(operation, stderr.trim().to_string()));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
     /// Checks if a file exists in the repository.
     #[must_use]
     pub fn file_exists(&self, relative_path: &str) -> bool {
@@ -205,6 +574,69 @@ impl GitRepo {
     }
 }
 
+/// Unstaged changes stashed by [`GitRepo::stash_unstaged`].
+///
+/// The changes are restored by [`StashGuard::restore`], or on drop if that
+/// was never called (e.g. when a check run errors out).
+#[derive(Debug)]
+#[must_use = "unstaged changes are restored when the guard is dropped"]
+pub struct StashGuard {
+    repo: GitRepo,
+    /// Commit id of the stash entry.
+    stash: String,
+    restored: bool,
+}
+
+impl StashGuard {
+    /// Restores the stashed changes, including the original index.
+    ///
+    /// If the stash cannot be applied it is kept, so `git stash list` still
+    /// has the changes.
+    pub fn restore(mut self) -> Result<()> {
+        self.restored = true;
+        self.apply()
+    }
+
+    fn apply(&self) -> Result<()> {
+        self.repo
+            .run_git("reset", &["reset", "--hard", "--quiet"])?;
+        self.repo.run_git(
+            "stash apply",
+            &["stash", "apply", "--index", "--quiet", &self.stash],
+        )?;
+
+        // Drop our entry by position, in case something else was stashed since
+        let entries = self
+            .repo
+            .run_git("stash list", &["stash", "list", "--format=%H"])?;
+        if let Some(index) = entries.lines().position(|h| h == self.stash) {
+            self.repo.run_git(
+                "stash drop",
+                &["stash", "drop", "--quiet", &format!("stash@{{{index}}}")],
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for StashGuard {
+    fn drop(&mut self) {
+        if !self.restored {
+            if let Err(e) = self.apply() {
+                tracing::error!(stash = %self.stash, error = %e, "Failed to restore unstaged changes");
+            }
+        }
+    }
+}
+
+/// Returns the directory shared by all worktrees for `git_dir`, which a
+/// linked worktree's git dir names in its `commondir` file.
+fn common_dir_of(git_dir: &Path) -> PathBuf {
+    std::fs::read_to_string(git_dir.join("commondir"))
+        .map_or_else(|_| git_dir.to_path_buf(), |dir| git_dir.join(dir.trim()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -247,6 +679,44 @@ mod tests {
         assert!(repo.git_dir().exists());
     }
 
+    #[test]
+    fn test_discover_with_git_dir_and_work_tree() {
+        let (temp, _) = create_test_repo();
+        let elsewhere = TempDir::new().expect("create temp dir");
+        let root = temp.path().canonicalize().expect("canonicalize temp");
+
+        let repo = GitRepo::discover_with(elsewhere.path(), Some(&root.join(".git")), Some(&root))
+            .expect("discover from env");
+        assert_eq!(repo.root(), root);
+        assert_eq!(repo.git_dir(), root.join(".git"));
+        assert!(repo.staged_files().expect("staged files").is_empty());
+    }
+
+    #[test]
+    fn test_discover_with_git_dir_only() {
+        let (temp, _) = create_test_repo();
+        let elsewhere = TempDir::new().expect("create temp dir");
+        let git_dir = temp.path().join(".git");
+
+        // Without a work tree, git treats the current directory as the work tree
+        let repo =
+            GitRepo::discover_with(elsewhere.path(), Some(&git_dir), None).expect("discover");
+        assert_eq!(repo.git_dir(), git_dir);
+        assert_eq!(
+            repo.root().canonicalize().expect("canonicalize root"),
+            elsewhere.path().canonicalize().expect("canonicalize cwd")
+        );
+    }
+
+    #[test]
+    fn test_discover_with_missing_git_dir() {
+        let elsewhere = TempDir::new().expect("create temp dir");
+        let err = GitRepo::discover_with(elsewhere.path(), Some(Path::new("nope/.git")), None)
+            .expect_err("missing GIT_DIR");
+        assert!(err.to_string().contains("GIT_DIR"));
+        assert!(err.to_string().contains("does not exist"));
+    }
+
     #[test]
     fn test_discover_from_subdirectory() {
         let (temp, _) = create_test_repo();
@@ -281,6 +751,56 @@ mod tests {
         assert!(hooks_dir.ends_with("hooks"));
     }
 
+    #[test]
+    fn test_hooks_dir_in_linked_worktree() {
+        let (temp, _) = create_test_repo();
+        let root = temp.path().canonicalize().expect("canonicalize temp");
+        let git = |args: &[&str]| {
+            let output = Command::new("git")
+                .args(args)
+                .current_dir(&root)
+                .output()
+                .expect("run git");
+            assert!(output.status.success(), "git {args:?} failed");
+        };
+        git(&["commit", "--allow-empty", "-m", "init"]);
+        git(&["worktree", "add", "wt"]);
+
+        let shared = root.join(".git/hooks");
+        let repo = GitRepo::discover_from(&root.join("wt")).expect("discover worktree");
+        assert_eq!(repo.git_dir(), root.join(".git/worktrees/wt"));
+        assert_eq!(repo.common_dir(), root.join(".git"));
+        assert_eq!(repo.hooks_dir(), shared);
+
+        let repo = GitRepo::discover_with(
+            &root,
+            Some(&root.join(".git/worktrees/wt")),
+            Some(&root.join("wt")),
+        )
+        .expect("discover from env");
+        assert_eq!(
+            repo.hooks_dir().canonicalize().expect("canonicalize hooks"),
+            shared
+        );
+    }
+
+    #[test]
+    fn test_hooks_manager() {
+        let (temp, repo) = create_test_repo();
+        assert_eq!(repo.hooks_manager(), None);
+
+        std::fs::write(temp.path().join("lefthook.yml"), "").expect("write config");
+        assert_eq!(repo.hooks_manager(), Some(HooksManager::Lefthook));
+
+        Command::new("git")
+            .args(["config", "core.hooksPath", ".husky/_"])
+            .current_dir(temp.path())
+            .output()
+            .expect("set hooksPath");
+        assert_eq!(repo.hooks_manager(), Some(HooksManager::Husky));
+        assert_eq!(repo.hooks_dir(), repo.root().join(".husky/_"));
+    }
+
     #[test]
     fn test_hook_path() {
         let (_temp, repo) = create_test_repo();
@@ -362,6 +882,36 @@ mod tests {
         assert!(staged.is_empty());
     }
 
+    #[test]
+    fn test_unmerged_paths() {
+        let (temp, repo) = create_test_repo();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(temp.path())
+                .output()
+                .expect("run git")
+        };
+
+        std::fs::write(temp.path().join("a.txt"), "base\n").expect("write file");
+        git(&["add", "a.txt"]);
+        git(&["commit", "-m", "base"]);
+        assert!(repo.unmerged_paths().expect("unmerged paths").is_empty());
+
+        git(&["checkout", "-b", "other"]);
+        std::fs::write(temp.path().join("a.txt"), "theirs\n").expect("write file");
+        git(&["commit", "-am", "theirs"]);
+        git(&["checkout", "-"]);
+        std::fs::write(temp.path().join("a.txt"), "ours\n").expect("write file");
+        git(&["commit", "-am", "ours"]);
+        let merge = git(&["merge", "other"]);
+        assert!(!merge.status.success());
+
+        let unmerged = repo.unmerged_paths().expect("unmerged paths");
+        assert_eq!(unmerged.len(), 1);
+        assert!(unmerged[0].ends_with("a.txt"));
+    }
+
     #[test]
     fn test_staged_files_with_file() {
         let (temp, repo) = create_test_repo();
@@ -380,6 +930,25 @@ mod tests {
         assert!(staged[0].ends_with("new_file.txt"));
     }
 
+    #[test]
+    fn test_staged_diff() {
+        let (temp, repo) = create_test_repo();
+        assert!(repo.staged_diff().expect("get staged diff").is_empty());
+
+        std::fs::write(temp.path().join("staged.txt"), "added line\n").expect("write file");
+        std::fs::write(temp.path().join("unstaged.txt"), "other\n").expect("write file");
+        Command::new("git")
+            .args(["add", "staged.txt"])
+            .current_dir(temp.path())
+            .output()
+            .expect("stage file");
+
+        let diff = repo.staged_diff().expect("get staged diff");
+        assert!(diff.contains("+++ b/staged.txt"));
+        assert!(diff.contains("+added line\n"));
+        assert!(!diff.contains("unstaged.txt"));
+    }
+
     #[test]
     fn test_staged_files_multiple() {
         let (temp, repo) = create_test_repo();
@@ -402,6 +971,13 @@ mod tests {
     // Branch tests
     // =========================================================================
 
+    #[test]
+    fn test_commit_identities() {
+        let (_temp, repo) = create_test_repo();
+        let identity = ("Test".to_string(), "test@test.com".to_string());
+        assert_eq!(repo.commit_identities(), [identity.clone(), identity]);
+    }
+
     #[test]
     fn test_current_branch_after_commit() {
         let (temp, repo) = create_test_repo();
@@ -429,6 +1005,85 @@ mod tests {
         // If it errors, that's acceptable - the method still works as expected
     }
 
+    /// Runs a git command in the test repo, asserting success.
+    fn git(path: &Path, args: &[&str]) {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(path)
+            .output()
+            .expect("run git");
+        assert!(output.status.success(), "git {args:?} failed");
+    }
+
+    /// Creates a repo with one commit on `main` and a `feature` branch one
+    /// commit ahead of it, plus a staged file.
+    fn create_feature_branch_repo() -> (TempDir, GitRepo) {
+        let (temp, repo) = create_test_repo();
+        let path = temp.path();
+
+        git(path, &["symbolic-ref", "HEAD", "refs/heads/main"]);
+        std::fs::write(path.join("base.txt"), "base").expect("write file");
+        git(path, &["add", "."]);
+        git(path, &["commit", "-m", "base"]);
+
+        git(path, &["checkout", "-b", "feature"]);
+        std::fs::write(path.join("feature.txt"), "feature").expect("write file");
+        git(path, &["add", "."]);
+        git(path, &["commit", "-m", "feature"]);
+
+        std::fs::write(path.join("staged.txt"), "staged").expect("write file");
+        git(path, &["add", "staged.txt"]);
+
+        (temp, repo)
+    }
+
+    #[test]
+    fn test_default_branch_ref_local_main() {
+        let (_temp, repo) = create_feature_branch_repo();
+        let branch = repo.default_branch_ref().expect("resolve default branch");
+        assert_eq!(branch.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn test_default_branch_ref_none() {
+        let (_temp, repo) = create_test_repo();
+        let branch = repo.default_branch_ref().expect("resolve default branch");
+        assert!(branch.is_none());
+    }
+
+    #[test]
+    fn test_merge_base_with_default_branch() {
+        let (temp, repo) = create_feature_branch_repo();
+
+        let output = Command::new("git")
+            .args(["rev-parse", "main"])
+            .current_dir(temp.path())
+            .output()
+            .expect("rev-parse main");
+        let main_sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        let base = repo.merge_base("HEAD", "main").expect("merge-base");
+        assert_eq!(base, main_sha);
+    }
+
+    #[test]
+    fn test_merge_base_unknown_ref() {
+        let (_temp, repo) = create_feature_branch_repo();
+        assert!(repo.merge_base("HEAD", "does-not-exist").is_err());
+    }
+
+    #[test]
+    fn test_changed_files_since_merge_base() {
+        let (_temp, repo) = create_feature_branch_repo();
+        let base = repo.merge_base("HEAD", "main").expect("merge-base");
+
+        let changed = repo.changed_files_since(&base).expect("changed files");
+        assert_eq!(changed.len(), 2);
+        assert!(changed.iter().any(|f| f.ends_with("feature.txt")));
+        assert!(changed.iter().any(|f| f.ends_with("staged.txt")));
+        assert!(!changed.iter().any(|f| f.ends_with("base.txt")));
+    }
+
     // =========================================================================
     // Uncommitted changes tests
     // =========================================================================
@@ -484,6 +1139,104 @@ mod tests {
         assert!(result.expect("check changes"));
     }
 
+    #[test]
+    fn test_working_tree_files_skips_ignored() {
+        let (temp, repo) = create_test_repo();
+        let path = temp.path();
+        std::fs::write(path.join(".gitignore"), "target/\n").expect("write file");
+        std::fs::create_dir_all(path.join("target")).expect("create dir");
+        std::fs::write(path.join("target/out.o"), "").expect("write file");
+        std::fs::write(path.join("tracked.rs"), "").expect("write file");
+        git(path, &["add", "tracked.rs"]);
+        std::fs::write(path.join("untracked.rs"), "").expect("write file");
+
+        let mut files = repo.working_tree_files().expect("list files");
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                path.join(".gitignore"),
+                path.join("tracked.rs"),
+                path.join("untracked.rs")
+            ]
+        );
+    }
+
+    // =========================================================================
+    // Stash tests
+    // =========================================================================
+
+    /// Creates a repo where `a.txt` has a staged edit and a further unstaged edit.
+    fn create_partially_staged_repo() -> (TempDir, GitRepo) {
+        let (temp, repo) = create_test_repo();
+        let path = temp.path();
+
+        std::fs::write(path.join("a.txt"), "base\n").expect("write file");
+        git(path, &["add", "."]);
+        git(path, &["commit", "-m", "base"]);
+
+        std::fs::write(path.join("a.txt"), "staged\n").expect("write file");
+        git(path, &["add", "a.txt"]);
+        std::fs::write(path.join("a.txt"), "unstaged\n").expect("write file");
+
+        (temp, repo)
+    }
+
+    fn index_content(path: &Path, file: &str) -> String {
+        let output = Command::new("git")
+            .args(["show", &format!(":{file}")])
+            .current_dir(path)
+            .output()
+            .expect("git show");
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    #[test]
+    fn test_stash_unstaged_hides_and_restores_changes() {
+        let (temp, repo) = create_partially_staged_repo();
+        let file = temp.path().join("a.txt");
+
+        let guard = repo
+            .stash_unstaged()
+            .expect("stash")
+            .expect("changes to stash");
+        assert_eq!(std::fs::read_to_string(&file).expect("read"), "staged\n");
+
+        guard.restore().expect("restore");
+        assert_eq!(std::fs::read_to_string(&file).expect("read"), "unstaged\n");
+        assert_eq!(index_content(temp.path(), "a.txt"), "staged\n");
+        assert_eq!(
+            repo.run_git("stash list", &["stash", "list"])
+                .expect("list"),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_stash_unstaged_restores_on_drop() {
+        let (temp, repo) = create_partially_staged_repo();
+        let file = temp.path().join("a.txt");
+
+        {
+            let _guard = repo.stash_unstaged().expect("stash");
+            std::fs::write(&file, "modified by a check\n").expect("write");
+        }
+
+        assert_eq!(std::fs::read_to_string(&file).expect("read"), "unstaged\n");
+        assert_eq!(index_content(temp.path(), "a.txt"), "staged\n");
+    }
+
+    #[test]
+    fn test_stash_unstaged_without_changes() {
+        let (temp, repo) = create_test_repo();
+        std::fs::write(temp.path().join("a.txt"), "base").expect("write file");
+        git(temp.path(), &["add", "."]);
+        git(temp.path(), &["commit", "-m", "base"]);
+
+        assert!(!repo.has_unstaged_changes().expect("check"));
+        assert!(repo.stash_unstaged().expect("stash").is_none());
+    }
+
     // =========================================================================
     // Path accessor tests
     // =========================================================================
@@ -0,0 +1,221 @@
+//! Dependency ordering for checks.
+//!
+//! Checks may declare `needs = ["other"]`. This module orders a set of checks
+//! into waves: every check in a wave depends only on checks in earlier waves,
+//! so each wave can run concurrently once the previous one has finished.
+//! Checks within a wave may be shuffled with a seed, to expose hidden ordering
+//! dependencies reproducibly.
+
+use crate::config::CheckConfig;
+use crate::core::error::{Error, Result};
+use std::collections::{HashMap, HashSet};
+use std::hash::BuildHasher;
+
+/// Returns the dependencies of `name` as declared in `checks`.
+fn needs_of<'a, S: BuildHasher>(
+    checks: &'a HashMap<String, CheckConfig, S>,
+    name: &str,
+) -> &'a [String] {
+    checks.get(name).map_or(&[], |c| c.needs.as_slice())
+}
+
+/// Orders `names` into dependency waves.
+///
+/// Only dependencies within `names` affect the ordering; a check that needs
+/// something outside the set is treated as ready. Within a wave, checks keep
+/// their order from `names`. Returns an error if the checks form a cycle.
+pub fn waves<S: BuildHasher>(
+    names: &[String],
+    checks: &HashMap<String, CheckConfig, S>,
+) -> Result<Vec<Vec<String>>> {
+    let selected: HashSet<&str> = names.iter().map(String::as_str).collect();
+    let mut done: HashSet<&str> = HashSet::new();
+    let mut waves = Vec::new();
+
+    while done.len() < selected.len() {
+        let wave: Vec<&String> = names
+            .iter()
+            .filter(|n| !done.contains(n.as_str()))
+            .filter(|n| {
+                needs_of(checks, n)
+                    .iter()
+                    .all(|dep| done.contains(dep.as_str()) || !selected.contains(dep.as_str()))
+            })
+            .collect();
+
+        if wave.is_empty() {
+            let remaining: Vec<&str> = names
+                .iter()
+                .map(String::as_str)
+                .filter(|n| !done.contains(n))
+                .collect();
+            return Err(cycle_error(&remaining, checks));
+        }
+
+        done.extend(wave.iter().map(|n| n.as_str()));
+        waves.push(wave.into_iter().cloned().collect());
+    }
+
+    Ok(waves)
+}
+
+/// Seeded shuffler for the checks within a wave (SplitMix64).
+///
+/// The same seed always yields the same permutations, so a failing shuffled
+/// run can be replayed.
+#[derive(Debug, Clone)]
+pub struct Shuffle {
+    state: u64,
+}
+
+impl Shuffle {
+    /// Creates a shuffler from `seed`.
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Shuffles `items` in place (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            // Truncation is fine: the value is already below `i + 1`
+            #[allow(clippy::cast_possible_truncation)]
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// Returns the next pseudo-random number.
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Builds an error describing one cycle among the `remaining` checks.
+///
+/// Every remaining check waits on another remaining check, so following
+/// dependencies from any of them must eventually revisit a check.
+fn cycle_error<S: BuildHasher>(
+    remaining: &[&str],
+    checks: &HashMap<String, CheckConfig, S>,
+) -> Error {
+    let pending: HashSet<&str> = remaining.iter().copied().collect();
+    let mut path: Vec<&str> = Vec::new();
+    let mut current = remaining.first().copied().unwrap_or_default();
+
+    while !path.contains(&current) {
+        path.push(current);
+        match needs_of(checks, current)
+            .iter()
+            .find(|dep| pending.contains(dep.as_str()))
+        {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+
+    let start = path.iter().position(|n| *n == current).unwrap_or(0);
+    let mut cycle: Vec<&str> = path[start..].to_vec();
+    cycle.push(current);
+
+    Error::ConfigInvalid {
+        field: format!("checks.{}.needs", cycle[0]),
+        message: format!("Dependency cycle: {}", cycle.join(" -> ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checks(deps: &[(&str, &[&str])]) -> HashMap<String, CheckConfig> {
+        deps.iter()
+            .map(|(name, needs)| {
+                (
+                    (*name).to_string(),
+                    CheckConfig {
+                        run: "true".to_string(),
+                        needs: needs.iter().map(|n| (*n).to_string()).collect(),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|n| (*n).to_string()).collect()
+    }
+
+    #[test]
+    fn test_waves_without_dependencies() {
+        let checks = checks(&[("a", &[]), ("b", &[])]);
+        let waves = waves(&names(&["a", "b"]), &checks).expect("waves");
+        assert_eq!(waves, vec![names(&["a", "b"])]);
+    }
+
+    #[test]
+    fn test_waves_follow_dependencies() {
+        let checks = checks(&[
+            ("build", &["clippy", "fmt"]),
+            ("clippy", &[]),
+            ("fmt", &[]),
+            ("e2e", &["build"]),
+        ]);
+        let waves = waves(&names(&["e2e", "build", "clippy", "fmt"]), &checks).expect("waves");
+        assert_eq!(
+            waves,
+            vec![
+                names(&["clippy", "fmt"]),
+                names(&["build"]),
+                names(&["e2e"])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_waves_ignore_unselected_dependencies() {
+        let checks = checks(&[("build", &["clippy"]), ("clippy", &[])]);
+        let waves = waves(&names(&["build"]), &checks).expect("waves");
+        assert_eq!(waves, vec![names(&["build"])]);
+    }
+
+    #[test]
+    fn test_waves_reject_cycle() {
+        let checks = checks(&[("a", &["b"]), ("b", &["c"]), ("c", &["a"]), ("d", &[])]);
+        let err = waves(&names(&["d", "a", "b", "c"]), &checks).expect_err("cycle");
+        assert_eq!(
+            err.to_string(),
+            "Invalid configuration: checks.a.needs - Dependency cycle: a -> b -> c -> a"
+        );
+    }
+
+    #[test]
+    fn test_shuffle_is_deterministic() {
+        let original = names(&["a", "b", "c", "d", "e", "f", "g", "h"]);
+        let shuffled = |seed| {
+            let mut list = original.clone();
+            Shuffle::new(seed).shuffle(&mut list);
+            list
+        };
+
+        assert_eq!(shuffled(42), shuffled(42));
+        assert_ne!(shuffled(42), shuffled(7));
+        assert_ne!(shuffled(42), original);
+
+        let mut sorted = shuffled(42);
+        sorted.sort();
+        assert_eq!(sorted, original);
+    }
+
+    #[test]
+    fn test_waves_reject_self_dependency() {
+        let checks = checks(&[("a", &["a"])]);
+        let err = waves(&names(&["a"]), &checks).expect_err("cycle");
+        assert!(err.to_string().contains("a -> a"));
+    }
+}
@@ -0,0 +1,121 @@
+//! `${VAR}` interpolation in check commands.
+//!
+//! Only the braced form is expanded; a bare `$VAR` is left for the shell, so
+//! existing shell-based commands keep working unchanged.
+
+/// Returns the names of the `${VAR}` references in `input`, in order.
+#[must_use]
+pub fn references(input: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = input;
+
+    while let Some((name, after)) = next_reference(rest) {
+        if let Some(name) = name {
+            names.push(name);
+        }
+        rest = after;
+    }
+
+    names
+}
+
+/// Replaces every `${VAR}` in `input` with the value returned by `lookup`.
+///
+/// Returns the name of the first variable `lookup` cannot resolve.
+pub fn expand(
+    input: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> std::result::Result<String, String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("${") {
+        let Some((name, after)) = next_reference(rest) else {
+            break;
+        };
+
+        match name {
+            Some(name) => {
+                let value = lookup(name).ok_or_else(|| name.to_string())?;
+                output.push_str(&rest[..start]);
+                output.push_str(&value);
+            },
+            None => output.push_str(&rest[..rest.len() - after.len()]),
+        }
+        rest = after;
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Finds the next `${...}` in `input`.
+///
+/// Returns the variable name (or `None` if the braces do not hold a valid
+/// name) and the text after the closing brace. Returns `None` if there is no
+/// complete reference left.
+fn next_reference(input: &str) -> Option<(Option<&str>, &str)> {
+    let start = input.find("${")?;
+    let body = &input[start + 2..];
+    let end = body.find('}')?;
+
+    let name = &body[..end];
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    Some((valid.then_some(name), &body[end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "TARGET" => Some("/tmp/target".to_string()),
+            "PROFILE" => Some("release".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand_braced_variables() {
+        assert_eq!(
+            expand("ls ${TARGET}/${PROFILE}", lookup).expect("expand"),
+            "ls /tmp/target/release"
+        );
+    }
+
+    #[test]
+    fn test_expand_leaves_bare_variables() {
+        assert_eq!(
+            expand("echo $HOME ${TARGET}", lookup).expect("expand"),
+            "echo $HOME /tmp/target"
+        );
+    }
+
+    #[test]
+    fn test_expand_unknown_variable() {
+        assert_eq!(
+            expand("echo ${MISSING}", lookup),
+            Err("MISSING".to_string())
+        );
+    }
+
+    #[test]
+    fn test_expand_ignores_invalid_and_unterminated() {
+        assert_eq!(
+            expand("echo ${1} ${a-b} ${TARGET", lookup).expect("expand"),
+            "echo ${1} ${a-b} ${TARGET"
+        );
+    }
+
+    #[test]
+    fn test_references() {
+        assert_eq!(references("${A} $B ${_c1} ${1x} ${D"), vec!["A", "_c1"]);
+        assert!(references("cargo test").is_empty());
+    }
+}
@@ -1,13 +1,25 @@
 //! Core functionality for agent-precommit.
 //!
 //! This module contains the main components:
+//! - [`cache`]: Skipping checks whose declared inputs are unchanged
 //! - [`detector`]: Mode detection (human, agent, CI)
+//! - [`doctor`]: Tool availability checks for `apc doctor`
 //! - [`runner`]: Check execution engine
 //! - [`error`]: Error types and result handling
 //! - [`git`]: Git repository operations
+//! - [`files`]: File selection for `{files}` placeholders
+//! - [`graph`]: Dependency ordering for checks
+//! - [`interpolate`]: `${VAR}` expansion in check commands
+//! - [`report`]: Machine-readable run reports
 
+pub mod cache;
 pub mod detector;
+pub mod doctor;
 pub mod error;
 pub mod executor;
+pub mod files;
 pub mod git;
+pub mod graph;
+pub mod interpolate;
+pub mod report;
 pub mod runner;
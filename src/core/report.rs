@@ -0,0 +1,1249 @@
+//! Machine-readable run reports.
+//!
+//! Converts a [`RunResult`] into a serializable report with a stable layout:
+//! checks are sorted by name and fields are emitted in declaration order, so
+//! two reports over the same result are byte-identical. Reports can also be
+//! rendered as a plain-text summary table, as JUnit XML, as SARIF, or as
+//! Prometheus metrics.
+
+use crate::config::{CheckConfig, Config, CONFIG_FILE_NAME};
+use crate::core::detector::{AgentKind, Detection, DetectionReason, EnvironmentSnapshot, Mode};
+use crate::core::error::{Error, Result};
+use crate::core::git::GitRepo;
+use crate::core::runner::{CheckResult, CheckStatus, MatrixResult, RunResult};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::hash::BuildHasher;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Bytes of each output stream kept in an `apc run --report json` report.
+pub const REPORT_OUTPUT_LIMIT: usize = 64 * 1024;
+
+/// Report for a single check.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckReport {
+    /// Name of the check (its key in `[checks]`).
+    pub name: String,
+    /// Display title: the check's description with `report_use_description`,
+    /// otherwise its name.
+    pub title: String,
+    /// Outcome of the check.
+    pub status: CheckStatus,
+    /// Whether the check passed without printing anything.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub no_output: bool,
+    /// Exit code of the check command.
+    pub exit_code: i32,
+    /// Whether the check timed out.
+    pub timed_out: bool,
+    /// Signal that terminated the check command, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signal: Option<i32>,
+    /// Number of times the command ran.
+    pub attempts: u32,
+    /// Duration in milliseconds.
+    pub duration_ms: u64,
+    /// Reason the check was skipped, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skip_reason: Option<String>,
+    /// Captured standard output.
+    pub stdout: String,
+    /// Captured standard error.
+    pub stderr: String,
+    /// Collected artifact files.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<String>,
+}
+
+impl From<&CheckResult> for CheckReport {
+    fn from(check: &CheckResult) -> Self {
+        Self {
+            name: check.name.clone(),
+            title: check.name.clone(),
+            status: check.status,
+            no_output: check.is_silent_pass(),
+            exit_code: check.output.exit_code,
+            timed_out: check.output.timed_out,
+            signal: check.output.signal,
+            attempts: check.attempts,
+            duration_ms: millis(check.output.duration),
+            skip_reason: check.skip_reason.clone(),
+            stdout: check.output.stdout.clone(),
+            stderr: check.output.stderr.clone(),
+            artifacts: check
+                .artifacts
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect(),
+        }
+    }
+}
+
+impl CheckReport {
+    /// Summarizes why a failed check failed, e.g. `Exit code 2`.
+    fn failure_message(&self) -> String {
+        if self.status == CheckStatus::TimedOutGroup {
+            "Run timed out".to_string()
+        } else if self.timed_out {
+            "Timed out".to_string()
+        } else if let Some(signal) = self.signal {
+            format!("Terminated by signal {signal}")
+        } else {
+            format!("Exit code {}", self.exit_code)
+        }
+    }
+
+    /// Returns stdout followed by stderr.
+    fn output(&self) -> String {
+        if self.stderr.is_empty() {
+            self.stdout.clone()
+        } else if self.stdout.is_empty() {
+            self.stderr.clone()
+        } else {
+            format!("{}\n{}", self.stdout, self.stderr)
+        }
+    }
+}
+
+/// Report for a whole run.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    /// Mode the checks ran in.
+    pub mode: String,
+    /// Whether all checks passed.
+    pub success: bool,
+    /// Number of passed checks.
+    pub passed: usize,
+    /// Number of failed checks.
+    pub failed: usize,
+    /// Number of skipped checks.
+    pub skipped: usize,
+    /// Total duration in milliseconds.
+    pub duration_ms: u64,
+    /// How the run was set up, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<RunContext>,
+    /// Per-check reports, sorted by name.
+    pub checks: Vec<CheckReport>,
+}
+
+/// Environment a run happened in, for reproducing it later.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunContext {
+    /// Mode the checks ran in.
+    pub mode: String,
+    /// Why that mode was chosen.
+    pub detection_reason: String,
+    /// Root of the repository, if inside one.
+    pub repo_root: Option<String>,
+    /// Current branch, if one is checked out.
+    pub branch: Option<String>,
+    /// Version of apc that produced the report.
+    pub apc_version: String,
+}
+
+impl RunContext {
+    /// Captures the context for a run with the given mode detection.
+    #[must_use]
+    pub fn new(detection: &Detection, repo: Option<&GitRepo>) -> Self {
+        Self {
+            mode: detection.mode.name().to_string(),
+            detection_reason: detection.reason.to_string(),
+            repo_root: repo.map(|r| r.root().display().to_string()),
+            branch: repo.and_then(|r| r.current_branch().ok()),
+            apc_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+impl From<&RunResult> for RunReport {
+    fn from(result: &RunResult) -> Self {
+        let mut checks: Vec<CheckReport> = result.checks.iter().map(CheckReport::from).collect();
+        checks.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self {
+            mode: result.mode.name().to_string(),
+            success: result.success(),
+            passed: result.passed_count(),
+            failed: result.failed_count(),
+            skipped: result.skipped_count(),
+            duration_ms: millis(result.duration),
+            context: None,
+            checks,
+        }
+    }
+}
+
+impl RunReport {
+    /// Titles each check with its configured description, where it has one.
+    #[must_use]
+    pub fn with_descriptions<S: BuildHasher>(
+        mut self,
+        checks: &HashMap<String, CheckConfig, S>,
+    ) -> Self {
+        for report in &mut self.checks {
+            if let Some(check) = checks.get(&report.name) {
+                if !check.description.is_empty() {
+                    report.title.clone_from(&check.description);
+                }
+            }
+        }
+        self
+    }
+
+    /// Drops skipped checks from the per-check list, keeping the totals.
+    #[must_use]
+    pub fn without_skipped(mut self) -> Self {
+        self.checks.retain(|c| !c.status.is_skip());
+        self
+    }
+
+    /// Cuts each check's stdout and stderr to their last `limit` bytes,
+    /// noting how much was dropped.
+    #[must_use]
+    pub fn with_output_limit(mut self, limit: usize) -> Self {
+        for check in &mut self.checks {
+            truncate_output(&mut check.stdout, limit);
+            truncate_output(&mut check.stderr, limit);
+        }
+        self
+    }
+
+    /// Attaches the run's context to the report.
+    #[must_use]
+    pub fn with_context(mut self, context: RunContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Serializes the report to JSON, optionally pretty-printed.
+    pub fn to_json(&self, pretty: bool) -> Result<String> {
+        to_json(self, pretty)
+    }
+
+    /// Renders the report as a plain-text table with one row per check.
+    #[must_use]
+    pub fn to_table(&self) -> String {
+        let width = self
+            .checks
+            .iter()
+            .map(|c| c.title.len())
+            .chain(std::iter::once("Check".len()))
+            .max()
+            .unwrap_or_default();
+
+        let mut table = format!("{:<width$}  {:<7}  {:>8}\n", "Check", "Status", "Duration");
+        for check in &self.checks {
+            let _ = writeln!(
+                table,
+                "{:<width$}  {:<7}  {:>8}",
+                check.title,
+                check.status.as_str(),
+                format_millis(check.duration_ms)
+            );
+        }
+        table
+    }
+
+    /// Renders the report as a Markdown table with one row per check and a
+    /// totals line, for GitHub step summaries.
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let verdict = if self.success {
+            "all checks passed"
+        } else {
+            "checks failed"
+        };
+        let mut markdown = format!(
+            "### apc {}: {verdict}\n\n| Check | Status | Duration |\n| --- | --- | --- |\n",
+            self.mode
+        );
+        for check in &self.checks {
+            let _ = writeln!(
+                markdown,
+                "| {} | {} | {} |",
+                check.title.replace('|', "\\|"),
+                check.status.as_str(),
+                format_millis(check.duration_ms)
+            );
+        }
+        let _ = writeln!(
+            markdown,
+            "\n{} passed, {} failed, {} skipped in {}",
+            self.passed,
+            self.failed,
+            self.skipped,
+            format_millis(self.duration_ms)
+        );
+        markdown
+    }
+
+    /// Renders the report as a JUnit XML `<testsuite>` with one `<testcase>`
+    /// per check, for CI systems that aggregate JUnit results.
+    ///
+    /// Failed checks carry their combined output in a `<failure>` element and
+    /// skipped checks a `<skipped>` element with the reason.
+    #[must_use]
+    pub fn to_junit(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            xml,
+            "<testsuite name=\"apc.{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" time=\"{}\">",
+            self.mode,
+            self.checks.len(),
+            self.failed,
+            self.skipped,
+            format_seconds(self.duration_ms)
+        );
+
+        for check in &self.checks {
+            let _ = write!(
+                xml,
+                "  <testcase name=\"{}\" classname=\"{}\" time=\"{}\"",
+                xml_escape(&check.title),
+                xml_escape(&check.name),
+                format_seconds(check.duration_ms)
+            );
+            match check.status {
+                CheckStatus::Passed => xml.push_str("/>\n"),
+                CheckStatus::Skipped
+                | CheckStatus::Cached
+                | CheckStatus::Cancelled
+                | CheckStatus::Aborted => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n    <skipped message=\"{}\"/>\n  </testcase>",
+                        xml_escape(check.skip_reason.as_deref().unwrap_or_default())
+                    );
+                },
+                CheckStatus::Failed | CheckStatus::TimedOutGroup => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n    <failure message=\"{}\">{}</failure>\n  </testcase>",
+                        check.failure_message(),
+                        xml_escape(&check.output())
+                    );
+                },
+            }
+        }
+
+        xml.push_str("</testsuite>\n");
+        xml
+    }
+
+    /// Renders the report as GitHub Actions workflow commands.
+    ///
+    /// Each check that ran gets its output in a collapsible `::group::`, and
+    /// each failed check an `::error` annotation with the first line of its
+    /// output.
+    #[must_use]
+    pub fn to_github(&self) -> String {
+        let mut commands = String::new();
+        for check in &self.checks {
+            if check.status.is_skip() {
+                continue;
+            }
+            let output = check.output();
+            let _ = writeln!(
+                commands,
+                "::group::{} ({})",
+                github_escape(&check.title),
+                check.status.as_str()
+            );
+            if !output.is_empty() {
+                let _ = writeln!(commands, "{}", output.trim_end());
+            }
+            commands.push_str("::endgroup::\n");
+            if check.status.is_failure() {
+                let first_line = output
+                    .lines()
+                    .find(|line| !line.trim().is_empty())
+                    .map_or_else(|| check.failure_message(), ToString::to_string);
+                let _ = writeln!(
+                    commands,
+                    "::error title={}::{}",
+                    github_property_escape(&check.title),
+                    github_escape(&first_line)
+                );
+            }
+        }
+        commands
+    }
+
+    /// Renders the report as a SARIF 2.1.0 log, for code scanning tools.
+    ///
+    /// Each failed check becomes a run with a single result whose rule id is
+    /// the check name and whose message is the check's output. Checks have no
+    /// file or line to point at, so results are located at the config file.
+    pub fn to_sarif(&self) -> Result<String> {
+        let runs: Vec<serde_json::Value> = self
+            .checks
+            .iter()
+            .filter(|check| check.status.is_failure())
+            .map(|check| {
+                let output = check.output();
+                let message = if output.trim().is_empty() {
+                    check.failure_message()
+                } else {
+                    output
+                };
+                serde_json::json!({
+                    "tool": {
+                        "driver": {
+                            "name": "apc",
+                            "version": env!("CARGO_PKG_VERSION"),
+                            "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                            "rules": [{
+                                "id": check.name,
+                                "shortDescription": { "text": check.title },
+                            }],
+                        },
+                    },
+                    "automationDetails": { "id": format!("apc/{}/", check.name) },
+                    "results": [{
+                        "ruleId": check.name,
+                        "level": "error",
+                        "message": { "text": message },
+                        "locations": [{
+                            "physicalLocation": {
+                                "artifactLocation": { "uri": CONFIG_FILE_NAME },
+                            },
+                        }],
+                    }],
+                })
+            })
+            .collect();
+
+        to_json(
+            &serde_json::json!({
+                "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+                "version": "2.1.0",
+                "runs": runs,
+            }),
+            true,
+        )
+    }
+
+    /// Renders the run as Prometheus text-format metrics, for node_exporter's
+    /// textfile collector.
+    ///
+    /// Skipped checks have no duration or outcome and are left out.
+    #[must_use]
+    pub fn to_prometheus(&self) -> String {
+        let mode = prometheus_label(&self.mode);
+        let ran: Vec<&CheckReport> = self.checks.iter().filter(|c| !c.status.is_skip()).collect();
+
+        let mut metrics = String::new();
+        let _ = writeln!(
+            metrics,
+            "# HELP apc_check_duration_seconds Duration of the check's last run."
+        );
+        let _ = writeln!(metrics, "# TYPE apc_check_duration_seconds gauge");
+        for check in &ran {
+            let _ = writeln!(
+                metrics,
+                "apc_check_duration_seconds{{check=\"{}\",mode=\"{mode}\"}} {}",
+                prometheus_label(&check.name),
+                format_seconds(check.duration_ms)
+            );
+        }
+
+        let _ = writeln!(
+            metrics,
+            "# HELP apc_check_passed Whether the check passed on its last run (1) or failed (0)."
+        );
+        let _ = writeln!(metrics, "# TYPE apc_check_passed gauge");
+        for check in &ran {
+            let _ = writeln!(
+                metrics,
+                "apc_check_passed{{check=\"{}\",mode=\"{mode}\"}} {}",
+                prometheus_label(&check.name),
+                u8::from(check.status == CheckStatus::Passed)
+            );
+        }
+
+        let _ = writeln!(
+            metrics,
+            "# HELP apc_run_duration_seconds Duration of the last apc run."
+        );
+        let _ = writeln!(metrics, "# TYPE apc_run_duration_seconds gauge");
+        let _ = writeln!(
+            metrics,
+            "apc_run_duration_seconds{{mode=\"{mode}\"}} {}",
+            format_seconds(self.duration_ms)
+        );
+        metrics
+    }
+}
+
+/// Report for a `--mode-matrix` run.
+#[derive(Debug, Clone, Serialize)]
+pub struct MatrixReport {
+    /// Whether every mode passed.
+    pub success: bool,
+    /// Total duration in milliseconds.
+    pub duration_ms: u64,
+    /// Per-mode reports, in the order the modes ran.
+    pub runs: Vec<RunReport>,
+}
+
+impl From<&MatrixResult> for MatrixReport {
+    fn from(matrix: &MatrixResult) -> Self {
+        Self {
+            success: matrix.success(),
+            duration_ms: millis(matrix.duration()),
+            runs: matrix.runs.iter().map(RunReport::from).collect(),
+        }
+    }
+}
+
+impl MatrixReport {
+    /// Titles each check with its configured description, where it has one.
+    #[must_use]
+    pub fn with_descriptions<S: BuildHasher>(
+        mut self,
+        checks: &HashMap<String, CheckConfig, S>,
+    ) -> Self {
+        self.runs = self
+            .runs
+            .into_iter()
+            .map(|run| run.with_descriptions(checks))
+            .collect();
+        self
+    }
+
+    /// Serializes the report to JSON, optionally pretty-printed.
+    pub fn to_json(&self, pretty: bool) -> Result<String> {
+        to_json(self, pretty)
+    }
+}
+
+/// Machine-readable result of `apc detect --json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectReport {
+    /// The detected mode.
+    pub mode: Mode,
+    /// Why that mode was chosen.
+    pub reason: DetectionReason,
+    /// The agent a known agent env var identified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<AgentKind>,
+    /// The environment detection looked at.
+    pub environment: EnvironmentSnapshot,
+}
+
+impl DetectReport {
+    /// Builds the report for a detection and the environment it saw.
+    #[must_use]
+    pub fn new(detection: Detection, environment: EnvironmentSnapshot) -> Self {
+        Self {
+            mode: detection.mode,
+            reason: detection.reason,
+            agent: detection.agent,
+            environment,
+        }
+    }
+
+    /// Serializes the report to JSON, optionally pretty-printed.
+    pub fn to_json(&self, pretty: bool) -> Result<String> {
+        to_json(self, pretty)
+    }
+}
+
+/// Machine-readable list of built-in presets, for `apc presets --json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PresetsReport {
+    /// Every preset `apc init --preset` accepts, in the order it lists them.
+    pub presets: Vec<PresetReport>,
+}
+
+/// A built-in preset in a [`PresetsReport`].
+#[derive(Debug, Clone, Serialize)]
+pub struct PresetReport {
+    /// Preset name.
+    pub name: &'static str,
+    /// What kind of project the preset is for.
+    pub description: &'static str,
+    /// Checks the preset runs in agent mode, in order.
+    pub checks: Vec<String>,
+}
+
+impl PresetsReport {
+    /// Builds the report from the built-in presets.
+    #[must_use]
+    pub fn new() -> Self {
+        let presets = crate::presets::available()
+            .iter()
+            .map(|&name| PresetReport {
+                name,
+                description: crate::presets::description(name),
+                checks: Config::for_preset(name).agent.checks,
+            })
+            .collect();
+        Self { presets }
+    }
+
+    /// Serializes the report to JSON, optionally pretty-printed.
+    pub fn to_json(&self, pretty: bool) -> Result<String> {
+        to_json(self, pretty)
+    }
+}
+
+impl Default for PresetsReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keeps the last `limit` bytes of an output stream, prefixed with how many
+/// bytes were cut.
+fn truncate_output(output: &mut String, limit: usize) {
+    if output.len() <= limit {
+        return;
+    }
+
+    let mut start = output.len() - limit;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    *output = format!("[{start} bytes truncated]\n{}", &output[start..]);
+}
+
+/// Serializes a report to JSON, optionally pretty-printed.
+fn to_json<T: Serialize>(report: &T, pretty: bool) -> Result<String> {
+    let json = if pretty {
+        serde_json::to_string_pretty(report)
+    } else {
+        serde_json::to_string(report)
+    };
+
+    json.map_err(|e| Error::Internal {
+        message: format!("Failed to serialize report: {e}"),
+    })
+}
+
+/// Formats milliseconds for display, switching to seconds above one second.
+pub(crate) fn format_millis(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else {
+        format!("{}.{}s", ms / 1000, (ms % 1000) / 100)
+    }
+}
+
+/// Escapes a Prometheus label value.
+fn prometheus_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Formats milliseconds as fractional seconds (JUnit `time`, Prometheus values).
+fn format_seconds(ms: u64) -> String {
+    format!("{}.{:03}", ms / 1000, ms % 1000)
+}
+
+/// Escapes text for an XML attribute or element, dropping characters XML
+/// cannot represent (such as the ESC of ANSI color codes).
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {},
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes the message of a GitHub Actions workflow command.
+fn github_escape(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a property value (such as `title`) of a workflow command.
+fn github_property_escape(text: &str) -> String {
+    github_escape(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Converts a duration to whole milliseconds, saturating on overflow.
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Expands placeholders in a `--report-file` path so archived reports get
+/// unique names: `{date}` (`2024-05-01`), `{time}` (`142530`), `{mode}`,
+/// `{branch}` and `{sha}` (the short commit hash).
+///
+/// Branch names and hashes are reduced to characters safe in a file name
+/// (`feature/x` becomes `feature-x`), and are `unknown` outside a repository
+/// or before the first commit.
+#[must_use]
+#[allow(clippy::literal_string_with_formatting_args)] // `{mode}` etc. are our placeholders
+pub fn expand_report_path(
+    template: &Path,
+    mode: Mode,
+    repo: Option<&GitRepo>,
+    now: DateTime<Local>,
+) -> PathBuf {
+    let template = template.to_string_lossy();
+    if !template.contains('{') {
+        return PathBuf::from(template.into_owned());
+    }
+
+    let git = |value: Option<String>| sanitize(value.as_deref().unwrap_or("unknown"));
+    let mut path = template
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &now.format("%H%M%S").to_string())
+        .replace("{mode}", mode.name());
+    if path.contains("{branch}") {
+        path = path.replace("{branch}", &git(repo.and_then(|r| r.current_branch().ok())));
+    }
+    if path.contains("{sha}") {
+        path = path.replace("{sha}", &git(repo.and_then(|r| r.head_sha().ok())));
+    }
+    PathBuf::from(path)
+}
+
+/// Replaces characters that aren't safe in a file name with `-`.
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::detector::Mode;
+    use crate::core::executor::CommandOutput;
+
+    fn make_check(name: &str, exit_code: i32) -> CheckResult {
+        CheckResult {
+            name: name.to_string(),
+            status: if exit_code == 0 {
+                CheckStatus::Passed
+            } else {
+                CheckStatus::Failed
+            },
+            output: CommandOutput {
+                exit_code,
+                stdout: format!("{name} out"),
+                stderr: String::new(),
+                timed_out: false,
+                cancelled: false,
+                signal: None,
+                duration: Duration::from_millis(12),
+            },
+            skip_reason: None,
+            artifacts: Vec::new(),
+            blocking: true,
+            attempts: 1,
+        }
+    }
+
+    fn make_result(checks: Vec<CheckResult>) -> RunResult {
+        RunResult {
+            mode: Mode::Agent,
+            checks,
+            duration: Duration::from_millis(40),
+        }
+    }
+
+    #[test]
+    fn test_presets_report() {
+        let report = PresetsReport::new();
+        assert_eq!(report.presets.len(), crate::presets::available().len());
+        let rust = report
+            .presets
+            .iter()
+            .find(|p| p.name == "rust")
+            .expect("rust preset");
+        assert!(!rust.description.is_empty());
+        assert!(rust.checks.contains(&"clippy".to_string()));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&report.to_json(false).expect("serialize")).expect("parse");
+        assert_eq!(json["presets"][0]["name"], "python");
+        assert!(json["presets"][0]["checks"].is_array());
+    }
+
+    #[test]
+    fn test_report_marks_silent_pass() {
+        let mut silent = make_check("fmt", 0);
+        silent.output.stdout = "\n".to_string();
+        let mut skipped = make_check("optional", 0);
+        skipped.output.stdout = String::new();
+        skipped.status = CheckStatus::Skipped;
+        let result = make_result(vec![silent, make_check("lint", 0), skipped]);
+        let report = RunReport::from(&result);
+
+        assert_eq!(report.checks[0].status, CheckStatus::Passed);
+        assert!(report.checks[0].no_output);
+        assert!(!report.checks[1].no_output);
+        assert_eq!(report.checks[2].status, CheckStatus::Skipped);
+        assert!(!report.checks[2].no_output);
+    }
+
+    #[test]
+    fn test_report_counts_and_status() {
+        let result = make_result(vec![make_check("lint", 0), make_check("test", 1)]);
+        let report = RunReport::from(&result);
+
+        assert_eq!(report.mode, "agent");
+        assert!(!report.success);
+        assert_eq!(report.passed, 1);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.checks[0].status, CheckStatus::Passed);
+        assert_eq!(report.checks[1].status, CheckStatus::Failed);
+    }
+
+    #[test]
+    fn test_report_sorts_checks_by_name() {
+        let result = make_result(vec![make_check("zeta", 0), make_check("alpha", 0)]);
+        let report = RunReport::from(&result);
+
+        let names: Vec<_> = report.checks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["alpha", "zeta"]);
+    }
+
+    #[test]
+    fn test_pretty_json_is_deterministic() {
+        let first = make_result(vec![
+            make_check("test", 1),
+            make_check("build", 0),
+            make_check("lint", 0),
+        ]);
+        let second = make_result(vec![
+            make_check("lint", 0),
+            make_check("test", 1),
+            make_check("build", 0),
+        ]);
+
+        let a = RunReport::from(&first).to_json(true).expect("serialize");
+        let b = RunReport::from(&second).to_json(true).expect("serialize");
+        let again = RunReport::from(&first).to_json(true).expect("serialize");
+
+        assert_eq!(a, b);
+        assert_eq!(a, again);
+        assert!(a.contains('\n'));
+    }
+
+    #[test]
+    fn test_json_field_order() {
+        let report = RunReport::from(&make_result(vec![make_check("lint", 0)]));
+        let json = report.to_json(false).expect("serialize");
+
+        let mode = json.find("\"mode\"").expect("mode");
+        let success = json.find("\"success\"").expect("success");
+        let checks = json.find("\"checks\"").expect("checks");
+        assert!(mode < success && success < checks);
+        assert!(!json.contains('\n'));
+    }
+
+    #[test]
+    fn test_context_included_only_when_set() {
+        let report = RunReport::from(&make_result(vec![make_check("lint", 0)]));
+        assert!(!report
+            .to_json(false)
+            .expect("serialize")
+            .contains("context"));
+
+        let detection = Detection {
+            mode: Mode::Ci,
+            reason: crate::core::detector::DetectionReason::CiEnvironment("CI".to_string()),
+            agent: None,
+        };
+        let json = report
+            .with_context(RunContext::new(&detection, None))
+            .to_json(false)
+            .expect("serialize");
+        assert!(json.contains(&format!(
+            "\"context\":{{\"mode\":\"ci\",\"detection_reason\":\"CI environment: CI\",\
+             \"repo_root\":null,\"branch\":null,\"apc_version\":\"{}\"}}",
+            env!("CARGO_PKG_VERSION")
+        )));
+    }
+
+    #[test]
+    fn test_artifacts_listed_when_present() {
+        let mut check = make_check("e2e", 1);
+        check.artifacts = vec![std::path::PathBuf::from(
+            "/repo/.git/apc-artifacts/e2e/run.log",
+        )];
+
+        let json = RunReport::from(&make_result(vec![check]))
+            .to_json(false)
+            .expect("serialize");
+        assert!(json.contains("\"artifacts\":[\"/repo/.git/apc-artifacts/e2e/run.log\"]"));
+
+        let json = RunReport::from(&make_result(vec![make_check("lint", 0)]))
+            .to_json(false)
+            .expect("serialize");
+        assert!(!json.contains("artifacts"));
+    }
+
+    #[test]
+    fn test_table_includes_every_check() {
+        let mut skipped = make_check("optional", 0);
+        skipped.status = CheckStatus::Skipped;
+        let result = make_result(vec![make_check("lint", 0), make_check("test", 1), skipped]);
+
+        let table = RunReport::from(&result).to_table();
+        let lines: Vec<_> = table.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "Check     Status   Duration");
+        assert_eq!(lines[1], "lint      passed       12ms");
+        assert_eq!(lines[2], "optional  skipped      12ms");
+        assert_eq!(lines[3], "test      failed       12ms");
+    }
+
+    #[test]
+    fn test_with_output_limit_keeps_tail() {
+        let mut check = make_check("noisy", 0);
+        check.output.stdout = format!("{}end", "x".repeat(REPORT_OUTPUT_LIMIT));
+        check.output.stderr = "short".to_string();
+        let result = RunResult {
+            mode: Mode::Human,
+            checks: vec![check],
+            duration: Duration::ZERO,
+        };
+
+        let report = RunReport::from(&result).with_output_limit(REPORT_OUTPUT_LIMIT);
+        let stdout = &report.checks[0].stdout;
+        assert!(stdout.starts_with("[3 bytes truncated]\n"));
+        assert!(stdout.ends_with("xend"));
+        assert_eq!(report.checks[0].stderr, "short");
+    }
+
+    #[test]
+    fn test_truncate_output_at_char_boundary() {
+        let mut output = "éé".to_string();
+        truncate_output(&mut output, 3);
+        assert_eq!(output, "[2 bytes truncated]\né");
+    }
+
+    #[test]
+    fn test_report_stopped_checks() {
+        let mut cancelled = make_check("lint", 0);
+        cancelled.status = CheckStatus::Cancelled;
+        cancelled.skip_reason = Some("Cancelled after a failure in its group".to_string());
+        let mut budget = make_check("test", 1);
+        budget.status = CheckStatus::TimedOutGroup;
+        let result = make_result(vec![cancelled, budget]);
+        let report = RunReport::from(&result);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&report.to_json(false).expect("serialize")).expect("parse");
+        assert_eq!(json["checks"][0]["status"], "cancelled");
+        assert_eq!(json["checks"][1]["status"], "timed_out_group");
+        assert_eq!((report.failed, report.skipped), (1, 1));
+
+        let junit = report.to_junit();
+        assert!(junit.contains("<skipped message=\"Cancelled after a failure in its group\"/>"));
+        assert!(junit.contains("<failure message=\"Run timed out\">"));
+    }
+
+    #[test]
+    fn test_without_skipped_keeps_totals() {
+        let mut skipped = make_check("optional", 0);
+        skipped.status = CheckStatus::Skipped;
+        let result = make_result(vec![make_check("lint", 0), skipped]);
+
+        let report = RunReport::from(&result).without_skipped();
+        assert_eq!(report.checks.len(), 1);
+        assert_eq!(report.checks[0].name, "lint");
+        assert_eq!(report.skipped, 1);
+        assert!(!report.to_table().contains("optional"));
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let mut skipped = make_check("optional", 0);
+        skipped.status = CheckStatus::Skipped;
+        let result = make_result(vec![make_check("lint", 0), make_check("a|b", 0), skipped]);
+
+        assert_eq!(
+            RunReport::from(&result).to_markdown(),
+            "### apc agent: all checks passed\n\n\
+             | Check | Status | Duration |\n\
+             | --- | --- | --- |\n\
+             | a\\|b | passed | 12ms |\n\
+             | lint | passed | 12ms |\n\
+             | optional | skipped | 12ms |\n\
+             \n2 passed, 0 failed, 1 skipped in 40ms\n"
+        );
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let mut skipped = make_check("optional", 0);
+        skipped.status = CheckStatus::Skipped;
+        skipped.skip_reason = Some("Condition not met".to_string());
+        let mut failed = make_check("test", 2);
+        failed.output.stderr = "assertion <left> & \"right\"".to_string();
+        let result = make_result(vec![failed, make_check("lint", 0), skipped]);
+
+        let xml = RunReport::from(&result).to_junit();
+        assert_eq!(
+            xml,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <testsuite name=\"apc.agent\" tests=\"3\" failures=\"1\" errors=\"0\" skipped=\"1\" time=\"0.040\">\n  \
+             <testcase name=\"lint\" classname=\"lint\" time=\"0.012\"/>\n  \
+             <testcase name=\"optional\" classname=\"optional\" time=\"0.012\">\n    \
+             <skipped message=\"Condition not met\"/>\n  </testcase>\n  \
+             <testcase name=\"test\" classname=\"test\" time=\"0.012\">\n    \
+             <failure message=\"Exit code 2\">test out\nassertion &lt;left&gt; &amp; &quot;right&quot;</failure>\n  \
+             </testcase>\n\
+             </testsuite>\n"
+        );
+    }
+
+    #[test]
+    fn test_junit_report_timeout_and_titles() {
+        let mut check = make_check("e2e", -1);
+        check.output.timed_out = true;
+        check.output.stdout = "\u{1b}[31mred\u{1b}[0m".to_string();
+        let mut checks = HashMap::new();
+        checks.insert(
+            "e2e".to_string(),
+            CheckConfig {
+                description: "End-to-end tests".to_string(),
+                ..Default::default()
+            },
+        );
+
+        let xml = RunReport::from(&make_result(vec![check]))
+            .with_descriptions(&checks)
+            .to_junit();
+        assert!(xml.contains("<testcase name=\"End-to-end tests\" classname=\"e2e\""));
+        assert!(xml.contains("<failure message=\"Timed out\">[31mred[0m</failure>"));
+    }
+
+    #[test]
+    fn test_github_report() {
+        let mut skipped = make_check("optional", 0);
+        skipped.status = CheckStatus::Skipped;
+        let mut failed = make_check("test, unit", 1);
+        failed.output.stdout = "\n100% broken: assertion failed\nmore".to_string();
+        let mut silent = make_check("lint", 3);
+        silent.output.stdout = String::new();
+        let result = make_result(vec![failed, silent, skipped]);
+
+        assert_eq!(
+            RunReport::from(&result).to_github(),
+            "::group::lint (failed)\n\
+             ::endgroup::\n\
+             ::error title=lint::Exit code 3\n\
+             ::group::test, unit (failed)\n\
+             \n100% broken: assertion failed\nmore\n\
+             ::endgroup::\n\
+             ::error title=test%2C unit::100%25 broken: assertion failed\n"
+        );
+    }
+
+    #[test]
+    fn test_sarif_report() {
+        let mut failed = make_check("audit", 1);
+        failed.output.stderr = "vulnerable dependency".to_string();
+        let result = make_result(vec![failed, make_check("lint", 0), make_check("fmt", 2)]);
+
+        let sarif = RunReport::from(&result).to_sarif().expect("sarif");
+        let log: serde_json::Value = serde_json::from_str(&sarif).expect("valid JSON");
+        assert_eq!(log["version"], "2.1.0");
+        let runs = log["runs"].as_array().expect("runs");
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0]["tool"]["driver"]["name"], "apc");
+        assert_eq!(runs[0]["automationDetails"]["id"], "apc/audit/");
+        let result = &runs[0]["results"][0];
+        assert_eq!(result["ruleId"], "audit");
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            result["message"]["text"],
+            "audit out\nvulnerable dependency"
+        );
+        assert_eq!(runs[1]["results"][0]["ruleId"], "fmt");
+
+        let passing = make_result(vec![make_check("lint", 0)]);
+        let sarif = RunReport::from(&passing).to_sarif().expect("sarif");
+        let log: serde_json::Value = serde_json::from_str(&sarif).expect("valid JSON");
+        assert_eq!(log["runs"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_prometheus_metrics() {
+        let mut skipped = make_check("optional", 0);
+        skipped.status = CheckStatus::Skipped;
+        let mut slow = make_check("test", 1);
+        slow.output.duration = Duration::from_millis(2500);
+        let result = make_result(vec![slow, make_check("lint", 0), skipped]);
+
+        let metrics = RunReport::from(&result).to_prometheus();
+        let samples: Vec<_> = metrics.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(
+            samples,
+            [
+                "apc_check_duration_seconds{check=\"lint\",mode=\"agent\"} 0.012",
+                "apc_check_duration_seconds{check=\"test\",mode=\"agent\"} 2.500",
+                "apc_check_passed{check=\"lint\",mode=\"agent\"} 1",
+                "apc_check_passed{check=\"test\",mode=\"agent\"} 0",
+                "apc_run_duration_seconds{mode=\"agent\"} 0.040",
+            ]
+        );
+        assert!(metrics.contains("# TYPE apc_check_duration_seconds gauge\n"));
+        assert!(metrics.contains("# TYPE apc_check_passed gauge\n"));
+        assert!(metrics.contains("# TYPE apc_run_duration_seconds gauge\n"));
+    }
+
+    #[test]
+    fn test_prometheus_label_escaping() {
+        assert_eq!(prometheus_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[test]
+    fn test_format_millis() {
+        assert_eq!(format_millis(12), "12ms");
+        assert_eq!(format_millis(1000), "1.0s");
+        assert_eq!(format_millis(83_450), "83.4s");
+    }
+
+    #[test]
+    fn test_matrix_report_keeps_mode_order() {
+        let human = RunResult {
+            mode: Mode::Human,
+            checks: vec![make_check("lint", 0)],
+            duration: Duration::from_millis(10),
+        };
+        let agent = make_result(vec![make_check("test", 1)]);
+        let matrix = MatrixResult {
+            runs: vec![human, agent],
+        };
+
+        let report = MatrixReport::from(&matrix);
+        assert!(!report.success);
+        assert_eq!(report.duration_ms, 50);
+        assert_eq!(report.runs[0].mode, "human");
+        assert_eq!(report.runs[1].mode, "agent");
+
+        let json = report.to_json(false).expect("serialize");
+        assert!(json.starts_with("{\"success\":false"));
+    }
+
+    #[test]
+    fn test_title_defaults_to_name() {
+        let report = RunReport::from(&make_result(vec![make_check("lint", 0)]));
+        assert_eq!(report.checks[0].title, "lint");
+    }
+
+    #[test]
+    fn test_with_descriptions_titles_checks() {
+        let checks = HashMap::from([
+            (
+                "lint".to_string(),
+                CheckConfig {
+                    run: "make lint".to_string(),
+                    description: "Lint sources".to_string(),
+                    ..Default::default()
+                },
+            ),
+            (
+                "test".to_string(),
+                CheckConfig {
+                    run: "make test".to_string(),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let result = make_result(vec![make_check("lint", 0), make_check("test", 0)]);
+
+        let report = RunReport::from(&result).with_descriptions(&checks);
+        assert_eq!(report.checks[0].name, "lint");
+        assert_eq!(report.checks[0].title, "Lint sources");
+        assert_eq!(report.checks[1].title, "test");
+        assert!(report.to_table().contains("Lint sources  passed"));
+    }
+
+    #[test]
+    fn test_skipped_check_has_reason() {
+        let mut check = make_check("optional", 0);
+        check.status = CheckStatus::Skipped;
+        check.skip_reason = Some("Condition not met".to_string());
+
+        let report = RunReport::from(&make_result(vec![check]));
+        let json = report.to_json(false).expect("serialize");
+
+        assert_eq!(report.checks[0].status, CheckStatus::Skipped);
+        assert!(json.contains("\"skip_reason\":\"Condition not met\""));
+    }
+
+    #[test]
+    #[allow(clippy::literal_string_with_formatting_args)] // report path placeholders
+    fn test_expand_report_path_without_repo() {
+        let now = chrono::TimeZone::with_ymd_and_hms(&Local, 2024, 5, 1, 14, 25, 30)
+            .single()
+            .expect("valid time");
+        let path = expand_report_path(
+            Path::new("reports/{date}-{time}-{mode}-{branch}-{sha}.xml"),
+            Mode::Ci,
+            None,
+            now,
+        );
+        assert_eq!(
+            path,
+            Path::new("reports/2024-05-01-142530-ci-unknown-unknown.xml")
+        );
+        assert_eq!(
+            expand_report_path(Path::new("apc.json"), Mode::Agent, None, now),
+            Path::new("apc.json")
+        );
+    }
+
+    #[test]
+    #[allow(clippy::literal_string_with_formatting_args)] // report path placeholders
+    fn test_expand_report_path_sanitizes_git_values() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .args(["-c", "user.name=apc", "-c", "user.email=apc@example.com"])
+                .args(args)
+                .current_dir(temp.path())
+                .output()
+                .expect("run git");
+            assert!(output.status.success(), "git {args:?} failed");
+        };
+        git(&["init", "-b", "feature/x+y"]);
+        git(&["commit", "--allow-empty", "-m", "init"]);
+        let repo = GitRepo::discover_from(temp.path()).expect("discover repo");
+        let sha = repo.head_sha().expect("head sha");
+
+        let path = expand_report_path(
+            Path::new("{branch}/{sha}.json"),
+            Mode::Human,
+            Some(&repo),
+            Local::now(),
+        );
+        assert_eq!(path, PathBuf::from(format!("feature-x-y/{sha}.json")));
+    }
+}
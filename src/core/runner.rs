@@ -5,50 +5,192 @@
 // Allow this for Rust 2024 compatibility - the drop order change is harmless here
 #![allow(tail_expr_drop_order)]
 
-use crate::config::{CheckConfig, Config};
-use crate::core::detector::Mode;
+use crate::config::{
+    AutoParallel, CheckConfig, ConditionMatch, Config, FailFast, GlobalsConfig, ParallelGroup,
+};
+use crate::core::cache::{self, Cache};
+use crate::core::detector::{AgentKind, Mode};
 use crate::core::error::{Error, Result};
-use crate::core::executor::{CommandOutput, ExecuteOptions, Executor};
+use crate::core::executor::{
+    CommandOutput, ExecuteOptions, Executor, StdinMode, NO_OUTPUT_PLACEHOLDER,
+};
+use crate::core::files;
 use crate::core::git::GitRepo;
+use crate::core::graph;
+use crate::core::interpolate;
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    /// The check ran and passed.
+    Passed,
+    /// The check ran and failed (including timeouts).
+    Failed,
+    /// The check did not run: its conditions were not met, a dependency
+    /// failed, or it was listed in `APC_SKIP_CHECKS`.
+    Skipped,
+    /// The check did not run because it passed before with the same inputs.
+    Cached,
+    /// The check was killed or never started because another check in its
+    /// group failed with `fail_fast` on.
+    Cancelled,
+    /// The check was killed or never started because the run used up
+    /// `[agent].total_timeout`. Counts as a failure.
+    TimedOutGroup,
+    /// The check was killed or never started because the run was aborted.
+    Aborted,
+}
+
+impl CheckStatus {
+    /// Returns true if the check counts as failed.
+    #[must_use]
+    pub const fn is_failure(self) -> bool {
+        matches!(self, Self::Failed | Self::TimedOutGroup)
+    }
+
+    /// Returns true if the check neither passed nor failed on its own.
+    #[must_use]
+    pub const fn is_skip(self) -> bool {
+        matches!(
+            self,
+            Self::Skipped | Self::Cached | Self::Cancelled | Self::Aborted
+        )
+    }
+
+    /// Returns the name of the status, as used in reports.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+            Self::Cached => "cached",
+            Self::Cancelled => "cancelled",
+            Self::TimedOutGroup => "timed_out_group",
+            Self::Aborted => "aborted",
+        }
+    }
+}
 
 /// Result of running a single check.
 #[derive(Debug, Clone)]
 pub struct CheckResult {
     /// Name of the check.
     pub name: String,
-    /// Whether the check passed.
-    pub passed: bool,
+    /// Outcome of the check.
+    pub status: CheckStatus,
     /// Output from the check.
     pub output: CommandOutput,
-    /// Whether the check was skipped.
-    pub skipped: bool,
-    /// Reason for skipping (if skipped).
+    /// Reason the check did not run or was stopped, if it was.
     pub skip_reason: Option<String>,
+    /// Artifact files collected after the check ran.
+    pub artifacts: Vec<PathBuf>,
+    /// Whether a failure of this check fails the run (false for `allow_failure`).
+    pub blocking: bool,
+    /// Number of times the command ran (0 if skipped, more than 1 if retried).
+    pub attempts: u32,
 }
 
 impl CheckResult {
-    /// Creates a skipped check result.
-    fn skipped(name: String, reason: String) -> Self {
+    /// Creates the result of a check that did not run, with `status`.
+    fn not_run(name: String, status: CheckStatus, reason: String) -> Self {
         Self {
             name,
-            passed: true, // Skipped checks don't fail
+            status,
             output: CommandOutput {
                 exit_code: 0,
                 stdout: String::new(),
                 stderr: String::new(),
                 timed_out: false,
+                cancelled: false,
+                signal: None,
                 duration: Duration::ZERO,
             },
-            skipped: true,
             skip_reason: Some(reason),
+            artifacts: Vec::new(),
+            blocking: true,
+            attempts: 0,
         }
     }
+
+    /// Creates a skipped check result.
+    fn skipped(name: String, reason: String) -> Self {
+        Self::not_run(name, CheckStatus::Skipped, reason)
+    }
+
+    /// Creates the result of a check that never started because the run was
+    /// cancelled.
+    fn aborted(name: String) -> Self {
+        Self::not_run(name, CheckStatus::Aborted, "Aborted".to_string())
+    }
+
+    /// Creates the result of a check cancelled by a failure in its group.
+    fn cancelled(name: String) -> Self {
+        Self::not_run(
+            name,
+            CheckStatus::Cancelled,
+            "Cancelled after a failure in its group".to_string(),
+        )
+    }
+
+    /// Creates the result of a check skipped by the result cache.
+    fn cached(name: String) -> Self {
+        Self::not_run(name, CheckStatus::Cached, cache::CACHED_REASON.to_string())
+    }
+
+    /// Creates the result of a check that never started because the run used
+    /// up its `total_timeout`.
+    fn not_started_in_time(name: String, budget: Duration) -> Self {
+        Self::skipped(name, String::new()).timed_out_in_group(budget)
+    }
+
+    /// Marks the check as stopped by the run's `total_timeout`: failed and
+    /// blocking, whatever it was doing.
+    fn timed_out_in_group(mut self, budget: Duration) -> Self {
+        let note = format!(
+            "Stopped: the run exceeded its total_timeout of {}",
+            humantime::format_duration(budget)
+        );
+        self.output.stderr = if self.output.stderr.is_empty() {
+            note
+        } else {
+            format!("{}\n{note}", self.output.stderr)
+        };
+        self.output.timed_out = true;
+        self.status = CheckStatus::TimedOutGroup;
+        self.skip_reason = None;
+        self.blocking = true;
+        self
+    }
+
+    /// Returns true if the check ran and passed without printing anything.
+    #[must_use]
+    pub fn is_silent_pass(&self) -> bool {
+        self.status == CheckStatus::Passed && !self.output.has_output()
+    }
+
+    /// Returns true if the check failed and its failure fails the run.
+    #[must_use]
+    pub const fn is_blocking_failure(&self) -> bool {
+        self.status.is_failure() && self.blocking
+    }
+
+    /// Creates a result for a check skipped because a dependency failed.
+    fn dependency_failed(name: &str, dependency: &str) -> Self {
+        Self::skipped(
+            name.to_string(),
+            format!("Dependency '{dependency}' failed"),
+        )
+    }
 }
 
 /// Result of running all checks.
@@ -63,10 +205,13 @@ pub struct RunResult {
 }
 
 impl RunResult {
-    /// Returns true if all checks passed.
+    /// Returns true if no blocking check failed and the run was not aborted.
     #[must_use]
     pub fn success(&self) -> bool {
-        self.checks.iter().all(|c| c.passed)
+        !self
+            .checks
+            .iter()
+            .any(|c| c.is_blocking_failure() || c.status == CheckStatus::Aborted)
     }
 
     /// Returns the number of passed checks.
@@ -74,34 +219,107 @@ impl RunResult {
     pub fn passed_count(&self) -> usize {
         self.checks
             .iter()
-            .filter(|c| c.passed && !c.skipped)
+            .filter(|c| c.status == CheckStatus::Passed)
             .count()
     }
 
     /// Returns the number of failed checks.
     #[must_use]
     pub fn failed_count(&self) -> usize {
-        self.checks.iter().filter(|c| !c.passed).count()
+        self.checks.iter().filter(|c| c.status.is_failure()).count()
     }
 
-    /// Returns the number of skipped checks.
+    /// Returns the number of checks that did not run to completion on their own.
     #[must_use]
     pub fn skipped_count(&self) -> usize {
-        self.checks.iter().filter(|c| c.skipped).count()
+        self.checks.iter().filter(|c| c.status.is_skip()).count()
     }
 
     /// Returns failed check results.
     #[must_use]
     pub fn failed_checks(&self) -> impl Iterator<Item = &CheckResult> {
-        self.checks.iter().filter(|c| !c.passed)
+        self.checks.iter().filter(|c| c.status.is_failure())
+    }
+
+    /// Returns up to `n` checks that ran, slowest first.
+    #[must_use]
+    pub fn slowest(&self, n: usize) -> Vec<&CheckResult> {
+        let mut ran: Vec<&CheckResult> =
+            self.checks.iter().filter(|c| !c.status.is_skip()).collect();
+        ran.sort_by_key(|c| std::cmp::Reverse(c.output.duration));
+        ran.truncate(n);
+        ran
+    }
+
+    /// Returns failed checks whose failure does not fail the run.
+    #[must_use]
+    pub fn non_blocking_failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks
+            .iter()
+            .filter(|c| c.status.is_failure() && !c.blocking)
+    }
+}
+
+/// A check with its variables, files and container resolved for one mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedCheck {
+    /// Shell command that runs, after `${VAR}` and `{files}` expansion.
+    pub command: String,
+    /// Environment variables set for the command, sorted by name.
+    pub env: Vec<(String, String)>,
+    /// Effective timeout.
+    pub timeout: Duration,
+}
+
+/// A check as a run would handle it, from [`Runner::plan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedCheck {
+    /// Name of the check.
+    pub name: String,
+    /// Shell command the check would run.
+    pub command: String,
+    /// Why the check would be skipped, if it would be.
+    pub skip_reason: Option<String>,
+}
+
+/// Results of running several modes in sequence (`--mode-matrix`).
+#[derive(Debug, Default)]
+pub struct MatrixResult {
+    /// One result per mode, in the order the modes ran.
+    pub runs: Vec<RunResult>,
+}
+
+impl MatrixResult {
+    /// Returns true if every mode succeeded.
+    #[must_use]
+    pub fn success(&self) -> bool {
+        self.runs.iter().all(RunResult::success)
+    }
+
+    /// Returns the combined duration of all modes.
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.runs.iter().map(|r| r.duration).sum()
     }
 }
 
 /// Runner for executing checks.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Runner {
     config: Config,
     repo: Option<GitRepo>,
+    files: Option<Vec<PathBuf>>,
+    cancel: Option<CancellationToken>,
+    only: Option<Vec<String>>,
+    ignore_conditions: bool,
+    only_changed: bool,
+    timeout: Option<Duration>,
+    stream: bool,
+    max_parallel: Option<usize>,
+    group_parallel: Option<usize>,
+    message_file: Option<PathBuf>,
+    shuffle_seed: Option<u64>,
+    agent: Option<AgentKind>,
 }
 
 impl Runner {
@@ -111,6 +329,18 @@ impl Runner {
         Self {
             config,
             repo: GitRepo::discover().ok(),
+            files: None,
+            cancel: None,
+            only: None,
+            ignore_conditions: false,
+            only_changed: false,
+            timeout: None,
+            stream: false,
+            max_parallel: None,
+            group_parallel: None,
+            message_file: None,
+            shuffle_seed: None,
+            agent: None,
         }
     }
 
@@ -120,15 +350,260 @@ impl Runner {
         Self {
             config,
             repo: Some(repo),
+            files: None,
+            cancel: None,
+            only: None,
+            ignore_conditions: false,
+            only_changed: false,
+            timeout: None,
+            stream: false,
+            max_parallel: None,
+            group_parallel: None,
+            message_file: None,
+            shuffle_seed: None,
+            agent: None,
         }
     }
 
+    /// Returns the configuration checks run with.
+    #[must_use]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Sets the files substituted for `{files}` in check commands.
+    ///
+    /// When unset, checks receive the staged files.
+    #[must_use]
+    pub fn with_files(mut self, files: Vec<PathBuf>) -> Self {
+        self.files = Some(files);
+        self
+    }
+
+    /// Sets a token that aborts the run when cancelled.
+    ///
+    /// Running checks are killed and checks not yet started are skipped.
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Restricts the run to the named checks from the mode's check list.
+    ///
+    /// Unlike [`Runner::run_single`], the checks still run with the mode's
+    /// timeout and parallel grouping.
+    #[must_use]
+    pub fn with_only(mut self, names: Vec<String>) -> Self {
+        self.only = Some(names);
+        self
+    }
+
+    /// Sets the detected agent, whose `[agent.per_agent]` checks run after
+    /// the agent mode checks.
+    #[must_use]
+    pub fn with_agent(mut self, agent: AgentKind) -> Self {
+        self.agent = Some(agent);
+        self
+    }
+
+    /// Runs checks even when their `enabled_if` condition is not met.
+    #[must_use]
+    pub fn ignore_conditions(mut self, ignore: bool) -> Self {
+        self.ignore_conditions = ignore;
+        self
+    }
+
+    /// Skips checks whose `paths` match none of the changed files in any mode,
+    /// as `[human].only_changed` does for human runs.
+    #[must_use]
+    pub const fn only_changed(mut self, only_changed: bool) -> Self {
+        self.only_changed = only_changed;
+        self
+    }
+
+    /// Echoes check output live, prefixed with the check name, while still
+    /// capturing it for reports.
+    #[must_use]
+    pub fn stream_output(mut self, stream: bool) -> Self {
+        self.stream = stream;
+        self
+    }
+
+    /// Sets how many checks may run at once, overriding the mode's
+    /// `max_parallel` and its default.
+    #[must_use]
+    pub fn with_max_parallel(mut self, limit: usize) -> Self {
+        self.max_parallel = Some(limit);
+        self
+    }
+
+    /// Caps how many checks of a parallel group run at once, for groups
+    /// without their own `max_parallel`.
+    #[must_use]
+    pub fn with_group_parallel(mut self, limit: usize) -> Self {
+        self.group_parallel = Some(limit);
+        self
+    }
+
+    /// Shuffles the checks within each parallel stage, seeded with `seed`.
+    ///
+    /// Dependencies are still honored; only checks that could run in either
+    /// order are reordered.
+    #[must_use]
+    pub fn with_shuffle(mut self, seed: u64) -> Self {
+        self.shuffle_seed = Some(seed);
+        self
+    }
+
+    /// Passes a commit message file to every check as `APC_COMMIT_MSG_FILE`,
+    /// and its contents on stdin.
+    #[must_use]
+    pub fn with_message_file(mut self, path: PathBuf) -> Self {
+        self.message_file = Some(path);
+        self
+    }
+
+    /// Overrides the mode and per-check timeouts for every check.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Returns true if the run was aborted via the cancellation token.
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
     /// Runs checks for the given mode.
     pub async fn run(&self, mode: Mode) -> Result<RunResult> {
-        let start = std::time::Instant::now();
+        let check_names = self.select_only(self.get_checks_for_mode(mode), mode)?;
+        self.run_named(mode, &check_names).await
+    }
+
+    /// Runs every configured check tagged with any of `tags`, whatever its mode.
+    ///
+    /// Checks run in name order with the given mode's timeout and execution
+    /// style. Returns an error if no check carries any of the tags.
+    pub async fn run_by_tags(&self, tags: &[String], mode: Mode) -> Result<RunResult> {
+        let check_names = self.tagged_checks(tags)?;
+        self.run_named(mode, &check_names).await
+    }
+
+    /// Runs the checks listed in `[commit_msg].checks`, for the commit-msg hook.
+    pub async fn run_commit_msg(&self, mode: Mode) -> Result<RunResult> {
+        self.run_named(mode, &self.config.commit_msg.checks).await
+    }
+
+    /// Returns the names of the checks tagged with any of `tags`, sorted.
+    fn tagged_checks(&self, tags: &[String]) -> Result<Vec<String>> {
+        let mut check_names: Vec<String> = self
+            .config
+            .checks
+            .iter()
+            .filter(|(_, check)| check.tags.iter().any(|t| tags.contains(t)))
+            .map(|(name, _)| name.clone())
+            .collect();
+        check_names.sort();
+
+        if check_names.is_empty() {
+            return Err(Error::ConfigInvalid {
+                field: "tag".to_string(),
+                message: format!(
+                    "No checks are tagged {}",
+                    tags.iter()
+                        .map(|t| format!("'{t}'"))
+                        .collect::<Vec<_>>()
+                        .join(" or ")
+                ),
+            });
+        }
+
+        Ok(check_names)
+    }
+
+    /// Resolves what [`Runner::run`] would do in `mode`, without running
+    /// anything: each check in execution order, with its command and why it
+    /// would be skipped.
+    pub fn plan(&self, mode: Mode) -> Result<Vec<PlannedCheck>> {
+        let check_names = self.select_only(self.get_checks_for_mode(mode), mode)?;
+        self.plan_named(mode, &check_names)
+    }
+
+    /// Resolves what [`Runner::run_by_tags`] would do, without running anything.
+    pub fn plan_by_tags(&self, tags: &[String], mode: Mode) -> Result<Vec<PlannedCheck>> {
+        let check_names = self.tagged_checks(tags)?;
+        self.plan_named(mode, &check_names)
+    }
+
+    /// Resolves what [`Runner::run_single`] would do, without running anything.
+    pub fn plan_single(&self, name: &str, mode: Mode) -> Result<PlannedCheck> {
+        let (name, check) = self.named_check(name)?;
+
+        self.context(mode).plan(name, check)
+    }
+
+    /// Returns the waves [`Runner::run`] would execute in order, each holding
+    /// the checks that run together, without running anything.
+    pub fn waves(&self, mode: Mode) -> Result<Vec<Vec<String>>> {
+        let check_names = self.select_only(self.get_checks_for_mode(mode), mode)?;
+        self.waves_named(mode, &check_names)
+    }
+
+    /// Returns the waves [`Runner::run_by_tags`] would execute in order.
+    pub fn waves_by_tags(&self, tags: &[String], mode: Mode) -> Result<Vec<Vec<String>>> {
+        let check_names = self.tagged_checks(tags)?;
+        self.waves_named(mode, &check_names)
+    }
+
+    /// Groups the named checks into waves the way [`Runner::run_named`] runs them.
+    fn waves_named(&self, mode: Mode, check_names: &[String]) -> Result<Vec<Vec<String>>> {
+        let checks = self.resolve_checks(check_names)?;
+        let check_map: HashMap<_, _> = checks.iter().cloned().collect();
+
+        if self.max_parallel(mode) == 1 && !mode.is_thorough() {
+            let names: Vec<String> = checks.iter().map(|(n, _)| n.clone()).collect();
+            return Ok(self
+                .stages(&names, &check_map)?
+                .concat()
+                .into_iter()
+                .map(|name| vec![name])
+                .collect());
+        }
+
+        Ok(self
+            .parallel_stages(mode, &checks, &check_map)?
+            .iter()
+            .map(|group| {
+                group
+                    .checks()
+                    .iter()
+                    .filter(|name| check_map.contains_key(*name))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .filter(|wave| !wave.is_empty())
+            .collect())
+    }
+
+    /// Plans the named checks in dependency order.
+    fn plan_named(&self, mode: Mode, check_names: &[String]) -> Result<Vec<PlannedCheck>> {
+        let check_map: HashMap<_, _> = self.resolve_checks(check_names)?.into_iter().collect();
+        let ctx = self.context(mode);
+
+        self.stages(check_names, &check_map)?
+            .concat()
+            .iter()
+            .filter_map(|name| check_map.get(name).map(|check| ctx.plan(name, check)))
+            .collect()
+    }
 
-        // Get checks for this mode
-        let check_names = self.get_checks_for_mode(mode);
+    /// Runs the named checks in the given mode.
+    async fn run_named(&self, mode: Mode, check_names: &[String]) -> Result<RunResult> {
+        let start = std::time::Instant::now();
 
         if check_names.is_empty() {
             return Ok(RunResult {
@@ -139,15 +614,25 @@ impl Runner {
         }
 
         // Resolve check configurations
-        let checks = self.resolve_checks(&check_names)?;
+        let checks = self.resolve_checks(check_names)?;
 
-        // Run checks based on mode settings
-        let results = if mode.is_thorough() {
-            self.run_parallel_groups(mode, &checks).await?
-        } else {
-            self.run_sequential(mode, &checks).await?
+        // Hide unstaged changes so checks see only the index
+        let stash = match &self.repo {
+            Some(repo) if mode == Mode::Human && self.config.human.stash_unstaged => {
+                repo.stash_unstaged()?
+            },
+            _ => None,
         };
 
+        let results = match self.total_timeout(mode) {
+            Some(budget) => self.run_with_budget(mode, &checks, budget).await?,
+            None => self.run_resolved(mode, &checks).await?,
+        };
+
+        if let Some(stash) = stash {
+            stash.restore()?;
+        }
+
         Ok(RunResult {
             mode,
             checks: results,
@@ -155,25 +640,163 @@ impl Runner {
         })
     }
 
+    /// Runs resolved checks one at a time or in parallel groups, per the mode.
+    async fn run_resolved(
+        &self,
+        mode: Mode,
+        checks: &[(String, CheckConfig)],
+    ) -> Result<Vec<CheckResult>> {
+        // Humans run one check at a time unless their parallelism is raised
+        let max_parallel = self.max_parallel(mode);
+        if max_parallel == 1 && !mode.is_thorough() {
+            self.run_sequential(mode, checks).await
+        } else {
+            self.run_parallel_groups(mode, checks, max_parallel).await
+        }
+    }
+
+    /// Returns the wall-clock budget for a whole run in `mode`
+    /// (`[agent].total_timeout`), if one is set. Human mode has none.
+    fn total_timeout(&self, mode: Mode) -> Option<Duration> {
+        if !mode.is_thorough() {
+            return None;
+        }
+        self.config
+            .agent
+            .total_timeout
+            .as_deref()
+            .and_then(|timeout| humantime::parse_duration(timeout).ok())
+    }
+
+    /// Runs checks within `budget`. When it runs out, running checks are
+    /// killed, and they and the checks that never started are marked as
+    /// timed out with the run.
+    async fn run_with_budget(
+        &self,
+        mode: Mode,
+        checks: &[(String, CheckConfig)],
+        budget: Duration,
+    ) -> Result<Vec<CheckResult>> {
+        let token = self
+            .cancel
+            .as_ref()
+            .map_or_else(CancellationToken::new, CancellationToken::child_token);
+        let runner = Self {
+            cancel: Some(token.clone()),
+            ..self.clone()
+        };
+
+        let execution = runner.run_resolved(mode, checks);
+        tokio::pin!(execution);
+        if let Ok(results) = tokio::time::timeout(budget, &mut execution).await {
+            return results;
+        }
+
+        // Let the run wind down so finished checks keep their results
+        token.cancel();
+        let mut results: Vec<CheckResult> = execution
+            .await?
+            .into_iter()
+            .map(|result| {
+                if result.status == CheckStatus::Aborted {
+                    result.timed_out_in_group(budget)
+                } else {
+                    result
+                }
+            })
+            .collect();
+        for (name, _) in checks {
+            if !results.iter().any(|r| &r.name == name) {
+                results.push(CheckResult::not_started_in_time(name.clone(), budget));
+            }
+        }
+        Ok(results)
+    }
+
     /// Runs a single check by name.
     pub async fn run_single(&self, name: &str, mode: Mode) -> Result<CheckResult> {
-        let check = self
-            .config
-            .checks
-            .get(name)
-            .ok_or_else(|| Error::CheckNotFound {
-                name: name.to_string(),
-            })?;
+        let (name, check) = self.named_check(name)?;
 
         self.run_check(name, check, mode).await
     }
 
-    /// Gets the list of checks for a mode.
+    /// Evaluates a check's `enabled_if` conditions as a run in `mode` would,
+    /// without running it.
+    pub fn explain_conditions(&self, name: &str, mode: Mode) -> Result<Conditions> {
+        let (_, check) = self.named_check(name)?;
+
+        let ctx = self.context(mode);
+        Ok(evaluate_conditions(
+            check,
+            ctx.repo.as_ref(),
+            ctx.files_changed_match(check)?,
+        ))
+    }
+
+    /// Resolves a check as a run in `mode` would execute it, without running it.
+    pub fn resolve_check(&self, name: &str, mode: Mode) -> Result<ResolvedCheck> {
+        let (name, check) = self.named_check(name)?;
+
+        let ctx = self.context(mode);
+        let (run, mut env) = interpolate_check(name, check, &self.config.globals)?;
+        let env_keys: Vec<String> = env.iter().map(|(key, _)| key.clone()).collect();
+        env.sort();
+
+        Ok(ResolvedCheck {
+            command: ctx.command_for(check, &run, &env_keys)?,
+            env,
+            timeout: ctx.timeout_for(check),
+        })
+    }
+
+    /// Gets the list of checks for a mode, including the detected agent's
+    /// `[agent.per_agent]` checks.
     fn get_checks_for_mode(&self, mode: Mode) -> Vec<String> {
         match mode {
             Mode::Human => self.config.human.checks.clone(),
-            Mode::Agent | Mode::Ci => self.config.agent.checks.clone(),
+            Mode::Agent | Mode::Ci => {
+                let mut checks = self.config.agent.checks.clone();
+                let extra = self
+                    .agent
+                    .and_then(|agent| self.config.agent.per_agent.get(&agent));
+                for name in extra.into_iter().flat_map(|extra| &extra.checks) {
+                    if !checks.contains(name) {
+                        checks.push(name.clone());
+                    }
+                }
+                checks
+            },
+        }
+    }
+
+    /// Filters a mode's checks down to the `--only` selection, if any.
+    fn select_only(&self, names: Vec<String>, mode: Mode) -> Result<Vec<String>> {
+        let Some(only) = &self.only else {
+            return Ok(names);
+        };
+        let only: Vec<String> = only
+            .iter()
+            .map(|n| self.config.check_name(n).unwrap_or(n).to_string())
+            .collect();
+
+        if let Some(missing) = only.iter().find(|n| !names.contains(n)) {
+            return Err(Error::ConfigInvalid {
+                field: "only".to_string(),
+                message: format!("'{missing}' is not a {} mode check", mode.name()),
+            });
         }
+
+        Ok(names.into_iter().filter(|n| only.contains(n)).collect())
+    }
+
+    /// Looks up a check by name or alias, returning its name and configuration.
+    fn named_check<'a>(&'a self, name: &'a str) -> Result<(&'a str, &'a CheckConfig)> {
+        self.config
+            .check_name(name)
+            .and_then(|name| Some((name, self.config.checks.get(name)?)))
+            .ok_or_else(|| Error::CheckNotFound {
+                name: name.to_string(),
+            })
     }
 
     /// Resolves check names to configurations.
@@ -194,6 +817,23 @@ impl Runner {
         Ok(checks)
     }
 
+    /// Orders `names` into dependency waves, shuffling each wave if
+    /// [`Runner::with_shuffle`] is set.
+    fn stages(
+        &self,
+        names: &[String],
+        check_map: &HashMap<String, CheckConfig>,
+    ) -> Result<Vec<Vec<String>>> {
+        let mut waves = graph::waves(names, check_map)?;
+        if let Some(seed) = self.shuffle_seed {
+            let mut shuffle = graph::Shuffle::new(seed);
+            for wave in &mut waves {
+                shuffle.shuffle(wave);
+            }
+        }
+        Ok(waves)
+    }
+
     /// Runs checks sequentially (for human mode).
     async fn run_sequential(
         &self,
@@ -201,15 +841,30 @@ impl Runner {
         checks: &[(String, CheckConfig)],
     ) -> Result<Vec<CheckResult>> {
         let mut results = Vec::with_capacity(checks.len());
+        let mut blocked = HashSet::new();
 
-        for (name, check) in checks {
-            let result = self.run_check(name, check, mode).await?;
+        let names: Vec<String> = checks.iter().map(|(n, _)| n.clone()).collect();
+        let check_map: HashMap<_, _> = checks.iter().cloned().collect();
+        let ordered = self.stages(&names, &check_map)?.concat();
 
-            let failed = !result.passed;
+        for name in &ordered {
+            let Some(check) = check_map.get(name) else {
+                continue;
+            };
+            let dependency = blocked_by(check, &blocked);
+            let result = match dependency {
+                Some(dependency) => CheckResult::dependency_failed(name, dependency),
+                None => self.run_check(name, check, mode).await?,
+            };
+
+            if result.status.is_failure() || dependency.is_some() {
+                blocked.insert(name.clone());
+            }
+            let failed = result.is_blocking_failure();
             results.push(result);
 
             // Fail fast in human mode
-            if failed && self.config.human.fail_fast {
+            if (failed && self.config.human.fail_fast) || self.is_cancelled() {
                 break;
             }
         }
@@ -217,27 +872,106 @@ impl Runner {
         Ok(results)
     }
 
-    /// Runs checks in parallel groups (for agent mode).
+    /// Returns how many checks may run at once in `mode`.
+    ///
+    /// Resolved from [`Runner::with_max_parallel`] (`--max-parallel`), then
+    /// `[human]`/`[agent].max_parallel`, then the mode default: 1 for humans
+    /// (sequential) and the number of CPUs for agents and CI.
+    #[must_use]
+    pub fn max_parallel(&self, mode: Mode) -> usize {
+        let configured = match mode {
+            Mode::Human => self.config.human.max_parallel,
+            Mode::Agent | Mode::Ci => self.config.agent.max_parallel,
+        };
+        let default = || {
+            if mode.is_thorough() {
+                concurrency::available_parallelism()
+            } else {
+                1
+            }
+        };
+        self.max_parallel
+            .or(configured)
+            .unwrap_or_else(default)
+            .max(1)
+    }
+
+    /// Returns how far a failing check stops a parallel run in `mode`.
+    ///
+    /// Human `fail_fast` has no groups to scope to, so it stops the run.
+    const fn fail_fast(&self, mode: Mode) -> FailFast {
+        match mode {
+            Mode::Human if self.config.human.fail_fast => FailFast::Run,
+            Mode::Human => FailFast::Off,
+            Mode::Agent | Mode::Ci => self.config.agent.fail_fast,
+        }
+    }
+
+    /// Returns the configured parallel groups, or waves derived from check
+    /// dependencies and split by `[agent].auto_parallel`, shuffled if
+    /// [`Runner::with_shuffle`] is set.
+    fn parallel_stages(
+        &self,
+        mode: Mode,
+        checks: &[(String, CheckConfig)],
+        check_map: &HashMap<String, CheckConfig>,
+    ) -> Result<Vec<ParallelGroup>> {
+        if !mode.is_thorough() || self.config.agent.parallel_groups.is_empty() {
+            let names: Vec<String> = checks.iter().map(|(n, _)| n.clone()).collect();
+            let strategy = if mode.is_thorough() {
+                self.config.agent.auto_parallel
+            } else {
+                AutoParallel::All
+            };
+            return Ok(self
+                .stages(&names, check_map)?
+                .into_iter()
+                .flat_map(|wave| auto_groups(wave, strategy, check_map))
+                .map(ParallelGroup::from)
+                .collect());
+        }
+
+        let mut groups = self.config.agent.parallel_groups.clone();
+        // Checks no group lists, such as per-agent extras, run together last
+        let ungrouped: Vec<String> = checks
+            .iter()
+            .map(|(n, _)| n)
+            .filter(|n| !groups.iter().any(|g| g.checks().contains(n)))
+            .cloned()
+            .collect();
+        if !ungrouped.is_empty() {
+            groups.push(ParallelGroup::from(ungrouped));
+        }
+        if let Some(seed) = self.shuffle_seed {
+            let mut shuffle = graph::Shuffle::new(seed);
+            for group in &mut groups {
+                shuffle.shuffle(group.checks_mut());
+            }
+        }
+        Ok(groups)
+    }
+
+    /// Runs checks in parallel groups, at most `max_parallel` at a time.
     async fn run_parallel_groups(
         &self,
         mode: Mode,
         checks: &[(String, CheckConfig)],
+        max_parallel: usize,
     ) -> Result<Vec<CheckResult>> {
         let check_map: HashMap<_, _> = checks.iter().cloned().collect();
+        let fail_fast = self.fail_fast(mode);
 
-        // Get parallel groups or create default groups
-        let groups = if self.config.agent.parallel_groups.is_empty() {
-            // Default: run all checks in parallel
-            vec![checks.iter().map(|(n, _)| n.clone()).collect()]
-        } else {
-            self.config.agent.parallel_groups.clone()
-        };
+        let groups = self.parallel_stages(mode, checks, &check_map)?;
+        let planned: Vec<String> = groups.iter().flat_map(|g| g.checks().to_vec()).collect();
 
         let mut all_results = Vec::new();
-        let semaphore = Arc::new(Semaphore::new(concurrency::available_parallelism()));
+        let mut blocked = HashSet::new();
+        let semaphore = Arc::new(Semaphore::new(max_parallel));
+        let ctx = Arc::new(self.context(mode));
 
         for group in groups {
             let group_checks: Vec<_> = group
+                .checks()
                 .iter()
                 .filter_map(|name| check_map.get(name).map(|c| (name.clone(), c.clone())))
                 .collect();
@@ -247,24 +981,66 @@ impl Runner {
             }
 
             let mut handles = Vec::new();
+            let group_semaphore = group
+                .max_parallel()
+                .or(self.group_parallel)
+                .map(|limit| Arc::new(Semaphore::new(limit)));
+            let group_cancel = (fail_fast != FailFast::Off).then(|| {
+                self.cancel
+                    .as_ref()
+                    .map_or_else(CancellationToken::new, CancellationToken::child_token)
+            });
+            let group_ctx = match &group_cancel {
+                Some(token) => Arc::new(CheckContext {
+                    cancel: Some(token.clone()),
+                    ..self.context(mode)
+                }),
+                None => Arc::clone(&ctx),
+            };
 
             for (name, check) in group_checks {
+                if let Some(dependency) = blocked_by(&check, &blocked) {
+                    all_results.push(CheckResult::dependency_failed(&name, dependency));
+                    blocked.insert(name);
+                    continue;
+                }
+
                 let sem = Arc::clone(&semaphore);
-                let config = self.config.clone();
-                let repo = self.repo.clone();
+                let group_sem = group_semaphore.clone();
+                let ctx = Arc::clone(&group_ctx);
+                let group_cancel = group_cancel.clone();
+                let run_cancel = self.cancel.clone();
 
                 handles.push(tokio::spawn(async move {
+                    // Take the group's slot before a global one so waiting here
+                    // doesn't hold back other groups
+                    let _group_permit = match &group_sem {
+                        Some(sem) => Some(sem.acquire().await.map_err(|_| Error::Internal {
+                            message: "Semaphore closed unexpectedly".to_string(),
+                        })?),
+                        None => None,
+                    };
                     // Acquire semaphore permit; if semaphore is closed, treat as internal error
                     let _permit = sem.acquire().await.map_err(|_| Error::Internal {
                         message: "Semaphore closed unexpectedly".to_string(),
                     })?;
-                    run_check_async(&name, &check, mode, &config, repo.as_ref()).await
+                    let result = run_check_async(&name, &check, &ctx).await?;
+                    Ok(match &group_cancel {
+                        Some(group) => cancel_group_on_failure(result, group, run_cancel.as_ref()),
+                        None => result,
+                    })
                 }));
             }
 
             for handle in handles {
                 match handle.await {
-                    Ok(result) => all_results.push(result?),
+                    Ok(result) => {
+                        let result = result?;
+                        if result.status.is_failure() || result.status == CheckStatus::Cancelled {
+                            blocked.insert(result.name.clone());
+                        }
+                        all_results.push(result);
+                    },
                     Err(e) => {
                         return Err(Error::Internal {
                             message: format!("Task join error: {e}"),
@@ -273,69 +1049,546 @@ impl Runner {
                 }
             }
 
-            // Check for failures if not running all checks
-            if !self.config.agent.fail_fast {
+            if self.is_cancelled() {
+                break;
+            }
+
+            // Only run-scoped fail-fast stops later groups
+            if fail_fast != FailFast::Run {
                 continue;
             }
 
-            if all_results.iter().any(|r: &CheckResult| !r.passed) {
+            if all_results.iter().any(CheckResult::is_blocking_failure) {
                 break;
             }
         }
 
+        // Tasks finish in any order; report checks in the order they were planned
+        let position: HashMap<&str, usize> = planned
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+        all_results.sort_by_key(|r| position.get(r.name.as_str()).copied());
+
         Ok(all_results)
     }
 
     /// Runs a single check.
     async fn run_check(&self, name: &str, check: &CheckConfig, mode: Mode) -> Result<CheckResult> {
-        run_check_async(name, check, mode, &self.config, self.repo.as_ref()).await
+        run_check_async(name, check, &self.context(mode)).await
+    }
+
+    /// Builds the shared context for executing checks in a mode.
+    fn context(&self, mode: Mode) -> CheckContext {
+        CheckContext {
+            mode,
+            config: self.config.clone(),
+            repo: self.repo.clone(),
+            files: self.files.clone(),
+            cancel: self.cancel.clone(),
+            ignore_conditions: self.ignore_conditions,
+            only_changed: self.only_changed,
+            ignore: self
+                .repo
+                .as_ref()
+                .and_then(|repo| files::IgnoreFile::load(repo.root())),
+            timeout: self.timeout,
+            stream: self.stream,
+            message_file: self.message_file.clone(),
+            skip_checks: std::env::var(SKIP_CHECKS_VAR)
+                .map(|value| parse_skip_checks(&value))
+                .unwrap_or_default(),
+        }
     }
 }
 
-/// Runs a check asynchronously (for parallel execution).
-async fn run_check_async(
-    name: &str,
-    check: &CheckConfig,
-    mode: Mode,
-    config: &Config,
-    repo: Option<&GitRepo>,
-) -> Result<CheckResult> {
-    // Check if the check is enabled
-    if !check_enabled(check, repo) {
-        return Ok(CheckResult::skipped(
-            name.to_string(),
-            "Condition not met".to_string(),
-        ));
+/// Environment variable naming checks to skip, comma-separated.
+const SKIP_CHECKS_VAR: &str = "APC_SKIP_CHECKS";
+
+/// Parses an `APC_SKIP_CHECKS` value into check names, ignoring blanks.
+fn parse_skip_checks(value: &str) -> HashSet<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// State shared by every check in a run.
+#[derive(Debug)]
+struct CheckContext {
+    mode: Mode,
+    config: Config,
+    repo: Option<GitRepo>,
+    /// Explicit file selection for `{files}`; staged files when `None`.
+    files: Option<Vec<PathBuf>>,
+    cancel: Option<CancellationToken>,
+    ignore_conditions: bool,
+    /// Filter checks by `paths` whatever the mode, from `--changed-since`.
+    only_changed: bool,
+    /// Paths from `.apcignore`, left out of every file selection.
+    ignore: Option<files::IgnoreFile>,
+    /// Timeout that overrides the configured ones, from `--timeout`.
+    timeout: Option<Duration>,
+    /// Echo output live as well as capturing it.
+    stream: bool,
+    /// Commit message file, from `--message-file`.
+    message_file: Option<PathBuf>,
+    /// Checks skipped by name or alias, from `APC_SKIP_CHECKS`.
+    skip_checks: HashSet<String>,
+}
+
+impl CheckContext {
+    /// Returns true if the run was aborted via the cancellation token.
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
     }
 
-    // Build execution options
-    let timeout_str = match mode {
-        Mode::Human => &config.human.timeout,
-        Mode::Agent | Mode::Ci => &config.agent.timeout,
-    };
+    /// Waits for `delay`, returning false if the run is cancelled first.
+    async fn sleep(&self, delay: Duration) -> bool {
+        let Some(token) = &self.cancel else {
+            tokio::time::sleep(delay).await;
+            return true;
+        };
+        tokio::select! {
+            () = tokio::time::sleep(delay) => true,
+            () = token.cancelled() => false,
+        }
+    }
+
+    /// Returns the check's own timeout, falling back to the mode timeout.
+    ///
+    /// A run-wide override takes precedence over both.
+    fn timeout_for(&self, check: &CheckConfig) -> Duration {
+        if let Some(timeout) = self.timeout {
+            return timeout;
+        }
+
+        let timeout_str = check.timeout.as_ref().unwrap_or(match self.mode {
+            Mode::Human => &self.config.human.timeout,
+            Mode::Agent | Mode::Ci => &self.config.agent.timeout,
+        });
+
+        parse_duration(timeout_str).unwrap_or_else(|| {
+            tracing::warn!(
+                timeout_str = %timeout_str,
+                default_secs = 300,
+                "Invalid timeout format, using default"
+            );
+            Duration::from_secs(300)
+        })
+    }
+
+    /// Builds the execution options for a check, apart from its `env`.
+    fn execute_options(&self, name: &str, check: &CheckConfig) -> ExecuteOptions {
+        let mut options = ExecuteOptions::default().timeout(self.timeout_for(check));
+
+        if let Some(limit) = self.config.globals.max_output_bytes {
+            options = options.max_output_bytes(limit);
+        }
+
+        let grace = self.config.globals.timeout_grace.as_deref();
+        if let Some(grace) = grace.and_then(|g| humantime::parse_duration(g).ok()) {
+            options = options.grace_period(grace);
+        }
+
+        if let Some(repo) = &self.repo {
+            options = options.cwd(repo.root());
+        }
+
+        if check.clean_env {
+            options = options.clean_env(check.env_inherit.clone());
+        }
+
+        if let Some(token) = &self.cancel {
+            options = options.cancel(token.clone());
+        }
+
+        if self.stream {
+            options = options.capture_and_stream(format!("[{name}] "));
+        }
+
+        options.shell(self.shell_for(check))
+    }
+
+    /// Returns the check's own shell, falling back to the mode's default shell,
+    /// `[globals].shell` and then the platform's.
+    fn shell_for<'a>(&'a self, check: &'a CheckConfig) -> &'a str {
+        let mode_shell = match self.mode {
+            Mode::Human => self.config.human.default_shell.as_deref(),
+            Mode::Agent | Mode::Ci => self.config.agent.default_shell.as_deref(),
+        };
+        match check
+            .shell
+            .as_deref()
+            .or(mode_shell)
+            .or(self.config.globals.shell.as_deref())
+        {
+            Some(shell) => shell,
+            None => Executor::default_shell(),
+        }
+    }
+
+    /// Builds the shell command for a check, wrapping it in `docker run` if
+    /// the check has a container.
+    fn command_for(&self, check: &CheckConfig, run: &str, env_keys: &[String]) -> Result<String> {
+        let command = self.expand_command(run)?;
+        let Some(image) = &check.container else {
+            return Ok(command);
+        };
+
+        let root = match &self.repo {
+            Some(repo) => repo.root().to_path_buf(),
+            None => std::env::current_dir()
+                .map_err(|e| Error::io("Failed to get current directory", e))?,
+        };
+        Ok(container_command(image, &command, &root, env_keys))
+    }
+
+    /// Evaluates a check's `files_changed` condition, or `None` if it has none.
+    fn files_changed_match(&self, check: &CheckConfig) -> Result<Option<bool>> {
+        let Some(pattern) = check
+            .enabled_if
+            .as_ref()
+            .and_then(|c| c.files_changed.as_ref())
+        else {
+            return Ok(None);
+        };
+
+        self.changed_files_match(std::slice::from_ref(pattern))
+            .map(Some)
+    }
+
+    /// Returns true if a changed file matches any of the glob patterns.
+    ///
+    /// Matches the explicit file selection if there is one, else the staged
+    /// files in human mode and the whole working tree in agent/CI mode.
+    /// Without a repo this is always true; invalid patterns match nothing.
+    fn changed_files_match(&self, patterns: &[String]) -> Result<bool> {
+        let Some(repo) = &self.repo else {
+            return Ok(true);
+        };
+        let patterns: Vec<glob::Pattern> = patterns
+            .iter()
+            .filter_map(|p| glob::Pattern::new(p).ok())
+            .collect();
+        if patterns.is_empty() {
+            return Ok(false);
+        }
+
+        let candidates = self.unignored(match &self.files {
+            Some(files) => files.clone(),
+            None if self.mode.is_thorough() => repo.working_tree_files()?,
+            None => repo.staged_files()?,
+        });
+
+        Ok(candidates.iter().any(|f| {
+            let relative = files::relative_to(f, repo.root());
+            patterns.iter().any(|p| p.matches_path(&relative))
+        }))
+    }
+
+    /// Returns true if `[human].only_changed` (or [`Runner::only_changed`])
+    /// skips the check: it lists `paths` and no changed file matches them.
+    fn unchanged_paths(&self, check: &CheckConfig) -> Result<bool> {
+        let filtering =
+            self.only_changed || (self.mode == Mode::Human && self.config.human.only_changed);
+        if !filtering || check.paths.is_empty() {
+            return Ok(false);
+        }
+        Ok(!self.changed_files_match(&check.paths)?)
+    }
+
+    /// Returns why a check should not run: the run was aborted, it is listed
+    /// in `APC_SKIP_CHECKS`, its conditions are not met, or it needs docker
+    /// and docker is missing.
+    fn skip_reason(&self, name: &str, check: &CheckConfig) -> Result<Option<String>> {
+        if self.is_cancelled() {
+            return Ok(Some("Aborted".to_string()));
+        }
 
-    let timeout = parse_duration(timeout_str).unwrap_or_else(|| {
-        tracing::warn!(
-            timeout_str = %timeout_str,
-            default_secs = 300,
-            "Invalid timeout format, using default"
+        let mut names = std::iter::once(name).chain(check.aliases.iter().map(String::as_str));
+        if names.any(|n| self.skip_checks.contains(n)) {
+            return Ok(Some(format!("skipped via {SKIP_CHECKS_VAR}")));
+        }
+
+        let enabled = check_enabled(check, self.repo.as_ref(), self.files_changed_match(check)?);
+        if !self.ignore_conditions && !enabled {
+            return Ok(Some("Condition not met".to_string()));
+        }
+
+        if !self.ignore_conditions && self.unchanged_paths(check)? {
+            return Ok(Some("No changed files match its paths".to_string()));
+        }
+
+        if let Some(image) = &check.container {
+            if !Executor::command_exists("docker") {
+                return Ok(Some(format!(
+                    "Docker is not available to run image '{image}'"
+                )));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the result cache and the check's key, if the check declares
+    /// `depends_files` (or `[agent].cache` is on in agent or CI mode) and the
+    /// run is in a repository.
+    ///
+    /// Without `depends_files`, the key covers the files the check's `paths`
+    /// match, or every file git tracks or doesn't ignore if it has none.
+    /// `--all` bypasses the cache along with the other conditions.
+    fn cache_key(&self, check: &CheckConfig) -> Option<(Cache, String)> {
+        let agent_cache = self.mode != Mode::Human && self.config.agent.cache;
+        if (check.depends_files.is_empty() && !agent_cache) || self.ignore_conditions {
+            return None;
+        }
+
+        let repo = self.repo.as_ref()?;
+        let inputs = if !check.depends_files.is_empty() {
+            files::glob_files(repo.root(), &check.depends_files)
+        } else if !check.paths.is_empty() {
+            files::glob_files(repo.root(), &check.paths)
+        } else {
+            repo.working_tree_files().ok()?
+        };
+        let key = cache::input_key(check, repo.root(), &inputs)?;
+        Some((Cache::new(repo.git_dir().join(cache::CACHE_FILE_NAME)), key))
+    }
+
+    /// Environment describing the staged files, for checks with
+    /// `pass_staged_files`.
+    ///
+    /// Paths are relative to the repo root. `APC_STAGED_FILES` joins them with
+    /// newlines; `APC_STAGED_FILES_<n>` holds each one verbatim for paths that
+    /// themselves contain whitespace.
+    fn staged_files_env(&self, check: &CheckConfig) -> Result<Vec<(String, String)>> {
+        let Some(repo) = self.repo.as_ref().filter(|_| check.pass_staged_files) else {
+            return Ok(Vec::new());
+        };
+
+        let staged: Vec<String> = self
+            .unignored(repo.staged_files()?)
+            .iter()
+            .map(|f| {
+                files::relative_to(f, repo.root())
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+
+        let mut env = vec![
+            ("APC_STAGED_FILES".to_string(), staged.join("\n")),
+            (
+                "APC_STAGED_FILES_COUNT".to_string(),
+                staged.len().to_string(),
+            ),
+        ];
+        env.extend(
+            staged
+                .into_iter()
+                .enumerate()
+                .map(|(i, file)| (format!("APC_STAGED_FILES_{i}"), file)),
         );
-        Duration::from_secs(300)
-    });
+        Ok(env)
+    }
 
-    let mut options = ExecuteOptions::default().timeout(timeout);
+    /// Writes the staged diff to a temporary file, for checks with
+    /// `pass_staged_diff`. The file is removed when the returned guard drops.
+    fn staged_diff_file(&self, check: &CheckConfig) -> Result<Option<StagedDiffFile>> {
+        let Some(repo) = self.repo.as_ref().filter(|_| check.pass_staged_diff) else {
+            return Ok(None);
+        };
 
-    if let Some(repo) = repo {
-        options = options.cwd(repo.root());
+        let mut file = tempfile::Builder::new()
+            .prefix("apc-staged-")
+            .suffix(".diff")
+            .tempfile()
+            .map_err(|e| Error::io("write staged diff", e))?;
+        std::io::Write::write_all(&mut file, repo.staged_diff()?.as_bytes())
+            .map_err(|e| Error::io("write staged diff", e))?;
+        Ok(Some(StagedDiffFile(file)))
     }
 
-    // Add environment variables from check config
-    for (key, value) in &check.env {
-        options = options.env(key.clone(), value.clone());
+    /// Resolves a check's command and whether it would be skipped, without
+    /// running it.
+    fn plan(&self, name: &str, check: &CheckConfig) -> Result<PlannedCheck> {
+        let mut skip_reason = self.skip_reason(name, check)?;
+        if skip_reason.is_none() {
+            if let Some((cache, key)) = self.cache_key(check) {
+                if cache.hit(name, &key) {
+                    skip_reason = Some(cache::CACHED_REASON.to_string());
+                }
+            }
+        }
+
+        let (run, env) = interpolate_check(name, check, &self.config.globals)?;
+        let env_keys: Vec<String> = env.into_iter().map(|(key, _)| key).collect();
+
+        Ok(PlannedCheck {
+            name: name.to_string(),
+            command: self.command_for(check, &run, &env_keys)?,
+            skip_reason,
+        })
     }
 
-    // Execute the command
-    let executor = Executor::new();
+    /// Expands `{files}` in a check command, if present.
+    fn expand_command(&self, command: &str) -> Result<String> {
+        if !files::uses_files(command) {
+            return Ok(command.to_string());
+        }
+
+        let Some(repo) = &self.repo else {
+            return Ok(files::expand_files(command, &[], std::path::Path::new("")));
+        };
+
+        let selected = self.unignored(match &self.files {
+            Some(f) => f.clone(),
+            None => repo.staged_files()?,
+        });
+
+        Ok(files::expand_files(command, &selected, repo.root()))
+    }
+
+    /// Removes the paths matched by `.apcignore` from `files`.
+    fn unignored(&self, mut files: Vec<PathBuf>) -> Vec<PathBuf> {
+        if let Some(ignore) = &self.ignore {
+            ignore.retain(&mut files);
+        }
+        files
+    }
+}
+
+/// Returns true if a finished check passed: its command exited 0, or for
+/// `expect_failure` checks exited non-zero without timing out or being aborted.
+fn check_passed(check: &CheckConfig, output: &CommandOutput) -> bool {
+    if check.expect_failure {
+        !output.success() && !output.timed_out && !output.cancelled
+    } else {
+        output.success()
+    }
+}
+
+/// Prints the ✓/✗ line for a finished check.
+///
+/// A pass without output is marked as such so it reads differently from a skip.
+fn print_check_status(name: &str, check: &CheckConfig, output: &CommandOutput) {
+    let passed = check_passed(check, output);
+    if passed && !output.has_output() {
+        eprintln!(
+            "{} {name} {}",
+            style("✓").green(),
+            style(NO_OUTPUT_PLACEHOLDER).dim()
+        );
+    } else if passed {
+        eprintln!("{} {name}", style("✓").green());
+    } else if check.allow_failure {
+        eprintln!("{} {name} failed (non-blocking)", style("⚠").yellow());
+    } else if output.timed_out {
+        eprintln!("{} {name} (timed out)", style("✗").red());
+    } else if let Some(signal) = output.signal {
+        eprintln!(
+            "{} {name} (terminated by signal {signal})",
+            style("✗").red()
+        );
+    } else if output.cancelled {
+        eprintln!("{} {name} (aborted)", style("✗").red());
+    } else if check.expect_failure {
+        eprintln!("{} {name} (expected failure, exited 0)", style("✗").red());
+    } else {
+        eprintln!("{} {name}", style("✗").red());
+    }
+}
+
+/// Splits a dependency wave into groups that run one after another.
+///
+/// `by_tag` keeps the wave's order of first appearance for each tag.
+fn auto_groups(
+    wave: Vec<String>,
+    strategy: AutoParallel,
+    check_map: &HashMap<String, CheckConfig>,
+) -> Vec<Vec<String>> {
+    match strategy {
+        AutoParallel::All => vec![wave],
+        AutoParallel::None => wave.into_iter().map(|name| vec![name]).collect(),
+        AutoParallel::ByTag => {
+            let mut tagged: Vec<(&str, Vec<String>)> = Vec::new();
+            let mut untagged = Vec::new();
+            for name in wave {
+                let Some(tag) = check_map.get(&name).and_then(|c| c.tags.first()) else {
+                    untagged.push(name);
+                    continue;
+                };
+                match tagged.iter_mut().find(|(t, _)| t == tag) {
+                    Some((_, group)) => group.push(name),
+                    None => tagged.push((tag, vec![name])),
+                }
+            }
+            tagged
+                .into_iter()
+                .map(|(_, group)| group)
+                .chain(Some(untagged).filter(|g| !g.is_empty()))
+                .collect()
+        },
+    }
+}
+
+/// Temporary file holding the staged diff for one check run, created with a
+/// unique name and removed on drop.
+struct StagedDiffFile(tempfile::NamedTempFile);
+
+/// Runs a check asynchronously (for parallel execution).
+async fn run_check_async(
+    name: &str,
+    check: &CheckConfig,
+    ctx: &CheckContext,
+) -> Result<CheckResult> {
+    let (config, repo) = (&ctx.config, ctx.repo.as_ref());
+
+    if ctx.is_cancelled() {
+        return Ok(CheckResult::aborted(name.to_string()));
+    }
+    if let Some(reason) = ctx.skip_reason(name, check)? {
+        return Ok(CheckResult::skipped(name.to_string(), reason));
+    }
+
+    // Skip checks whose declared inputs are unchanged since they last passed
+    let cache_key = ctx.cache_key(check);
+    if let Some((cache, key)) = &cache_key {
+        if cache.hit(name, key) {
+            return Ok(CheckResult::cached(name.to_string()));
+        }
+    }
+
+    let mut options = ctx.execute_options(name, check);
+
+    // Add environment variables from check config
+    let (run, mut env) = interpolate_check(name, check, &config.globals)?;
+    env.extend(ctx.staged_files_env(check)?);
+    let staged_diff = ctx.staged_diff_file(check)?;
+    if let Some(file) = &staged_diff {
+        env.push((
+            "APC_STAGED_DIFF_FILE".to_string(),
+            file.0.path().to_string_lossy().into_owned(),
+        ));
+    }
+    if let Some(path) = &ctx.message_file {
+        env.push((
+            "APC_COMMIT_MSG_FILE".to_string(),
+            path.to_string_lossy().into_owned(),
+        ));
+        let message = std::fs::read(path).map_err(|e| Error::io("read commit message", e))?;
+        options = options.stdin(StdinMode::Bytes(message));
+    }
+    let env_keys: Vec<String> = env.iter().map(|(key, _)| key.clone()).collect();
+    for (key, value) in env {
+        options = options.env(key, value);
+    }
 
     // Show progress
     let pb = ProgressBar::new_spinner();
@@ -348,60 +1601,349 @@ async fn run_check_async(
     pb.set_message(format!("Running {name}..."));
     pb.enable_steady_tick(Duration::from_millis(100));
 
-    let output = executor.execute(&check.run, options).await?;
+    let command = ctx.command_for(check, &run, &env_keys)?;
+    let (output, attempts, interrupted) =
+        execute_with_retries(name, check, &command, &options, ctx, &pb).await?;
 
     pb.finish_and_clear();
 
-    // Format result
-    if output.success() {
-        eprintln!("{} {name}", style("✓").green());
-    } else if output.timed_out {
-        eprintln!("{} {name} (timed out)", style("✗").red());
-    } else {
-        eprintln!("{} {name}", style("✗").red());
+    print_check_status(name, check, &output);
+
+    let passed = check_passed(check, &output);
+    if let (true, Some((cache, key))) = (passed, &cache_key) {
+        if let Err(e) = cache.store(name, key) {
+            tracing::warn!(check = %name, error = %e, "Failed to cache check result");
+        }
     }
 
+    let artifacts = if !check.artifacts.is_empty() && (!passed || check.artifacts_always) {
+        collect_check_artifacts(name, check, config, repo)
+    } else {
+        Vec::new()
+    };
+
+    let status = if output.cancelled || interrupted {
+        CheckStatus::Aborted
+    } else if passed {
+        CheckStatus::Passed
+    } else {
+        CheckStatus::Failed
+    };
     Ok(CheckResult {
         name: name.to_string(),
-        passed: output.success(),
+        status,
         output,
-        skipped: false,
         skip_reason: None,
+        artifacts,
+        blocking: !check.allow_failure,
+        attempts,
     })
 }
 
-/// Checks if a check is enabled based on its conditions.
-fn check_enabled(check: &CheckConfig, repo: Option<&GitRepo>) -> bool {
-    let Some(ref condition) = check.enabled_if else {
-        return true;
+/// Runs a check's command, retrying failures up to `check.retries` times.
+///
+/// Returns the last output, the number of attempts, and whether the run was
+/// cancelled while waiting to retry.
+async fn execute_with_retries(
+    name: &str,
+    check: &CheckConfig,
+    command: &str,
+    options: &ExecuteOptions,
+    ctx: &CheckContext,
+    pb: &ProgressBar,
+) -> Result<(CommandOutput, u32, bool)> {
+    let executor = Executor::new();
+    let retry_delay = check
+        .retry_delay
+        .as_deref()
+        .and_then(parse_duration)
+        .unwrap_or(Duration::ZERO);
+
+    let mut attempts = 1;
+    let mut output = executor.execute(command, options.clone()).await?;
+    while !check_passed(check, &output)
+        && !output.timed_out
+        && attempts <= check.retries
+        && !ctx.is_cancelled()
+    {
+        pb.set_message(format!(
+            "Retrying {name} (attempt {}/{})...",
+            attempts + 1,
+            check.retries + 1
+        ));
+        if !ctx.sleep(retry_delay).await {
+            return Ok((output, attempts, true));
+        }
+        attempts += 1;
+        output = executor.execute(command, options.clone()).await?;
+    }
+    Ok((output, attempts, false))
+}
+
+/// Applies fail-fast to a finished check.
+///
+/// The first blocking failure cancels the rest of the group. Checks that were
+/// then killed or never started are reported as cancelled. A check that
+/// finished on its own keeps its result, even if it failed after the group
+/// was cancelled.
+fn cancel_group_on_failure(
+    result: CheckResult,
+    group: &CancellationToken,
+    run: Option<&CancellationToken>,
+) -> CheckResult {
+    if result.status == CheckStatus::Aborted
+        && group.is_cancelled()
+        && !run.is_some_and(CancellationToken::is_cancelled)
+    {
+        return CheckResult::cancelled(result.name);
+    }
+
+    if result.is_blocking_failure() {
+        group.cancel();
+    }
+    result
+}
+
+/// Collects a check's artifacts into `<artifact_dir>/<check>/`.
+///
+/// Collection problems are logged rather than failing the check.
+fn collect_check_artifacts(
+    name: &str,
+    check: &CheckConfig,
+    config: &Config,
+    repo: Option<&GitRepo>,
+) -> Vec<PathBuf> {
+    let Some(repo) = repo else {
+        return Vec::new();
     };
 
-    // Check file_exists condition
-    if let Some(ref path) = condition.file_exists {
-        if let Some(repo) = repo {
-            if !repo.file_exists(path) {
-                return false;
-            }
+    let base = config.output.artifact_dir.as_ref().map_or_else(
+        || repo.git_dir().join("apc-artifacts"),
+        |dir| repo.root().join(dir),
+    );
+
+    match files::collect_artifacts(repo.root(), &check.artifacts, &base.join(name)) {
+        Ok(collected) => collected,
+        Err(e) => {
+            tracing::warn!(check = %name, error = %e, "Failed to collect artifacts");
+            Vec::new()
+        },
+    }
+}
+
+/// Wraps `command` to run in a throwaway `image` container with `root` mounted at `/w`.
+///
+/// Env vars are forwarded by name, so docker reads their values from its own
+/// environment rather than the command line.
+fn container_command(image: &str, command: &str, root: &Path, env: &[String]) -> String {
+    let mut args = vec![
+        "docker run --rm".to_string(),
+        format!(
+            "-v {}",
+            files::shell_quote(&format!("{}:/w", root.display()))
+        ),
+        "-w /w".to_string(),
+    ];
+    args.extend(
+        env.iter()
+            .map(|key| format!("-e {}", files::shell_quote(key))),
+    );
+    args.push(files::shell_quote(image));
+    args.push(format!("sh -c {}", files::shell_quote(command)));
+    args.join(" ")
+}
+
+/// Expands `${VAR}` in a check's env values and command.
+///
+/// Env values resolve from `[globals.env]`, then the process environment; the
+/// command additionally sees the check's own (expanded) env first.
+fn interpolate_check(
+    name: &str,
+    check: &CheckConfig,
+    globals: &GlobalsConfig,
+) -> Result<(String, Vec<(String, String)>)> {
+    let unknown = |field: String| {
+        move |var: String| Error::ConfigInvalid {
+            field,
+            message: format!("Unknown variable ${{{var}}}"),
         }
+    };
+
+    let mut env = Vec::with_capacity(check.env.len());
+    for (key, value) in &check.env {
+        let value = interpolate::expand(value, |var| globals.resolve(var))
+            .map_err(unknown(format!("checks.{name}.env.{key}")))?;
+        env.push((key.clone(), value));
     }
 
-    // Check dir_exists condition
-    if let Some(ref path) = condition.dir_exists {
-        if let Some(repo) = repo {
-            if !repo.dir_exists(path) {
-                return false;
-            }
+    let run = interpolate::expand(&check.run, |var| {
+        env.iter()
+            .find(|(key, _)| key == var)
+            .map(|(_, value)| value.clone())
+            .or_else(|| globals.resolve(var))
+    })
+    .map_err(unknown(format!("checks.{name}.run")))?;
+
+    Ok((run, env))
+}
+
+/// Returns the first dependency of `check` that failed or was itself blocked.
+fn blocked_by<'a>(check: &'a CheckConfig, blocked: &HashSet<String>) -> Option<&'a str> {
+    check
+        .needs
+        .iter()
+        .find(|dep| blocked.contains(dep.as_str()))
+        .map(String::as_str)
+}
+
+/// Outcome of a single `enabled_if` condition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionOutcome {
+    /// Name of the condition (e.g. `file_exists`).
+    pub condition: &'static str,
+    /// Value the condition was configured with.
+    pub value: String,
+    /// Whether the condition holds.
+    pub met: bool,
+    /// What was found (e.g. "not found").
+    pub detail: &'static str,
+}
+
+impl std::fmt::Display for ConditionOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} '{}': {}", self.condition, self.value, self.detail)
+    }
+}
+
+/// Evaluated `enabled_if` conditions of a check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conditions {
+    /// How the conditions are combined.
+    pub r#match: ConditionMatch,
+    /// Each configured condition, in declaration order.
+    pub outcomes: Vec<ConditionOutcome>,
+}
+
+impl Conditions {
+    /// Returns true if the check is enabled.
+    #[must_use]
+    pub fn enabled(&self) -> bool {
+        match self.r#match {
+            ConditionMatch::All => self.outcomes.iter().all(|o| o.met),
+            ConditionMatch::Any => self.outcomes.is_empty() || self.outcomes.iter().any(|o| o.met),
         }
     }
 
-    // Check command_exists condition
-    if let Some(ref cmd) = condition.command_exists {
-        if !Executor::command_exists(cmd) {
-            return false;
+    /// Returns the conditions that do not hold.
+    pub fn unmet(&self) -> impl Iterator<Item = &ConditionOutcome> {
+        self.outcomes.iter().filter(|o| !o.met)
+    }
+}
+
+/// Checks if a check is enabled based on its conditions.
+///
+/// `files_changed` is the already-evaluated `files_changed` condition, if any.
+/// Conditions that cannot be evaluated without a repo count as met.
+fn check_enabled(check: &CheckConfig, repo: Option<&GitRepo>, files_changed: Option<bool>) -> bool {
+    evaluate_conditions(check, repo, files_changed).enabled()
+}
+
+/// Evaluates each of a check's `enabled_if` conditions.
+///
+/// `files_changed` is the already-evaluated `files_changed` condition, if any.
+/// Conditions that cannot be evaluated without a repo count as met.
+#[must_use]
+pub fn evaluate_conditions(
+    check: &CheckConfig,
+    repo: Option<&GitRepo>,
+    files_changed: Option<bool>,
+) -> Conditions {
+    let Some(ref condition) = check.enabled_if else {
+        return Conditions {
+            r#match: ConditionMatch::All,
+            outcomes: Vec::new(),
+        };
+    };
+
+    let outcome = |condition: &'static str, value: &str, met: bool, details: [&'static str; 2]| {
+        ConditionOutcome {
+            condition,
+            value: value.to_string(),
+            met,
+            detail: if met { details[0] } else { details[1] },
         }
+    };
+    // Without a repo, path conditions can't be checked and count as met
+    let path_outcome = |condition, path: &str, test: fn(&GitRepo, &str) -> bool, details| {
+        repo.map_or_else(
+            || {
+                outcome(
+                    condition,
+                    path,
+                    true,
+                    ["not in a git repository, assumed met"; 2],
+                )
+            },
+            |repo| outcome(condition, path, test(repo, path), details),
+        )
+    };
+
+    let mut outcomes = Vec::new();
+    if let Some(path) = &condition.file_exists {
+        outcomes.push(path_outcome(
+            "file_exists",
+            path,
+            |repo, path| repo.file_exists(path),
+            ["found", "not found"],
+        ));
+    }
+    if let Some(path) = &condition.dir_exists {
+        outcomes.push(path_outcome(
+            "dir_exists",
+            path,
+            |repo, path| repo.dir_exists(path),
+            ["found", "not found"],
+        ));
+    }
+    if let Some(cmd) = &condition.command_exists {
+        let met = Executor::command_exists(cmd);
+        outcomes.push(outcome(
+            "command_exists",
+            cmd,
+            met,
+            ["found in PATH", "not found in PATH"],
+        ));
+    }
+    if let Some(path) = &condition.file_absent {
+        outcomes.push(path_outcome(
+            "file_absent",
+            path,
+            |repo, path| !repo.file_exists(path),
+            ["not found", "exists"],
+        ));
+    }
+    if let Some(cmd) = &condition.command_absent {
+        let met = !Executor::command_exists(cmd);
+        outcomes.push(outcome(
+            "command_absent",
+            cmd,
+            met,
+            ["not in PATH", "found in PATH"],
+        ));
+    }
+    if let (Some(pattern), Some(met)) = (&condition.files_changed, files_changed) {
+        outcomes.push(outcome(
+            "files_changed",
+            pattern,
+            met,
+            ["matching files changed", "no changed files match"],
+        ));
     }
 
-    true
+    Conditions {
+        r#match: condition.r#match.unwrap_or_default(),
+        outcomes,
+    }
 }
 
 /// Parses a duration string like "30s", "5m", "1h".
@@ -413,15 +1955,14 @@ fn parse_duration(s: &str) -> Option<Duration> {
 mod concurrency {
     /// Returns the number of available CPU cores for parallel execution.
     pub fn available_parallelism() -> usize {
-        std::thread::available_parallelism()
-            .map(|p| p.get())
-            .unwrap_or(4)
+        std::thread::available_parallelism().map_or(4, std::num::NonZeroUsize::get)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::PerAgentConfig;
 
     // =========================================================================
     // Helper functions for tests
@@ -430,32 +1971,40 @@ mod tests {
     fn make_passed_check(name: &str) -> CheckResult {
         CheckResult {
             name: name.to_string(),
-            passed: true,
+            status: CheckStatus::Passed,
             output: CommandOutput {
                 exit_code: 0,
                 stdout: String::new(),
                 stderr: String::new(),
                 timed_out: false,
+                cancelled: false,
+                signal: None,
                 duration: Duration::ZERO,
             },
-            skipped: false,
             skip_reason: None,
+            artifacts: Vec::new(),
+            blocking: true,
+            attempts: 1,
         }
     }
 
     fn make_failed_check(name: &str) -> CheckResult {
         CheckResult {
             name: name.to_string(),
-            passed: false,
+            status: CheckStatus::Failed,
             output: CommandOutput {
                 exit_code: 1,
                 stdout: String::new(),
                 stderr: "Error".to_string(),
                 timed_out: false,
+                cancelled: false,
+                signal: None,
                 duration: Duration::ZERO,
             },
-            skipped: false,
             skip_reason: None,
+            artifacts: Vec::new(),
+            blocking: true,
+            attempts: 1,
         }
     }
 
@@ -507,8 +2056,7 @@ mod tests {
     #[test]
     fn test_check_result_skipped() {
         let result = CheckResult::skipped("test".to_string(), "reason".to_string());
-        assert!(result.passed);
-        assert!(result.skipped);
+        assert_eq!(result.status, CheckStatus::Skipped);
         assert_eq!(result.skip_reason, Some("reason".to_string()));
         assert_eq!(result.output.exit_code, 0);
         assert!(!result.output.timed_out);
@@ -631,6 +2179,22 @@ mod tests {
         assert_eq!(failed[1].name, "fail2");
     }
 
+    #[test]
+    fn test_run_result_non_blocking_failure_succeeds() {
+        let mut coverage = make_failed_check("coverage");
+        coverage.blocking = false;
+        let result = RunResult {
+            mode: Mode::Agent,
+            checks: vec![make_passed_check("lint"), coverage],
+            duration: Duration::ZERO,
+        };
+
+        assert!(result.success());
+        assert_eq!(result.failed_count(), 1);
+        let names: Vec<_> = result.non_blocking_failures().map(|c| &c.name).collect();
+        assert_eq!(names, vec!["coverage"]);
+    }
+
     #[test]
     fn test_run_result_mode_preserved() {
         let human_result = RunResult {
@@ -695,9 +2259,9 @@ mod tests {
             run: "echo test".to_string(),
             description: "test".to_string(),
             enabled_if: None,
-            env: HashMap::new(),
+            ..Default::default()
         };
-        assert!(check_enabled(&check, None));
+        assert!(check_enabled(&check, None, None));
     }
 
     #[test]
@@ -706,9 +2270,9 @@ mod tests {
             run: "echo test".to_string(),
             description: "test".to_string(),
             enabled_if: Some(crate::config::EnabledCondition::default()),
-            env: HashMap::new(),
+            ..Default::default()
         };
-        assert!(check_enabled(&check, None));
+        assert!(check_enabled(&check, None, None));
     }
 
     #[test]
@@ -720,10 +2284,11 @@ mod tests {
                 file_exists: None,
                 dir_exists: None,
                 command_exists: Some("sh".to_string()),
+                ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         };
-        assert!(check_enabled(&check, None));
+        assert!(check_enabled(&check, None, None));
     }
 
     #[test]
@@ -735,20 +2300,554 @@ mod tests {
                 file_exists: None,
                 dir_exists: None,
                 command_exists: Some("definitely_not_a_command_12345".to_string()),
+                ..Default::default()
             }),
-            env: HashMap::new(),
+            ..Default::default()
         };
-        assert!(!check_enabled(&check, None));
+        assert!(!check_enabled(&check, None, None));
     }
 
-    // =========================================================================
-    // concurrency tests
-    // =========================================================================
+    fn missing_command_condition(r#match: Option<ConditionMatch>) -> CheckConfig {
+        CheckConfig {
+            run: "echo test".to_string(),
+            enabled_if: Some(crate::config::EnabledCondition {
+                command_exists: Some("definitely_not_a_command_12345".to_string()),
+                files_changed: Some("*.rs".to_string()),
+                r#match,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
 
     #[test]
-    fn test_available_parallelism() {
-        let parallelism = concurrency::available_parallelism();
-        assert!(parallelism >= 1);
+    fn test_check_enabled_match_all() {
+        let check = missing_command_condition(None);
+        assert!(!check_enabled(&check, None, Some(true)));
+        let check = missing_command_condition(Some(ConditionMatch::All));
+        assert!(!check_enabled(&check, None, Some(true)));
+    }
+
+    #[test]
+    fn test_check_enabled_match_any() {
+        let check = missing_command_condition(Some(ConditionMatch::Any));
+        assert!(check_enabled(&check, None, Some(true)));
+        assert!(!check_enabled(&check, None, Some(false)));
+    }
+
+    #[test]
+    fn test_check_enabled_command_absent() {
+        let condition = |cmd: &str| CheckConfig {
+            run: "echo test".to_string(),
+            enabled_if: Some(crate::config::EnabledCondition {
+                command_absent: Some(cmd.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(check_enabled(
+            &condition("definitely_not_a_command_12345"),
+            None,
+            None
+        ));
+        assert!(!check_enabled(&condition("sh"), None, None));
+    }
+
+    #[test]
+    fn test_check_enabled_file_absent_combines_with_match() {
+        let (temp, repo) = artifact_repo();
+        std::fs::write(temp.path().join("legacy.cfg"), "").expect("write file");
+        let check = |r#match: Option<ConditionMatch>| CheckConfig {
+            run: "echo test".to_string(),
+            enabled_if: Some(crate::config::EnabledCondition {
+                file_exists: Some("legacy.cfg".to_string()),
+                file_absent: Some("migrated.toml".to_string()),
+                command_absent: Some("sh".to_string()),
+                r#match,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert!(!check_enabled(&check(None), Some(&repo), None));
+        assert!(check_enabled(
+            &check(Some(ConditionMatch::Any)),
+            Some(&repo),
+            None
+        ));
+
+        std::fs::write(temp.path().join("migrated.toml"), "").expect("write file");
+        let only_absent = CheckConfig {
+            run: "echo test".to_string(),
+            enabled_if: Some(crate::config::EnabledCondition {
+                file_absent: Some("migrated.toml".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(!check_enabled(&only_absent, Some(&repo), None));
+    }
+
+    fn conditional_check(condition: crate::config::EnabledCondition) -> CheckConfig {
+        CheckConfig {
+            run: "echo test".to_string(),
+            enabled_if: Some(condition),
+            ..Default::default()
+        }
+    }
+
+    /// Evaluates a single-condition check and returns its one outcome.
+    fn only_outcome(
+        condition: crate::config::EnabledCondition,
+        repo: Option<&GitRepo>,
+        files_changed: Option<bool>,
+    ) -> ConditionOutcome {
+        let conditions = evaluate_conditions(&conditional_check(condition), repo, files_changed);
+        assert_eq!(conditions.outcomes.len(), 1);
+        conditions.outcomes[0].clone()
+    }
+
+    #[test]
+    fn test_evaluate_conditions_file_exists_unmet() {
+        let (_temp, repo) = artifact_repo();
+        let outcome = only_outcome(
+            crate::config::EnabledCondition {
+                file_exists: Some("Cargo.toml".to_string()),
+                ..Default::default()
+            },
+            Some(&repo),
+            None,
+        );
+        assert!(!outcome.met);
+        assert_eq!(outcome.to_string(), "file_exists 'Cargo.toml': not found");
+    }
+
+    #[test]
+    fn test_evaluate_conditions_dir_exists_unmet() {
+        let (_temp, repo) = artifact_repo();
+        let outcome = only_outcome(
+            crate::config::EnabledCondition {
+                dir_exists: Some("frontend".to_string()),
+                ..Default::default()
+            },
+            Some(&repo),
+            None,
+        );
+        assert!(!outcome.met);
+        assert_eq!(outcome.to_string(), "dir_exists 'frontend': not found");
+    }
+
+    #[test]
+    fn test_evaluate_conditions_command_exists_unmet() {
+        let outcome = only_outcome(
+            crate::config::EnabledCondition {
+                command_exists: Some("definitely_not_a_command_12345".to_string()),
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+        assert!(!outcome.met);
+        assert_eq!(
+            outcome.to_string(),
+            "command_exists 'definitely_not_a_command_12345': not found in PATH"
+        );
+    }
+
+    #[test]
+    fn test_evaluate_conditions_file_absent_unmet() {
+        let (temp, repo) = artifact_repo();
+        std::fs::write(temp.path().join("legacy.cfg"), "").expect("write file");
+        let outcome = only_outcome(
+            crate::config::EnabledCondition {
+                file_absent: Some("legacy.cfg".to_string()),
+                ..Default::default()
+            },
+            Some(&repo),
+            None,
+        );
+        assert!(!outcome.met);
+        assert_eq!(outcome.to_string(), "file_absent 'legacy.cfg': exists");
+    }
+
+    #[test]
+    fn test_evaluate_conditions_command_absent_unmet() {
+        let outcome = only_outcome(
+            crate::config::EnabledCondition {
+                command_absent: Some("sh".to_string()),
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+        assert!(!outcome.met);
+        assert_eq!(outcome.to_string(), "command_absent 'sh': found in PATH");
+    }
+
+    #[test]
+    fn test_evaluate_conditions_files_changed_unmet() {
+        let outcome = only_outcome(
+            crate::config::EnabledCondition {
+                files_changed: Some("**/*.py".to_string()),
+                ..Default::default()
+            },
+            None,
+            Some(false),
+        );
+        assert!(!outcome.met);
+        assert_eq!(
+            outcome.to_string(),
+            "files_changed '**/*.py': no changed files match"
+        );
+    }
+
+    #[test]
+    fn test_evaluate_conditions_without_repo_assumes_met() {
+        let outcome = only_outcome(
+            crate::config::EnabledCondition {
+                file_exists: Some("Cargo.toml".to_string()),
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+        assert!(outcome.met);
+        assert_eq!(outcome.detail, "not in a git repository, assumed met");
+    }
+
+    #[test]
+    fn test_evaluate_conditions_lists_only_unmet() {
+        let check = conditional_check(crate::config::EnabledCondition {
+            command_exists: Some("sh".to_string()),
+            command_absent: Some("sh".to_string()),
+            r#match: Some(ConditionMatch::Any),
+            ..Default::default()
+        });
+        let conditions = evaluate_conditions(&check, None, None);
+        assert!(conditions.enabled());
+        let unmet: Vec<_> = conditions.unmet().map(|o| o.condition).collect();
+        assert_eq!(unmet, ["command_absent"]);
+    }
+
+    #[test]
+    fn test_runner_explain_conditions_unknown_check() {
+        let runner = Runner::new(Config::default());
+        let err = runner
+            .explain_conditions("missing", Mode::Human)
+            .expect_err("unknown check");
+        assert!(matches!(err, Error::CheckNotFound { .. }));
+    }
+
+    #[test]
+    fn test_runner_resolve_check() {
+        let mut config = test_config_with_checks(vec![]);
+        config.human.timeout = "45s".to_string();
+        config
+            .globals
+            .env
+            .insert("PROFILE".to_string(), "release".to_string());
+        config.checks.insert(
+            "build".to_string(),
+            CheckConfig {
+                run: "cargo build --profile ${PROFILE} -j ${JOBS}".to_string(),
+                env: HashMap::from([
+                    ("JOBS".to_string(), "4".to_string()),
+                    ("CARGO_TERM_COLOR".to_string(), "never".to_string()),
+                ]),
+                ..Default::default()
+            },
+        );
+        let runner = Runner::new(config);
+
+        let resolved = runner
+            .resolve_check("build", Mode::Human)
+            .expect("resolve check");
+        assert_eq!(resolved.command, "cargo build --profile release -j 4");
+        assert_eq!(
+            resolved.env,
+            [
+                ("CARGO_TERM_COLOR".to_string(), "never".to_string()),
+                ("JOBS".to_string(), "4".to_string()),
+            ]
+        );
+        assert_eq!(resolved.timeout, Duration::from_secs(45));
+
+        let agent = runner
+            .resolve_check("build", Mode::Agent)
+            .expect("resolve check");
+        assert_eq!(agent.timeout, Duration::from_secs(15 * 60));
+
+        let err = runner
+            .resolve_check("missing", Mode::Human)
+            .expect_err("unknown check");
+        assert!(matches!(err, Error::CheckNotFound { .. }));
+    }
+
+    #[test]
+    fn test_plan_resolves_without_running() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let marker = temp.path().join("ran");
+        let mut config = test_config_with_checks(vec![
+            ("build", "cargo build", "human"),
+            ("touch", &format!("touch {}", marker.display()), "human"),
+            ("python", "ruff check .", "human"),
+        ]);
+        if let Some(check) = config.checks.get_mut("touch") {
+            check.needs = vec!["build".to_string()];
+        }
+        if let Some(check) = config.checks.get_mut("python") {
+            check.enabled_if = Some(crate::config::EnabledCondition {
+                command_exists: Some("apc-plan-test-missing-tool".to_string()),
+                ..Default::default()
+            });
+        }
+        config.human.checks = vec![
+            "touch".to_string(),
+            "python".to_string(),
+            "build".to_string(),
+        ];
+
+        let plan = Runner::new(config.clone()).plan(Mode::Human).expect("plan");
+        let names: Vec<&str> = plan.iter().map(|c| c.name.as_str()).collect();
+        // Dependencies come first, otherwise the mode's order is kept
+        assert_eq!(names, ["python", "build", "touch"]);
+        assert_eq!(plan[0].skip_reason.as_deref(), Some("Condition not met"));
+        assert_eq!(plan[1].command, "cargo build");
+        assert_eq!(plan[1].skip_reason, None);
+        assert!(!marker.exists());
+
+        let plan = Runner::new(config)
+            .ignore_conditions(true)
+            .with_only(vec!["python".to_string()])
+            .plan(Mode::Human)
+            .expect("plan");
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].skip_reason, None);
+    }
+
+    #[tokio::test]
+    async fn test_shuffle_same_seed_same_order() {
+        let names = ["a", "b", "c", "d", "e", "f"];
+        let mut config =
+            test_config_with_checks(names.iter().map(|n| (*n, "true", "human")).collect());
+        config.checks.insert(
+            "last".to_string(),
+            CheckConfig {
+                run: "true".to_string(),
+                needs: names.iter().map(ToString::to_string).collect(),
+                ..Default::default()
+            },
+        );
+        config.human.checks.insert(0, "last".to_string());
+        config.human.fail_fast = false;
+
+        let order = |runner: &Runner| -> Vec<String> {
+            runner
+                .plan(Mode::Human)
+                .expect("plan")
+                .into_iter()
+                .map(|p| p.name)
+                .collect()
+        };
+        let seeded = Runner::new(config.clone()).with_shuffle(42);
+        let first = order(&seeded);
+        assert_eq!(first, order(&Runner::new(config.clone()).with_shuffle(42)));
+        assert_ne!(first, order(&Runner::new(config.clone())));
+        assert_eq!(first.last().map(String::as_str), Some("last"));
+
+        let result = seeded.run(Mode::Human).await.expect("should complete");
+        let ran: Vec<String> = result.checks.into_iter().map(|c| c.name).collect();
+        assert_eq!(ran, first);
+    }
+
+    #[test]
+    fn test_auto_groups() {
+        let mut check_map = HashMap::new();
+        for (name, tags) in [
+            ("lint-a", &["lint"][..]),
+            ("test", &["test", "lint"][..]),
+            ("misc", &[][..]),
+            ("lint-b", &["lint"][..]),
+        ] {
+            check_map.insert(
+                name.to_string(),
+                CheckConfig {
+                    run: "true".to_string(),
+                    tags: tags.iter().map(ToString::to_string).collect(),
+                    ..Default::default()
+                },
+            );
+        }
+        let wave: Vec<String> = ["lint-a", "test", "misc", "lint-b"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        assert_eq!(
+            auto_groups(wave.clone(), AutoParallel::All, &check_map),
+            vec![wave.clone()]
+        );
+        assert_eq!(
+            auto_groups(wave.clone(), AutoParallel::None, &check_map).len(),
+            4
+        );
+        assert_eq!(
+            auto_groups(wave, AutoParallel::ByTag, &check_map),
+            [
+                vec!["lint-a".to_string(), "lint-b".to_string()],
+                vec!["test".to_string()],
+                vec!["misc".to_string()],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_auto_parallel_by_tag_runs_tags_in_turn() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let done = |name: &str| temp.path().join(name).display().to_string();
+        let mut config = test_config_with_checks(vec![]);
+        config.agent.auto_parallel = AutoParallel::ByTag;
+        config.agent.max_parallel = Some(3);
+        for (name, tag, run) in [
+            ("lint-a", "lint", format!("sleep 0.2; touch {}", done("a"))),
+            ("lint-b", "lint", format!("sleep 0.2; touch {}", done("b"))),
+            (
+                "test",
+                "test",
+                format!("test -f {} && test -f {}", done("a"), done("b")),
+            ),
+        ] {
+            config.agent.checks.push(name.to_string());
+            config.checks.insert(
+                name.to_string(),
+                CheckConfig {
+                    run,
+                    tags: vec![tag.to_string()],
+                    ..Default::default()
+                },
+            );
+        }
+
+        let result = Runner::new(config.clone())
+            .run(Mode::Agent)
+            .await
+            .expect("should complete");
+        assert!(result.success());
+
+        // Run together, the test check doesn't wait for the lints
+        for marker in ["a", "b"] {
+            std::fs::remove_file(temp.path().join(marker)).expect("remove marker");
+        }
+        config.agent.auto_parallel = AutoParallel::All;
+        let result = Runner::new(config)
+            .run(Mode::Agent)
+            .await
+            .expect("should complete");
+        assert!(!result.success());
+    }
+
+    #[test]
+    fn test_waves_follow_needs_and_groups() {
+        let mut config = test_config_with_checks(vec![
+            ("lint", "true", "both"),
+            ("unit", "true", "both"),
+            ("build", "true", "both"),
+        ]);
+        if let Some(build) = config.checks.get_mut("build") {
+            build.needs = vec!["lint".to_string()];
+        }
+        let names = |waves: &[&[&str]]| -> Vec<Vec<String>> {
+            waves
+                .iter()
+                .map(|w| w.iter().map(ToString::to_string).collect())
+                .collect()
+        };
+
+        let runner = Runner::new(config.clone());
+        assert_eq!(
+            runner.waves(Mode::Agent).expect("waves"),
+            names(&[&["lint", "unit"], &["build"]])
+        );
+        // Humans run one check at a time
+        assert_eq!(
+            runner.waves(Mode::Human).expect("waves"),
+            names(&[&["lint"], &["unit"], &["build"]])
+        );
+
+        config.agent.parallel_groups = vec![vec!["unit".to_string()].into()];
+        let runner = Runner::new(config);
+        assert_eq!(
+            runner.waves(Mode::Agent).expect("waves"),
+            names(&[&["unit"], &["lint", "build"]])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_per_agent_checks_run_for_detected_agent() {
+        let mut config = test_config_with_checks(vec![
+            ("lint", "echo lint", "agent"),
+            ("audit", "echo audit", "none"),
+        ]);
+        config.agent.per_agent.insert(
+            AgentKind::Devin,
+            PerAgentConfig {
+                checks: vec!["audit".to_string(), "lint".to_string()],
+            },
+        );
+        // Extras outside the configured groups still run
+        config.agent.parallel_groups = vec![vec!["lint".to_string()].into()];
+
+        let names = |result: RunResult| -> Vec<String> {
+            result.checks.into_iter().map(|c| c.name).collect()
+        };
+        let devin = Runner::new(config.clone()).with_agent(AgentKind::Devin);
+        let result = devin.run(Mode::Agent).await.expect("should complete");
+        assert_eq!(names(result), ["lint", "audit"]);
+        let result = devin.run(Mode::Human).await.expect("should complete");
+        assert!(result.checks.is_empty());
+
+        let cursor = Runner::new(config).with_agent(AgentKind::Cursor);
+        let result = cursor.run(Mode::Agent).await.expect("should complete");
+        assert_eq!(names(result), ["lint"]);
+    }
+
+    #[test]
+    fn test_plan_by_tags_and_single() {
+        let mut config = test_config_with_checks(vec![
+            ("lint", "cargo clippy", "agent"),
+            ("test", "cargo test", "agent"),
+        ]);
+        if let Some(check) = config.checks.get_mut("lint") {
+            check.tags = vec!["fast".to_string()];
+        }
+        let runner = Runner::new(config);
+
+        let plan = runner
+            .plan_by_tags(&["fast".to_string()], Mode::Human)
+            .expect("plan");
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].name, "lint");
+        assert!(runner
+            .plan_by_tags(&["slow".to_string()], Mode::Human)
+            .is_err());
+
+        let single = runner.plan_single("test", Mode::Agent).expect("plan");
+        assert_eq!(single.command, "cargo test");
+        assert!(matches!(
+            runner.plan_single("missing", Mode::Agent),
+            Err(Error::CheckNotFound { .. })
+        ));
+    }
+
+    // =========================================================================
+    // concurrency tests
+    // =========================================================================
+
+    #[test]
+    fn test_available_parallelism() {
+        let parallelism = concurrency::available_parallelism();
+        assert!(parallelism >= 1);
     }
 
     // =========================================================================
@@ -799,7 +2898,7 @@ mod tests {
                     run: cmd.to_string(),
                     description: name.to_string(),
                     enabled_if: None,
-                    env: HashMap::new(),
+                    ..Default::default()
                 },
             );
             match mode {
@@ -896,8 +2995,33 @@ mod tests {
         let result = runner.run_single("check1", Mode::Human).await;
         assert!(result.is_ok());
         let check_result = result.expect("should succeed");
-        assert!(check_result.passed);
+        assert_eq!(check_result.status, CheckStatus::Passed);
+        assert_eq!(check_result.name, "check1");
+    }
+
+    #[tokio::test]
+    async fn test_runner_run_single_by_alias() {
+        let mut config = test_config_with_checks(vec![
+            ("check1", "echo one", "human"),
+            ("check2", "echo two", "human"),
+        ]);
+        config
+            .checks
+            .get_mut("check1")
+            .expect("check exists")
+            .aliases = vec!["lint-old".to_string()];
+        let runner = Runner::new(config).with_only(vec!["lint-old".to_string()]);
+
+        let check_result = runner
+            .run_single("lint-old", Mode::Human)
+            .await
+            .expect("should succeed");
+        assert_eq!(check_result.status, CheckStatus::Passed);
         assert_eq!(check_result.name, "check1");
+
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        assert_eq!(result.checks.len(), 1);
+        assert_eq!(result.checks[0].name, "check1");
     }
 
     #[tokio::test]
@@ -942,6 +3066,7 @@ mod tests {
                 description: "env check".to_string(),
                 enabled_if: None,
                 env,
+                ..Default::default()
             },
         );
 
@@ -967,8 +3092,9 @@ mod tests {
                     command_exists: Some("definitely_not_a_real_command_99999".to_string()),
                     file_exists: None,
                     dir_exists: None,
+                    ..Default::default()
                 }),
-                env: HashMap::new(),
+                ..Default::default()
             },
         );
 
@@ -1001,49 +3127,1467 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_runner_records_duration() {
-        let config = test_config_with_checks(vec![("sleep-check", "sleep 0.1", "human")]);
-        let runner = Runner::new(config);
+    async fn test_runner_only_filters_mode_checks() {
+        let config = test_config_with_checks(vec![
+            ("lint", "echo lint", "human"),
+            ("test", "echo test", "human"),
+            ("build", "echo build", "human"),
+        ]);
+        let runner = Runner::new(config).with_only(vec!["build".to_string(), "lint".to_string()]);
 
         let result = runner.run(Mode::Human).await.expect("should succeed");
-        // Overall duration should be at least 100ms
-        assert!(result.duration >= Duration::from_millis(50));
-        // Check duration should also be recorded
-        assert!(result.checks[0].output.duration >= Duration::from_millis(50));
+        let names: Vec<_> = result.checks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["lint", "build"]);
     }
 
-    // =========================================================================
-    // get_checks_for_mode tests
-    // =========================================================================
-
-    #[test]
-    fn test_get_checks_for_mode_human() {
+    #[tokio::test]
+    async fn test_runner_only_rejects_check_outside_mode() {
         let config = test_config_with_checks(vec![
-            ("h-check", "echo h", "human"),
-            ("a-check", "echo a", "agent"),
+            ("human-only", "echo human", "human"),
+            ("agent-only", "echo agent", "agent"),
         ]);
-        let runner = Runner::new(config);
-        let checks = runner.get_checks_for_mode(Mode::Human);
-        assert_eq!(checks, vec!["h-check".to_string()]);
+        let runner = Runner::new(config).with_only(vec!["agent-only".to_string()]);
+
+        let err = runner.run(Mode::Human).await.expect_err("should fail");
+        assert!(err
+            .to_string()
+            .contains("'agent-only' is not a human mode check"));
     }
 
-    #[test]
-    fn test_get_checks_for_mode_agent() {
-        let config = test_config_with_checks(vec![
-            ("h-check", "echo h", "human"),
-            ("a-check", "echo a", "agent"),
+    #[tokio::test]
+    async fn test_runner_ignore_conditions_runs_disabled_check() {
+        let mut config = test_config_with_checks(vec![("conditional", "echo ran", "human")]);
+        if let Some(check) = config.checks.get_mut("conditional") {
+            check.enabled_if = Some(crate::config::EnabledCondition {
+                command_exists: Some("definitely_not_a_real_command_99999".to_string()),
+                file_exists: None,
+                dir_exists: None,
+                ..Default::default()
+            });
+        }
+
+        let result = Runner::new(config)
+            .ignore_conditions(true)
+            .run(Mode::Human)
+            .await
+            .expect("should succeed");
+        assert_eq!(result.passed_count(), 1);
+        assert_eq!(result.skipped_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_runner_check_timeout_overrides_mode_timeout() {
+        let mut config = test_config_with_checks(vec![
+            ("fast", "sleep 5", "human"),
+            ("slow", "sleep 0.5", "human"),
         ]);
-        let runner = Runner::new(config);
-        let checks = runner.get_checks_for_mode(Mode::Agent);
-        assert_eq!(checks, vec!["a-check".to_string()]);
+        config.human.timeout = "300ms".to_string();
+        config.human.fail_fast = false;
+        if let Some(check) = config.checks.get_mut("fast") {
+            check.timeout = Some("100ms".to_string());
+        }
+        if let Some(check) = config.checks.get_mut("slow") {
+            check.timeout = Some("10s".to_string());
+        }
+
+        let result = Runner::new(config)
+            .run(Mode::Human)
+            .await
+            .expect("should complete");
+        assert!(result.checks[0].output.timed_out);
+        assert!(result.checks[0].output.duration < Duration::from_millis(300));
+        assert_eq!(result.checks[1].status, CheckStatus::Passed);
     }
 
-    #[test]
-    fn test_get_checks_for_mode_ci_uses_agent_checks() {
-        let config = test_config_with_checks(vec![("a-check", "echo a", "agent")]);
+    #[tokio::test]
+    async fn test_runner_run_by_tags_crosses_modes() {
+        let mut config = test_config_with_checks(vec![
+            ("lint", "true", "human"),
+            ("unit", "true", "agent"),
+            ("e2e", "true", "agent"),
+        ]);
+        for (name, tags) in [
+            ("lint", &["fast"][..]),
+            ("unit", &["fast", "test"]),
+            ("e2e", &["slow"]),
+        ] {
+            if let Some(check) = config.checks.get_mut(name) {
+                check.tags = tags.iter().map(|t| (*t).to_string()).collect();
+            }
+        }
         let runner = Runner::new(config);
-        // CI mode uses the same checks as Agent mode
-        let checks = runner.get_checks_for_mode(Mode::Ci);
-        assert_eq!(checks, vec!["a-check".to_string()]);
+
+        let result = runner
+            .run_by_tags(&["fast".to_string()], Mode::Human)
+            .await
+            .expect("should complete");
+        let names: Vec<_> = result.checks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["lint", "unit"]);
+
+        let err = runner
+            .run_by_tags(&["nightly".to_string()], Mode::Human)
+            .await
+            .expect_err("no tagged checks");
+        assert!(err.to_string().contains("No checks are tagged 'nightly'"));
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_runner_uses_mode_default_shell() {
+        if !Executor::command_exists("bash") {
+            return;
+        }
+        let mut config = test_config_with_checks(vec![
+            ("bash", "test \"$0\" = bash", "human"),
+            ("sh", "test \"$0\" = sh", "human"),
+        ]);
+        config.human.default_shell = Some("bash".to_string());
+        if let Some(check) = config.checks.get_mut("sh") {
+            check.shell = Some("sh".to_string());
+        }
+
+        let result = Runner::new(config)
+            .run(Mode::Human)
+            .await
+            .expect("should complete");
+        assert!(result
+            .checks
+            .iter()
+            .all(|c| c.status == CheckStatus::Passed));
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_runner_uses_globals_shell() {
+        if !Executor::command_exists("bash") {
+            return;
+        }
+        let mut config = test_config_with_checks(vec![
+            ("bash", "test \"$0\" = bash", "both"),
+            ("sh", "test \"$0\" = sh", "agent"),
+        ]);
+        config.globals.shell = Some("bash".to_string());
+        config.agent.default_shell = Some("sh".to_string());
+        if let Some(check) = config.checks.get_mut("bash") {
+            check.shell = Some("bash".to_string());
+        }
+
+        let runner = Runner::new(config);
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        assert!(result
+            .checks
+            .iter()
+            .all(|c| c.status == CheckStatus::Passed));
+        let result = runner.run(Mode::Agent).await.expect("should complete");
+        assert!(result
+            .checks
+            .iter()
+            .all(|c| c.status == CheckStatus::Passed));
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_runner_uses_check_shell() {
+        if !Executor::command_exists("bash") {
+            return;
+        }
+        // `[[` is a bashism that a POSIX sh such as dash rejects
+        let mut config =
+            test_config_with_checks(vec![("bashism", "[[ -n \"$BASH_VERSION\" ]]", "human")]);
+        if let Some(check) = config.checks.get_mut("bashism") {
+            check.shell = Some("bash".to_string());
+        }
+
+        let result = Runner::new(config)
+            .run(Mode::Human)
+            .await
+            .expect("should complete");
+        assert_eq!(result.checks[0].status, CheckStatus::Passed);
+    }
+
+    #[tokio::test]
+    async fn test_runner_with_timeout_overrides_config_timeouts() {
+        let mut config = test_config_with_checks(vec![("slow", "sleep 0.5", "human")]);
+        config.human.timeout = "100ms".to_string();
+        if let Some(check) = config.checks.get_mut("slow") {
+            check.timeout = Some("100ms".to_string());
+        }
+
+        let result = Runner::new(config.clone())
+            .run(Mode::Human)
+            .await
+            .expect("should complete");
+        assert!(result.checks[0].output.timed_out);
+
+        let result = Runner::new(config)
+            .with_timeout(Duration::from_secs(10))
+            .run(Mode::Human)
+            .await
+            .expect("should complete");
+        assert_eq!(result.checks[0].status, CheckStatus::Passed);
+    }
+
+    fn add_needs(config: &mut Config, name: &str, needs: &[&str]) {
+        if let Some(check) = config.checks.get_mut(name) {
+            check.needs = needs.iter().map(|n| (*n).to_string()).collect();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runner_needs_runs_dependencies_first() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let marker = temp.path().join("clippy-done");
+        let mut config = test_config_with_checks(vec![
+            ("build", &format!("test -f '{}'", marker.display()), "agent"),
+            (
+                "clippy",
+                &format!("sleep 0.2 && touch '{}'", marker.display()),
+                "agent",
+            ),
+        ]);
+        add_needs(&mut config, "build", &["clippy"]);
+
+        let result = Runner::new(config).run(Mode::Agent).await.expect("run");
+        let names: Vec<_> = result.checks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["clippy", "build"]);
+        assert!(result.success());
+    }
+
+    #[tokio::test]
+    async fn test_runner_needs_skips_dependents_of_failed_check() {
+        let mut config = test_config_with_checks(vec![
+            ("clippy", "exit 1", "agent"),
+            ("build", "echo build", "agent"),
+            ("e2e", "echo e2e", "agent"),
+            ("fmt", "echo fmt", "agent"),
+        ]);
+        config.agent.fail_fast = FailFast::Off;
+        add_needs(&mut config, "build", &["clippy"]);
+        add_needs(&mut config, "e2e", &["build"]);
+
+        let result = Runner::new(config).run(Mode::Agent).await.expect("run");
+        let by_name: HashMap<_, _> = result.checks.iter().map(|c| (c.name.as_str(), c)).collect();
+
+        assert_eq!(by_name["clippy"].status, CheckStatus::Failed);
+        assert_eq!(by_name["fmt"].status, CheckStatus::Passed);
+        assert_eq!(by_name["build"].status, CheckStatus::Skipped);
+        assert_eq!(
+            by_name["build"].skip_reason.as_deref(),
+            Some("Dependency 'clippy' failed")
+        );
+        assert_eq!(
+            by_name["e2e"].skip_reason.as_deref(),
+            Some("Dependency 'build' failed")
+        );
+    }
+
+    /// Config with two agent checks that fail if they ever run at the same time.
+    fn exclusive_checks_config(lock: &Path) -> Config {
+        let run = format!("mkdir '{0}' && sleep 0.3 && rmdir '{0}'", lock.display());
+        let mut config = test_config_with_checks(vec![("a", &run, "agent"), ("b", &run, "agent")]);
+        config.agent.parallel_groups = vec![vec!["a".to_string(), "b".to_string()].into()];
+        config
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_runner_group_max_parallel_limits_concurrency() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let lock = temp.path().join("lock");
+
+        // Only observable when the global cap lets the checks overlap
+        if concurrency::available_parallelism() > 1 {
+            let config = exclusive_checks_config(&lock);
+            let result = Runner::new(config)
+                .run(Mode::Agent)
+                .await
+                .expect("should complete");
+            assert_eq!(result.failed_count(), 1, "checks should overlap uncapped");
+        }
+
+        let mut config = exclusive_checks_config(&lock);
+        config.agent.parallel_groups = vec![ParallelGroup::Limited {
+            checks: vec!["a".to_string(), "b".to_string()],
+            max_parallel: 1,
+        }];
+        let result = Runner::new(config)
+            .run(Mode::Agent)
+            .await
+            .expect("should complete");
+        assert_eq!(result.passed_count(), 2);
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_runner_with_group_parallel_sets_default_cap() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let config = exclusive_checks_config(&temp.path().join("lock"));
+
+        let result = Runner::new(config)
+            .with_group_parallel(1)
+            .run(Mode::Agent)
+            .await
+            .expect("should complete");
+        assert_eq!(result.passed_count(), 2);
+    }
+
+    #[test]
+    fn test_max_parallel_resolution() {
+        let cpus = concurrency::available_parallelism();
+        let config = Config::default();
+        let runner = Runner::new(config.clone());
+        assert_eq!(runner.max_parallel(Mode::Human), 1);
+        assert_eq!(runner.max_parallel(Mode::Agent), cpus);
+        assert_eq!(runner.max_parallel(Mode::Ci), cpus);
+
+        let mut configured = config;
+        configured.human.max_parallel = Some(3);
+        configured.agent.max_parallel = Some(2);
+        let runner = Runner::new(configured.clone());
+        assert_eq!(runner.max_parallel(Mode::Human), 3);
+        assert_eq!(runner.max_parallel(Mode::Agent), 2);
+        assert_eq!(runner.max_parallel(Mode::Ci), 2);
+
+        // The flag wins over both mode config and defaults
+        let runner = Runner::new(configured).with_max_parallel(5);
+        assert_eq!(runner.max_parallel(Mode::Human), 5);
+        assert_eq!(runner.max_parallel(Mode::Agent), 5);
+    }
+
+    /// Two checks that each wait for the other to start, so they only pass
+    /// when run concurrently.
+    fn rendezvous_config(dir: &Path, mode: &str) -> Config {
+        let run = |own: &str, other: &str| {
+            format!(
+                "touch '{0}/{own}' && for i in $(seq 100); do [ -f '{0}/{other}' ] && exit 0; sleep 0.02; done; exit 1",
+                dir.display()
+            )
+        };
+        test_config_with_checks(vec![
+            ("a", &run("a", "b"), mode),
+            ("b", &run("b", "a"), mode),
+        ])
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_runner_human_max_parallel_runs_checks_concurrently() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let mut config = rendezvous_config(temp.path(), "human");
+        config.human.max_parallel = Some(2);
+
+        let result = Runner::new(config)
+            .run(Mode::Human)
+            .await
+            .expect("should complete");
+        assert_eq!(result.passed_count(), 2);
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_runner_agent_max_parallel_one_runs_checks_in_turn() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let config = rendezvous_config(temp.path(), "agent");
+
+        let result = Runner::new(config)
+            .with_max_parallel(1)
+            .run(Mode::Agent)
+            .await
+            .expect("should complete");
+        assert_eq!(result.checks.len(), 2);
+        assert_eq!(result.failed_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_runner_parallel_results_in_planned_order() {
+        let mut config = test_config_with_checks(vec![
+            ("slow", "sleep 0.3", "agent"),
+            ("first", "echo first", "agent"),
+            ("blocked", "echo blocked", "agent"),
+            ("failing", "exit 1", "agent"),
+        ]);
+        config.checks.get_mut("blocked").expect("check").needs = vec!["failing".to_string()];
+        config.agent.parallel_groups = vec![
+            vec!["failing".to_string(), "first".to_string()].into(),
+            vec!["slow".to_string(), "blocked".to_string()].into(),
+        ];
+
+        // `blocked` is settled before `slow` finishes, but is listed after it
+        let result = Runner::new(config).run(Mode::Agent).await.expect("run");
+        let names: Vec<&str> = result.checks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["failing", "first", "slow", "blocked"]);
+    }
+
+    fn fail_fast_config(fail_fast: FailFast) -> Config {
+        let mut config = test_config_with_checks(vec![
+            ("lint", "exit 1", "agent"),
+            ("slow", "sleep 0.5", "agent"),
+            ("later", "echo later", "agent"),
+        ]);
+        config.agent.parallel_groups = vec![
+            vec!["lint".to_string(), "slow".to_string()].into(),
+            vec!["later".to_string()].into(),
+        ];
+        config.agent.fail_fast = fail_fast;
+        config
+    }
+
+    #[tokio::test]
+    async fn test_runner_group_fail_fast_cancels_group_only() {
+        let config = fail_fast_config(FailFast::Group);
+
+        let result = Runner::new(config).run(Mode::Agent).await.expect("run");
+        let by_name: HashMap<_, _> = result.checks.iter().map(|c| (c.name.as_str(), c)).collect();
+
+        assert!(!result.success());
+        assert_eq!(by_name["lint"].status, CheckStatus::Failed);
+        assert_eq!(by_name["slow"].status, CheckStatus::Cancelled);
+        assert_eq!(
+            by_name["slow"].skip_reason.as_deref(),
+            Some("Cancelled after a failure in its group")
+        );
+        assert_eq!(by_name["later"].status, CheckStatus::Passed);
+    }
+
+    #[tokio::test]
+    async fn test_runner_run_fail_fast_stops_later_groups() {
+        let config = fail_fast_config(FailFast::Run);
+
+        let result = Runner::new(config).run(Mode::Agent).await.expect("run");
+        let names: Vec<_> = result.checks.iter().map(|c| c.name.as_str()).collect();
+
+        assert_eq!(names, vec!["lint", "slow"]);
+        assert_eq!(result.checks[1].status, CheckStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_runner_run_fail_fast_kills_running_checks() {
+        let mut config = fail_fast_config(FailFast::Run);
+        config.agent.max_parallel = Some(2);
+        if let Some(check) = config.checks.get_mut("lint") {
+            check.run = "sleep 0.2; exit 1".to_string();
+        }
+        if let Some(check) = config.checks.get_mut("slow") {
+            check.run = "sleep 30".to_string();
+        }
+
+        let start = std::time::Instant::now();
+        let result = Runner::new(config).run(Mode::Agent).await.expect("run");
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(!result.success());
+        assert!(result
+            .checks
+            .iter()
+            .any(|c| c.name == "slow" && c.status == CheckStatus::Cancelled));
+    }
+
+    #[test]
+    fn test_cancel_group_on_failure_keeps_own_failure() {
+        let group = CancellationToken::new();
+        group.cancel();
+
+        let result = cancel_group_on_failure(make_failed_check("lint"), &group, None);
+        assert_eq!(result.status, CheckStatus::Failed);
+        assert_eq!(result.output.stderr, "Error");
+
+        let killed = CheckResult {
+            status: CheckStatus::Aborted,
+            ..make_failed_check("slow")
+        };
+        let result = cancel_group_on_failure(killed, &group, None);
+        assert_eq!(result.status, CheckStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_runner_group_fail_fast_skips_dependents_of_cancelled() {
+        let mut config = fail_fast_config(FailFast::Group);
+        add_needs(&mut config, "later", &["slow"]);
+
+        let result = Runner::new(config).run(Mode::Agent).await.expect("run");
+        let later = result
+            .checks
+            .iter()
+            .find(|c| c.name == "later")
+            .expect("later");
+        assert_eq!(
+            later.skip_reason.as_deref(),
+            Some("Dependency 'slow' failed")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_runner_needs_in_sequential_mode() {
+        let mut config = test_config_with_checks(vec![
+            ("build", "echo build", "human"),
+            ("clippy", "exit 1", "human"),
+        ]);
+        config.human.fail_fast = false;
+        add_needs(&mut config, "build", &["clippy"]);
+
+        let result = Runner::new(config).run(Mode::Human).await.expect("run");
+        let names: Vec<_> = result.checks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["clippy", "build"]);
+        assert_eq!(result.checks[1].status, CheckStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_runner_allow_failure_does_not_fail_fast() {
+        let mut config = test_config_with_checks(vec![
+            ("coverage", "exit 1", "human"),
+            ("lint", "echo lint", "human"),
+        ]);
+        if let Some(check) = config.checks.get_mut("coverage") {
+            check.allow_failure = true;
+        }
+
+        let result = Runner::new(config).run(Mode::Human).await.expect("run");
+        assert_eq!(result.checks.len(), 2);
+        assert_eq!(result.checks[0].status, CheckStatus::Failed);
+        assert!(!result.checks[0].blocking);
+        assert_eq!(result.checks[1].status, CheckStatus::Passed);
+        assert!(result.success());
+    }
+
+    #[tokio::test]
+    async fn test_runner_expect_failure_inverts_result() {
+        let mut config = test_config_with_checks(vec![
+            ("rejects", "echo 'bad input' >&2; exit 2", "human"),
+            ("accepts", "true", "human"),
+            ("slow", "sleep 5", "human"),
+        ]);
+        config.human.fail_fast = false;
+        config.human.timeout = "200ms".to_string();
+        for check in config.checks.values_mut() {
+            check.expect_failure = true;
+        }
+
+        let result = Runner::new(config).run(Mode::Human).await.expect("run");
+        let by_name: HashMap<_, _> = result.checks.iter().map(|c| (c.name.as_str(), c)).collect();
+        assert_eq!(by_name["rejects"].status, CheckStatus::Passed);
+        assert_eq!(by_name["accepts"].status, CheckStatus::Failed);
+        assert!(by_name["slow"].output.timed_out);
+        assert_eq!(by_name["slow"].status, CheckStatus::Failed);
+        assert!(!result.success());
+    }
+
+    #[tokio::test]
+    async fn test_runner_retries_until_success() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let marker = temp.path().join("attempted");
+        let command = format!(
+            "test -f '{0}' || {{ touch '{0}'; exit 1; }}",
+            marker.display()
+        );
+        let mut config = test_config_with_checks(vec![("flaky", &command, "human")]);
+        if let Some(check) = config.checks.get_mut("flaky") {
+            check.retries = 2;
+            check.retry_delay = Some("10ms".to_string());
+        }
+
+        let result = Runner::new(config).run(Mode::Human).await.expect("run");
+        assert_eq!(result.checks[0].status, CheckStatus::Passed);
+        assert_eq!(result.checks[0].attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn test_runner_retries_exhausted() {
+        let mut config = test_config_with_checks(vec![("broken", "exit 3", "human")]);
+        if let Some(check) = config.checks.get_mut("broken") {
+            check.retries = 2;
+        }
+
+        let result = Runner::new(config).run(Mode::Human).await.expect("run");
+        assert_eq!(result.checks[0].status, CheckStatus::Failed);
+        assert_eq!(result.checks[0].attempts, 3);
+        assert_eq!(result.checks[0].output.exit_code, 3);
+    }
+
+    #[tokio::test]
+    async fn test_runner_retry_delay_stops_on_cancellation() {
+        let mut config = test_config_with_checks(vec![("broken", "exit 3", "human")]);
+        if let Some(check) = config.checks.get_mut("broken") {
+            check.retries = 2;
+            check.retry_delay = Some("30s".to_string());
+        }
+        let token = CancellationToken::new();
+        let runner = Runner::new(config).with_cancellation(token.clone());
+
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            token.cancel();
+        });
+
+        let start = std::time::Instant::now();
+        let result = runner.run(Mode::Human).await.expect("run");
+        canceller.await.expect("canceller task");
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(result.checks[0].status, CheckStatus::Aborted);
+        assert_eq!(result.checks[0].attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_runner_does_not_retry_timeouts() {
+        let mut config = test_config_with_checks(vec![("slow", "sleep 5", "human")]);
+        if let Some(check) = config.checks.get_mut("slow") {
+            check.timeout = Some("100ms".to_string());
+            check.retries = 2;
+        }
+
+        let result = Runner::new(config).run(Mode::Human).await.expect("run");
+        assert!(result.checks[0].output.timed_out);
+        assert_eq!(result.checks[0].attempts, 1);
+    }
+
+    #[test]
+    fn test_interpolate_check_resolution_order() {
+        let mut globals = GlobalsConfig::default();
+        globals.env.insert("DIR".to_string(), "global".to_string());
+        globals.env.insert("NAME".to_string(), "app".to_string());
+
+        let check = CheckConfig {
+            run: "ls ${DIR}/${NAME} $HOME".to_string(),
+            env: HashMap::from([("DIR".to_string(), "${NAME}-local".to_string())]),
+            ..Default::default()
+        };
+
+        let (run, env) = interpolate_check("build", &check, &globals).expect("interpolate");
+        assert_eq!(run, "ls app-local/app $HOME");
+        assert_eq!(env, vec![("DIR".to_string(), "app-local".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_runner_expands_globals_in_command() {
+        let mut config =
+            test_config_with_checks(vec![("globals", "test '${TARGET}' = out/debug", "human")]);
+        config
+            .globals
+            .env
+            .insert("TARGET".to_string(), "out/debug".to_string());
+
+        let result = Runner::new(config).run(Mode::Human).await.expect("run");
+        assert!(result.success());
+    }
+
+    #[tokio::test]
+    async fn test_runner_records_duration() {
+        let config = test_config_with_checks(vec![("sleep-check", "sleep 0.1", "human")]);
+        let runner = Runner::new(config);
+
+        let result = runner.run(Mode::Human).await.expect("should succeed");
+        // Overall duration should be at least 100ms
+        assert!(result.duration >= Duration::from_millis(50));
+        // Check duration should also be recorded
+        assert!(result.checks[0].output.duration >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_runner_expands_files_placeholder() {
+        let config = test_config_with_checks(vec![(
+            "files-check",
+            "test {files} = '/outside/my file.txt'",
+            "human",
+        )]);
+        let runner = Runner::new(config).with_files(vec![PathBuf::from("/outside/my file.txt")]);
+
+        let result = runner.run(Mode::Human).await.expect("should succeed");
+        assert!(result.success());
+    }
+
+    #[tokio::test]
+    async fn test_runner_files_placeholder_in_parallel_mode() {
+        let config = test_config_with_checks(vec![(
+            "files-check",
+            "test \"{files}\" = '/outside/a.rs /outside/b.rs'",
+            "agent",
+        )]);
+        let runner = Runner::new(config).with_files(vec![
+            PathBuf::from("/outside/a.rs"),
+            PathBuf::from("/outside/b.rs"),
+        ]);
+
+        let result = runner.run(Mode::Agent).await.expect("should succeed");
+        assert!(result.success());
+    }
+
+    #[tokio::test]
+    async fn test_runner_cancelled_skips_remaining_checks() {
+        let config = test_config_with_checks(vec![
+            ("first", "echo one", "human"),
+            ("second", "echo two", "human"),
+        ]);
+        let token = CancellationToken::new();
+        token.cancel();
+        let runner = Runner::new(config).with_cancellation(token);
+
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        assert_eq!(result.checks.len(), 1);
+        assert_eq!(result.checks[0].status, CheckStatus::Aborted);
+        assert_eq!(result.checks[0].skip_reason.as_deref(), Some("Aborted"));
+    }
+
+    #[tokio::test]
+    async fn test_runner_cancellation_kills_running_check() {
+        let config = test_config_with_checks(vec![("slow", "sleep 30", "agent")]);
+        let token = CancellationToken::new();
+        let runner = Runner::new(config).with_cancellation(token.clone());
+
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            token.cancel();
+        });
+
+        let start = std::time::Instant::now();
+        let result = runner.run(Mode::Agent).await.expect("should complete");
+        canceller.await.expect("canceller task");
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(!result.success());
+        assert_eq!(result.checks[0].status, CheckStatus::Aborted);
+        assert_eq!(result.checks[0].output.exit_code, 130);
+    }
+
+    #[test]
+    fn test_parse_skip_checks() {
+        let names = parse_skip_checks(" lint, test-unit ,,");
+        assert_eq!(names.len(), 2);
+        assert!(names.contains("lint"));
+        assert!(names.contains("test-unit"));
+        assert!(parse_skip_checks("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_runner_skip_checks_by_name_or_alias() {
+        let mut config = test_config_with_checks(vec![
+            ("lint", "exit 1", "human"),
+            ("flaky", "exit 1", "human"),
+            ("test", "echo ok", "human"),
+        ]);
+        config.checks.get_mut("flaky").expect("check").aliases = vec!["e2e".to_string()];
+        let runner = Runner::new(config);
+        let ctx = CheckContext {
+            skip_checks: parse_skip_checks("lint,e2e"),
+            ..runner.context(Mode::Human)
+        };
+
+        for name in ["lint", "flaky"] {
+            let check = &runner.config().checks[name];
+            let result = run_check_async(name, check, &ctx).await.expect("run");
+            assert_eq!(result.status, CheckStatus::Skipped);
+            assert_eq!(
+                result.skip_reason.as_deref(),
+                Some("skipped via APC_SKIP_CHECKS")
+            );
+        }
+        let check = &runner.config().checks["test"];
+        let result = run_check_async("test", check, &ctx).await.expect("run");
+        assert_eq!(result.status, CheckStatus::Passed);
+    }
+
+    #[test]
+    fn test_run_result_slowest() {
+        let check = |name: &str, millis: u64, skipped: bool| CheckResult {
+            status: if skipped {
+                CheckStatus::Skipped
+            } else {
+                CheckStatus::Passed
+            },
+            output: CommandOutput {
+                duration: Duration::from_millis(millis),
+                ..CheckResult::skipped(name.to_string(), String::new()).output
+            },
+            ..CheckResult::skipped(name.to_string(), String::new())
+        };
+        let result = RunResult {
+            mode: Mode::Agent,
+            checks: vec![
+                check("lint", 200, false),
+                check("test", 900, false),
+                check("cached", 5000, true),
+                check("fmt", 50, false),
+            ],
+            duration: Duration::from_secs(1),
+        };
+
+        let names: Vec<&str> = result.slowest(2).iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["test", "lint"]);
+        assert_eq!(result.slowest(10).len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_runner_total_timeout_stops_run() {
+        let mut config = test_config_with_checks(vec![
+            ("fast", "echo ok", "agent"),
+            ("slow", "sleep 30", "agent"),
+            ("later", "echo later", "agent"),
+        ]);
+        config.agent.total_timeout = Some("500ms".to_string());
+
+        let start = std::time::Instant::now();
+        let result = Runner::new(config)
+            .with_max_parallel(1)
+            .run(Mode::Agent)
+            .await
+            .expect("should complete");
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(!result.success());
+        let by_name: HashMap<_, _> = result.checks.iter().map(|c| (c.name.as_str(), c)).collect();
+        assert_eq!(by_name["fast"].status, CheckStatus::Passed);
+        for name in ["slow", "later"] {
+            let check = by_name[name];
+            assert_eq!(
+                check.status,
+                CheckStatus::TimedOutGroup,
+                "{name} should be timed out with the run"
+            );
+            assert!(check.output.stderr.contains("total_timeout of 500ms"));
+        }
+        // Stopped checks count as failed, never as skipped
+        assert_eq!((result.failed_count(), result.skipped_count()), (2, 0));
+    }
+
+    #[tokio::test]
+    async fn test_runner_total_timeout_keeps_own_exit_130() {
+        let mut config = test_config_with_checks(vec![
+            ("interrupted", "exit 130", "agent"),
+            ("slow", "sleep 30", "agent"),
+        ]);
+        config.agent.total_timeout = Some("500ms".to_string());
+
+        let result = Runner::new(config)
+            .with_max_parallel(1)
+            .run(Mode::Agent)
+            .await
+            .expect("should complete");
+
+        let by_name: HashMap<_, _> = result.checks.iter().map(|c| (c.name.as_str(), c)).collect();
+        let interrupted = by_name["interrupted"];
+        assert_eq!(interrupted.status, CheckStatus::Failed);
+        assert_eq!(interrupted.output.exit_code, 130);
+        assert!(!interrupted.output.stderr.contains("total_timeout"));
+        assert_eq!(by_name["slow"].status, CheckStatus::TimedOutGroup);
+    }
+
+    #[tokio::test]
+    async fn test_runner_total_timeout_ignored_in_human_mode() {
+        let mut config = test_config_with_checks(vec![("slow", "sleep 0.3", "human")]);
+        config.agent.total_timeout = Some("10ms".to_string());
+
+        let result = Runner::new(config)
+            .run(Mode::Human)
+            .await
+            .expect("should complete");
+        assert!(result.success());
+    }
+
+    fn artifact_check(run: &str, always: bool) -> CheckConfig {
+        CheckConfig {
+            run: run.to_string(),
+            artifacts: vec!["logs/*.log".to_string()],
+            artifacts_always: always,
+            ..Default::default()
+        }
+    }
+
+    fn artifact_repo() -> (tempfile::TempDir, GitRepo) {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(temp.path())
+            .output()
+            .expect("init repo");
+        let repo = GitRepo::discover_from(temp.path()).expect("discover repo");
+        (temp, repo)
+    }
+
+    fn files_changed_config(pattern: &str) -> Config {
+        let mut config = test_config_with_checks(vec![("python", "echo ran", "both")]);
+        if let Some(check) = config.checks.get_mut("python") {
+            check.enabled_if = Some(crate::config::EnabledCondition {
+                files_changed: Some(pattern.to_string()),
+                ..Default::default()
+            });
+        }
+        config
+    }
+
+    #[tokio::test]
+    async fn test_files_changed_matches_staged_files() {
+        let (temp, repo) = artifact_repo();
+        std::fs::create_dir_all(temp.path().join("pkg")).expect("create dir");
+        std::fs::write(temp.path().join("pkg/app.py"), "").expect("write file");
+        std::fs::write(temp.path().join("main.rs"), "").expect("write file");
+
+        let runner = Runner::with_repo(files_changed_config("**/*.py"), repo.clone());
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        assert_eq!(result.skipped_count(), 1);
+
+        std::process::Command::new("git")
+            .args(["add", "pkg/app.py"])
+            .current_dir(temp.path())
+            .output()
+            .expect("stage file");
+        let runner = Runner::with_repo(files_changed_config("**/*.py"), repo);
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        assert_eq!(result.passed_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_files_changed_matches_working_tree_in_agent_mode() {
+        let (temp, repo) = artifact_repo();
+        std::fs::write(temp.path().join("app.py"), "").expect("write file");
+
+        let runner = Runner::with_repo(files_changed_config("**/*.py"), repo.clone());
+        let result = runner.run(Mode::Agent).await.expect("should complete");
+        assert_eq!(result.passed_count(), 1);
+
+        let runner = Runner::with_repo(files_changed_config("*.go"), repo);
+        let result = runner.run(Mode::Agent).await.expect("should complete");
+        assert_eq!(result.skipped_count(), 1);
+    }
+
+    fn only_changed_config(only_changed: bool) -> Config {
+        let mut config = test_config_with_checks(vec![
+            ("cargo-test", "echo ran", "human"),
+            ("secrets", "echo ran", "human"),
+        ]);
+        config.human.only_changed = only_changed;
+        if let Some(check) = config.checks.get_mut("cargo-test") {
+            check.paths = vec!["src/**".to_string(), "Cargo.*".to_string()];
+        }
+        config
+    }
+
+    fn stage(temp: &tempfile::TempDir, path: &str) {
+        let file = temp.path().join(path);
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent).expect("create dir");
+        }
+        std::fs::write(&file, "").expect("write file");
+        std::process::Command::new("git")
+            .args(["add", path])
+            .current_dir(temp.path())
+            .output()
+            .expect("stage file");
+    }
+
+    #[tokio::test]
+    async fn test_only_changed_skips_checks_without_matching_staged_files() {
+        let (temp, repo) = artifact_repo();
+        stage(&temp, "README.md");
+
+        let runner = Runner::with_repo(only_changed_config(true), repo.clone());
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        let cargo_test = result
+            .checks
+            .iter()
+            .find(|c| c.name == "cargo-test")
+            .expect("cargo-test result");
+        assert_eq!(cargo_test.status, CheckStatus::Skipped);
+        assert_eq!(
+            cargo_test.skip_reason.as_deref(),
+            Some("No changed files match its paths")
+        );
+        // Checks without paths always run
+        assert_eq!(result.passed_count(), 1);
+
+        stage(&temp, "src/lib.rs");
+        let runner = Runner::with_repo(only_changed_config(true), repo);
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        assert_eq!(result.passed_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_apcignore_excludes_staged_files() {
+        let (temp, repo) = artifact_repo();
+        stage(&temp, "src/generated/schema.rs");
+        stage(&temp, "src/lib.rs");
+        std::fs::write(temp.path().join(".apcignore"), "src/\n").expect("write ignore file");
+
+        let mut config = only_changed_config(true);
+        config.human.checks.push("files".to_string());
+        config.checks.insert(
+            "files".to_string(),
+            CheckConfig::from_command("echo \"[{files}]\"".to_string()),
+        );
+
+        let runner = Runner::with_repo(config.clone(), repo.clone());
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        let by_name: HashMap<_, _> = result.checks.iter().map(|c| (c.name.as_str(), c)).collect();
+        assert_eq!(by_name["cargo-test"].status, CheckStatus::Skipped);
+        assert_eq!(by_name["files"].output.stdout.trim_end(), "[]");
+
+        std::fs::write(temp.path().join(".apcignore"), "src/generated/\n")
+            .expect("write ignore file");
+        let runner = Runner::with_repo(config, repo);
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        let by_name: HashMap<_, _> = result.checks.iter().map(|c| (c.name.as_str(), c)).collect();
+        assert!(!by_name["cargo-test"].status.is_skip());
+        assert_eq!(by_name["files"].output.stdout.trim_end(), "[src/lib.rs]");
+    }
+
+    #[tokio::test]
+    async fn test_only_changed_off_or_outside_human_mode_runs_everything() {
+        let (temp, repo) = artifact_repo();
+        stage(&temp, "README.md");
+
+        let runner = Runner::with_repo(only_changed_config(false), repo.clone());
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        assert_eq!(result.passed_count(), 2);
+
+        let mut config = only_changed_config(true);
+        config.agent.checks = vec!["cargo-test".to_string()];
+        let runner = Runner::with_repo(config, repo.clone());
+        let result = runner.run(Mode::Agent).await.expect("should complete");
+        assert_eq!(result.passed_count(), 1);
+
+        let runner = Runner::with_repo(only_changed_config(true), repo).ignore_conditions(true);
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        assert_eq!(result.passed_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_only_changed_builder_filters_agent_runs_by_given_files() {
+        let (temp, repo) = artifact_repo();
+        let mut config = only_changed_config(false);
+        config.agent.checks = vec!["cargo-test".to_string()];
+
+        let runner = Runner::with_repo(config.clone(), repo.clone())
+            .with_files(vec![temp.path().join("README.md")])
+            .only_changed(true);
+        let result = runner.run(Mode::Agent).await.expect("should complete");
+        assert_eq!(result.checks[0].status, CheckStatus::Skipped);
+
+        let runner = Runner::with_repo(config, repo)
+            .with_files(vec![temp.path().join("src/lib.rs")])
+            .only_changed(true);
+        let result = runner.run(Mode::Agent).await.expect("should complete");
+        assert_eq!(result.passed_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pass_staged_files_sets_env() {
+        let (temp, repo) = artifact_repo();
+        stage(&temp, "src/lib.rs");
+        stage(&temp, "docs/my notes.md");
+
+        let mut config = test_config_with_checks(vec![]);
+        config.human.checks = vec!["lint".to_string()];
+        config.checks.insert(
+            "lint".to_string(),
+            CheckConfig {
+                run: "printf '%s|%s|%s' \"$APC_STAGED_FILES_COUNT\" \"$APC_STAGED_FILES_0\" \"$APC_STAGED_FILES\""
+                    .to_string(),
+                pass_staged_files: true,
+                ..Default::default()
+            },
+        );
+
+        let runner = Runner::with_repo(config, repo);
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        assert!(result.success());
+        assert_eq!(
+            result.checks[0].output.stdout.trim_end(),
+            "2|docs/my notes.md|docs/my notes.md\nsrc/lib.rs"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pass_staged_diff_writes_temp_file() {
+        let (temp, repo) = artifact_repo();
+        stage(&temp, "src/lib.rs");
+        std::fs::write(temp.path().join("src/lib.rs"), "let key = 1;\n").expect("write file");
+        std::process::Command::new("git")
+            .args(["add", "src/lib.rs"])
+            .current_dir(temp.path())
+            .output()
+            .expect("stage file");
+
+        let mut config = test_config_with_checks(vec![]);
+        config.human.checks = vec!["secrets".to_string()];
+        config.checks.insert(
+            "secrets".to_string(),
+            CheckConfig {
+                run: "echo \"$APC_STAGED_DIFF_FILE\" && grep '^+let' \"$APC_STAGED_DIFF_FILE\""
+                    .to_string(),
+                pass_staged_diff: true,
+                ..Default::default()
+            },
+        );
+
+        let runner = Runner::with_repo(config, repo);
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        assert!(result.success());
+        let mut lines = result.checks[0].output.stdout.lines();
+        let path = PathBuf::from(lines.next().expect("diff file path"));
+        assert_eq!(lines.next(), Some("+let key = 1;"));
+        assert!(
+            !path.exists(),
+            "diff file should be removed after the check"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_staged_files_env_only_when_opted_in() {
+        let (temp, repo) = artifact_repo();
+        stage(&temp, "src/lib.rs");
+
+        let mut config = test_config_with_checks(vec![]);
+        config.human.checks = vec!["lint".to_string()];
+        config.checks.insert(
+            "lint".to_string(),
+            CheckConfig::from_command(
+                "printf '%s' \"${APC_STAGED_FILES_COUNT:-unset}\"".to_string(),
+            ),
+        );
+
+        let runner = Runner::with_repo(config, repo);
+        let result = runner.run(Mode::Human).await.expect("should complete");
+        assert_eq!(result.checks[0].output.stdout.trim_end(), "unset");
+    }
+
+    #[tokio::test]
+    async fn test_run_commit_msg_passes_message_file() {
+        let mut config = test_config_with_checks(vec![]);
+        config.human.checks = vec!["other".to_string()];
+        config.commit_msg.checks = vec!["subject".to_string()];
+        config.checks.insert(
+            "other".to_string(),
+            CheckConfig::from_command("false".to_string()),
+        );
+        config.checks.insert(
+            "subject".to_string(),
+            CheckConfig::from_command("head -n 1 \"$APC_COMMIT_MSG_FILE\"".to_string()),
+        );
+
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let message = temp.path().join("COMMIT_EDITMSG");
+        std::fs::write(&message, "fix: handle empty input\n\nbody\n").expect("write message");
+
+        let runner = Runner::new(config).with_message_file(message);
+        let result = runner
+            .run_commit_msg(Mode::Human)
+            .await
+            .expect("should complete");
+        assert_eq!(result.checks.len(), 1);
+        assert_eq!(result.checks[0].name, "subject");
+        assert_eq!(
+            result.checks[0].output.stdout.trim_end(),
+            "fix: handle empty input"
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_run_commit_msg_feeds_message_on_stdin() {
+        let mut config = test_config_with_checks(vec![("subject", "head -n 1", "none")]);
+        config.commit_msg.checks = vec!["subject".to_string()];
+
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        let message = temp.path().join("COMMIT_EDITMSG");
+        std::fs::write(&message, "fix: handle empty input\n\nbody\n").expect("write message");
+
+        let result = Runner::new(config)
+            .with_message_file(message)
+            .run_commit_msg(Mode::Human)
+            .await
+            .expect("should complete");
+        assert_eq!(
+            result.checks[0].output.stdout.trim_end(),
+            "fix: handle empty input"
+        );
+    }
+
+    #[test]
+    fn test_container_command() {
+        let command = container_command(
+            "rust:1.82",
+            "cargo test --features 'a b'",
+            Path::new("/src/my repo"),
+            &["RUST_LOG".to_string()],
+        );
+        assert_eq!(
+            command,
+            r"docker run --rm -v '/src/my repo:/w' -w /w -e RUST_LOG 'rust:1.82' sh -c 'cargo test --features '\''a b'\'''"
+        );
+    }
+
+    fn docker_available() -> bool {
+        std::process::Command::new("docker")
+            .arg("info")
+            .output()
+            .is_ok_and(|o| o.status.success())
+    }
+
+    #[tokio::test]
+    async fn test_runner_container_check() {
+        if !docker_available() {
+            return;
+        }
+
+        let mut config =
+            test_config_with_checks(vec![("boxed", r#"echo "$GREETING" && pwd"#, "human")]);
+        if let Some(check) = config.checks.get_mut("boxed") {
+            check.container = Some("alpine:3".to_string());
+            check
+                .env
+                .insert("GREETING".to_string(), "from container".to_string());
+        }
+
+        let (_temp, repo) = artifact_repo();
+        let result = Runner::with_repo(config, repo)
+            .run(Mode::Human)
+            .await
+            .expect("should complete");
+        assert_eq!(result.passed_count(), 1);
+        assert_eq!(result.checks[0].output.stdout, "from container\n/w\n");
+    }
+
+    #[tokio::test]
+    async fn test_artifacts_collected_on_failure() {
+        let (_temp, repo) = artifact_repo();
+        let mut config = test_config_with_checks(vec![]);
+        config.human.checks = vec!["e2e".to_string()];
+        config.checks.insert(
+            "e2e".to_string(),
+            artifact_check("mkdir -p logs && echo boom > logs/run.log && exit 1", false),
+        );
+
+        let runner = Runner::with_repo(config, repo.clone());
+        let result = runner.run(Mode::Human).await.expect("should complete");
+
+        let expected = repo.git_dir().join("apc-artifacts/e2e/logs/run.log");
+        assert_eq!(result.checks[0].artifacts, vec![expected.clone()]);
+        assert_eq!(
+            std::fs::read_to_string(expected).expect("read artifact"),
+            "boom\n"
+        );
+    }
+
+    fn cached_check_config(run: &str) -> Config {
+        let mut config = test_config_with_checks(vec![]);
+        config.human.checks = vec!["clippy".to_string()];
+        config.checks.insert(
+            "clippy".to_string(),
+            CheckConfig {
+                run: run.to_string(),
+                depends_files: vec!["src/**/*.rs".to_string()],
+                ..Default::default()
+            },
+        );
+        config
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_depends_files_caches_until_inputs_change() {
+        let (temp, repo) = artifact_repo();
+        std::fs::create_dir(temp.path().join("src")).expect("create src");
+        std::fs::write(temp.path().join("src/lib.rs"), "fn a() {}").expect("write file");
+        let runner = Runner::with_repo(cached_check_config("echo ran >> runs.log"), repo);
+        let runs = || {
+            std::fs::read_to_string(temp.path().join("runs.log"))
+                .map_or(0, |log| log.lines().count())
+        };
+
+        let first = runner.run(Mode::Human).await.expect("should complete");
+        assert!(!first.checks[0].status.is_skip());
+        assert_eq!(runs(), 1);
+
+        // Unrelated files don't affect the key
+        std::fs::write(temp.path().join("README.md"), "docs").expect("write file");
+        let second = runner.run(Mode::Human).await.expect("should complete");
+        assert_eq!(second.checks[0].status, CheckStatus::Cached);
+        assert_eq!(
+            second.checks[0].skip_reason.as_deref(),
+            Some(cache::CACHED_REASON)
+        );
+        assert_eq!(runs(), 1);
+
+        // A declared input does
+        std::fs::write(temp.path().join("src/lib.rs"), "fn b() {}").expect("write file");
+        let third = runner.run(Mode::Human).await.expect("should complete");
+        assert!(!third.checks[0].status.is_skip());
+        assert_eq!(runs(), 2);
+
+        // --all bypasses the cache
+        let runner = runner.ignore_conditions(true);
+        let forced = runner.run(Mode::Human).await.expect("should complete");
+        assert!(!forced.checks[0].status.is_skip());
+        assert_eq!(runs(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_depends_files_does_not_cache_failures() {
+        let (_temp, repo) = artifact_repo();
+        let runner = Runner::with_repo(cached_check_config("exit 1"), repo);
+
+        runner.run(Mode::Human).await.expect("should complete");
+        let again = runner.run(Mode::Human).await.expect("should complete");
+        assert!(!again.checks[0].status.is_skip());
+        assert!(!again.success());
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_agent_cache_keys_on_paths_or_all_files() {
+        let (temp, repo) = artifact_repo();
+        let logs = tempfile::TempDir::new().expect("create temp dir");
+        std::fs::create_dir(temp.path().join("src")).expect("create src");
+        std::fs::write(temp.path().join("src/lib.rs"), "fn a() {}").expect("write file");
+
+        let mut config = test_config_with_checks(vec![]);
+        config.human.checks = vec!["lint".to_string()];
+        config.agent.checks = vec!["lint".to_string(), "test".to_string()];
+        config.agent.cache = true;
+        let log = |name: &str| logs.path().join(name).display().to_string();
+        config.checks.insert(
+            "lint".to_string(),
+            CheckConfig {
+                run: format!("echo ran >> {}", log("lint.log")),
+                paths: vec!["src/**".to_string()],
+                ..Default::default()
+            },
+        );
+        config.checks.insert(
+            "test".to_string(),
+            CheckConfig::from_command(format!("echo ran >> {}", log("test.log"))),
+        );
+        let runner = Runner::with_repo(config, repo);
+        let runs = |name: &str| {
+            std::fs::read_to_string(logs.path().join(name)).map_or(0, |log| log.lines().count())
+        };
+
+        let first = runner.run(Mode::Agent).await.expect("should complete");
+        assert!(first.checks.iter().all(|c| c.status != CheckStatus::Cached));
+        assert_eq!((runs("lint.log"), runs("test.log")), (1, 1));
+
+        let second = runner.run(Mode::Agent).await.expect("should complete");
+        assert!(second.success());
+        assert!(second
+            .checks
+            .iter()
+            .all(|c| c.status == CheckStatus::Cached));
+        assert_eq!((runs("lint.log"), runs("test.log")), (1, 1));
+
+        // A file outside `paths` only invalidates the check without them
+        std::fs::write(temp.path().join("README.md"), "docs").expect("write file");
+        let third = runner.run(Mode::Agent).await.expect("should complete");
+        assert_eq!((runs("lint.log"), runs("test.log")), (1, 2));
+        assert!(third.checks.iter().any(|c| c.status == CheckStatus::Cached));
+
+        // `[agent].cache` doesn't apply to human mode
+        runner.run(Mode::Human).await.expect("should complete");
+        assert_eq!(runs("lint.log"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_artifacts_absent_on_success() {
+        let (_temp, repo) = artifact_repo();
+        let mut config = test_config_with_checks(vec![]);
+        config.human.checks = vec!["e2e".to_string()];
+        config.checks.insert(
+            "e2e".to_string(),
+            artifact_check("mkdir -p logs && echo ok > logs/run.log", false),
+        );
+
+        let runner = Runner::with_repo(config, repo.clone());
+        let result = runner.run(Mode::Human).await.expect("should complete");
+
+        assert!(result.success());
+        assert!(result.checks[0].artifacts.is_empty());
+        assert!(!repo.git_dir().join("apc-artifacts/e2e").exists());
+    }
+
+    #[tokio::test]
+    async fn test_artifacts_always_collects_on_success() {
+        let (temp, repo) = artifact_repo();
+        let mut config = test_config_with_checks(vec![]);
+        config.human.checks = vec!["e2e".to_string()];
+        config.output.artifact_dir = Some("ci-artifacts".to_string());
+        config.checks.insert(
+            "e2e".to_string(),
+            artifact_check("mkdir -p logs && echo ok > logs/run.log", true),
+        );
+
+        let runner = Runner::with_repo(config, repo);
+        let result = runner.run(Mode::Human).await.expect("should complete");
+
+        assert!(result.success());
+        assert_eq!(result.checks[0].artifacts.len(), 1);
+        assert!(temp.path().join("ci-artifacts/e2e/logs/run.log").exists());
+    }
+
+    // =========================================================================
+    // get_checks_for_mode tests
+    // =========================================================================
+
+    #[test]
+    fn test_get_checks_for_mode_human() {
+        let config = test_config_with_checks(vec![
+            ("h-check", "echo h", "human"),
+            ("a-check", "echo a", "agent"),
+        ]);
+        let runner = Runner::new(config);
+        let checks = runner.get_checks_for_mode(Mode::Human);
+        assert_eq!(checks, vec!["h-check".to_string()]);
+    }
+
+    #[test]
+    fn test_get_checks_for_mode_agent() {
+        let config = test_config_with_checks(vec![
+            ("h-check", "echo h", "human"),
+            ("a-check", "echo a", "agent"),
+        ]);
+        let runner = Runner::new(config);
+        let checks = runner.get_checks_for_mode(Mode::Agent);
+        assert_eq!(checks, vec!["a-check".to_string()]);
+    }
+
+    #[test]
+    fn test_get_checks_for_mode_ci_uses_agent_checks() {
+        let config = test_config_with_checks(vec![("a-check", "echo a", "agent")]);
+        let runner = Runner::new(config);
+        // CI mode uses the same checks as Agent mode
+        let checks = runner.get_checks_for_mode(Mode::Ci);
+        assert_eq!(checks, vec!["a-check".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_stash_unstaged_hides_changes_during_checks() {
+        let (temp, repo) = artifact_repo();
+        let path = temp.path();
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .args(args)
+                .current_dir(path)
+                .output()
+                .expect("run git");
+            assert!(output.status.success(), "git {args:?} failed");
+        };
+        git(&["config", "user.email", "test@test.com"]);
+        git(&["config", "user.name", "Test"]);
+        std::fs::write(path.join("a.txt"), "base\n").expect("write");
+        git(&["add", "."]);
+        git(&["commit", "-m", "base"]);
+        std::fs::write(path.join("a.txt"), "staged\n").expect("write");
+        git(&["add", "a.txt"]);
+        std::fs::write(path.join("a.txt"), "unstaged\n").expect("write");
+
+        let mut config =
+            test_config_with_checks(vec![("index-only", "grep -qx staged a.txt", "human")]);
+        config.human.stash_unstaged = true;
+
+        let result = Runner::with_repo(config, repo)
+            .run(Mode::Human)
+            .await
+            .expect("run");
+        assert!(result.success());
+        assert_eq!(
+            std::fs::read_to_string(path.join("a.txt")).expect("read"),
+            "unstaged\n"
+        );
     }
 }
@@ -52,4 +52,4 @@ pub mod presets;
 pub use config::Config;
 pub use core::detector::{Detector, Mode};
 pub use core::error::{Error, Result};
-pub use core::runner::{CheckResult, RunResult, Runner};
+pub use core::runner::{CheckResult, CheckStatus, RunResult, Runner};
@@ -2,6 +2,10 @@
 //!
 //! Presets provide sensible default configurations for different tech stacks.
 
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+
 /// Available preset names.
 pub mod names {
     /// Python projects (pytest, ruff, mypy).
@@ -12,12 +16,35 @@ pub mod names {
     pub const RUST: &str = "rust";
     /// Go projects (go test, golangci-lint).
     pub const GO: &str = "go";
+    /// Projects driven by Makefile targets (make lint/test/build).
+    pub const MAKE: &str = "make";
+    /// Lua projects and Neovim plugins (stylua, luacheck, busted).
+    pub const LUA: &str = "lua";
+    /// Ruby and Rails projects (rubocop, rspec, brakeman).
+    pub const RUBY: &str = "ruby";
+    /// Java projects built with Maven or Gradle (spotless, checkstyle).
+    pub const JAVA: &str = "java";
+    /// Deno projects (deno fmt, lint, check, test).
+    pub const DENO: &str = "deno";
+    /// PHP projects using Composer (php-cs-fixer, phpstan or psalm, phpunit).
+    pub const PHP: &str = "php";
 }
 
 /// Returns a list of available preset names.
 #[must_use]
 pub const fn available() -> &'static [&'static str] {
-    &[names::PYTHON, names::NODE, names::RUST, names::GO]
+    &[
+        names::PYTHON,
+        names::NODE,
+        names::RUST,
+        names::GO,
+        names::MAKE,
+        names::LUA,
+        names::RUBY,
+        names::JAVA,
+        names::DENO,
+        names::PHP,
+    ]
 }
 
 /// Returns true if the preset name is valid.
@@ -34,10 +61,134 @@ pub fn description(name: &str) -> &'static str {
         names::NODE => "Node.js/TypeScript projects (npm, eslint, jest, tsc)",
         names::RUST => "Rust projects (cargo fmt, clippy, cargo test)",
         names::GO => "Go projects (gofmt, golangci-lint, go test)",
+        names::MAKE => "Makefile-driven projects (make lint, make test, make build)",
+        names::LUA => "Lua projects and Neovim plugins (stylua, luacheck, busted)",
+        names::RUBY => "Ruby and Rails projects (rubocop or standardrb, rspec, brakeman)",
+        names::JAVA => "Java projects on Maven or Gradle (spotless, checkstyle, verify/check)",
+        names::DENO => "Deno projects (deno fmt, deno lint, deno check, deno test)",
+        names::PHP => "PHP projects on Composer (php-cs-fixer, phpstan or psalm, phpunit)",
         _ => "Unknown preset",
     }
 }
 
+/// Returns the external tools a preset's checks expect to find in PATH.
+#[must_use]
+pub fn required_tools(name: &str) -> &'static [&'static str] {
+    match name {
+        names::PYTHON => &["python", "pytest", "pre-commit", "gitleaks"],
+        names::NODE => &["npm", "npx"],
+        names::RUST => &["cargo", "rustfmt", "clippy-driver"],
+        names::GO => &["go", "gofmt", "golangci-lint"],
+        names::MAKE => &["make"],
+        names::LUA => &["stylua", "luacheck", "busted"],
+        names::RUBY => &["ruby", "bundle", "rspec"],
+        names::JAVA => &["java"],
+        names::DENO => &["deno"],
+        names::PHP => &["php", "composer"],
+        _ => &[],
+    }
+}
+
+/// Returns the tools required by a preset that are not in PATH.
+#[must_use]
+pub fn missing_tools(name: &str) -> Vec<&'static str> {
+    required_tools(name)
+        .iter()
+        .copied()
+        .filter(|tool| which::which(tool).is_err())
+        .collect()
+}
+
+/// Returns the targets defined by the Makefile in `dir`.
+///
+/// Uses make's database dump (`make -pRrq :`), which evaluates includes and
+/// variables without running any recipes. Returns `None` if `make` is not
+/// available or there is no Makefile.
+#[must_use]
+pub fn make_targets(dir: &Path) -> Option<HashSet<String>> {
+    if !dir.join("Makefile").exists() {
+        return None;
+    }
+
+    let output = Command::new("make")
+        .args(["-pRrq", ":"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+
+    // -q exits non-zero when targets are out of date; the database is still printed
+    Some(parse_make_targets(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Returns the Deno config file in `dir`: `deno.jsonc` if that is the only
+/// one, else `deno.json`.
+#[must_use]
+pub fn deno_config_file(dir: &Path) -> &'static str {
+    if dir.join("deno.jsonc").exists() && !dir.join("deno.json").exists() {
+        "deno.jsonc"
+    } else {
+        "deno.json"
+    }
+}
+
+/// Returns the static analysis config in `dir`: `psalm.xml` if Psalm is set
+/// up and PHPStan is not, else `phpstan.neon`.
+#[must_use]
+pub fn php_analyser_config(dir: &Path) -> &'static str {
+    if dir.join("psalm.xml").exists() && !dir.join("phpstan.neon").exists() {
+        "psalm.xml"
+    } else {
+        "phpstan.neon"
+    }
+}
+
+/// Maven and Gradle build files, which declare a project's plugins.
+const JAVA_BUILD_FILES: [&str; 3] = ["pom.xml", "build.gradle", "build.gradle.kts"];
+
+/// Returns which of `plugins` the Maven or Gradle build files in `dir` mention.
+#[must_use]
+pub fn java_plugins(dir: &Path, plugins: &[&str]) -> HashSet<String> {
+    let build: String = JAVA_BUILD_FILES
+        .iter()
+        .filter_map(|file| std::fs::read_to_string(dir.join(file)).ok())
+        .collect();
+
+    plugins
+        .iter()
+        .filter(|plugin| build.contains(*plugin))
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Extracts explicit target names from a `make -p` database dump.
+fn parse_make_targets(database: &str) -> HashSet<String> {
+    let mut targets = HashSet::new();
+
+    for line in database.lines() {
+        if line.starts_with(['#', '.', '\t', ' ']) {
+            continue;
+        }
+
+        let Some((names, rest)) = line.split_once(':') else {
+            continue;
+        };
+
+        // Skip variable assignments (`X := y`, `X ::= y`, `URL = a:b`)
+        if rest.starts_with('=') || rest.starts_with(":=") || names.contains('=') {
+            continue;
+        }
+
+        targets.extend(
+            names
+                .split_whitespace()
+                .filter(|n| !n.contains('%'))
+                .map(str::to_string),
+        );
+    }
+
+    targets
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,5 +211,136 @@ mod tests {
     fn test_description() {
         assert!(!description("python").is_empty());
         assert!(!description("rust").is_empty());
+        assert!(!description("make").is_empty());
+    }
+
+    #[test]
+    fn test_required_tools() {
+        for name in available() {
+            assert!(!required_tools(name).is_empty(), "{name} lists no tools");
+        }
+        assert!(required_tools("rust").contains(&"cargo"));
+        assert!(required_tools("python").contains(&"pytest"));
+        assert!(required_tools("node").contains(&"npm"));
+        assert!(required_tools("go").contains(&"golangci-lint"));
+        assert_eq!(required_tools("make"), &["make"]);
+        assert_eq!(required_tools("lua"), &["stylua", "luacheck", "busted"]);
+        assert!(required_tools("invalid").is_empty());
+    }
+
+    #[test]
+    fn test_missing_tools_is_subset_of_required() {
+        for name in available() {
+            let required = required_tools(name);
+            assert!(missing_tools(name).iter().all(|t| required.contains(t)));
+        }
+        assert!(missing_tools("invalid").is_empty());
+    }
+
+    #[test]
+    fn test_make_is_valid() {
+        assert!(is_valid("make"));
+    }
+
+    #[test]
+    fn test_lua_is_valid() {
+        assert!(is_valid("lua"));
+        assert!(!description("lua").is_empty());
+    }
+
+    #[test]
+    fn test_ruby_is_valid() {
+        assert!(is_valid("ruby"));
+        assert!(!description("ruby").is_empty());
+        assert!(required_tools("ruby").contains(&"bundle"));
+    }
+
+    #[test]
+    fn test_php_analyser_config() {
+        let temp = tempfile::tempdir().expect("create temp dir");
+        assert_eq!(php_analyser_config(temp.path()), "phpstan.neon");
+        std::fs::write(temp.path().join("psalm.xml"), "<psalm/>").expect("write file");
+        assert_eq!(php_analyser_config(temp.path()), "psalm.xml");
+        std::fs::write(temp.path().join("phpstan.neon"), "").expect("write file");
+        assert_eq!(php_analyser_config(temp.path()), "phpstan.neon");
+    }
+
+    #[test]
+    fn test_parse_make_targets() {
+        let database = "\
+# Variables
+CURDIR := /tmp/project
+URL = https://example.com
+MAKEFLAGS = pqrR
+
+# Files
+lint: deps
+\techo lint
+
+test build: lint
+
+# Not a target:
+.PHONY: lint test
+
+%.o: %.c
+";
+        let targets = parse_make_targets(database);
+        assert!(targets.contains("lint"));
+        assert!(targets.contains("test"));
+        assert!(targets.contains("build"));
+        assert!(!targets.contains("CURDIR"));
+        assert!(!targets.contains("URL"));
+        assert!(!targets.contains(".PHONY"));
+        assert!(!targets.iter().any(|t| t.contains('%')));
+    }
+
+    #[test]
+    fn test_deno_config_file() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        assert_eq!(deno_config_file(temp.path()), "deno.json");
+        std::fs::write(temp.path().join("deno.jsonc"), "{}").expect("write file");
+        assert_eq!(deno_config_file(temp.path()), "deno.jsonc");
+        std::fs::write(temp.path().join("deno.json"), "{}").expect("write file");
+        assert_eq!(deno_config_file(temp.path()), "deno.json");
+    }
+
+    #[test]
+    fn test_java_plugins() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        assert!(java_plugins(temp.path(), &["spotless", "checkstyle"]).is_empty());
+
+        std::fs::write(
+            temp.path().join("build.gradle.kts"),
+            "plugins {\n    id(\"com.diffplug.spotless\") version \"6.25.0\"\n}\n",
+        )
+        .expect("write build file");
+        let plugins = java_plugins(temp.path(), &["spotless", "checkstyle"]);
+        assert!(plugins.contains("spotless"));
+        assert!(!plugins.contains("checkstyle"));
+    }
+
+    #[test]
+    fn test_make_targets_without_makefile() {
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        assert!(make_targets(temp.path()).is_none());
+    }
+
+    #[test]
+    fn test_make_targets_from_makefile() {
+        if which::which("make").is_err() {
+            return;
+        }
+
+        let temp = tempfile::TempDir::new().expect("create temp dir");
+        std::fs::write(
+            temp.path().join("Makefile"),
+            "lint:\n\techo lint\n\ntest: lint\n\techo test\n",
+        )
+        .expect("write Makefile");
+
+        let targets = make_targets(temp.path()).expect("make available");
+        assert!(targets.contains("lint"));
+        assert!(targets.contains("test"));
+        assert!(!targets.contains("build"));
     }
 }
@@ -101,6 +101,43 @@ fn test_detect_with_apc_mode_env() {
         .stderr(predicate::str::contains("ci"));
 }
 
+#[test]
+fn test_detect_agent_author() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = []
+
+[agent]
+checks = []
+
+[detection]
+agent_authors = ["bot@company.com"]
+priority = ["agent_author"]
+"#,
+    )
+    .expect("write config");
+
+    apc_cmd()
+        .arg("detect")
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Detected mode: human"));
+
+    apc_cmd()
+        .arg("detect")
+        .env("GIT_AUTHOR_EMAIL", "bot@company.com")
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Agent git author: bot@company.com",
+        ));
+}
+
 #[test]
 fn test_detect_with_claude_code_env() {
     let temp = create_test_repo();
@@ -124,7 +161,8 @@ fn test_detect_with_cursor_env() {
         .current_dir(temp.path())
         .assert()
         .success()
-        .stderr(predicate::str::contains("agent"));
+        .stderr(predicate::str::contains("agent"))
+        .stderr(predicate::str::contains("Agent: Cursor"));
 }
 
 #[test]
@@ -219,327 +257,620 @@ fn test_init_with_go_preset() {
 }
 
 #[test]
-fn test_init_already_exists() {
+fn test_init_with_lua_preset() {
     let temp = create_test_repo();
-    std::fs::write(temp.path().join("agent-precommit.toml"), "").expect("create config");
 
     apc_cmd()
-        .arg("init")
+        .args(["init", "--preset", "lua"])
         .current_dir(temp.path())
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("already exists"));
+        .success();
+
+    let config =
+        std::fs::read_to_string(temp.path().join("agent-precommit.toml")).expect("read config");
+
+    assert!(config.contains("stylua --check ."));
+    assert!(config.contains("luacheck ."));
+    assert!(config.contains("busted"));
 }
 
 #[test]
-fn test_init_force() {
+fn test_init_with_preset_reports_missing_tools() {
     let temp = create_test_repo();
-    std::fs::write(temp.path().join("agent-precommit.toml"), "").expect("create config");
+    let empty_path = tempfile::TempDir::new().expect("create temp dir");
 
     apc_cmd()
-        .args(["init", "--force"])
+        .args(["init", "--preset", "go"])
+        .env("PATH", empty_path.path())
         .current_dir(temp.path())
         .assert()
-        .success();
+        .success()
+        .stderr(predicate::str::contains(
+            "Missing tools for this preset: go, gofmt, golangci-lint",
+        ));
 }
 
 #[test]
-fn test_validate_no_config() {
+fn test_init_already_exists() {
     let temp = create_test_repo();
+    std::fs::write(temp.path().join("agent-precommit.toml"), "").expect("create config");
 
     apc_cmd()
-        .arg("validate")
+        .arg("init")
         .current_dir(temp.path())
         .assert()
         .failure()
-        .stderr(predicate::str::contains("not found"));
+        .stderr(predicate::str::contains("already exists"));
 }
 
 #[test]
-fn test_validate_valid_config() {
+fn test_init_force() {
     let temp = create_test_repo();
-
-    // Initialize first
-    apc_cmd()
-        .arg("init")
-        .current_dir(temp.path())
-        .output()
-        .expect("init");
+    std::fs::write(temp.path().join("agent-precommit.toml"), "").expect("create config");
 
     apc_cmd()
-        .arg("validate")
+        .args(["init", "--force"])
         .current_dir(temp.path())
         .assert()
-        .success()
-        .stderr(predicate::str::contains("valid"));
+        .success();
 }
 
 #[test]
-fn test_validate_invalid_config() {
+fn test_explain_check() {
     let temp = create_test_repo();
-
-    // Write invalid config
     std::fs::write(
         temp.path().join("agent-precommit.toml"),
         r#"
 [human]
-timeout = "invalid"
+checks = []
+timeout = "45s"
+
+[agent]
+checks = ["lint"]
+
+[globals.env]
+TARGET = "src"
+
+[checks.lint]
+run = "ruff check ${TARGET}"
+description = "Lint Python"
+timeout = "2m"
+env = { RUFF_CACHE_DIR = "/tmp/ruff" }
+enabled_if = { file_exists = "pyproject.toml" }
 "#,
     )
     .expect("write config");
 
+    let output = apc_cmd()
+        .args(["explain", "lint", "--mode", "agent"])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Lint Python"));
+    assert!(stderr.contains("command: ruff check src"));
+    assert!(stderr.contains("env:     RUFF_CACHE_DIR=/tmp/ruff"));
+    assert!(stderr.contains("timeout: 2m (human), 2m (agent, ci)"));
+    assert!(stderr.contains("modes:   agent, ci"));
+    assert!(stderr.contains("file_exists 'pyproject.toml': not found"));
+    assert!(stderr.contains("'lint' is skipped: condition not met"));
+
     apc_cmd()
-        .arg("validate")
+        .args(["explain", "missing"])
         .current_dir(temp.path())
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("Invalid duration"));
+        .failure();
 }
 
 #[test]
-fn test_list_checks() {
+fn test_check_aliases() {
     let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["lint"]
 
-    apc_cmd()
-        .arg("init")
+[agent]
+checks = ["lint"]
+
+[checks.lint]
+run = "echo linted"
+aliases = ["flake8", "pylint"]
+"#,
+    )
+    .expect("write config");
+
+    let output = apc_cmd()
+        .args(["run", "--check", "flake8", "--mode", "human"])
         .current_dir(temp.path())
         .output()
-        .expect("init");
+        .expect("run apc");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("lint"));
 
-    apc_cmd()
+    let output = apc_cmd()
+        .args(["explain", "pylint"])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("command: echo linted"));
+    assert!(stderr.contains("aliases: flake8, pylint"));
+
+    let output = apc_cmd()
         .arg("list")
         .current_dir(temp.path())
-        .assert()
-        .success()
-        .stderr(predicate::str::contains("Human mode checks"))
-        .stderr(predicate::str::contains("Agent mode checks"));
+        .output()
+        .expect("run apc");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("lint (also: flake8, pylint)"));
 }
 
 #[test]
-fn test_list_checks_human_only() {
+fn test_doctor_reports_missing_tools() {
     let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["status", "lint"]
 
-    apc_cmd()
-        .arg("init")
-        .current_dir(temp.path())
-        .output()
-        .expect("init");
+[agent]
+checks = ["status"]
 
-    apc_cmd()
-        .args(["list", "--mode", "human"])
+[checks.status]
+run = "git status"
+
+[checks.lint]
+run = "apc-doctor-missing-linter ."
+"#,
+    )
+    .expect("write config");
+
+    let output = apc_cmd()
+        .arg("doctor")
         .current_dir(temp.path())
-        .assert()
-        .success()
-        .stderr(predicate::str::contains("Human mode checks"));
+        .output()
+        .expect("run apc");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Hook not installed"));
+    assert!(stderr.contains("Configuration is valid"));
+    assert!(stderr.contains("apc-doctor-missing-linter"));
+    assert!(stderr.contains("(missing)"));
 }
 
 #[test]
-fn test_install_hook() {
+fn test_doctor_passes_when_ready() {
     let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["status", "lint"]
+
+[agent]
+checks = []
+
+[checks.status]
+run = "git status"
+
+[checks.lint]
+run = "apc-doctor-missing-linter ."
+enabled_if = { command_exists = "apc-doctor-missing-linter" }
+"#,
+    )
+    .expect("write config");
 
     apc_cmd()
         .arg("install")
         .current_dir(temp.path())
         .assert()
-        .success()
-        .stderr(predicate::str::contains("Installed pre-commit hook"));
+        .success();
 
-    let hook_path = temp.path().join(".git/hooks/pre-commit");
-    assert!(hook_path.exists());
+    let output = apc_cmd()
+        .arg("doctor")
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
 
-    let hook_content = std::fs::read_to_string(&hook_path).expect("read hook");
-    assert!(hook_content.contains("agent-precommit"));
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Hook installed"));
+    assert!(stderr.contains("(missing, checks skipped)"));
 }
 
 #[test]
-fn test_install_hook_idempotent() {
+fn test_user_config_merged_under_repo_config() {
     let temp = create_test_repo();
+    let xdg = TempDir::new().expect("create config home");
+    std::fs::create_dir_all(xdg.path().join("agent-precommit")).expect("create config dir");
+    std::fs::write(
+        xdg.path().join("agent-precommit").join("config.toml"),
+        r#"
+[checks.personal]
+run = "echo from-user-config"
+
+[checks.shared]
+run = "echo user-version"
+"#,
+    )
+    .expect("write user config");
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["personal", "shared"]
+
+[agent]
+checks = []
+
+[checks.shared]
+run = "echo repo-version"
+"#,
+    )
+    .expect("write config");
 
-    // Install first time
     apc_cmd()
-        .arg("install")
+        .args(["run", "--mode", "human", "--dry-run"])
+        .env("XDG_CONFIG_HOME", xdg.path())
         .current_dir(temp.path())
         .assert()
-        .success();
+        .success()
+        .stderr(predicate::str::contains("personal: echo from-user-config"))
+        .stderr(predicate::str::contains("shared: echo repo-version"));
 
-    // Install again - should be idempotent
     apc_cmd()
-        .arg("install")
+        .arg("status")
+        .env("XDG_CONFIG_HOME", xdg.path())
         .current_dir(temp.path())
         .assert()
         .success()
-        .stderr(predicate::str::contains("already installed"));
+        .stderr(predicate::str::contains("User config:"));
 }
 
 #[test]
-fn test_install_hook_force_overwrites() {
+fn test_doctor_uses_user_config_without_repo_config() {
     let temp = create_test_repo();
-
-    // Create a custom hook
-    let hooks_dir = temp.path().join(".git/hooks");
-    std::fs::create_dir_all(&hooks_dir).expect("create hooks dir");
+    let xdg = TempDir::new().expect("create config home");
+    std::fs::create_dir_all(xdg.path().join("agent-precommit")).expect("create config dir");
     std::fs::write(
-        hooks_dir.join("pre-commit"),
-        "#!/bin/sh\necho 'custom hook'",
+        xdg.path().join("agent-precommit").join("config.toml"),
+        r#"
+[human]
+checks = ["lint"]
+
+[agent]
+checks = []
+
+[checks.lint]
+run = "apc-doctor-user-linter ."
+"#,
     )
-    .expect("write custom hook");
+    .expect("write user config");
 
-    // Install with --force
-    apc_cmd()
-        .args(["install", "--force"])
+    let output = apc_cmd()
+        .arg("doctor")
+        .env("XDG_CONFIG_HOME", xdg.path())
         .current_dir(temp.path())
-        .assert()
-        .success();
-
-    // Verify our hook was installed
-    let hook_content = std::fs::read_to_string(hooks_dir.join("pre-commit")).expect("read hook");
-    assert!(hook_content.contains("agent-precommit"));
+        .output()
+        .expect("run apc");
 
-    // Verify backup was created
-    assert!(hooks_dir.join("pre-commit.bak").exists());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No agent-precommit.toml found"));
+    assert!(stderr.contains("apc-doctor-user-linter"), "{stderr}");
 }
 
 #[test]
-fn test_install_hook_refuses_without_force() {
+fn test_status_reports_install_state() {
     let temp = create_test_repo();
 
-    // Create a custom hook
-    let hooks_dir = temp.path().join(".git/hooks");
-    std::fs::create_dir_all(&hooks_dir).expect("create hooks dir");
+    let output = apc_cmd()
+        .arg("status")
+        .env("APC_MODE", "human")
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No agent-precommit.toml found"));
+    assert!(stderr.contains("Hook not installed"));
+
     std::fs::write(
-        hooks_dir.join("pre-commit"),
-        "#!/bin/sh\necho 'custom hook'",
-    )
-    .expect("write custom hook");
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["lint"]
 
-    // Install without --force should fail
+[agent]
+checks = ["lint", "test"]
+
+[checks.lint]
+run = "true"
+
+[checks.test]
+run = "true"
+"#,
+    )
+    .expect("write config");
     apc_cmd()
         .arg("install")
         .current_dir(temp.path())
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("--force"));
+        .success();
+
+    let output = apc_cmd()
+        .arg("status")
+        .env("APC_MODE", "agent")
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Config: "));
+    assert!(stderr.contains("agent-precommit.toml"));
+    assert!(stderr.contains("Hook installed"));
+    assert!(stderr.contains("Mode: agent"));
+    assert!(stderr.contains("Checks: 1 human, 2 agent, 0 commit-msg"));
 }
 
 #[test]
-fn test_uninstall_hook() {
+fn test_doctor_fails_on_invalid_timeout() {
     let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        "[human]\nchecks = []\ntimeout = \"soon\"\n",
+    )
+    .expect("write config");
 
-    // Install first
-    apc_cmd()
-        .arg("install")
+    let output = apc_cmd()
+        .arg("doctor")
         .current_dir(temp.path())
         .output()
-        .expect("install");
-
-    // Then uninstall
-    apc_cmd()
-        .arg("uninstall")
-        .current_dir(temp.path())
-        .assert()
-        .success()
-        .stderr(predicate::str::contains("Removed"));
+        .expect("run apc");
 
-    assert!(!temp.path().join(".git/hooks/pre-commit").exists());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("human.timeout"));
 }
 
 #[test]
-fn test_uninstall_no_hook() {
+fn test_run_yaml_config() {
     let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.yaml"),
+        "human:\n  checks: [greet]\nagent:\n  checks: []\nchecks:\n  greet:\n    run: echo hello from yaml\n",
+    )
+    .expect("write config");
 
-    // Uninstall when no hook exists
     apc_cmd()
-        .arg("uninstall")
+        .args(["run", "--mode", "human", "--check", "greet", "--json"])
         .current_dir(temp.path())
         .assert()
         .success()
-        .stderr(predicate::str::contains("No hook installed"));
-}
+        .stdout(predicate::str::contains("hello from yaml"));
 
-#[test]
-fn test_uninstall_refuses_foreign_hook() {
-    let temp = create_test_repo();
-
-    // Create a custom hook (not ours)
-    let hooks_dir = temp.path().join(".git/hooks");
-    std::fs::create_dir_all(&hooks_dir).expect("create hooks dir");
-    std::fs::write(
-        hooks_dir.join("pre-commit"),
-        "#!/bin/sh\necho 'custom hook'",
-    )
-    .expect("write custom hook");
-
-    // Uninstall should refuse
     apc_cmd()
-        .arg("uninstall")
+        .args(["validate", "--strict"])
         .current_dir(temp.path())
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("not installed by agent-precommit"));
+        .success();
 }
 
 #[test]
-fn test_skip_with_env_var() {
+fn test_run_shuffle_prints_seed() {
     let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        "[human]\nchecks = [\"a\", \"b\", \"c\", \"d\"]\n\n[agent]\nchecks = []\n\n[checks.a]\nrun = \"true\"\n\n[checks.b]\nrun = \"true\"\n\n[checks.c]\nrun = \"true\"\n\n[checks.d]\nrun = \"true\"\n",
+    )
+    .expect("write config");
+
+    let dry_run = || {
+        let output = apc_cmd()
+            .args([
+                "run",
+                "--mode",
+                "human",
+                "--dry-run",
+                "--shuffle",
+                "--seed",
+                "7",
+            ])
+            .current_dir(temp.path())
+            .output()
+            .expect("run apc");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    let first = dry_run();
+    assert!(first.contains("Shuffling checks with seed 7 (replay with --shuffle --seed 7)"));
+    assert_eq!(first, dry_run());
 
     apc_cmd()
-        .arg("run")
-        .env("APC_SKIP", "1")
+        .args(["run", "--mode", "human", "--shuffle"])
         .current_dir(temp.path())
         .assert()
         .success()
-        .stderr(predicate::str::contains("Skipping"));
+        .stderr(predicate::str::contains("Shuffling checks with seed "));
 }
 
 #[test]
-fn test_run_with_mode_override() {
+fn test_run_output_lines() {
     let temp = create_test_repo();
+    let config = "[human]\nchecks = [\"long\"]\n{}\n[agent]\nchecks = []\n\n[checks.long]\nrun = \"seq 1 30 | sed 's/^/line-/'; exit 1\"\n";
+    let write_config = |human_extra: &str| {
+        std::fs::write(
+            temp.path().join("agent-precommit.toml"),
+            config.replace("{}", human_extra),
+        )
+        .expect("write config");
+    };
+    let run = |extra: &[&str]| {
+        let output = apc_cmd()
+            .args(["run", "--mode", "human"])
+            .args(extra)
+            .current_dir(temp.path())
+            .output()
+            .expect("run apc");
+        assert!(!output.status.success());
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    write_config("");
+    let stderr = run(&[]);
+    assert!(stderr.contains("line-20\n"));
+    assert!(!stderr.contains("line-21"));
+    assert!(stderr.contains("... 10 more line(s) (--output-lines 0 shows all)"));
+
+    let stderr = run(&["--output-lines", "0"]);
+    assert!(stderr.contains("line-30"));
+    assert!(!stderr.contains("more line(s)"));
+
+    write_config("output_lines = 25\n");
+    let stderr = run(&[]);
+    assert!(stderr.contains("line-25\n"));
+    assert!(!stderr.contains("line-26"));
+
+    let stderr = run(&["--output-lines", "5"]);
+    assert!(stderr.contains("line-5\n"));
+    assert!(!stderr.contains("line-6"));
+}
 
-    // Initialize config
-    apc_cmd()
-        .arg("init")
+#[test]
+fn test_agent_cache_and_cache_clear() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["lint"]
+
+[agent]
+checks = ["lint"]
+cache = true
+
+[checks.lint]
+run = "echo linting"
+paths = ["src/**"]
+"#,
+    )
+    .expect("write config");
+    let run = || {
+        apc_cmd()
+            .args(["run", "--mode", "agent"])
+            .current_dir(temp.path())
+            .output()
+            .expect("run apc")
+    };
+
+    assert!(run().status.success());
+    assert!(temp.path().join(".git/apc-cache.json").exists());
+    let cached = run();
+    assert!(cached.status.success());
+    assert!(String::from_utf8_lossy(&cached.stderr).contains("0 passed, 1 skipped"));
+
+    let output = apc_cmd()
+        .args(["cache", "clear"])
         .current_dir(temp.path())
         .output()
-        .expect("init");
+        .expect("run apc");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Cleared"));
+    assert!(!temp.path().join(".git/apc-cache.json").exists());
+    assert!(!String::from_utf8_lossy(&run().stderr).contains("0 passed, 1 skipped"));
+}
 
-    // Run with mode override
-    apc_cmd()
-        .args(["run", "--mode", "agent"])
-        .current_dir(temp.path())
-        .assert();
-    // We just check it doesn't crash - the checks will fail without proper setup
+#[test]
+fn test_per_agent_checks_run_for_detected_agent() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = []
+
+[agent]
+checks = ["lint"]
+
+[agent.per_agent.devin]
+checks = ["security-scan"]
+
+[checks.lint]
+run = "echo linting"
+
+[checks.security-scan]
+run = "echo scanning"
+"#,
+    )
+    .expect("write config");
+    let run = |var: &str| {
+        let output = apc_cmd()
+            .arg("run")
+            .env_clear()
+            .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+            .env(var, "1")
+            .current_dir(temp.path())
+            .output()
+            .expect("run apc");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    assert!(run("DEVIN_SESSION").contains("security-scan"));
+    let cursor = run("CURSOR_SESSION");
+    assert!(cursor.contains("lint"));
+    assert!(!cursor.contains("security-scan"));
 }
 
 #[test]
-fn test_not_git_repo() {
-    let temp = TempDir::new().expect("create temp dir");
+#[cfg(unix)]
+fn test_run_reports_check_killed_by_signal() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["crash"]
+
+[agent]
+checks = []
+
+[checks.crash]
+run = "kill -KILL $$"
+"#,
+    )
+    .expect("write config");
 
-    // The install command requires a git repository
     apc_cmd()
-        .arg("install")
+        .args(["run", "--mode", "human"])
         .current_dir(temp.path())
         .assert()
         .failure()
-        .stderr(predicate::str::contains("Not in a Git repository"));
+        .stderr(predicate::str::contains("crash (terminated by signal 9)"));
 }
 
 #[test]
-fn test_config_command_no_config() {
+fn test_validate_no_config() {
     let temp = create_test_repo();
 
     apc_cmd()
-        .arg("config")
+        .arg("validate")
         .current_dir(temp.path())
         .assert()
         .failure()
-        .stderr(predicate::str::contains("No configuration file found"));
+        .stderr(predicate::str::contains("not found"));
 }
 
 #[test]
-fn test_config_command_with_config() {
+fn test_validate_valid_config() {
     let temp = create_test_repo();
 
-    // Initialize
+    // Initialize first
     apc_cmd()
         .arg("init")
         .current_dir(temp.path())
@@ -547,431 +878,2740 @@ fn test_config_command_with_config() {
         .expect("init");
 
     apc_cmd()
-        .arg("config")
+        .arg("validate")
         .current_dir(temp.path())
         .assert()
         .success()
-        .stderr(predicate::str::contains("agent-precommit.toml"));
+        .stderr(predicate::str::contains("valid"));
 }
 
 #[test]
-fn test_config_command_raw() {
+fn test_validate_invalid_config() {
     let temp = create_test_repo();
 
-    // Initialize
-    apc_cmd()
-        .arg("init")
-        .current_dir(temp.path())
-        .output()
-        .expect("init");
+    // Write invalid config
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+timeout = "invalid"
+"#,
+    )
+    .expect("write config");
 
     apc_cmd()
-        .args(["config", "--raw"])
+        .arg("validate")
         .current_dir(temp.path())
         .assert()
-        .success()
-        .stdout(predicate::str::contains("[human]"));
+        .failure()
+        .stderr(predicate::str::contains("Invalid duration"));
 }
 
 #[test]
-fn test_completions_bash() {
-    apc_cmd()
-        .args(["completions", "bash"])
-        .assert()
-        .success()
-        .stdout(predicate::str::contains("complete"));
-}
+fn test_list_checks() {
+    let temp = create_test_repo();
 
-#[test]
-fn test_completions_zsh() {
     apc_cmd()
-        .args(["completions", "zsh"])
-        .assert()
-        .success()
-        .stdout(predicate::str::contains("compdef"));
-}
+        .arg("init")
+        .current_dir(temp.path())
+        .output()
+        .expect("init");
 
-#[test]
-fn test_completions_fish() {
     apc_cmd()
-        .args(["completions", "fish"])
+        .arg("list")
+        .current_dir(temp.path())
         .assert()
         .success()
-        .stdout(predicate::str::contains("complete"));
+        .stderr(predicate::str::contains("Human mode checks"))
+        .stderr(predicate::str::contains("Agent mode checks"));
 }
 
-// ============================================================================
-// Pre-commit integration tests
-// ============================================================================
-
 #[test]
-fn test_init_detects_precommit_config() {
+fn test_list_checks_human_only() {
     let temp = create_test_repo();
 
-    // Create a pre-commit config
-    std::fs::write(temp.path().join(".pre-commit-config.yaml"), "repos: []")
-        .expect("write pre-commit config");
-
     apc_cmd()
         .arg("init")
         .current_dir(temp.path())
+        .output()
+        .expect("init");
+
+    apc_cmd()
+        .args(["list", "--mode", "human"])
+        .current_dir(temp.path())
         .assert()
         .success()
-        .stderr(predicate::str::contains("Detected .pre-commit-config.yaml"));
-
-    let config =
-        std::fs::read_to_string(temp.path().join("agent-precommit.toml")).expect("read config");
-
-    assert!(config.contains("pre_commit = true"));
+        .stderr(predicate::str::contains("Human mode checks"));
 }
 
 #[test]
-fn test_run_skips_disabled_checks() {
+fn test_list_shows_commands_and_enabled_state() {
     let temp = create_test_repo();
-
-    // Write config with checks that have conditions
     std::fs::write(
         temp.path().join("agent-precommit.toml"),
         r#"
 [human]
-checks = ["nonexistent-check"]
-timeout = "30s"
+checks = ["fmt", "python-lint"]
 
 [agent]
 checks = []
-timeout = "15m"
 
-[checks.nonexistent-check]
-run = "echo test"
-description = "Test check"
-[checks.nonexistent-check.enabled_if]
-file_exists = "nonexistent-file.txt"
+[checks.fmt]
+run = "cargo fmt --check"
+description = "Check formatting"
+
+[checks.python-lint]
+run = "ruff check ."
+env = { RUFF_CACHE_DIR = "/tmp/ruff" }
+enabled_if = { file_exists = "pyproject.toml" }
 "#,
     )
     .expect("write config");
 
-    // Run should succeed (skipped checks count as passed)
-    apc_cmd()
-        .arg("run")
+    let output = apc_cmd()
+        .arg("list")
         .current_dir(temp.path())
-        .assert()
-        .success();
+        .output()
+        .expect("run apc");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("✓ fmt          cargo fmt --check  Check formatting"));
+    assert!(stderr.contains("- python-lint  ruff check .       (no description)"));
+    assert!(!stderr.contains("RUFF_CACHE_DIR"));
+
+    let output = apc_cmd()
+        .args(["list", "--verbose"])
+        .env("RUST_LOG", "error")
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("env:     RUFF_CACHE_DIR=/tmp/ruff"));
+    assert!(stderr.contains("✗ file_exists"));
 }
 
-// ============================================================================
-// E2E workflow tests
-// ============================================================================
-
 #[test]
-fn test_full_workflow_init_install_run() {
+fn test_install_hook() {
     let temp = create_test_repo();
 
-    // Step 1: Initialize
     apc_cmd()
-        .args(["init", "--preset", "rust"])
+        .arg("install")
         .current_dir(temp.path())
         .assert()
-        .success();
+        .success()
+        .stderr(predicate::str::contains("Installed pre-commit hook"));
 
-    assert!(temp.path().join("agent-precommit.toml").exists());
+    let hook_path = temp.path().join(".git/hooks/pre-commit");
+    assert!(hook_path.exists());
 
-    // Step 2: Validate config
+    let hook_content = std::fs::read_to_string(&hook_path).expect("read hook");
+    assert!(hook_content.contains("agent-precommit"));
+}
+
+#[test]
+fn test_install_hook_idempotent() {
+    let temp = create_test_repo();
+
+    // Install first time
     apc_cmd()
-        .arg("validate")
+        .arg("install")
         .current_dir(temp.path())
         .assert()
         .success();
 
-    // Step 3: Install hook
+    // Install again - should be idempotent
     apc_cmd()
         .arg("install")
         .current_dir(temp.path())
         .assert()
-        .success();
+        .success()
+        .stderr(predicate::str::contains("already installed"));
+}
 
-    assert!(temp.path().join(".git/hooks/pre-commit").exists());
+#[test]
+fn test_install_hook_force_overwrites() {
+    let temp = create_test_repo();
 
-    // Step 4: List checks
-    apc_cmd()
-        .arg("list")
-        .current_dir(temp.path())
-        .assert()
-        .success();
+    // Create a custom hook
+    let hooks_dir = temp.path().join(".git/hooks");
+    std::fs::create_dir_all(&hooks_dir).expect("create hooks dir");
+    std::fs::write(
+        hooks_dir.join("pre-commit"),
+        "#!/bin/sh\necho 'custom hook'",
+    )
+    .expect("write custom hook");
 
-    // Step 5: Uninstall hook
+    // Install with --force
     apc_cmd()
-        .arg("uninstall")
+        .args(["install", "--force"])
         .current_dir(temp.path())
         .assert()
         .success();
 
-    assert!(!temp.path().join(".git/hooks/pre-commit").exists());
+    // Verify our hook was installed
+    let hook_content = std::fs::read_to_string(hooks_dir.join("pre-commit")).expect("read hook");
+    assert!(hook_content.contains("agent-precommit"));
+
+    // Verify backup was created
+    assert!(hooks_dir.join("pre-commit.bak").exists());
 }
 
 #[test]
-fn test_workflow_with_custom_checks() {
+fn test_install_hook_refuses_without_force() {
     let temp = create_test_repo();
 
-    // Write custom config
+    // Create a custom hook
+    let hooks_dir = temp.path().join(".git/hooks");
+    std::fs::create_dir_all(&hooks_dir).expect("create hooks dir");
+    std::fs::write(
+        hooks_dir.join("pre-commit"),
+        "#!/bin/sh\necho 'custom hook'",
+    )
+    .expect("write custom hook");
+
+    // Install without --force should fail
+    apc_cmd()
+        .arg("install")
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--force"));
+}
+
+#[test]
+fn test_install_chain_into_husky_hook() {
+    let temp = create_test_repo();
+    let husky = temp.path().join(".husky");
+    std::fs::create_dir_all(husky.join("_")).expect("create husky dir");
+    std::fs::write(
+        husky.join("_/pre-commit"),
+        "#!/bin/sh\n. \"$(dirname \"$0\")/h\"\n",
+    )
+    .expect("write husky wrapper");
+    std::fs::write(husky.join("pre-commit"), "npm test\n").expect("write husky hook");
+    git(&temp, &["config", "core.hooksPath", ".husky/_"]);
+
+    apc_cmd()
+        .arg("install")
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Husky manages"))
+        .stderr(predicate::str::contains("--chain"));
+
+    for _ in 0..2 {
+        apc_cmd()
+            .args(["install", "--chain"])
+            .current_dir(temp.path())
+            .assert()
+            .success();
+    }
+    let hook = std::fs::read_to_string(husky.join("pre-commit")).expect("read hook");
+    assert!(hook.starts_with("npm test\n"));
+    assert_eq!(hook.matches("apc run").count(), 1);
+
+    apc_cmd()
+        .arg("uninstall")
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Removed apc from pre-commit hook"));
+    let hook = std::fs::read_to_string(husky.join("pre-commit")).expect("read hook");
+    assert_eq!(hook, "npm test\n");
+}
+
+#[test]
+fn test_install_chain_creates_and_uninstall_removes_hook() {
+    let temp = create_test_repo();
+    let hook = temp.path().join(".git/hooks/commit-msg");
+
+    apc_cmd()
+        .args(["install", "--chain", "--hook", "commit-msg"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+    let content = std::fs::read_to_string(&hook).expect("read hook");
+    assert!(content.contains("apc run --hook commit-msg --message-file \"$1\""));
+
+    apc_cmd()
+        .args(["uninstall", "--hook", "commit-msg"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+    assert!(!hook.exists());
+}
+
+#[test]
+fn test_uninstall_hook() {
+    let temp = create_test_repo();
+
+    // Install first
+    apc_cmd()
+        .arg("install")
+        .current_dir(temp.path())
+        .output()
+        .expect("install");
+
+    // Then uninstall
+    apc_cmd()
+        .arg("uninstall")
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Removed"));
+
+    assert!(!temp.path().join(".git/hooks/pre-commit").exists());
+}
+
+#[test]
+fn test_uninstall_no_hook() {
+    let temp = create_test_repo();
+
+    // Uninstall when no hook exists
+    apc_cmd()
+        .arg("uninstall")
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("No hook installed"));
+}
+
+#[test]
+fn test_uninstall_refuses_foreign_hook() {
+    let temp = create_test_repo();
+
+    // Create a custom hook (not ours)
+    let hooks_dir = temp.path().join(".git/hooks");
+    std::fs::create_dir_all(&hooks_dir).expect("create hooks dir");
+    std::fs::write(
+        hooks_dir.join("pre-commit"),
+        "#!/bin/sh\necho 'custom hook'",
+    )
+    .expect("write custom hook");
+
+    // Uninstall should refuse
+    apc_cmd()
+        .arg("uninstall")
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not installed by agent-precommit"));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_uninstall_restore_puts_back_backup() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = create_test_repo();
+    let hooks_dir = temp.path().join(".git/hooks");
+    let hook = hooks_dir.join("pre-commit");
+    std::fs::create_dir_all(&hooks_dir).expect("create hooks dir");
+    std::fs::write(&hook, "#!/bin/sh\necho 'custom hook'\n").expect("write custom hook");
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o750)).expect("chmod");
+
+    apc_cmd()
+        .args(["install", "--force"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    apc_cmd()
+        .args(["uninstall", "--restore"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Restored previous pre-commit hook",
+        ));
+
+    let content = std::fs::read_to_string(&hook).expect("read hook");
+    assert!(content.contains("custom hook"));
+    let mode = std::fs::metadata(&hook)
+        .expect("hook metadata")
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o750);
+    assert!(!hooks_dir.join("pre-commit.bak").exists());
+}
+
+#[test]
+fn test_skip_with_env_var() {
+    let temp = create_test_repo();
+
+    apc_cmd()
+        .arg("run")
+        .env("APC_SKIP", "1")
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Skipping"));
+}
+
+#[test]
+fn test_run_with_mode_override() {
+    let temp = create_test_repo();
+
+    // Initialize config
+    apc_cmd()
+        .arg("init")
+        .current_dir(temp.path())
+        .output()
+        .expect("init");
+
+    // Run with mode override
+    apc_cmd()
+        .args(["run", "--mode", "agent"])
+        .current_dir(temp.path())
+        .assert();
+    // We just check it doesn't crash - the checks will fail without proper setup
+}
+
+#[test]
+fn test_not_git_repo() {
+    let temp = TempDir::new().expect("create temp dir");
+
+    // The install command requires a git repository
+    apc_cmd()
+        .arg("install")
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Not in a Git repository"));
+}
+
+#[test]
+fn test_config_command_no_config() {
+    let temp = create_test_repo();
+
+    apc_cmd()
+        .arg("config")
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No configuration file found"));
+}
+
+#[test]
+fn test_config_command_with_config() {
+    let temp = create_test_repo();
+
+    // Initialize
+    apc_cmd()
+        .arg("init")
+        .current_dir(temp.path())
+        .output()
+        .expect("init");
+
+    apc_cmd()
+        .arg("config")
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("agent-precommit.toml"));
+}
+
+#[test]
+fn test_config_command_raw() {
+    let temp = create_test_repo();
+
+    // Initialize
+    apc_cmd()
+        .arg("init")
+        .current_dir(temp.path())
+        .output()
+        .expect("init");
+
+    apc_cmd()
+        .args(["config", "--raw"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[human]"));
+}
+
+#[test]
+fn test_completions_bash() {
+    apc_cmd()
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("complete"));
+}
+
+#[test]
+fn test_completions_zsh() {
+    apc_cmd()
+        .args(["completions", "zsh"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("compdef"));
+}
+
+#[test]
+fn test_completions_fish() {
+    apc_cmd()
+        .args(["completions", "fish"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("complete"));
+}
+
+// ============================================================================
+// Pre-commit integration tests
+// ============================================================================
+
+#[test]
+fn test_init_detects_precommit_config() {
+    let temp = create_test_repo();
+
+    // Create a pre-commit config
+    std::fs::write(temp.path().join(".pre-commit-config.yaml"), "repos: []")
+        .expect("write pre-commit config");
+
+    apc_cmd()
+        .arg("init")
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Detected .pre-commit-config.yaml"));
+
+    let config =
+        std::fs::read_to_string(temp.path().join("agent-precommit.toml")).expect("read config");
+
+    assert!(config.contains("pre_commit = true"));
+}
+
+#[test]
+fn test_run_skips_disabled_checks() {
+    let temp = create_test_repo();
+
+    // Write config with checks that have conditions
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["nonexistent-check"]
+timeout = "30s"
+
+[agent]
+checks = []
+timeout = "15m"
+
+[checks.nonexistent-check]
+run = "echo test"
+description = "Test check"
+[checks.nonexistent-check.enabled_if]
+file_exists = "nonexistent-file.txt"
+"#,
+    )
+    .expect("write config");
+
+    // Run should succeed (skipped checks count as passed)
+    apc_cmd()
+        .arg("run")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_run_explain_skip() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["build"]
+
+[agent]
+checks = []
+
+[checks.build]
+run = "touch ran"
+
+[checks.build.enabled_if]
+file_exists = "Cargo.toml"
+command_exists = "sh"
+"#,
+    )
+    .expect("write config");
+
+    apc_cmd()
+        .args(["run", "--mode", "human", "--explain-skip", "build"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "file_exists 'Cargo.toml': not found",
+        ))
+        .stderr(predicate::str::contains(
+            "command_exists 'sh': found in PATH",
+        ))
+        .stderr(predicate::str::contains(
+            "'build' is skipped: condition not met",
+        ));
+
+    assert!(!temp.path().join("ran").exists());
+
+    apc_cmd()
+        .args(["run", "--mode", "agent", "--explain-skip", "build"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("'build' is not in [agent].checks"));
+}
+
+// ============================================================================
+// E2E workflow tests
+// ============================================================================
+
+#[test]
+fn test_full_workflow_init_install_run() {
+    let temp = create_test_repo();
+
+    // Step 1: Initialize
+    apc_cmd()
+        .args(["init", "--preset", "rust"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    assert!(temp.path().join("agent-precommit.toml").exists());
+
+    // Step 2: Validate config
+    apc_cmd()
+        .arg("validate")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    // Step 3: Install hook
+    apc_cmd()
+        .arg("install")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    assert!(temp.path().join(".git/hooks/pre-commit").exists());
+
+    // Step 4: List checks
+    apc_cmd()
+        .arg("list")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    // Step 5: Uninstall hook
+    apc_cmd()
+        .arg("uninstall")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    assert!(!temp.path().join(".git/hooks/pre-commit").exists());
+}
+
+#[test]
+fn test_workflow_with_custom_checks() {
+    let temp = create_test_repo();
+
+    // Write custom config
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["echo-test"]
+timeout = "30s"
+
+[agent]
+checks = ["echo-test"]
+timeout = "15m"
+
+[checks.echo-test]
+run = "echo 'Hello from custom check'"
+description = "A simple echo test"
+"#,
+    )
+    .expect("write config");
+
+    // Validate
+    apc_cmd()
+        .arg("validate")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    // Run - should execute our echo command
+    apc_cmd()
+        .arg("run")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_workflow_failing_check() {
+    let temp = create_test_repo();
+
+    // Write config with a failing check
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["fail-check"]
+timeout = "30s"
+
+[agent]
+checks = ["fail-check"]
+timeout = "15m"
+
+[checks.fail-check]
+run = "exit 1"
+description = "A check that always fails"
+"#,
+    )
+    .expect("write config");
+
+    // Run should fail
+    apc_cmd()
+        .arg("run")
+        .current_dir(temp.path())
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_environment_variables_in_checks() {
+    let temp = create_test_repo();
+
+    // Write config with env vars
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["env-check"]
+timeout = "30s"
+
+[agent]
+checks = []
+timeout = "15m"
+
+[checks.env-check]
+run = "test -n \"$MY_TEST_VAR\""
+description = "Check env var is set"
+[checks.env-check.env]
+MY_TEST_VAR = "hello"
+"#,
+    )
+    .expect("write config");
+
+    // Run should succeed because env var is set
+    apc_cmd()
+        .arg("run")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_check_with_working_directory() {
+    let temp = create_test_repo();
+
+    // Create a subdirectory with a file
+    let subdir = temp.path().join("subdir");
+    std::fs::create_dir(&subdir).expect("create subdir");
+    std::fs::write(subdir.join("marker.txt"), "exists").expect("write marker");
+
+    // Write config
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["file-check"]
+timeout = "30s"
+
+[agent]
+checks = []
+timeout = "15m"
+
+[checks.file-check]
+run = "test -f subdir/marker.txt"
+description = "Check file exists"
+"#,
+    )
+    .expect("write config");
+
+    // Run from repo root should find the file
+    apc_cmd()
+        .arg("run")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+}
+
+// ============================================================================
+// Edge case tests
+// ============================================================================
+
+#[test]
+fn test_deeply_nested_config_discovery() {
+    let temp = create_test_repo();
+
+    // Create nested directories
+    let deep_dir = temp.path().join("a/b/c/d/e");
+    std::fs::create_dir_all(&deep_dir).expect("create deep dir");
+
+    // Initialize at root
+    apc_cmd()
+        .arg("init")
+        .current_dir(temp.path())
+        .output()
+        .expect("init");
+
+    // Validate from deep directory should find config
+    apc_cmd()
+        .arg("validate")
+        .current_dir(&deep_dir)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_run_specific_check() {
+    let temp = create_test_repo();
+
+    // Write config with multiple checks
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["check1", "check2"]
+timeout = "30s"
+
+[agent]
+checks = []
+timeout = "15m"
+
+[checks.check1]
+run = "echo check1"
+description = "First check"
+
+[checks.check2]
+run = "echo check2"
+description = "Second check"
+"#,
+    )
+    .expect("write config");
+
+    // Run specific check
+    apc_cmd()
+        .args(["run", "--check", "check1"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_run_nonexistent_check() {
+    let temp = create_test_repo();
+
+    // Write minimal config
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = []
+timeout = "30s"
+
+[agent]
+checks = []
+timeout = "15m"
+"#,
+    )
+    .expect("write config");
+
+    // Run nonexistent check
+    apc_cmd()
+        .args(["run", "--check", "nonexistent"])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not found"));
+}
+
+#[test]
+fn test_run_without_config_prints_defaults_notice() {
+    let temp = create_test_repo();
+
+    apc_cmd()
+        .args(["run", "--mode", "human"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "No agent-precommit.toml found, using defaults - run `apc init`",
+        ));
+
+    apc_cmd()
+        .args(["--quiet", "run", "--mode", "human"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("using defaults").not());
+}
+
+#[test]
+fn test_run_allow_failure_is_non_blocking() {
+    let temp = create_test_repo();
+
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["coverage", "lint"]
+
+[agent]
+checks = []
+
+[checks.coverage]
+run = "exit 1"
+allow_failure = true
+
+[checks.lint]
+run = "echo lint"
+"#,
+    )
+    .expect("write config");
+
+    apc_cmd()
+        .args(["run", "--mode", "human"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("All checks passed"))
+        .stderr(predicate::str::contains("⚠ coverage failed (non-blocking)"));
+}
+
+#[test]
+fn test_run_only_is_mode_scoped() {
+    let temp = create_test_repo();
+
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["lint", "test"]
+timeout = "30s"
+
+[agent]
+checks = []
+
+[checks.lint]
+run = "echo lint"
+
+[checks.test]
+run = "echo test"
+
+[checks.extra]
+run = "echo extra"
+"#,
+    )
+    .expect("write config");
+
+    // --only picks from the mode's checks
+    apc_cmd()
+        .args(["run", "--mode", "human", "--only", "test"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("✓ test"))
+        .stderr(predicate::str::contains("✓ lint").not());
+
+    // A check outside the mode is rejected by --only...
+    apc_cmd()
+        .args(["run", "--mode", "human", "--only", "extra"])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a human mode check"));
+
+    // ...but --check runs it ad hoc
+    apc_cmd()
+        .args(["run", "--mode", "human", "--check", "extra"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("✓ extra"));
+}
+
+#[test]
+fn test_parallel_group_execution() {
+    let temp = create_test_repo();
+
+    // Write config with parallel groups
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = []
+timeout = "30s"
+
+[agent]
+checks = ["check1", "check2", "check3"]
+timeout = "15m"
+parallel_groups = [["check1", "check2"], ["check3"]]
+
+[checks.check1]
+run = "echo check1"
+description = "First check"
+
+[checks.check2]
+run = "echo check2"
+description = "Second check"
+
+[checks.check3]
+run = "echo check3"
+description = "Third check"
+"#,
+    )
+    .expect("write config");
+
+    // Run in agent mode
+    apc_cmd()
+        .args(["run", "--mode", "agent"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+}
+
+// ============================================================================
+// File selection tests
+// ============================================================================
+
+/// Runs a git command in a test repository.
+fn git(temp: &TempDir, args: &[&str]) {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(temp.path())
+        .output()
+        .expect("run git");
+    assert!(output.status.success(), "git {args:?} failed");
+}
+
+/// Config whose only check records the `{files}` it receives.
+const FILES_RECORDING_CONFIG: &str = r#"
+[human]
+checks = ["record-files"]
+timeout = "30s"
+
+[agent]
+checks = []
+timeout = "15m"
+
+[checks.record-files]
+run = "echo {files} > files.out"
+description = "Record selected files"
+"#;
+
+#[test]
+fn test_run_since_ref_auto_uses_merge_base() {
+    let temp = create_test_repo();
+
+    git(&temp, &["symbolic-ref", "HEAD", "refs/heads/main"]);
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        FILES_RECORDING_CONFIG,
+    )
+    .expect("write config");
+    std::fs::write(temp.path().join("base.txt"), "base").expect("write file");
+    git(&temp, &["add", "."]);
+    git(&temp, &["commit", "-m", "base"]);
+
+    // Two commits ahead of main, plus a staged change
+    git(&temp, &["checkout", "-b", "feature"]);
+    std::fs::write(temp.path().join("first.txt"), "first").expect("write file");
+    git(&temp, &["add", "."]);
+    git(&temp, &["commit", "-m", "first"]);
+    std::fs::write(temp.path().join("second.txt"), "second").expect("write file");
+    git(&temp, &["add", "."]);
+    git(&temp, &["commit", "-m", "second"]);
+    std::fs::write(temp.path().join("staged.txt"), "staged").expect("write file");
+    git(&temp, &["add", "staged.txt"]);
+
+    apc_cmd()
+        .args(["run", "--mode", "human", "--since-ref-auto"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("merge-base with main"));
+
+    let recorded = std::fs::read_to_string(temp.path().join("files.out")).expect("read output");
+    assert!(recorded.contains("first.txt"));
+    assert!(recorded.contains("second.txt"));
+    assert!(recorded.contains("staged.txt"));
+    assert!(!recorded.contains("base.txt"));
+}
+
+#[test]
+fn test_run_changed_since_filters_by_paths() {
+    let temp = create_test_repo();
+
+    git(&temp, &["symbolic-ref", "HEAD", "refs/heads/main"]);
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        "[human]\nchecks = []\n\n[agent]\nchecks = [\"python\", \"rust\"]\n\n[checks.python]\nrun = \"echo {files} > files.out\"\npaths = [\"**/*.py\"]\n\n[checks.rust]\nrun = \"true\"\npaths = [\"**/*.rs\"]\n",
+    )
+    .expect("write config");
+    std::fs::write(temp.path().join("lib.rs"), "fn main() {}").expect("write file");
+    git(&temp, &["add", "."]);
+    git(&temp, &["commit", "-m", "base"]);
+
+    git(&temp, &["checkout", "-b", "feature"]);
+    std::fs::write(temp.path().join("app.py"), "print()").expect("write file");
+    git(&temp, &["add", "."]);
+    git(&temp, &["commit", "-m", "feature"]);
+
+    apc_cmd()
+        .args([
+            "run",
+            "--mode",
+            "agent",
+            "--changed-since",
+            "main",
+            "--json",
+        ])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("merge-base with main"))
+        .stdout(predicate::str::contains("No changed files match its paths"));
+
+    let recorded = std::fs::read_to_string(temp.path().join("files.out")).expect("read output");
+    assert_eq!(recorded.trim(), "app.py");
+
+    apc_cmd()
+        .args(["run", "--mode", "agent", "--changed-since", "no-such-ref"])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no-such-ref"));
+}
+
+#[test]
+fn test_run_since_ref_auto_falls_back_to_staged() {
+    let temp = create_test_repo();
+
+    // No main/master branch and no remote
+    git(&temp, &["symbolic-ref", "HEAD", "refs/heads/trunk"]);
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        FILES_RECORDING_CONFIG,
+    )
+    .expect("write config");
+    std::fs::write(temp.path().join("staged.txt"), "staged").expect("write file");
+    git(&temp, &["add", "staged.txt"]);
+
+    apc_cmd()
+        .args(["run", "--mode", "human", "--since-ref-auto"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("using staged files"));
+
+    let recorded = std::fs::read_to_string(temp.path().join("files.out")).expect("read output");
+    assert_eq!(recorded.trim(), "staged.txt");
+}
+
+#[test]
+fn test_run_from_stdin_files() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        FILES_RECORDING_CONFIG,
+    )
+    .expect("write config");
+    std::fs::write(temp.path().join("staged.txt"), "staged").expect("write file");
+    git(&temp, &["add", "staged.txt"]);
+
+    assert_cmd::Command::from_std(apc_cmd())
+        .args(["run", "--mode", "human", "--from-stdin-files"])
+        .current_dir(temp.path())
+        .write_stdin("src/app.py\n\nmy notes.txt\n")
+        .assert()
+        .success();
+
+    let recorded = std::fs::read_to_string(temp.path().join("files.out")).expect("read output");
+    assert_eq!(recorded.trim(), "src/app.py my notes.txt");
+
+    assert_cmd::Command::from_std(apc_cmd())
+        .args(["run", "--mode", "human", "--from-stdin-files"])
+        .current_dir(temp.path())
+        .write_stdin("../outside.txt\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "'../outside.txt' is outside the repository",
+        ));
+}
+
+// ============================================================================
+// JSON output tests
+// ============================================================================
+
+const JSON_CONFIG: &str = r#"
+[human]
+checks = ["zeta", "alpha"]
+timeout = "30s"
+fail_fast = false
+
+[agent]
+checks = []
+timeout = "15m"
+
+[checks.zeta]
+run = "echo zeta"
+
+[checks.alpha]
+run = "echo alpha"
+"#;
+
+#[test]
+fn test_run_json_output() {
+    let temp = create_test_repo();
+    std::fs::write(temp.path().join("agent-precommit.toml"), JSON_CONFIG).expect("write config");
+
+    let output = apc_cmd()
+        .args(["run", "--mode", "human", "--json"])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json: serde_json::Value = serde_json::from_str(stdout.trim()).expect("valid json");
+    assert_eq!(json["mode"], "human");
+    assert_eq!(json["passed"], 2);
+    assert_eq!(json["checks"][0]["name"], "alpha");
+    assert_eq!(json["checks"][1]["name"], "zeta");
+    assert_eq!(stdout.trim().lines().count(), 1);
+}
+
+#[test]
+fn test_run_report_json_to_file() {
+    let temp = create_test_repo();
+    std::fs::write(temp.path().join("agent-precommit.toml"), JSON_CONFIG).expect("write config");
+
+    let output = apc_cmd()
+        .args([
+            "run",
+            "--mode",
+            "human",
+            "--report",
+            "json",
+            "--report-file",
+            "report.json",
+        ])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let report = std::fs::read_to_string(temp.path().join("report.json")).expect("read report");
+    let json: serde_json::Value = serde_json::from_str(&report).expect("valid json");
+    assert_eq!(json["mode"], "human");
+    assert_eq!(json["success"], true);
+    assert_eq!(json["passed"], 2);
+    assert_eq!(json["checks"][0]["name"], "alpha");
+    assert_eq!(json["checks"][1]["name"], "zeta");
+    assert_eq!(json["checks"][1]["status"], "passed");
+    assert_eq!(json["checks"][1]["stdout"], "zeta\n");
+    assert_eq!(json["checks"][1]["exit_code"], 0);
+}
+
+#[test]
+#[allow(clippy::literal_string_with_formatting_args)] // `{sha}` is apc's placeholder
+fn test_run_report_file_placeholders() {
+    let temp = create_test_repo();
+    std::fs::write(temp.path().join("agent-precommit.toml"), JSON_CONFIG).expect("write config");
+    git(&temp, &["checkout", "-q", "-b", "feature/reports"]);
+    git(&temp, &["add", "."]);
+    git(&temp, &["commit", "-q", "-m", "init"]);
+
+    let output = apc_cmd()
+        .args([
+            "run",
+            "--mode",
+            "human",
+            "--report",
+            "junit",
+            "--report-file",
+            "reports/{mode}/{branch}-{sha}.xml",
+        ])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+    assert!(output.status.success());
+
+    let sha = std::process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(temp.path())
+        .output()
+        .expect("get sha");
+    let sha = String::from_utf8_lossy(&sha.stdout).trim().to_string();
+    let report = temp
+        .path()
+        .join(format!("reports/human/feature-reports-{sha}.xml"));
+    assert!(report.exists(), "missing {}", report.display());
+}
+
+#[test]
+fn test_run_marks_silent_pass() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["quiet", "optional"]
+
+[agent]
+checks = []
+
+[checks.quiet]
+run = "true"
+
+[checks.optional]
+run = "echo never"
+enabled_if = { file_exists = "missing.txt" }
+"#,
+    )
+    .expect("write config");
+
+    let output = apc_cmd()
+        .args(["run", "--mode", "human", "--report", "json"])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("quiet (no output)"));
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid json");
+    let checks = json["checks"].as_array().expect("checks");
+    let quiet = checks.iter().find(|c| c["name"] == "quiet").expect("quiet");
+    assert_eq!(quiet["status"], "passed");
+    assert_eq!(quiet["no_output"], true);
+    let optional = checks
+        .iter()
+        .find(|c| c["name"] == "optional")
+        .expect("optional");
+    assert_eq!(optional["status"], "skipped");
+    assert!(optional.get("no_output").is_none());
+}
+
+#[test]
+fn test_run_writes_github_step_summary_on_success() {
+    let temp = create_test_repo();
+    let config = r#"
+[ci]
+report_on_success = true
+
+[human]
+checks = []
+
+[agent]
+checks = ["lint", "test"]
+
+[checks.lint]
+run = "true"
+
+[checks.test]
+run = "exit ${STATUS}"
+"#;
+    std::fs::write(temp.path().join("agent-precommit.toml"), config).expect("write config");
+    let summary = temp.path().join("step-summary.md");
+
+    let output = apc_cmd()
+        .args(["run", "--mode", "ci"])
+        .env("STATUS", "0")
+        .env("GITHUB_ACTIONS", "true")
+        .env("GITHUB_STEP_SUMMARY", &summary)
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(output.status.success());
+    let markdown = std::fs::read_to_string(&summary).expect("read step summary");
+    assert!(markdown.starts_with("### apc ci: all checks passed"));
+    assert!(markdown.contains("| lint | passed |"));
+    assert!(markdown.contains("| test | passed |"));
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("::notice title=apc::"));
+
+    // Failing runs leave the summary alone
+    std::fs::remove_file(&summary).expect("remove summary");
+    let output = apc_cmd()
+        .args(["run", "--mode", "ci"])
+        .env("STATUS", "1")
+        .env("GITHUB_ACTIONS", "true")
+        .env("GITHUB_STEP_SUMMARY", &summary)
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(!output.status.success());
+    assert!(!summary.exists());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_run_dry_run() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["touch", "optional"]
+
+[agent]
+checks = []
+
+[checks.touch]
+run = "touch ran.txt"
+
+[checks.optional]
+run = "echo never"
+enabled_if = { file_exists = "missing.txt" }
+"#,
+    )
+    .expect("write config");
+
+    let output = apc_cmd()
+        .args(["run", "--mode", "human", "--dry-run"])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Dry run: 2 check(s) in human mode, 1 skipped"));
+    assert!(stderr.contains("touch: touch ran.txt"));
+    assert!(stderr.contains("optional: skipped (Condition not met)"));
+    assert!(!temp.path().join("ran.txt").exists());
+}
+
+#[test]
+fn test_run_plan_prints_waves() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = []
+
+[agent]
+checks = ["lint", "unit", "build"]
+
+[checks.lint]
+run = "touch ran.txt"
+
+[checks.unit]
+run = "touch ran.txt"
+
+[checks.build]
+run = "touch ran.txt"
+needs = ["lint"]
+"#,
+    )
+    .expect("write config");
+
+    let output = apc_cmd()
+        .args(["run", "--mode", "agent", "--plan"])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Plan: 3 check(s) in 2 wave(s), agent mode"));
+    assert!(stderr.contains("1. lint, unit"));
+    assert!(stderr.contains("2. build"));
+    assert!(!temp.path().join("ran.txt").exists());
+}
+
+#[test]
+fn test_run_aborts_on_unmerged_paths() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        "[human]\nchecks = [\"touch\"]\n\n[agent]\nchecks = []\n\n[checks.touch]\nrun = \"touch ran.txt\"\n",
+    )
+    .expect("write config");
+    std::fs::write(temp.path().join("a.txt"), "base\n").expect("write file");
+    git(&temp, &["add", "."]);
+    git(&temp, &["commit", "-m", "base"]);
+    git(&temp, &["checkout", "-b", "other"]);
+    std::fs::write(temp.path().join("a.txt"), "theirs\n").expect("write file");
+    git(&temp, &["commit", "-am", "theirs"]);
+    git(&temp, &["checkout", "-"]);
+    std::fs::write(temp.path().join("a.txt"), "ours\n").expect("write file");
+    git(&temp, &["commit", "-am", "ours"]);
+    let merge = std::process::Command::new("git")
+        .args(["merge", "other"])
+        .current_dir(temp.path())
+        .output()
+        .expect("run git");
+    assert!(!merge.status.success());
+
+    let output = apc_cmd()
+        .args(["run", "--mode", "human"])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Resolve conflicts in: a.txt"));
+    assert!(!temp.path().join("ran.txt").exists());
+
+    apc_cmd()
+        .args(["run", "--mode", "human", "--force"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+    assert!(temp.path().join("ran.txt").exists());
+}
+
+#[test]
+fn test_run_quiet_skips() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["lint", "optional"]
+
+[agent]
+checks = []
+
+[checks.lint]
+run = "true"
+
+[checks.optional]
+run = "echo never"
+enabled_if = { file_exists = "missing.txt" }
+"#,
+    )
+    .expect("write config");
+
+    let output = apc_cmd()
+        .args(["run", "--mode", "human", "--print-summary-table"])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("optional  skipped"));
+
+    for args in [
+        &[
+            "run",
+            "--mode",
+            "human",
+            "--print-summary-table",
+            "--quiet-skips",
+        ][..],
+        &["run", "--mode", "human", "--dry-run", "--quiet-skips"][..],
+    ] {
+        let output = apc_cmd()
+            .args(args)
+            .current_dir(temp.path())
+            .output()
+            .expect("run apc");
+
+        assert!(output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!stderr.contains("optional"), "{args:?}: {stderr}");
+        assert!(stderr.contains("1 skipped"), "{args:?}: {stderr}");
+    }
+}
+
+#[test]
+fn test_run_report_junit() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["ok", "broken"]
+
+[agent]
+checks = []
+
+[checks.ok]
+run = "true"
+
+[checks.broken]
+run = "echo 'boom' >&2; exit 3"
+"#,
+    )
+    .expect("write config");
+
+    apc_cmd()
+        .args([
+            "run",
+            "--mode",
+            "human",
+            "--report",
+            "junit",
+            "--report-file",
+            "junit.xml",
+        ])
+        .current_dir(temp.path())
+        .assert()
+        .failure();
+
+    let xml = std::fs::read_to_string(temp.path().join("junit.xml")).expect("read report");
+    assert!(xml.contains("<testsuite name=\"apc.human\" tests=\"2\" failures=\"1\""));
+    assert!(xml.contains("<testcase name=\"ok\" classname=\"ok\""));
+    assert!(xml.contains("<failure message=\"Exit code 3\">boom"));
+}
+
+#[test]
+fn test_run_report_github() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["ok", "broken"]
+
+[agent]
+checks = []
+
+[checks.ok]
+run = "echo fine"
+
+[checks.broken]
+run = "echo 'boom' >&2; exit 3"
+"#,
+    )
+    .expect("write config");
+
+    apc_cmd()
+        .args(["run", "--mode", "human", "--report", "github"])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "::group::broken (failed)\nboom\n::endgroup::\n::error title=broken::boom\n",
+        ))
+        .stdout(predicate::str::contains(
+            "::group::ok (passed)\nfine\n::endgroup::",
+        ));
+}
+
+#[test]
+fn test_run_report_sarif() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["ok", "broken"]
+
+[agent]
+checks = []
+
+[checks.ok]
+run = "true"
+
+[checks.broken]
+run = "echo 'boom' >&2; exit 3"
+"#,
+    )
+    .expect("write config");
+
+    apc_cmd()
+        .args([
+            "run",
+            "--mode",
+            "human",
+            "--report",
+            "sarif",
+            "--report-file",
+            "apc.sarif",
+        ])
+        .current_dir(temp.path())
+        .assert()
+        .failure();
+
+    let sarif = std::fs::read_to_string(temp.path().join("apc.sarif")).expect("read report");
+    let log: serde_json::Value = serde_json::from_str(&sarif).expect("valid JSON");
+    assert_eq!(log["version"], "2.1.0");
+    let runs = log["runs"].as_array().expect("runs");
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0]["results"][0]["ruleId"], "broken");
+    assert_eq!(runs[0]["results"][0]["message"]["text"], "boom\n");
+}
+
+#[test]
+fn test_run_metrics_textfile() {
+    let temp = create_test_repo();
+    std::fs::write(temp.path().join("agent-precommit.toml"), JSON_CONFIG).expect("write config");
+
+    apc_cmd()
+        .args(["run", "--mode", "human", "--metrics-textfile", "apc.prom"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    let metrics = std::fs::read_to_string(temp.path().join("apc.prom")).expect("read metrics");
+    assert!(metrics.contains("apc_check_passed{check=\"alpha\",mode=\"human\"} 1"));
+    assert!(metrics.contains("apc_check_duration_seconds{check=\"zeta\",mode=\"human\"} "));
+    assert!(metrics.contains("apc_run_duration_seconds{mode=\"human\"} "));
+    assert!(!temp.path().join("apc.prom.tmp").exists());
+}
+
+#[test]
+fn test_run_json_context() {
+    let temp = create_test_repo();
+    std::fs::write(temp.path().join("agent-precommit.toml"), JSON_CONFIG).expect("write config");
+    for args in [
+        &["add", "."][..],
+        &["commit", "-m", "init"],
+        &["checkout", "-b", "feature/context"],
+    ] {
+        std::process::Command::new("git")
+            .args(args)
+            .current_dir(temp.path())
+            .output()
+            .expect("run git");
+    }
+
+    let output = apc_cmd()
+        .args(["run", "--mode", "human", "--json-context"])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid json");
+    let context = &json["context"];
+    let root = temp.path().canonicalize().expect("canonicalize");
+    assert_eq!(context["mode"], "human");
+    assert_eq!(context["detection_reason"], "--mode flag");
+    assert_eq!(context["repo_root"], root.display().to_string());
+    assert_eq!(context["branch"], "feature/context");
+    assert_eq!(context["apc_version"], env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn test_run_output_json_pretty() {
+    let temp = create_test_repo();
+    std::fs::write(temp.path().join("agent-precommit.toml"), JSON_CONFIG).expect("write config");
+
+    let output = apc_cmd()
+        .args(["run", "--mode", "human", "--output-json-pretty"])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.trim().lines().count() > 1);
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("valid json");
+    assert_eq!(json["success"], true);
+}
+
+#[test]
+fn test_run_json_report_use_description() {
+    let temp = create_test_repo();
+    let config = format!(
+        "{JSON_CONFIG}description = \"Alpha check\"\n\n[output]\nreport_use_description = true\n"
+    );
+    std::fs::write(temp.path().join("agent-precommit.toml"), config).expect("write config");
+
+    let output = apc_cmd()
+        .args(["run", "--mode", "human", "--json"])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid json");
+    assert_eq!(json["checks"][0]["name"], "alpha");
+    assert_eq!(json["checks"][0]["title"], "Alpha check");
+    assert_eq!(json["checks"][1]["title"], "zeta");
+}
+
+#[test]
+fn test_run_print_summary_table_on_success() {
+    let temp = create_test_repo();
+    std::fs::write(temp.path().join("agent-precommit.toml"), JSON_CONFIG).expect("write config");
+
+    apc_cmd()
+        .args(["run", "--mode", "human", "--print-summary-table"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::is_match(r"Check\s+Status\s+Duration").expect("regex"))
+        .stderr(predicate::str::is_match(r"(?m)^alpha\s+passed\s+\d+ms$").expect("regex"));
+}
+
+#[test]
+fn test_run_skip_checks_env() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["flaky", "ok"]
+
+[agent]
+checks = []
+
+[checks.flaky]
+run = "exit 1"
+
+[checks.ok]
+run = "true"
+"#,
+    )
+    .expect("write config");
+
+    apc_cmd()
+        .args(["run", "--mode", "human"])
+        .current_dir(temp.path())
+        .assert()
+        .failure();
+
+    apc_cmd()
+        .args(["run", "--mode", "human", "--json"])
+        .env("APC_SKIP_CHECKS", "flaky")
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("skipped via APC_SKIP_CHECKS"));
+}
+
+#[test]
+fn test_run_timings_lists_slowest_first() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["quick", "slow"]
+
+[agent]
+checks = []
+
+[checks.quick]
+run = "true"
+
+[checks.slow]
+run = "sleep 0.3"
+"#,
+    )
+    .expect("write config");
+
+    apc_cmd()
+        .args(["run", "--mode", "human", "--timings"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(
+            predicate::str::is_match(r"Slowest checks:\n\s+slow\s+\d+ms\s+\d+%\n\s+quick\s")
+                .expect("regex"),
+        );
+
+    apc_cmd()
+        .args(["run", "--mode", "human"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Slowest checks:").not());
+
+    apc_cmd()
+        .args(["--verbose", "run", "--mode", "human"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Slowest checks:"));
+}
+
+#[test]
+fn test_run_fail_fast_group_runs_later_groups() {
+    let temp = create_test_repo();
+    let config = r#"
+[human]
+checks = []
+
+[agent]
+checks = ["lint", "later"]
+fail_fast = true
+parallel_groups = [["lint"], ["later"]]
+
+[checks.lint]
+run = "exit 1"
+
+[checks.later]
+run = "echo later"
+"#;
+    std::fs::write(temp.path().join("agent-precommit.toml"), config).expect("write config");
+
+    apc_cmd()
+        .args(["run", "--mode", "agent"])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("✓ later").not());
+
+    apc_cmd()
+        .args(["run", "--mode", "agent", "--fail-fast-group"])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("✓ later"));
+}
+
+const MATRIX_CONFIG: &str = r#"
+[human]
+checks = ["human-lint"]
+
+[agent]
+checks = ["agent-test"]
+
+[checks.human-lint]
+run = "echo human-lint"
+
+[checks.agent-test]
+run = "echo agent-test"
+"#;
+
+#[test]
+fn test_run_mode_matrix_runs_each_mode() {
+    let temp = create_test_repo();
+    std::fs::write(temp.path().join("agent-precommit.toml"), MATRIX_CONFIG).expect("write config");
+
+    apc_cmd()
+        .args(["run", "--mode-matrix", "human,agent"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("✓ human-lint"))
+        .stderr(predicate::str::contains("✓ agent-test"))
+        .stderr(predicate::str::contains("human: 1 passed, 0 failed"))
+        .stderr(predicate::str::contains("agent: 1 passed, 0 failed"));
+}
+
+#[test]
+fn test_run_mode_matrix_fails_if_any_mode_fails() {
+    let temp = create_test_repo();
+    let config = MATRIX_CONFIG.replace("echo agent-test", "exit 1");
+    std::fs::write(temp.path().join("agent-precommit.toml"), config).expect("write config");
+
+    let output = apc_cmd()
+        .args(["run", "--mode-matrix", "human,agent", "--json"])
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(!output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid json");
+    assert_eq!(json["success"], false);
+    assert_eq!(json["runs"][0]["mode"], "human");
+    assert_eq!(json["runs"][0]["success"], true);
+    assert_eq!(json["runs"][1]["checks"][0]["name"], "agent-test");
+    assert_eq!(json["runs"][1]["success"], false);
+}
+
+// ============================================================================
+// Make preset tests
+// ============================================================================
+
+#[test]
+fn test_presets_lists_builtin_presets() {
+    apc_cmd()
+        .arg("presets")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("ruby"))
+        .stderr(predicate::str::contains(
+            "checks: no-merge-conflicts, fmt-check, clippy",
+        ));
+
+    let output = apc_cmd()
+        .args(["presets", "--json"])
+        .output()
+        .expect("run apc");
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    let names: Vec<&str> = json["presets"]
+        .as_array()
+        .expect("presets array")
+        .iter()
+        .filter_map(|p| p["name"].as_str())
+        .collect();
+    assert!(names.contains(&"rust"));
+    assert!(names.contains(&"deno"));
+}
+
+#[test]
+fn test_init_with_preset_file() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("company-preset.toml"),
+        "[company-lint]\nrun = \"company-lint --strict\"\n",
+    )
+    .expect("write preset");
+
+    apc_cmd()
+        .args(["init", "--preset-file", "company-preset.toml"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Using preset file"));
+
+    let config =
+        std::fs::read_to_string(temp.path().join("agent-precommit.toml")).expect("read config");
+    assert!(config.contains("company-lint --strict"));
+
+    apc_cmd()
+        .arg("validate")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_init_with_invalid_preset_file_writes_nothing() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("company-preset.toml"),
+        "[agent]\nchecks = [\"undefined\"]\n",
+    )
+    .expect("write preset");
+
+    apc_cmd()
+        .args(["init", "--preset-file", "company-preset.toml"])
+        .current_dir(temp.path())
+        .assert()
+        .failure();
+    assert!(!temp.path().join("agent-precommit.toml").exists());
+}
+
+#[test]
+fn test_init_merge_keeps_existing_config() {
+    let temp = create_test_repo();
     std::fs::write(
         temp.path().join("agent-precommit.toml"),
         r#"
 [human]
-checks = ["echo-test"]
-timeout = "30s"
+checks = []
 
 [agent]
-checks = ["echo-test"]
+checks = ["test-unit"]
+timeout = "42m"
+
+[checks.test-unit]
+run = "cargo nextest run"
+"#,
+    )
+    .expect("write config");
+
+    apc_cmd()
+        .args(["init", "--preset", "rust", "--merge"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Merged"));
+
+    let merged =
+        std::fs::read_to_string(temp.path().join("agent-precommit.toml")).expect("read config");
+    assert!(merged.contains("cargo nextest run"));
+    assert!(merged.contains("42m"));
+    assert!(merged.contains("cargo clippy"));
+
+    apc_cmd()
+        .args(["init", "--preset", "rust", "--merge", "--prefer-preset"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+    let merged =
+        std::fs::read_to_string(temp.path().join("agent-precommit.toml")).expect("read config");
+    assert!(!merged.contains("cargo nextest run"));
+    assert!(merged.contains("42m"));
+
+    apc_cmd()
+        .arg("validate")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_init_with_make_preset() {
+    let temp = create_test_repo();
+
+    apc_cmd()
+        .args(["init", "--preset", "make"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    let config =
+        std::fs::read_to_string(temp.path().join("agent-precommit.toml")).expect("read config");
+
+    assert!(config.contains("make lint"));
+    assert!(config.contains("make test"));
+    assert!(config.contains("make build"));
+
+    apc_cmd()
+        .arg("validate")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_init_with_java_preset_only_includes_declared_plugins() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("pom.xml"),
+        "<project><build><plugins><plugin>\n<artifactId>maven-checkstyle-plugin</artifactId>\n</plugin></plugins></build></project>\n",
+    )
+    .expect("write pom.xml");
+
+    apc_cmd()
+        .args(["init", "--preset", "java"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    let config =
+        std::fs::read_to_string(temp.path().join("agent-precommit.toml")).expect("read config");
+
+    assert!(config.contains("mvn -q verify"));
+    assert!(config.contains("./gradlew check"));
+    assert!(config.contains("checkstyle:check"));
+    assert!(!config.contains("spotless"));
+
+    apc_cmd()
+        .arg("validate")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_init_with_php_preset_gates_on_psalm_config() {
+    let temp = create_test_repo();
+    std::fs::write(temp.path().join("composer.json"), "{}\n").expect("write composer.json");
+    std::fs::write(temp.path().join("psalm.xml"), "<psalm/>\n").expect("write psalm.xml");
+
+    apc_cmd()
+        .args(["init", "--preset", "php"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    let config =
+        std::fs::read_to_string(temp.path().join("agent-precommit.toml")).expect("read config");
+
+    assert!(config.contains("php-cs-fixer fix --dry-run --diff"));
+    assert!(config.contains("composer validate"));
+    assert!(config.contains("file_exists = \"psalm.xml\""));
+    assert!(!config.contains("phpstan.neon"));
+
+    apc_cmd()
+        .arg("validate")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_init_with_make_preset_only_includes_existing_targets() {
+    if which::which("make").is_err() {
+        return;
+    }
+
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("Makefile"),
+        "lint:\n\techo lint\n\ntest:\n\techo test\n",
+    )
+    .expect("write Makefile");
+
+    apc_cmd()
+        .args(["init", "--preset", "make"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    let config =
+        std::fs::read_to_string(temp.path().join("agent-precommit.toml")).expect("read config");
+
+    assert!(config.contains("make lint"));
+    assert!(config.contains("make test"));
+    assert!(!config.contains("make build"));
+
+    apc_cmd()
+        .arg("validate")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+}
+
+// ============================================================================
+// Signal handling tests
+// ============================================================================
+
+#[cfg(unix)]
+#[test]
+fn test_run_abort_on_signal_kills_checks() {
+    use std::time::{Duration, Instant};
+
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["quick", "slow"]
+timeout = "5m"
+fail_fast = false
+
+[agent]
+checks = []
 timeout = "15m"
 
-[checks.echo-test]
-run = "echo 'Hello from custom check'"
-description = "A simple echo test"
+[checks.quick]
+run = "true"
+
+[checks.slow]
+run = "sleep 31.4159; echo finished"
 "#,
     )
     .expect("write config");
 
-    // Validate
+    let child = apc_cmd()
+        .args(["run", "--mode", "human", "--abort-on-signal"])
+        .current_dir(temp.path())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("spawn apc");
+
+    // Wait until the slow check is running
+    let deadline = Instant::now() + Duration::from_secs(30);
+    loop {
+        let running = Command::new("pgrep")
+            .args(["-f", "sleep 31.4159"])
+            .output()
+            .expect("run pgrep");
+        if !running.stdout.is_empty() {
+            break;
+        }
+        assert!(Instant::now() < deadline, "slow check never started");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .expect("send SIGINT");
+
+    let output = child.wait_with_output().expect("wait for apc");
+    assert_eq!(output.status.code(), Some(130));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Aborted"));
+
+    // SIGKILL delivery is asynchronous; give the kernel a moment to reap
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let survivors = Command::new("pgrep")
+            .args(["-f", "sleep 31.4159"])
+            .output()
+            .expect("run pgrep");
+        if survivors.stdout.is_empty() {
+            break;
+        }
+        assert!(Instant::now() < deadline, "orphaned sleep remains");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+// ============================================================================
+// Schema tests
+// ============================================================================
+
+#[test]
+fn test_schema_command() {
+    let output = apc_cmd().arg("schema").output().expect("run apc");
+    assert!(output.status.success());
+
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid json");
+    assert!(schema["properties"]["checks"].is_object());
+}
+
+#[test]
+fn test_validate_schema_unknown_section() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = []
+
+[agent]
+checks = []
+
+[agnet]
+timeout = "1m"
+"#,
+    )
+    .expect("write config");
+
+    // Lenient validation silently drops the typo'd section
+    apc_cmd()
+        .arg("validate")
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    apc_cmd()
+        .args(["validate", "--schema"])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown key: agnet"));
+}
+
+#[test]
+fn test_validate_schema_unknown_check_field() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        r#"
+[human]
+checks = ["lint"]
+
+[agent]
+checks = []
+
+[checks.lint]
+run = "echo lint"
+enabled-if = { file_exists = "Makefile" }
+"#,
+    )
+    .expect("write config");
+
+    apc_cmd()
+        .args(["validate", "--schema"])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("checks.lint.enabled-if"));
+}
+
+#[test]
+fn test_validate_schema_valid_config() {
+    let temp = create_test_repo();
+
     apc_cmd()
-        .arg("validate")
+        .args(["init", "--preset", "rust"])
         .current_dir(temp.path())
         .assert()
         .success();
 
-    // Run - should execute our echo command
     apc_cmd()
-        .arg("run")
+        .args(["validate", "--schema"])
         .current_dir(temp.path())
         .assert()
-        .success();
+        .success()
+        .stderr(predicate::str::contains("Configuration is valid"));
 }
 
 #[test]
-fn test_workflow_failing_check() {
+fn test_validate_strict_misspelled_timeout() {
     let temp = create_test_repo();
-
-    // Write config with a failing check
     std::fs::write(
         temp.path().join("agent-precommit.toml"),
         r#"
 [human]
-checks = ["fail-check"]
-timeout = "30s"
+checks = []
+timout = "30s"
 
 [agent]
-checks = ["fail-check"]
-timeout = "15m"
-
-[checks.fail-check]
-run = "exit 1"
-description = "A check that always fails"
+checks = []
 "#,
     )
     .expect("write config");
 
-    // Run should fail
     apc_cmd()
-        .arg("run")
+        .arg("validate")
         .current_dir(temp.path())
         .assert()
-        .failure();
+        .success();
+
+    apc_cmd()
+        .args(["validate", "--strict"])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("human.timout (line 4)"));
 }
 
 #[test]
-fn test_environment_variables_in_checks() {
+fn test_commit_msg_conventional() {
     let temp = create_test_repo();
-
-    // Write config with env vars
     std::fs::write(
         temp.path().join("agent-precommit.toml"),
-        r#"
-[human]
-checks = ["env-check"]
-timeout = "30s"
-
-[agent]
-checks = []
-timeout = "15m"
-
-[checks.env-check]
-run = "test -n \"$MY_TEST_VAR\""
-description = "Check env var is set"
-[checks.env-check.env]
-MY_TEST_VAR = "hello"
-"#,
+        "[human]\nchecks = []\n\n[agent]\nchecks = []\n\n[commit_msg.conventional]\ntypes = [\"feat\", \"fix\"]\nmax_subject_length = 20\n",
     )
     .expect("write config");
+    let message = temp.path().join("COMMIT_EDITMSG");
 
-    // Run should succeed because env var is set
+    std::fs::write(&message, "fix(runner): handle timeouts\n").expect("write message");
     apc_cmd()
-        .arg("run")
+        .args(["commit-msg", "COMMIT_EDITMSG"])
         .current_dir(temp.path())
         .assert()
         .success();
+
+    std::fs::write(&message, "chore: bump every dependency\n").expect("write message");
+    apc_cmd()
+        .args(["commit-msg", "COMMIT_EDITMSG"])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown type 'chore' (allowed: feat, fix)",
+        ))
+        .stderr(predicate::str::contains(
+            "Subject is 21 characters (max 20)",
+        ));
 }
 
 #[test]
-fn test_check_with_working_directory() {
+fn test_install_commit_msg_hook() {
     let temp = create_test_repo();
 
-    // Create a subdirectory with a file
-    let subdir = temp.path().join("subdir");
-    std::fs::create_dir(&subdir).expect("create subdir");
-    std::fs::write(subdir.join("marker.txt"), "exists").expect("write marker");
+    apc_cmd()
+        .args(["install", "--hook", "commit-msg"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Installed commit-msg hook"));
 
-    // Write config
-    std::fs::write(
-        temp.path().join("agent-precommit.toml"),
-        r#"
-[human]
-checks = ["file-check"]
-timeout = "30s"
+    let hook =
+        std::fs::read_to_string(temp.path().join(".git/hooks/commit-msg")).expect("read hook");
+    assert!(hook.contains("apc run --hook commit-msg --message-file \"$1\""));
+    assert!(!temp.path().join(".git/hooks/pre-commit").exists());
 
-[agent]
-checks = []
-timeout = "15m"
+    apc_cmd()
+        .args(["uninstall", "--hook", "commit-msg"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Removed commit-msg hook"));
+    assert!(!temp.path().join(".git/hooks/commit-msg").exists());
+}
 
-[checks.file-check]
-run = "test -f subdir/marker.txt"
-description = "Check file exists"
-"#,
+#[test]
+fn test_run_commit_msg_hook_checks() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        "[human]\nchecks = [\"fail\"]\n\n[agent]\nchecks = []\n\n[commit_msg]\nchecks = [\"ticket\"]\n\n[checks.fail]\nrun = \"false\"\n\n[checks.ticket]\nrun = \"grep -qE '^[A-Z]+-[0-9]+: ' \\\"$APC_COMMIT_MSG_FILE\\\"\"\n",
     )
     .expect("write config");
+    let message = temp.path().join("COMMIT_EDITMSG");
 
-    // Run from repo root should find the file
+    std::fs::write(&message, "APC-12: handle timeouts\n").expect("write message");
     apc_cmd()
-        .arg("run")
+        .args([
+            "run",
+            "--mode",
+            "human",
+            "--hook",
+            "commit-msg",
+            "--message-file",
+            "COMMIT_EDITMSG",
+        ])
         .current_dir(temp.path())
         .assert()
         .success();
-}
 
-// ============================================================================
-// Edge case tests
-// ============================================================================
+    std::fs::write(&message, "handle timeouts\n").expect("write message");
+    apc_cmd()
+        .args([
+            "run",
+            "--mode",
+            "human",
+            "--hook",
+            "commit-msg",
+            "--message-file",
+            "COMMIT_EDITMSG",
+        ])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("ticket"));
+}
 
 #[test]
-fn test_deeply_nested_config_discovery() {
+fn test_run_timeout_override() {
     let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        "[human]\nchecks = [\"slow\"]\ntimeout = \"100ms\"\n\n[agent]\nchecks = []\n\n[checks.slow]\nrun = \"sleep 0.5\"\n",
+    )
+    .expect("write config");
 
-    // Create nested directories
-    let deep_dir = temp.path().join("a/b/c/d/e");
-    std::fs::create_dir_all(&deep_dir).expect("create deep dir");
-
-    // Initialize at root
     apc_cmd()
-        .arg("init")
+        .args(["run", "--mode", "human"])
         .current_dir(temp.path())
-        .output()
-        .expect("init");
+        .assert()
+        .failure();
 
-    // Validate from deep directory should find config
     apc_cmd()
-        .arg("validate")
-        .current_dir(&deep_dir)
+        .args(["run", "--mode", "human", "--timeout", "10s"])
+        .current_dir(temp.path())
         .assert()
         .success();
+
+    apc_cmd()
+        .args(["run", "--timeout", "later"])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'later'"));
 }
 
 #[test]
-fn test_run_specific_check() {
+fn test_run_by_tag_and_list_tags() {
     let temp = create_test_repo();
-
-    // Write config with multiple checks
     std::fs::write(
         temp.path().join("agent-precommit.toml"),
-        r#"
-[human]
-checks = ["check1", "check2"]
-timeout = "30s"
+        r#"[human]
+checks = ["lint"]
 
 [agent]
-checks = []
-timeout = "15m"
+checks = ["unit", "broken"]
 
-[checks.check1]
-run = "echo check1"
-description = "First check"
+[checks.lint]
+run = "true"
+tags = ["fast"]
 
-[checks.check2]
-run = "echo check2"
-description = "Second check"
+[checks.unit]
+run = "true"
+tags = ["fast", "test"]
+
+[checks.broken]
+run = "false"
 "#,
     )
     .expect("write config");
 
-    // Run specific check
     apc_cmd()
-        .args(["run", "--check", "check1"])
+        .args([
+            "run",
+            "--mode",
+            "agent",
+            "--tag",
+            "fast",
+            "--print-summary-table",
+        ])
         .current_dir(temp.path())
         .assert()
-        .success();
+        .success()
+        .stderr(predicate::str::contains("lint"))
+        .stderr(predicate::str::contains("unit"))
+        .stderr(predicate::str::contains("broken").not());
+
+    apc_cmd()
+        .arg("list")
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("[fast, test]"));
 }
 
 #[test]
-fn test_run_nonexistent_check() {
+fn test_run_stream_prints_and_captures_output() {
     let temp = create_test_repo();
-
-    // Write minimal config
     std::fs::write(
         temp.path().join("agent-precommit.toml"),
-        r#"
-[human]
-checks = []
-timeout = "30s"
-
-[agent]
-checks = []
-timeout = "15m"
-"#,
+        "[human]\nchecks = [\"greet\"]\n\n[agent]\nchecks = []\n\n[checks.greet]\nrun = \"echo hello\"\n",
     )
     .expect("write config");
 
-    // Run nonexistent check
-    apc_cmd()
-        .args(["run", "--check", "nonexistent"])
+    let output = apc_cmd()
+        .args(["run", "--mode", "human", "--stream", "--json"])
         .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[greet] hello"));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid json");
+    assert_eq!(json["checks"][0]["stdout"], "hello\n");
+}
+
+#[test]
+fn test_install_honors_git_dir_and_work_tree() {
+    let temp = create_test_repo();
+    let elsewhere = TempDir::new().expect("create temp dir");
+
+    apc_cmd()
+        .arg("install")
+        .current_dir(elsewhere.path())
+        .env("GIT_DIR", temp.path().join(".git"))
+        .env("GIT_WORK_TREE", temp.path())
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("not found"));
+        .success()
+        .stderr(predicate::str::contains("Installed pre-commit hook"));
+
+    assert!(temp.path().join(".git/hooks/pre-commit").exists());
 }
 
 #[test]
-fn test_parallel_group_execution() {
+fn test_install_from_linked_worktree_uses_shared_hooks() {
     let temp = create_test_repo();
+    git(&temp, &["commit", "--allow-empty", "-m", "init"]);
+    git(&temp, &["worktree", "add", "wt"]);
 
-    // Write config with parallel groups
-    std::fs::write(
-        temp.path().join("agent-precommit.toml"),
-        r#"
-[human]
-checks = []
-timeout = "30s"
+    apc_cmd()
+        .arg("install")
+        .current_dir(temp.path().join("wt"))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Installed pre-commit hook"));
 
-[agent]
-checks = ["check1", "check2", "check3"]
-timeout = "15m"
-parallel_groups = [["check1", "check2"], ["check3"]]
+    assert!(temp.path().join(".git/hooks/pre-commit").exists());
+    assert!(!temp.path().join(".git/worktrees/wt/hooks").exists());
+}
 
-[checks.check1]
-run = "echo check1"
-description = "First check"
+#[test]
+fn test_detect_tty_heuristic_disabled() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        "[detection]\nuse_tty_heuristic = false\n\n[human]\nchecks = []\n\n[agent]\nchecks = []\n",
+    )
+    .expect("write config");
 
-[checks.check2]
-run = "echo check2"
-description = "Second check"
+    // Clear agent and CI indicators; the test harness never has a TTY
+    apc_cmd()
+        .arg("detect")
+        .env_clear()
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Detected mode: human"))
+        .stderr(predicate::str::contains(
+            "No TTY on stdin and stdout (skipped: detection.use_tty_heuristic = false)",
+        ));
+}
 
-[checks.check3]
-run = "echo check3"
-description = "Third check"
-"#,
+#[test]
+fn test_detect_custom_priority() {
+    let temp = create_test_repo();
+    std::fs::write(
+        temp.path().join("agent-precommit.toml"),
+        "[detection]\npriority = [\"ci\", \"known_agent_env\"]\n\n[human]\nchecks = []\n\n[agent]\nchecks = []\n",
     )
     .expect("write config");
 
-    // Run in agent mode
     apc_cmd()
-        .args(["run", "--mode", "agent"])
+        .arg("detect")
+        .env_clear()
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .env("CLAUDE_CODE", "1")
+        .env("CI", "true")
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Detected mode: ci"))
+        .stderr(predicate::str::contains(
+            "1. CI env vars\n  2. Known agent env vars\n  3. Default: human",
+        ));
+}
+
+#[test]
+fn test_diff_config_against_preset() {
+    let temp = create_test_repo();
+
+    apc_cmd()
+        .args(["init", "--preset", "rust"])
         .current_dir(temp.path())
         .assert()
         .success();
+
+    apc_cmd()
+        .args(["diff-config", "--preset", "rust"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "No differences from preset 'rust'",
+        ));
+
+    let config_path = temp.path().join("agent-precommit.toml");
+    let mut config: toml::Table = std::fs::read_to_string(&config_path)
+        .expect("read config")
+        .parse()
+        .expect("parse config");
+    let checks = config["checks"].as_table_mut().expect("checks table");
+    checks["test-unit"]
+        .as_table_mut()
+        .expect("test-unit table")
+        .insert("run".to_string(), "cargo nextest run".into());
+    let mut audit = toml::Table::new();
+    audit.insert("run".to_string(), "cargo audit".into());
+    checks.insert("audit".to_string(), audit.into());
+    std::fs::write(&config_path, toml::to_string(&config).expect("serialize")).expect("write");
+
+    apc_cmd()
+        .args(["diff-config", "--preset", "rust"])
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("+ audit"))
+        .stderr(predicate::str::contains("~ test-unit"))
+        .stderr(predicate::str::contains("-> \"cargo nextest run\""));
+}
+
+#[test]
+fn test_detect_json() {
+    let temp = create_test_repo();
+
+    let output = apc_cmd()
+        .args(["detect", "--json"])
+        .env_clear()
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .env("CLAUDE_CODE", "1")
+        .env("AGENT_MODE", "0")
+        .current_dir(temp.path())
+        .output()
+        .expect("run apc");
+
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid json");
+    assert_eq!(json["mode"], "agent");
+    assert_eq!(json["reason"]["kind"], "known_agent_env_var");
+    assert_eq!(json["reason"]["value"], "CLAUDE_CODE");
+    assert_eq!(json["agent"], "claude_code");
+    assert_eq!(json["environment"]["agent_mode"], "0");
+    assert_eq!(json["environment"]["apc_mode"], serde_json::Value::Null);
+    assert_eq!(
+        json["environment"]["agent_env_vars"],
+        serde_json::json!(["CLAUDE_CODE"])
+    );
+    assert_eq!(json["environment"]["stdout_tty"], false);
 }